@@ -0,0 +1,256 @@
+//! Synthetic load generator for the gateway, reporting latency percentiles so
+//! proxy and telemetry overhead can be tracked across builds.
+//!
+//! Against the built-in mock providers the numbers are the gateway's own overhead:
+//!
+//! ```bash
+//! MOCK_PROVIDERS=true cargo run --release --features mock-providers &
+//! cargo run --release -p gateway-bench -- --concurrency 64 --requests 20000 --stream-ratio 0.5 --prompt-bytes 512,8192
+//! ```
+
+mod report;
+
+use clap::Parser;
+use futures_util::StreamExt;
+use noveum_gateway_client::{ChatCompletionRequest, ChatMessage, ClientError, GatewayClient};
+use report::{Report, Sample};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Parser)]
+#[command(name = "gateway-bench", version, about = "Generate chat and streaming load against the gateway")]
+struct Args {
+    /// Gateway base URL
+    #[arg(long, env = "GATEWAY_URL", default_value = "http://localhost:3000")]
+    url: String,
+
+    /// Provider requests are sent to (x-provider)
+    #[arg(long, default_value = "openai")]
+    provider: String,
+
+    #[arg(long, default_value = "gpt-4o-mini")]
+    model: String,
+
+    /// Provider API key; any value works against the mock providers
+    #[arg(long, env = "BENCH_API_KEY", default_value = "bench-key", hide_env_values = true)]
+    api_key: String,
+
+    /// Requests in flight at once
+    #[arg(short, long, default_value_t = 32)]
+    concurrency: usize,
+
+    /// Requests to send, unless --duration is given
+    #[arg(short = 'n', long, default_value_t = 1000)]
+    requests: u64,
+
+    /// Send requests for this many seconds instead
+    #[arg(long)]
+    duration: Option<u64>,
+
+    /// Share of requests sent with `stream: true`, from 0 to 1
+    #[arg(long, default_value_t = 0.5, value_parser = parse_ratio)]
+    stream_ratio: f64,
+
+    /// Prompt sizes in bytes, used in turn, e.g. 256,4096,65536
+    #[arg(long, value_delimiter = ',', default_value = "512")]
+    prompt_bytes: Vec<usize>,
+
+    /// `max_tokens` sent with each request
+    #[arg(long, default_value_t = 64)]
+    max_tokens: u32,
+
+    /// Requests sent and discarded before measuring, to open connections
+    #[arg(long, default_value_t = 0)]
+    warmup: u64,
+
+    /// Per-request timeout in seconds
+    #[arg(long, default_value_t = 60)]
+    timeout: u64,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn parse_ratio(value: &str) -> Result<f64, String> {
+    let ratio: f64 = value.parse().map_err(|e: std::num::ParseFloatError| e.to_string())?;
+    if (0.0..=1.0).contains(&ratio) {
+        Ok(ratio)
+    } else {
+        Err("must be between 0 and 1".to_string())
+    }
+}
+
+/// When the workers stop taking new requests
+#[derive(Clone, Copy)]
+enum Limit {
+    Requests(u64),
+    Until(Instant),
+}
+
+struct Workload {
+    client: GatewayClient,
+    /// One request per prompt size
+    requests: Vec<ChatCompletionRequest>,
+    stream_ratio: f64,
+}
+
+impl Workload {
+    fn new(args: &Args) -> Result<Self, ClientError> {
+        let client = GatewayClient::builder(&args.url)
+            .provider(&args.provider)
+            .api_key(&args.api_key)
+            .project_id("gateway-bench")
+            .timeout(Duration::from_secs(args.timeout))
+            .build()?;
+        let requests = args
+            .prompt_bytes
+            .iter()
+            .map(|&bytes| {
+                ChatCompletionRequest::new(&args.model, vec![ChatMessage::user(filler(bytes))]).max_tokens(args.max_tokens)
+            })
+            .collect();
+        Ok(Self {
+            client,
+            requests,
+            stream_ratio: args.stream_ratio,
+        })
+    }
+
+    /// Streaming requests are spread evenly, so every run sends the same mix
+    fn is_streaming(&self, index: u64) -> bool {
+        ((index + 1) as f64 * self.stream_ratio).floor() > (index as f64 * self.stream_ratio).floor()
+    }
+
+    async fn send(&self, index: u64) -> Sample {
+        let request = &self.requests[index as usize % self.requests.len()];
+        let streaming = self.is_streaming(index);
+        let start = Instant::now();
+        let result = if streaming {
+            self.stream(request, start).await
+        } else {
+            self.client.chat(request).await.map(|_| None)
+        };
+        let latency = start.elapsed();
+        match result {
+            Ok(first_chunk) => Sample { streaming, latency, first_chunk, error: None },
+            Err(e) => Sample { streaming, latency, first_chunk: None, error: Some(error_label(&e)) },
+        }
+    }
+
+    /// Read the whole stream, returning when its first chunk arrived
+    async fn stream(&self, request: &ChatCompletionRequest, start: Instant) -> Result<Option<Duration>, ClientError> {
+        let mut stream = self.client.chat_stream(request).await?;
+        let mut first_chunk = None;
+        while let Some(chunk) = stream.next().await {
+            chunk?;
+            first_chunk.get_or_insert_with(|| start.elapsed());
+        }
+        Ok(first_chunk)
+    }
+}
+
+/// Prompt text of exactly `bytes` bytes
+fn filler(bytes: usize) -> String {
+    "The quick brown fox jumps over the lazy dog. ".chars().cycle().take(bytes).collect()
+}
+
+fn error_label(error: &ClientError) -> String {
+    match error {
+        ClientError::Api { status, code: Some(code), .. } => format!("{} {}", status, code),
+        ClientError::Api { status, .. } => status.to_string(),
+        ClientError::Http(e) if e.is_timeout() => "timeout".to_string(),
+        ClientError::Http(_) => "connection".to_string(),
+        ClientError::Stream(_) => "stream".to_string(),
+        ClientError::Decode(_) => "decode".to_string(),
+        ClientError::InvalidHeader(_) => "invalid header".to_string(),
+    }
+}
+
+/// Send requests from `concurrency` workers until `limit`, returning the samples and wall time
+async fn run(workload: Arc<Workload>, concurrency: usize, limit: Limit) -> (Vec<Sample>, Duration) {
+    let next = Arc::new(AtomicU64::new(0));
+    let start = Instant::now();
+    let workers: Vec<_> = (0..concurrency.max(1))
+        .map(|_| {
+            let workload = workload.clone();
+            let next = next.clone();
+            tokio::spawn(async move {
+                let mut samples = Vec::new();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let done = match limit {
+                        Limit::Requests(total) => index >= total,
+                        Limit::Until(deadline) => Instant::now() >= deadline,
+                    };
+                    if done {
+                        return samples;
+                    }
+                    samples.push(workload.send(index).await);
+                }
+            })
+        })
+        .collect();
+
+    let mut samples = Vec::new();
+    for worker in workers {
+        match worker.await {
+            Ok(worker_samples) => samples.extend(worker_samples),
+            Err(e) => eprintln!("Worker failed: {}", e),
+        }
+    }
+    (samples, start.elapsed())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    if args.prompt_bytes.is_empty() {
+        eprintln!("--prompt-bytes needs at least one size");
+        return ExitCode::FAILURE;
+    }
+    let workload = match Workload::new(&args) {
+        Ok(workload) => Arc::new(workload),
+        Err(e) => {
+            eprintln!("Failed to create the client: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.warmup > 0 {
+        eprintln!("Warming up with {} requests", args.warmup);
+        run(workload.clone(), args.concurrency, Limit::Requests(args.warmup)).await;
+    }
+    let limit = match args.duration {
+        Some(secs) => {
+            eprintln!("Sending requests to {} for {}s at concurrency {}", args.url, secs, args.concurrency);
+            Limit::Until(Instant::now() + Duration::from_secs(secs))
+        }
+        None => {
+            eprintln!("Sending {} requests to {} at concurrency {}", args.requests, args.url, args.concurrency);
+            Limit::Requests(args.requests)
+        }
+    };
+    let (samples, elapsed) = run(workload, args.concurrency, limit).await;
+
+    let report = Report::new(&samples, elapsed, args.concurrency, args.stream_ratio, args.prompt_bytes);
+    if args.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize the report: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        report.print();
+    }
+
+    // Nothing succeeded: most likely the gateway isn't reachable
+    if report.requests > 0 && report.errors == report.requests {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
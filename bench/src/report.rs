@@ -0,0 +1,119 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Outcome of one request
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub streaming: bool,
+    pub latency: Duration,
+    /// Time to the first streamed chunk
+    pub first_chunk: Option<Duration>,
+    /// e.g. `503 circuit_open`, `timeout`
+    pub error: Option<String>,
+}
+
+/// Latency distribution of the successful requests in a group, in milliseconds
+#[derive(Debug, Serialize)]
+pub struct Latencies {
+    pub count: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Latencies {
+    fn from_durations(durations: impl Iterator<Item = Duration>) -> Option<Self> {
+        let mut millis: Vec<f64> = durations.map(|d| d.as_secs_f64() * 1000.0).collect();
+        if millis.is_empty() {
+            return None;
+        }
+        millis.sort_by(f64::total_cmp);
+        Some(Self {
+            count: millis.len(),
+            mean_ms: millis.iter().sum::<f64>() / millis.len() as f64,
+            p50_ms: percentile(&millis, 0.50),
+            p95_ms: percentile(&millis, 0.95),
+            p99_ms: percentile(&millis, 0.99),
+            max_ms: millis[millis.len() - 1],
+        })
+    }
+}
+
+/// Nearest-rank percentile over an already sorted slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Results of a run; `--json` prints this as is so runs can be compared across builds
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub requests: usize,
+    pub errors: usize,
+    pub elapsed_secs: f64,
+    pub requests_per_sec: f64,
+    pub concurrency: usize,
+    pub stream_ratio: f64,
+    pub prompt_bytes: Vec<usize>,
+    pub all: Option<Latencies>,
+    pub chat: Option<Latencies>,
+    pub stream: Option<Latencies>,
+    pub first_chunk: Option<Latencies>,
+    pub error_counts: BTreeMap<String, usize>,
+}
+
+impl Report {
+    pub fn new(samples: &[Sample], elapsed: Duration, concurrency: usize, stream_ratio: f64, prompt_bytes: Vec<usize>) -> Self {
+        let ok = || samples.iter().filter(|s| s.error.is_none());
+        let mut error_counts = BTreeMap::new();
+        for error in samples.iter().filter_map(|s| s.error.clone()) {
+            *error_counts.entry(error).or_insert(0) += 1;
+        }
+        Self {
+            requests: samples.len(),
+            errors: error_counts.values().sum(),
+            elapsed_secs: elapsed.as_secs_f64(),
+            requests_per_sec: samples.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            concurrency,
+            stream_ratio,
+            prompt_bytes,
+            all: Latencies::from_durations(ok().map(|s| s.latency)),
+            chat: Latencies::from_durations(ok().filter(|s| !s.streaming).map(|s| s.latency)),
+            stream: Latencies::from_durations(ok().filter(|s| s.streaming).map(|s| s.latency)),
+            first_chunk: Latencies::from_durations(ok().filter_map(|s| s.first_chunk)),
+            error_counts,
+        }
+    }
+
+    pub fn print(&self) {
+        println!(
+            "{} requests ({} errors) in {:.2}s: {:.1} req/s at concurrency {}",
+            self.requests, self.errors, self.elapsed_secs, self.requests_per_sec, self.concurrency
+        );
+        println!();
+        println!("{:<12} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9}", "ms", "count", "mean", "p50", "p95", "p99", "max");
+        for (name, latencies) in [
+            ("all", &self.all),
+            ("chat", &self.chat),
+            ("stream", &self.stream),
+            ("first chunk", &self.first_chunk),
+        ] {
+            if let Some(l) = latencies {
+                println!(
+                    "{:<12} {:>8} {:>9.1} {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+                    name, l.count, l.mean_ms, l.p50_ms, l.p95_ms, l.p99_ms, l.max_ms
+                );
+            }
+        }
+        if !self.error_counts.is_empty() {
+            println!();
+            println!("errors:");
+            for (error, count) in &self.error_counts {
+                println!("  {:<30} {}", error, count);
+            }
+        }
+    }
+}
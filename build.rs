@@ -0,0 +1,24 @@
+use std::process::Command;
+
+fn main() {
+    // Docker builds have no .git directory, so the SHA can be passed in as GIT_SHA instead
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+
+    let sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| String::from_utf8(output.stdout).ok())
+                .map(|sha| sha.trim().to_string())
+        });
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=GIT_SHA={}", sha);
+    }
+}
@@ -0,0 +1,36 @@
+//! Non-streaming chat completion through the gateway.
+//!
+//! ```bash
+//! OPENAI_API_KEY=... cargo run -p noveum-gateway-client --example chat
+//! ```
+
+use noveum_gateway_client::{ChatCompletion, ChatCompletionRequest, ChatMessage, GatewayClient, Result};
+
+pub async fn run(client: &GatewayClient, model: &str) -> Result<ChatCompletion> {
+    let request = ChatCompletionRequest::new(
+        model,
+        vec![
+            ChatMessage::system("You are a concise assistant."),
+            ChatMessage::user("Write a very short poem about Rust programming language"),
+        ],
+    )
+    .max_tokens(100);
+    client.chat(&request).await
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let gateway_url = std::env::var("GATEWAY_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let client = GatewayClient::builder(gateway_url)
+        .provider("openai")
+        .api_key(std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set"))
+        .project_id("examples")
+        .build()?;
+
+    let completion = run(&client, "gpt-4o-mini").await?;
+    println!("{}", completion.text().unwrap_or_default());
+    if let Some(usage) = completion.usage {
+        println!("({} prompt + {} completion tokens)", usage.prompt_tokens, usage.completion_tokens);
+    }
+    Ok(())
+}
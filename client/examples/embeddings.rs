@@ -0,0 +1,31 @@
+//! Embeddings through the gateway.
+//!
+//! ```bash
+//! OPENAI_API_KEY=... cargo run -p noveum-gateway-client --example embeddings
+//! ```
+
+use noveum_gateway_client::{EmbeddingRequest, EmbeddingResponse, GatewayClient, Result};
+
+pub async fn run(client: &GatewayClient, model: &str) -> Result<EmbeddingResponse> {
+    let request = EmbeddingRequest::new(
+        model,
+        vec!["The gateway routes requests.".to_string(), "Rust is fast.".to_string()],
+    );
+    client.embeddings(&request).await
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let gateway_url = std::env::var("GATEWAY_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let client = GatewayClient::builder(gateway_url)
+        .provider("openai")
+        .api_key(std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set"))
+        .project_id("examples")
+        .build()?;
+
+    let response = run(&client, "text-embedding-3-small").await?;
+    for embedding in &response.data {
+        println!("input {}: {} dimensions", embedding.index, embedding.embedding.len());
+    }
+    Ok(())
+}
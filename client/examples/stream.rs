@@ -0,0 +1,46 @@
+//! Streaming chat completion, printing each delta as it arrives.
+//!
+//! ```bash
+//! GROQ_API_KEY=... cargo run -p noveum-gateway-client --example stream
+//! ```
+
+use futures_util::StreamExt;
+use noveum_gateway_client::{ChatCompletionRequest, ChatMessage, GatewayClient, GatewayHeaders, Result};
+use std::io::Write;
+
+/// Stream a reply, calling `on_delta` for each piece of text; returns the whole reply
+pub async fn run(client: &GatewayClient, model: &str, mut on_delta: impl FnMut(&str)) -> Result<String> {
+    let request = ChatCompletionRequest::new(model, vec![ChatMessage::user("Count from one to ten in words.")])
+        .max_tokens(100);
+    // Per-request headers on top of the client's defaults
+    let client = client.with_headers(GatewayHeaders::new().thread_id("stream-example"))?;
+
+    let mut stream = client.chat_stream(&request).await?;
+    let mut text = String::new();
+    while let Some(chunk) = stream.next().await {
+        if let Some(delta) = chunk?.text() {
+            on_delta(delta);
+            text.push_str(delta);
+        }
+    }
+    Ok(text)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let gateway_url = std::env::var("GATEWAY_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let client = GatewayClient::builder(gateway_url)
+        .provider("groq")
+        .api_key(std::env::var("GROQ_API_KEY").expect("GROQ_API_KEY must be set"))
+        .project_id("examples")
+        .experiment_id("streaming")
+        .build()?;
+
+    run(&client, "llama-3.1-8b-instant", |delta| {
+        print!("{}", delta);
+        let _ = std::io::stdout().flush();
+    })
+    .await?;
+    println!();
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Invalid header value for {0}")]
+    InvalidHeader(&'static str),
+
+    #[error("Gateway returned {status}: {message}")]
+    Api {
+        status: u16,
+        message: String,
+        /// Machine-readable reason from the error body, e.g. `model_not_found`
+        code: Option<String>,
+    },
+
+    #[error("Failed to decode response: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    #[error("Stream error: {0}")]
+    Stream(String),
+}
+
+impl ClientError {
+    /// Build an API error from a non-success response body in the gateway's
+    /// OpenAI-style `{"error": {...}}` format, falling back to the raw text
+    pub(crate) fn from_body(status: u16, body: &str) -> Self {
+        let error = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|json| json.get("error").cloned());
+        let message = error
+            .as_ref()
+            .and_then(|e| e.get("message").and_then(|m| m.as_str()))
+            .map(String::from)
+            .unwrap_or_else(|| body.to_string());
+        let code = error
+            .as_ref()
+            .and_then(|e| e.get("code").and_then(|c| c.as_str()))
+            .map(String::from);
+        ClientError::Api { status, message, code }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
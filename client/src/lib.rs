@@ -0,0 +1,232 @@
+//! Typed async client for the Noveum AI Gateway.
+//!
+//! ```no_run
+//! use noveum_gateway_client::{ChatCompletionRequest, ChatMessage, GatewayClient};
+//!
+//! # async fn run() -> noveum_gateway_client::Result<()> {
+//! let client = GatewayClient::builder("http://localhost:3000")
+//!     .provider("openai")
+//!     .api_key(std::env::var("OPENAI_API_KEY").unwrap())
+//!     .project_id("my-project")
+//!     .build()?;
+//!
+//! let request = ChatCompletionRequest::new("gpt-4o-mini", vec![ChatMessage::user("Hello!")]);
+//! let completion = client.chat(&request).await?;
+//! println!("{}", completion.text().unwrap_or_default());
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+mod stream;
+mod types;
+
+pub use error::{ClientError, Result};
+pub use stream::ChatStream;
+pub use types::*;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// Client for one gateway; cheap to clone
+#[derive(Debug, Clone)]
+pub struct GatewayClient {
+    http: reqwest::Client,
+    base_url: String,
+    headers: HeaderMap,
+}
+
+impl GatewayClient {
+    /// Client for the gateway at `base_url` with no default headers
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: impl Into<String>) -> GatewayClientBuilder {
+        GatewayClientBuilder {
+            base_url: base_url.into(),
+            headers: GatewayHeaders::default(),
+            timeout: None,
+        }
+    }
+
+    /// A copy of this client that also sends `headers`, overriding any
+    /// default with the same name; useful for per-request project or thread ids
+    pub fn with_headers(&self, headers: GatewayHeaders) -> Result<Self> {
+        let mut client = self.clone();
+        client.headers.extend(headers.into_header_map()?);
+        Ok(client)
+    }
+
+    pub async fn chat(&self, request: &ChatCompletionRequest) -> Result<ChatCompletion> {
+        let request = ChatCompletionRequest {
+            stream: false,
+            ..request.clone()
+        };
+        self.post_json("/v1/chat/completions", &request).await
+    }
+
+    /// Send the request with `stream: true` and iterate over its chunks
+    pub async fn chat_stream(&self, request: &ChatCompletionRequest) -> Result<ChatStream> {
+        let request = ChatCompletionRequest {
+            stream: true,
+            ..request.clone()
+        };
+        let response = self.post("/v1/chat/completions", &request).await?;
+        Ok(ChatStream::new(response))
+    }
+
+    pub async fn embeddings(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse> {
+        self.post_json("/v1/embeddings", request).await
+    }
+
+    async fn post(&self, path: &str, body: &impl Serialize) -> Result<reqwest::Response> {
+        let response = self
+            .http
+            .post(format!("{}{}", self.base_url, path))
+            .headers(self.headers.clone())
+            .json(body)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ClientError::from_body(status.as_u16(), &body));
+        }
+        Ok(response)
+    }
+
+    async fn post_json<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        let bytes = self.post(path, body).await?.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+pub struct GatewayClientBuilder {
+    base_url: String,
+    headers: GatewayHeaders,
+    timeout: Option<Duration>,
+}
+
+impl GatewayClientBuilder {
+    /// Provider every request goes to unless overridden (`x-provider`)
+    pub fn provider(mut self, provider: impl Into<String>) -> Self {
+        self.headers = self.headers.provider(provider);
+        self
+    }
+
+    /// Provider API key, sent as a bearer token
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.headers = self.headers.api_key(api_key);
+        self
+    }
+
+    pub fn project_id(mut self, project_id: impl Into<String>) -> Self {
+        self.headers = self.headers.project_id(project_id);
+        self
+    }
+
+    pub fn organization_id(mut self, organization_id: impl Into<String>) -> Self {
+        self.headers = self.headers.organization_id(organization_id);
+        self
+    }
+
+    pub fn experiment_id(mut self, experiment_id: impl Into<String>) -> Self {
+        self.headers = self.headers.experiment_id(experiment_id);
+        self
+    }
+
+    /// Default headers, merged with any set by the other builder methods
+    pub fn headers(mut self, headers: GatewayHeaders) -> Self {
+        self.headers.entries.extend(headers.entries);
+        self
+    }
+
+    /// Overall timeout per request, including reading a streamed body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Result<GatewayClient> {
+        let mut http = reqwest::Client::builder();
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        Ok(GatewayClient {
+            http: http.build()?,
+            base_url: self.base_url.trim_end_matches('/').to_string(),
+            headers: self.headers.into_header_map()?,
+        })
+    }
+}
+
+/// The gateway's routing and attribution headers
+#[derive(Debug, Clone, Default)]
+pub struct GatewayHeaders {
+    entries: Vec<(&'static str, String)>,
+}
+
+impl GatewayHeaders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(mut self, name: &'static str, value: String) -> Self {
+        self.entries.retain(|(existing, _)| *existing != name);
+        self.entries.push((name, value));
+        self
+    }
+
+    pub fn provider(self, provider: impl Into<String>) -> Self {
+        self.set("x-provider", provider.into())
+    }
+
+    pub fn api_key(self, api_key: impl Into<String>) -> Self {
+        self.set("authorization", format!("Bearer {}", api_key.into()))
+    }
+
+    pub fn project_id(self, project_id: impl Into<String>) -> Self {
+        self.set("x-project-id", project_id.into())
+    }
+
+    pub fn organization_id(self, organization_id: impl Into<String>) -> Self {
+        self.set("x-organization-id", organization_id.into())
+    }
+
+    pub fn experiment_id(self, experiment_id: impl Into<String>) -> Self {
+        self.set("x-experiment-id", experiment_id.into())
+    }
+
+    pub fn user_id(self, user_id: impl Into<String>) -> Self {
+        self.set("x-user-id", user_id.into())
+    }
+
+    /// Conversation id; pins routing pools and groups requests in telemetry
+    pub fn thread_id(self, thread_id: impl Into<String>) -> Self {
+        self.set("x-thread-id", thread_id.into())
+    }
+
+    /// Tenant key, when the gateway is configured for multi-tenancy
+    pub fn gateway_key(self, gateway_key: impl Into<String>) -> Self {
+        self.set("x-gateway-key", gateway_key.into())
+    }
+
+    /// `interactive`, `normal` or `batch`
+    pub fn priority(self, priority: impl Into<String>) -> Self {
+        self.set("x-priority", priority.into())
+    }
+
+    fn into_header_map(self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.entries {
+            let value = HeaderValue::from_str(&value).map_err(|_| ClientError::InvalidHeader(name))?;
+            headers.insert(HeaderName::from_static(name), value);
+        }
+        if let Some(value) = headers.get_mut(AUTHORIZATION) {
+            value.set_sensitive(true);
+        }
+        Ok(headers)
+    }
+}
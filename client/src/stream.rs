@@ -0,0 +1,116 @@
+use crate::{
+    error::{ClientError, Result},
+    types::ChatCompletionChunk,
+};
+use bytes::{Bytes, BytesMut};
+use futures_util::{stream::BoxStream, Stream, StreamExt};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Chunks of a streamed chat completion, parsed from the gateway's SSE body.
+/// Ends after `data: [DONE]`; an `error` event ends it with [`ClientError::Api`].
+pub struct ChatStream {
+    inner: BoxStream<'static, Result<ChatCompletionChunk>>,
+}
+
+impl ChatStream {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        let state = SseState {
+            body: response.bytes_stream().boxed(),
+            buffer: BytesMut::new(),
+            done: false,
+        };
+        let inner = futures_util::stream::unfold(state, |mut state| async move {
+            let item = state.next_chunk().await?;
+            Some((item, state))
+        })
+        .boxed();
+        Self { inner }
+    }
+
+    /// Read the rest of the stream, returning the concatenated text of the first choice
+    pub async fn collect_text(mut self) -> Result<String> {
+        let mut text = String::new();
+        while let Some(chunk) = self.next().await {
+            if let Some(delta) = chunk?.text() {
+                text.push_str(delta);
+            }
+        }
+        Ok(text)
+    }
+}
+
+impl Stream for ChatStream {
+    type Item = Result<ChatCompletionChunk>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+struct SseState {
+    body: BoxStream<'static, reqwest::Result<Bytes>>,
+    buffer: BytesMut,
+    done: bool,
+}
+
+impl SseState {
+    async fn next_chunk(&mut self) -> Option<Result<ChatCompletionChunk>> {
+        while !self.done {
+            if let Some(event) = self.take_event() {
+                if let Some(item) = self.parse_event(&event) {
+                    return Some(item);
+                }
+                continue;
+            }
+            match self.body.next().await {
+                Some(Ok(bytes)) => self.buffer.extend_from_slice(&bytes),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+                None => {
+                    self.done = true;
+                    if !self.buffer.iter().all(u8::is_ascii_whitespace) {
+                        return Some(Err(ClientError::Stream("stream ended mid-event".to_string())));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Split the next complete event off the buffer
+    fn take_event(&mut self) -> Option<String> {
+        let end = self.buffer.windows(2).position(|w| w == b"\n\n")?;
+        let event = self.buffer.split_to(end + 2);
+        Some(String::from_utf8_lossy(&event).replace('\r', ""))
+    }
+
+    fn parse_event(&mut self, event: &str) -> Option<Result<ChatCompletionChunk>> {
+        let data: String = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if data.is_empty() {
+            return None;
+        }
+        if data == "[DONE]" {
+            self.done = true;
+            return None;
+        }
+        let json: serde_json::Value = match serde_json::from_str(&data) {
+            Ok(json) => json,
+            Err(e) => return Some(Err(e.into())),
+        };
+        if json.get("error").is_some() {
+            self.done = true;
+            return Some(Err(ClientError::from_body(200, &data)));
+        }
+        Some(serde_json::from_value(json).map_err(ClientError::from))
+    }
+}
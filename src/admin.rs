@@ -0,0 +1,255 @@
+use crate::{
+    cluster,
+    error::AppError,
+    graphql,
+    providers::catalog,
+    proxy::{canary, rate_limits},
+    openapi::ErrorResponse,
+    telemetry::{
+        plugins::sqlite::{self, RequestLogQuery},
+        schema,
+        stats::StatsQuery,
+        tail::TailQuery,
+        MetricsRegistry,
+    },
+};
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::Request,
+    middleware::{from_fn, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use futures::stream::{self, Stream};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{convert::Infallible, sync::Arc};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+/// Key required on `/admin/*` routes, read once from `ADMIN_API_KEY`
+static ADMIN_API_KEY: Lazy<Option<String>> = Lazy::new(|| {
+    std::env::var("ADMIN_API_KEY")
+        .ok()
+        .filter(|key| !key.trim().is_empty())
+});
+
+/// Routes for operator-facing endpoints, all guarded by the admin key
+pub fn router(registry: Arc<MetricsRegistry>) -> Router {
+    if ADMIN_API_KEY.is_none() {
+        warn!("ADMIN_API_KEY is not set; admin endpoints will reject all requests");
+    }
+
+    Router::new()
+        .route("/admin/stats", get(stats))
+        .route("/admin/tail", get(tail))
+        .route("/admin/requests", get(requests))
+        .route("/admin/schema", get(schema_registry))
+        .route("/admin/canaries", get(canaries))
+        .route("/admin/limits", get(limits))
+        .route("/admin/cluster", get(cluster_status))
+        .route("/admin/providers/:name/test", post(test_provider))
+        .with_state(registry.clone())
+        .merge(graphql::router(registry))
+        .layer(from_fn(require_admin_key))
+}
+
+/// Accepts the admin key as `x-admin-key` or as a bearer token
+pub(crate) async fn require_admin_key(request: Request<Body>, next: Next) -> Result<Response, AppError> {
+    let expected = ADMIN_API_KEY
+        .as_deref()
+        .ok_or_else(|| AppError::Forbidden("Admin API is disabled; set ADMIN_API_KEY to enable it".to_string()))?;
+
+    let headers = request.headers();
+    let provided = headers
+        .get("x-admin-key")
+        .and_then(|h| h.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(http::header::AUTHORIZATION)
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.strip_prefix("Bearer "))
+        });
+
+    if !provided.is_some_and(|key| keys_match(key, expected)) {
+        debug!("Rejected admin request to {}", request.uri().path());
+        return Err(AppError::MissingApiKey);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Compares SHA-256 digests, which are always the same length, without stopping
+/// at the first differing byte, so response timing reveals nothing about the key
+fn keys_match(provided: &str, expected: &str) -> bool {
+    let provided = Sha256::digest(provided.as_bytes());
+    let expected = Sha256::digest(expected.as_bytes());
+    provided.iter().zip(expected.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/stats",
+    tag = "admin",
+    params(StatsQuery),
+    security(("admin_key" = [])),
+    responses(
+        (status = 200, description = "Aggregates over the in-memory window, grouped by provider/model/org", body = Value),
+        (status = 401, description = "Missing or wrong admin key", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn stats(
+    State(registry): State<Arc<MetricsRegistry>>,
+    Query(query): Query<StatsQuery>,
+) -> impl IntoResponse {
+    let store = registry.stats();
+    let stats = store.query(&query);
+    debug!("Serving {} aggregate stats entries", stats.len());
+
+    Json(json!({
+        "window_secs": store.window().as_secs(),
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "stats": stats,
+    }))
+}
+
+/// Search recent requests kept in the SQLite request log (`ENABLE_REQUEST_LOG`)
+#[utoipa::path(
+    get,
+    path = "/admin/requests",
+    tag = "admin",
+    params(RequestLogQuery),
+    security(("admin_key" = [])),
+    responses(
+        (status = 200, description = "Matching request summaries, newest first", body = Value),
+        (status = 400, description = "Invalid filter, or the request log is disabled", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn requests(Query(query): Query<RequestLogQuery>) -> Result<Json<Value>, AppError> {
+    let log = sqlite::get()
+        .ok_or_else(|| AppError::RequestError("Request log is disabled; set ENABLE_REQUEST_LOG=true".to_string()))?;
+    let rows = tokio::task::spawn_blocking(move || log.query(&query))
+        .await
+        .map_err(|e| AppError::RequestError(format!("Request log query failed: {}", e)))??;
+    debug!("Serving {} request log rows", rows.len());
+    Ok(Json(json!({ "count": rows.len(), "requests": rows })))
+}
+
+/// Telemetry schema versions, so consumers can tell which fields each version carries
+#[utoipa::path(
+    get,
+    path = "/admin/schema",
+    tag = "admin",
+    security(("admin_key" = [])),
+    responses((status = 200, description = "Current and supported telemetry schema versions", body = Value))
+)]
+pub(crate) async fn schema_registry() -> impl IntoResponse {
+    Json(json!({
+        "current_version": schema::CURRENT_SCHEMA_VERSION,
+        "versions": schema::REGISTRY,
+    }))
+}
+
+/// Configured canaries and whether any were rolled back
+#[utoipa::path(
+    get,
+    path = "/admin/canaries",
+    tag = "admin",
+    security(("admin_key" = [])),
+    responses((status = 200, description = "Canary configs with their state and rollback reason", body = Value))
+)]
+pub(crate) async fn canaries() -> impl IntoResponse {
+    Json(json!({ "canaries": canary::status() }))
+}
+
+/// Provider rate-limit state per key, as last reported in response headers
+#[utoipa::path(
+    get,
+    path = "/admin/limits",
+    tag = "admin",
+    security(("admin_key" = [])),
+    responses((status = 200, description = "Remaining requests and tokens per provider key", body = Value))
+)]
+pub(crate) async fn limits() -> impl IntoResponse {
+    Json(json!({
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "limits": rate_limits::snapshot(),
+    }))
+}
+
+/// This replica's node id and whether it leads the cluster's background jobs
+#[utoipa::path(
+    get,
+    path = "/admin/cluster",
+    tag = "admin",
+    security(("admin_key" = [])),
+    responses((status = 200, description = "Node id, whether clustering is enabled and whether this replica is the leader", body = Value))
+)]
+pub(crate) async fn cluster_status() -> impl IntoResponse {
+    Json(json!(cluster::status()))
+}
+
+/// Check a provider's configured key by listing its models, e.g. after key rotation
+#[utoipa::path(
+    post,
+    path = "/admin/providers/{name}/test",
+    tag = "admin",
+    params(("name" = String, Path, description = "Provider name, e.g. `openai`")),
+    security(("admin_key" = [])),
+    responses(
+        (status = 200, description = "Auth status (`ok`, `unauthorized`, `no_credentials`, `unreachable` or `error`), latency and model count", body = Value),
+        (status = 400, description = "Provider has no model list to test against", body = ErrorResponse),
+    )
+)]
+pub(crate) async fn test_provider(Path(name): Path<String>) -> Result<Json<catalog::ProviderCheck>, AppError> {
+    let check = catalog::check_provider(&name).await.ok_or_else(|| {
+        AppError::RequestError(format!("{} has no model list endpoint to test credentials against", name))
+    })?;
+    debug!("Provider {} credential check: {:?} in {}ms", name, check.auth, check.latency_ms);
+    Ok(Json(check))
+}
+
+/// Server-sent events feed of request summaries as they complete
+#[utoipa::path(
+    get,
+    path = "/admin/tail",
+    tag = "admin",
+    params(TailQuery),
+    security(("admin_key" = [])),
+    responses((status = 200, description = "Server-sent events, one per completed request", content_type = "text/event-stream"))
+)]
+pub(crate) async fn tail(
+    State(registry): State<Arc<MetricsRegistry>>,
+    Query(query): Query<TailQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = registry.tail().subscribe();
+
+    let events = stream::unfold((receiver, query), |(mut receiver, query)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if query.matches(&event) => {
+                    let event = Event::default()
+                        .event("request")
+                        .json_data(&event)
+                        .unwrap_or_else(|_| Event::default().comment("unserializable event"));
+                    return Some((Ok(event), (receiver, query)));
+                }
+                Ok(_) => continue,
+                // Tell a slow reader what it missed rather than disconnecting it
+                Err(RecvError::Lagged(skipped)) => {
+                    let event = Event::default().event("lagged").data(skipped.to_string());
+                    return Some((Ok(event), (receiver, query)));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,246 @@
+use axum::{
+    body::Body,
+    http::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::error::{AppError, ProviderUnreachable};
+use crate::shared_state::{self, SharedState};
+use crate::telemetry::timing::UpstreamTimings;
+
+/// Consecutive failed requests (upstream 5xx or no response) that open a provider's
+/// breaker, from `CIRCUIT_BREAKER_THRESHOLD`; unset or 0 disables it
+static THRESHOLD: Lazy<u32> = Lazy::new(|| {
+    let threshold = std::env::var("CIRCUIT_BREAKER_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if threshold > 0 {
+        info!("Circuit breaker opens after {} consecutive provider failures", threshold);
+    }
+    threshold
+});
+
+/// How long an open breaker rejects requests before letting a probe through,
+/// from `CIRCUIT_BREAKER_COOLDOWN_SECS`
+static COOLDOWN: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(
+        std::env::var("CIRCUIT_BREAKER_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30)
+            .max(1),
+    )
+});
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    /// When a request started testing the provider after the cooldown; others
+    /// wait for its result, or for a cooldown in case it never finishes
+    probe_started: Option<Instant>,
+}
+
+static BREAKERS: Lazy<Mutex<HashMap<String, Breaker>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How a request got past the breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    Closed,
+    /// The one request testing an open breaker after its cooldown
+    Probe,
+}
+
+/// How a request may pass the provider's breaker, or the seconds until it lets one through
+fn check(provider: &str) -> Result<Pass, u64> {
+    let mut breakers = BREAKERS.lock();
+    let Some(breaker) = breakers.get_mut(provider) else {
+        return Ok(Pass::Closed);
+    };
+    let Some(open_until) = breaker.open_until else {
+        return Ok(Pass::Closed);
+    };
+    let now = Instant::now();
+    if now < open_until {
+        return Err((open_until - now).as_secs_f64().ceil() as u64);
+    }
+    if breaker.probe_started.is_some_and(|at| now < at + *COOLDOWN) {
+        return Err(1);
+    }
+    debug!("Circuit breaker for {} is half-open; sending a probe", provider);
+    breaker.probe_started = Some(now);
+    Ok(Pass::Probe)
+}
+
+/// A change to a breaker that other replicas need to hear about
+enum Change {
+    Opened,
+    Closed,
+}
+
+fn record(provider: &str, pass: Pass, failed: bool) -> Option<Change> {
+    let mut breakers = BREAKERS.lock();
+    let breaker = breakers.entry(provider.to_string()).or_default();
+    // Only the probe decides an open breaker; requests that started before it
+    // opened finish without closing it or extending it
+    if breaker.open_until.is_some() {
+        if pass != Pass::Probe {
+            return None;
+        }
+        if failed {
+            debug!("Probe to {} failed; circuit breaker stays open", provider);
+            breaker.open_until = Some(Instant::now() + *COOLDOWN);
+            breaker.probe_started = None;
+            return Some(Change::Opened);
+        }
+        info!("Circuit breaker for {} closed", provider);
+        breakers.remove(provider);
+        return Some(Change::Closed);
+    }
+    if !failed {
+        breakers.remove(provider);
+        return None;
+    }
+    breaker.failures += 1;
+    if breaker.failures < *THRESHOLD {
+        return None;
+    }
+    warn!("Circuit breaker for {} opened after {} failures", provider, breaker.failures);
+    breaker.open_until = Some(Instant::now() + *COOLDOWN);
+    breaker.probe_started = None;
+    Some(Change::Opened)
+}
+
+/// Shared-state key holding when a provider's open breaker lets a probe through,
+/// in Unix milliseconds; absent while the breaker is closed
+fn shared_key(provider: &str) -> String {
+    format!("breaker:{}", provider)
+}
+
+/// Adopt the breaker other replicas share: open it here when another replica
+/// opened it, and close it when another replica's probe succeeded
+async fn sync(shared: &SharedState, provider: &str) {
+    let probe_at = match shared.get_json::<i64>(&shared_key(provider)).await {
+        Ok(probe_at) => probe_at,
+        Err(e) => {
+            warn!("Failed to read the shared circuit breaker for {}: {}", provider, e);
+            return;
+        }
+    };
+    let mut breakers = BREAKERS.lock();
+    let Some(probe_at) = probe_at else {
+        if breakers.get(provider).is_some_and(|breaker| breaker.open_until.is_some()) {
+            info!("Circuit breaker for {} closed by another replica", provider);
+            breakers.remove(provider);
+        }
+        return;
+    };
+    let remaining = (probe_at - chrono::Utc::now().timestamp_millis()).max(0) as u64;
+    let open_until = Instant::now() + Duration::from_millis(remaining);
+    let breaker = breakers.entry(provider.to_string()).or_default();
+    if breaker.open_until.map_or(true, |until| until < open_until) {
+        if breaker.open_until.is_none() {
+            warn!("Circuit breaker for {} opened by another replica", provider);
+        }
+        breaker.open_until = Some(open_until);
+        breaker.probe_started = None;
+    }
+}
+
+/// Tell the other replicas the breaker opened or closed
+async fn publish(shared: &SharedState, provider: &str, change: Change) {
+    let key = shared_key(provider);
+    let result = match change {
+        // Kept past the cooldown so the probe's outcome is still shared
+        Change::Opened => {
+            let probe_at = chrono::Utc::now().timestamp_millis() + COOLDOWN.as_millis() as i64;
+            shared.put_json(&key, &probe_at, *COOLDOWN * 2).await
+        }
+        Change::Closed => shared.delete(&key).await,
+    };
+    if let Err(e) = result {
+        warn!("Failed to share the circuit breaker for {}: {}", provider, e);
+    }
+}
+
+/// Whether this replica gets to send the probe; one replica probes for the cluster
+async fn claim_probe(shared: &SharedState, provider: &str) -> bool {
+    let holder = uuid::Uuid::new_v4().to_string();
+    match shared.hold_lock(&format!("breaker:{}:probe", provider), &holder, *COOLDOWN).await {
+        Ok(claimed) => claimed,
+        Err(e) => {
+            warn!("Failed to claim the circuit breaker probe for {}: {}", provider, e);
+            true
+        }
+    }
+}
+
+/// Whether the provider failed the request: a 5xx relayed from it, or no response
+/// at all. The gateway's own errors, such as missing configuration, don't count.
+fn provider_failed(response: &Response) -> bool {
+    let extensions = response.extensions();
+    response.status().is_server_error()
+        && (extensions.get::<UpstreamTimings>().is_some() || extensions.get::<ProviderUnreachable>().is_some())
+}
+
+fn circuit_open(provider: &str, retry_after_secs: u64) -> Response {
+    AppError::Unavailable {
+        message: format!("{} is temporarily unavailable; retry the request", provider),
+        code: "circuit_open",
+        retry_after_secs,
+    }
+    .into_response()
+}
+
+/// Rejects requests to a provider with 503 and `Retry-After` while its breaker
+/// is open, and tracks the outcome of the requests it lets through. With Redis
+/// configured, replicas share open breakers and a single probe. Runs after
+/// maintenance and routing, so it sees the provider that is actually called.
+pub async fn breaker_middleware(request: Request<Body>, next: Next) -> Response {
+    if *THRESHOLD == 0 {
+        return next.run(request).await;
+    }
+    let Some(provider) = request
+        .headers()
+        .get("x-provider")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_lowercase)
+    else {
+        return next.run(request).await;
+    };
+
+    let shared = shared_state::get().await;
+    if let Some(shared) = shared {
+        sync(shared, &provider).await;
+    }
+    let pass = match check(&provider) {
+        Ok(pass) => pass,
+        Err(retry_after_secs) => {
+            debug!("Rejecting request to {} while its circuit breaker is open", provider);
+            return circuit_open(&provider, retry_after_secs);
+        }
+    };
+    if pass == Pass::Probe {
+        if let Some(shared) = shared {
+            if !claim_probe(shared, &provider).await {
+                debug!("Another replica is probing {}", provider);
+                return circuit_open(&provider, 1);
+            }
+        }
+    }
+
+    let response = next.run(request).await;
+    if let Some(change) = record(&provider, pass, provider_failed(&response)) {
+        if let Some(shared) = shared {
+            publish(shared, &provider, change).await;
+        }
+    }
+    response
+}
@@ -0,0 +1,164 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::{stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+use crate::providers::routes;
+
+/// Marks responses altered by the fault injector
+const CHAOS_HEADER: &str = "x-gateway-chaos";
+
+/// Faults injected for one provider, each applied to a percentage of requests
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChaosRule {
+    /// Extra delay before the request is forwarded
+    #[serde(default)]
+    pub latency_ms: u64,
+    #[serde(default = "default_percent")]
+    pub latency_percent: f64,
+    /// Share of requests answered with a synthetic error instead of reaching the provider
+    #[serde(default)]
+    pub error_percent: f64,
+    #[serde(default = "default_error_status")]
+    pub error_status: u16,
+    /// Share of streaming responses cut off after `drop_after_chunks` chunks
+    #[serde(default)]
+    pub drop_stream_percent: f64,
+    #[serde(default = "default_drop_after")]
+    pub drop_after_chunks: usize,
+}
+
+fn default_percent() -> f64 {
+    100.0
+}
+
+fn default_error_status() -> u16 {
+    500
+}
+
+fn default_drop_after() -> usize {
+    3
+}
+
+/// Rules from `CHAOS_CONFIG`, keyed by provider; `*` applies to providers without their own rule
+static RULES: Lazy<HashMap<String, ChaosRule>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("CHAOS_CONFIG") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, ChaosRule>>(&raw) {
+        Ok(rules) => {
+            warn!("Fault injection is enabled for: {:?}", rules.keys().collect::<Vec<_>>());
+            rules
+                .into_iter()
+                .map(|(provider, rule)| (provider.to_lowercase(), rule))
+                .collect()
+        }
+        Err(e) => {
+            error!("Failed to parse CHAOS_CONFIG: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+fn rule_for(provider: &str) -> Option<&'static ChaosRule> {
+    RULES.get(&provider.to_lowercase()).or_else(|| RULES.get("*"))
+}
+
+/// True for roughly `percent`% of calls
+fn roll(percent: f64) -> bool {
+    if percent <= 0.0 {
+        return false;
+    }
+    let sample = (uuid::Uuid::new_v4().as_u128() % 10_000) as f64 / 100.0;
+    sample < percent
+}
+
+/// Injects latency, synthetic errors and dropped streams according to `CHAOS_CONFIG`,
+/// into proxied requests that name their provider; health checks are left alone
+pub async fn chaos_middleware(request: Request<Body>, next: Next) -> Response {
+    if !routes::is_proxy_path(request.uri().path()) {
+        return next.run(request).await;
+    }
+    let Some(provider) = request
+        .headers()
+        .get("x-provider")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string)
+    else {
+        return next.run(request).await;
+    };
+    let Some(rule) = rule_for(&provider) else {
+        return next.run(request).await;
+    };
+
+    if rule.latency_ms > 0 && roll(rule.latency_percent) {
+        debug!("Chaos: delaying {} request by {}ms", provider, rule.latency_ms);
+        tokio::time::sleep(Duration::from_millis(rule.latency_ms)).await;
+    }
+
+    if roll(rule.error_percent) {
+        let status = StatusCode::from_u16(rule.error_status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        debug!("Chaos: failing {} request with {}", provider, status);
+        return synthetic_error(status);
+    }
+
+    let response = next.run(request).await;
+
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| v.contains("text/event-stream"));
+    if is_stream && roll(rule.drop_stream_percent) {
+        debug!("Chaos: dropping {} stream after {} chunks", provider, rule.drop_after_chunks);
+        return drop_stream(response, rule.drop_after_chunks);
+    }
+    response
+}
+
+fn synthetic_error(status: StatusCode) -> Response {
+    let body = Json(json!({
+        "error": {
+            "message": format!("Injected fault: {}", status),
+            "type": "chaos_injected",
+            "param": null,
+            "code": status.as_u16().to_string()
+        }
+    }));
+    let mut response = (status, body).into_response();
+    response.headers_mut().insert(CHAOS_HEADER, HeaderValue::from_static("error"));
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    }
+    response
+}
+
+/// Forward the first chunks, then fail the body so the connection is cut mid-stream
+fn drop_stream(response: Response, after_chunks: usize) -> Response {
+    let (mut parts, body) = response.into_parts();
+    parts.headers.insert(CHAOS_HEADER, HeaderValue::from_static("drop-stream"));
+
+    let cut = stream::once(async {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::ConnectionAborted,
+            "stream dropped by fault injection",
+        ))
+    });
+    let truncated = body
+        .into_data_stream()
+        .take(after_chunks)
+        .map(|chunk| chunk.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)))
+        .chain(cut);
+
+    Response::from_parts(parts, Body::from_stream(truncated))
+}
@@ -0,0 +1,384 @@
+use crate::{
+    chaos::ChaosRule,
+    experiments::ExperimentConfig,
+    identity::IdentityHeaders,
+    maintenance::MaintenanceWindow,
+    proxy::{canary::CanaryConfig, stream_failover::FailoverTarget},
+    providers::{
+        catalog::{self, AuthStatus},
+        defaults::OrgDefaults,
+    },
+    routing::RouteTarget,
+    telemetry::{capture::CaptureProfile, extraction::ExtractRule, filter::FilterRule, provider_metrics::get_metrics_extractor,
+        stream_moderation::RuleConfig as ModerationRule,
+    },
+    tenancy::TenantConfig,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use colored::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+#[derive(Parser)]
+#[command(name = "noveum-ai-gateway", version, about = "Noveum AI Gateway")]
+pub struct Cli {
+    /// Defaults to `serve`
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the gateway
+    Serve,
+    /// Check the environment configuration and exit non-zero if anything is invalid
+    ValidateConfig,
+    /// Test each provider's API key and connectivity by listing its models
+    CheckProviders,
+    /// Send recorded requests from a JSONL file through a running gateway
+    Replay {
+        /// One request per line: `{"method": "POST", "path": "/v1/chat/completions", "headers": {...}, "body": {...}}`
+        file: PathBuf,
+        #[arg(long, default_value = "http://localhost:3000")]
+        gateway_url: String,
+        /// Extra header for every request, as `name: value`; overrides the recorded one
+        #[arg(long = "header", short = 'H')]
+        headers: Vec<String>,
+    },
+    /// Print per-million-token prices for models, as used for cost tracking
+    ExportPricing {
+        /// Only this provider
+        #[arg(long)]
+        provider: Option<String>,
+        /// Models to price; defaults to the provider model lists fetched with `<PROVIDER>_API_KEY`
+        #[arg(long, value_delimiter = ',')]
+        models: Vec<String>,
+        #[arg(long, value_enum, default_value = "json")]
+        format: PricingFormat,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PricingFormat {
+    Json,
+    Csv,
+}
+
+fn parses<T: DeserializeOwned>(raw: &str) -> Result<(), String> {
+    serde_json::from_str::<T>(raw).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn number(raw: &str) -> Result<(), String> {
+    raw.parse::<f64>().map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn boolean(raw: &str) -> Result<(), String> {
+    raw.parse::<bool>().map(|_| ()).map_err(|_| "expected true or false".to_string())
+}
+
+fn port(raw: &str) -> Result<(), String> {
+    raw.parse::<u16>().map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn capture_profile(raw: &str) -> Result<(), String> {
+    parses::<CaptureProfile>(&Value::String(raw.trim().to_lowercase()).to_string())
+}
+
+fn maintenance_windows(raw: &str) -> Result<(), String> {
+    let windows: Vec<MaintenanceWindow> = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    windows.iter().try_for_each(MaintenanceWindow::validate)
+}
+
+type Check = fn(&str) -> Result<(), String>;
+
+/// Settings checked by `validate-config`; unset ones fall back to their defaults
+const SETTINGS: &[(&str, Check)] = &[
+    ("PORT", port),
+    ("WORKER_THREADS", number),
+    ("MAX_BLOCKING_THREADS", number),
+    ("THREAD_STACK_SIZE", number),
+    ("MAX_CONNECTIONS", number),
+    ("TCP_KEEPALIVE_INTERVAL", number),
+    ("TCP_NODELAY", boolean),
+    ("BUFFER_SIZE", number),
+    ("COMPRESSION_MIN_SIZE", number),
+    ("ENABLE_COMPRESSION", boolean),
+    ("ENABLE_REQUEST_DECOMPRESSION", boolean),
+    ("MAX_DECOMPRESSED_BODY_BYTES", number),
+    ("ENABLE_ELASTICSEARCH", boolean),
+    ("ELASTICSEARCH_INDEX_PER_ENVIRONMENT", boolean),
+    ("ENABLE_PROMETHEUS", boolean),
+    ("ENABLE_NOVEUM", boolean),
+    ("ENABLE_CLOUDWATCH", boolean),
+    ("ENABLE_ANOMALY_DETECTION", boolean),
+    ("ENABLE_REQUEST_LOG", boolean),
+    ("REQUEST_LOG_RETENTION_HOURS", number),
+    ("REQUEST_LOG_MAX_ROWS", number),
+    ("ENABLE_USAGE_HEADERS", boolean),
+    ("ENABLE_OTEL_TRACING", boolean),
+    ("SCORING_SAMPLE_RATE", number),
+    ("STATS_WINDOW_SECS", number),
+    ("STATS_MAX_SAMPLES", number),
+    ("DEDUP_WINDOW_MS", number),
+    ("MAX_STREAM_DURATION_SECS", number),
+    ("STREAM_MODERATION_RULES", parses::<Vec<ModerationRule>>),
+    ("STREAM_MODERATION_WINDOW_CHARS", number),
+    ("STICKY_PIN_TTL_SECS", number),
+    ("MODEL_VALIDATION", boolean),
+    ("MODEL_REFRESH_INTERVAL_SECS", number),
+    ("RATE_LIMIT_SYNC", boolean),
+    ("RATE_LIMIT_RESERVE_REQUESTS", number),
+    ("RATE_LIMIT_RESERVE_TOKENS", number),
+    ("RATE_LIMIT_MAX_DELAY_MS", number),
+    ("REQUIRE_TENANT", boolean),
+    ("BUDGET_RESERVATION_MIN_USD", number),
+    ("BUDGET_RESERVATION_TTL_SECS", number),
+    ("GATEWAY_TENANTS", parses::<Vec<TenantConfig>>),
+    ("ROUTING_POOLS", parses::<HashMap<String, Vec<RouteTarget>>>),
+    ("CANARY_CONFIG", parses::<Vec<CanaryConfig>>),
+    ("EXPERIMENTS", parses::<Vec<ExperimentConfig>>),
+    ("STREAM_FAILOVER", parses::<HashMap<String, FailoverTarget>>),
+    ("STREAM_RETRY_ATTEMPTS", number),
+    ("MAINTENANCE_WINDOWS", maintenance_windows),
+    ("CLUSTER_LEADER_TTL_SECS", number),
+    ("SHUTDOWN_DRAIN_SECS", number),
+    ("CIRCUIT_BREAKER_THRESHOLD", number),
+    ("CIRCUIT_BREAKER_COOLDOWN_SECS", number),
+    ("CHAOS_CONFIG", parses::<HashMap<String, ChaosRule>>),
+    ("ORG_DEFAULTS", parses::<HashMap<String, OrgDefaults>>),
+    ("FX_RATES", parses::<HashMap<String, f64>>),
+    ("IDENTITY_HEADERS", parses::<IdentityHeaders>),
+    ("MODEL_ALIASES", parses::<HashMap<String, String>>),
+    ("LONG_CONTEXT_FALLBACKS", parses::<HashMap<String, String>>),
+    ("MODEL_CONTEXT_WINDOWS", parses::<HashMap<String, u32>>),
+    ("MAX_STREAM_DURATION_BY_PROVIDER", parses::<HashMap<String, f64>>),
+    ("CAPTURE_PROFILE", capture_profile),
+    ("CAPTURE_PROFILES", parses::<HashMap<String, CaptureProfile>>),
+    ("TELEMETRY_EXTRACT_RULES", parses::<Vec<ExtractRule>>),
+    ("TELEMETRY_FILTERS", parses::<Vec<FilterRule>>),
+    ("HEADER_PASSTHROUGH", parses::<HashMap<String, Vec<String>>>),
+];
+
+fn report(name: &str, result: &Result<String, String>) {
+    match result {
+        Ok(detail) => println!("  {} {} {}", "✓".bright_green(), name, detail.dimmed()),
+        Err(e) => println!("  {} {}: {}", "✗".bright_red(), name, e.bright_red()),
+    }
+}
+
+pub fn validate_config() -> ExitCode {
+    let mut failures = 0;
+    for (name, check) in SETTINGS {
+        let Ok(raw) = std::env::var(name) else {
+            continue;
+        };
+        let result = check(&raw).map(|()| String::new());
+        failures += usize::from(result.is_err());
+        report(name, &result);
+    }
+
+    // Tenants may come from a file instead of the variable
+    if std::env::var("GATEWAY_TENANTS").is_err() {
+        if let Ok(path) = std::env::var("GATEWAY_TENANTS_FILE") {
+            let result = std::fs::read_to_string(&path)
+                .map_err(|e| format!("can't read {}: {}", path, e))
+                .and_then(|raw| parses::<Vec<TenantConfig>>(&raw))
+                .map(|()| path.clone());
+            failures += usize::from(result.is_err());
+            report("GATEWAY_TENANTS_FILE", &result);
+        }
+    }
+
+    if failures > 0 {
+        println!("\n{}", format!("{} invalid setting(s)", failures).bright_red());
+        ExitCode::FAILURE
+    } else {
+        println!("\n{}", "Configuration is valid".bright_green());
+        ExitCode::SUCCESS
+    }
+}
+
+pub async fn check_providers() -> ExitCode {
+    let mut failures = 0;
+    for check in catalog::check_providers().await {
+        if check.auth == AuthStatus::NoCredentials {
+            println!("  {} {} {}", "-".dimmed(), check.provider, "(no API key configured)".dimmed());
+            continue;
+        }
+        let result = match (check.models, check.error) {
+            (Some(count), _) => Ok(format!("{} models in {}ms", count, check.latency_ms)),
+            (None, error) => Err(format!("{:?}: {}", check.auth, error.unwrap_or_default())),
+        };
+        failures += usize::from(result.is_err());
+        report(check.provider, &result);
+    }
+    if failures > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// One recorded request in a replay file
+#[derive(Deserialize)]
+struct ReplayRequest {
+    #[serde(default = "default_method")]
+    method: String,
+    #[serde(default = "default_path")]
+    path: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    body: Option<Value>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+fn default_path() -> String {
+    "/v1/chat/completions".to_string()
+}
+
+pub async fn replay(file: PathBuf, gateway_url: String, headers: Vec<String>) -> ExitCode {
+    let raw = match std::fs::read_to_string(&file) {
+        Ok(raw) => raw,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", file.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let overrides: Vec<(&str, &str)> = headers
+        .iter()
+        .filter_map(|header| header.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()
+        .expect("Failed to create replay HTTP client");
+    let base_url = gateway_url.trim_end_matches('/');
+    let (mut sent, mut failures) = (0, 0);
+
+    for (index, line) in raw.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let line_number = index + 1;
+        let request: ReplayRequest = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                failures += 1;
+                println!("  {} line {}: invalid request: {}", "✗".bright_red(), line_number, e);
+                continue;
+            }
+        };
+        let Ok(method) = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes()) else {
+            failures += 1;
+            println!("  {} line {}: invalid method {}", "✗".bright_red(), line_number, request.method);
+            continue;
+        };
+
+        let mut builder = client.request(method, format!("{}{}", base_url, request.path));
+        for (name, value) in &request.headers {
+            if !overrides.iter().any(|(o, _)| o.eq_ignore_ascii_case(name)) {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+        }
+        for (name, value) in &overrides {
+            builder = builder.header(*name, *value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.json(body);
+        }
+
+        sent += 1;
+        let started = Instant::now();
+        // Read the whole body so streamed responses are timed to completion
+        let result = match builder.send().await {
+            Ok(response) => {
+                let status = response.status();
+                match response.bytes().await {
+                    Ok(_) if status.is_success() => Ok(format!("{} in {:?}", status, started.elapsed())),
+                    Ok(body) => Err(format!("{}: {}", status, String::from_utf8_lossy(&body))),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        failures += usize::from(result.is_err());
+        report(&format!("line {} {} {}", line_number, request.method, request.path), &result);
+    }
+
+    println!("\n{} sent, {} failed", sent, failures);
+    if failures > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// A model's price in USD per million tokens
+#[derive(Serialize)]
+struct Price {
+    provider: String,
+    model: String,
+    input_per_million: f64,
+    output_per_million: f64,
+}
+
+pub async fn export_pricing(provider: Option<String>, models: Vec<String>, format: PricingFormat) -> ExitCode {
+    let providers: Vec<String> = match provider {
+        Some(provider) => vec![provider.to_lowercase()],
+        None => catalog::listable_providers().map(String::from).collect(),
+    };
+    if models.is_empty() {
+        // Fills the model lists for providers with a key configured
+        catalog::check_providers().await;
+    }
+
+    let mut prices = Vec::new();
+    for provider in &providers {
+        let extractor = get_metrics_extractor(provider);
+        let candidates = if models.is_empty() { catalog::models(provider) } else { models.clone() };
+        for model in candidates {
+            let input = extractor.estimate_cost(&model, 1_000_000, 0);
+            let output = extractor.estimate_cost(&model, 0, 1_000_000);
+            if let (Some(input), Some(output)) = (input, output) {
+                prices.push(Price {
+                    provider: provider.clone(),
+                    model,
+                    input_per_million: input,
+                    output_per_million: output,
+                });
+            }
+        }
+    }
+    if prices.is_empty() {
+        eprintln!("No prices found; pass --models, or set <PROVIDER>_API_KEY to price the listed models");
+        return ExitCode::FAILURE;
+    }
+
+    match format {
+        PricingFormat::Json => match serde_json::to_string_pretty(&prices) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to serialize prices: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        PricingFormat::Csv => {
+            println!("provider,model,input_per_million,output_per_million");
+            for price in &prices {
+                println!(
+                    "{},{},{},{}",
+                    price.provider, price.model, price.input_per_million, price.output_per_million
+                );
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,123 @@
+use crate::shared_state;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// Shared-state key of the leader lease
+const LEADER_LOCK: &str = "cluster:leader";
+
+/// Replicas elect a leader when `CLUSTER_MODE=redis`, through a lease in the
+/// Redis shared state (`REDIS_URL`); otherwise each runs standalone
+static ENABLED: Lazy<bool> = Lazy::new(|| match std::env::var("CLUSTER_MODE") {
+    Ok(mode) if mode.eq_ignore_ascii_case("redis") => true,
+    Ok(mode) if !mode.is_empty() => {
+        error!("Unsupported CLUSTER_MODE {}; running standalone", mode);
+        false
+    }
+    _ => false,
+});
+
+/// How long the leader's lease lasts without renewal, from `CLUSTER_LEADER_TTL_SECS`;
+/// it is renewed every third of that
+static LEASE: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(
+        std::env::var("CLUSTER_LEADER_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(15)
+            .max(3),
+    )
+});
+
+/// This replica's name in the election
+static NODE_ID: Lazy<String> = Lazy::new(|| {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "gateway".to_string());
+    format!("{}-{}", host, &uuid::Uuid::new_v4().simple().to_string()[..8])
+});
+
+/// Whether this replica takes part in an election; cleared if the shared state is unavailable
+static CLUSTERED: AtomicBool = AtomicBool::new(false);
+
+/// Whether this replica holds the leader lease
+static LEADER: AtomicBool = AtomicBool::new(false);
+
+/// Whether this replica should run cluster-wide background jobs. Standalone
+/// replicas always do; clustered ones only while they hold the lease.
+pub fn is_leader() -> bool {
+    !CLUSTERED.load(Ordering::Relaxed) || LEADER.load(Ordering::Relaxed)
+}
+
+/// Claim a one-off job (e.g. sending an alert) for this replica for `ttl`, so
+/// only one replica in the cluster does it. Standalone replicas always get it,
+/// and so does every replica when Redis fails: a duplicate beats a lost alert.
+pub async fn claim(name: &str, ttl: Duration) -> bool {
+    if !CLUSTERED.load(Ordering::Relaxed) {
+        return true;
+    }
+    let Some(shared) = shared_state::get().await else {
+        return true;
+    };
+    match shared.hold_lock(&format!("cluster:claim:{}", name), &NODE_ID, ttl).await {
+        Ok(claimed) => claimed,
+        Err(e) => {
+            warn!("Failed to claim {}: {}", name, e);
+            true
+        }
+    }
+}
+
+/// This replica's view of the cluster, for the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterStatus {
+    pub node_id: String,
+    pub clustered: bool,
+    pub leader: bool,
+}
+
+pub fn status() -> ClusterStatus {
+    ClusterStatus {
+        node_id: NODE_ID.clone(),
+        clustered: CLUSTERED.load(Ordering::Relaxed),
+        leader: is_leader(),
+    }
+}
+
+/// Start taking part in the leader election when clustering is enabled. A
+/// replica that can't reach Redis gives up the lease rather than risk two leaders.
+pub fn spawn() {
+    if !*ENABLED {
+        return;
+    }
+    // No replica leads until it has won the lease
+    CLUSTERED.store(true, Ordering::Relaxed);
+    info!("Joining leader election as {} (lease {:?})", *NODE_ID, *LEASE);
+    tokio::spawn(async {
+        if std::env::var("REDIS_URL").map_or(true, |url| url.is_empty()) {
+            error!("CLUSTER_MODE=redis needs REDIS_URL; running standalone");
+            CLUSTERED.store(false, Ordering::Relaxed);
+            return;
+        }
+        let mut ticker = tokio::time::interval(*LEASE / 3);
+        loop {
+            ticker.tick().await;
+            // Until Redis is reachable no replica leads; the connection is retried
+            let leading = match shared_state::get().await {
+                Some(shared) => match shared.hold_lock(LEADER_LOCK, &NODE_ID, *LEASE).await {
+                    Ok(leading) => leading,
+                    Err(e) => {
+                        warn!("Leader election failed: {}", e);
+                        false
+                    }
+                },
+                None => false,
+            };
+            match (LEADER.swap(leading, Ordering::Relaxed), leading) {
+                (false, true) => info!("{} is now the cluster leader", *NODE_ID),
+                (true, false) => warn!("{} is no longer the cluster leader", *NODE_ID),
+                _ => {}
+            }
+        }
+    });
+}
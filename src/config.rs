@@ -2,15 +2,48 @@ use num_cpus;
 use std::env;
 use tracing::debug;
 use tracing::info;
+use tracing::warn;
+
+/// HTTP versions accepted by the listener
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpVersions {
+    /// HTTP/1.1 and cleartext HTTP/2 (prior knowledge), detected per connection
+    Auto,
+    Http1,
+    Http2,
+}
+
+impl HttpVersions {
+    fn from_env() -> Self {
+        match env::var("HTTP_VERSIONS").unwrap_or_default().to_lowercase().as_str() {
+            "http1" | "h1" => HttpVersions::Http1,
+            "http2" | "h2" | "h2c" => HttpVersions::Http2,
+            "http3" | "h3" => {
+                warn!("HTTP/3 is not supported yet; serving HTTP/1.1 and HTTP/2");
+                HttpVersions::Auto
+            }
+            _ => HttpVersions::Auto,
+        }
+    }
+}
 
 pub struct AppConfig {
     pub port: u16,
     pub host: String,
     pub worker_threads: usize,
+    pub max_blocking_threads: usize,
+    pub thread_stack_size: usize,
     pub max_connections: usize,
     pub tcp_keepalive_interval: u64,
     pub tcp_nodelay: bool,
     pub buffer_size: usize,
+    pub compression_enabled: bool,
+    pub compression_min_size: u16,
+    pub request_decompression_enabled: bool,
+    /// Largest request body accepted once decompressed, so a small compressed body can't inflate without bound
+    pub max_decompressed_body_bytes: usize,
+    pub http_versions: HttpVersions,
+    pub http2_max_concurrent_streams: u32,
 }
 
 impl AppConfig {
@@ -39,6 +72,14 @@ impl AppConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(default_workers),
+            max_blocking_threads: env::var("MAX_BLOCKING_THREADS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(512),
+            thread_stack_size: env::var("THREAD_STACK_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2 * 1024 * 1024), // 2MB default
             max_connections: env::var("MAX_CONNECTIONS")
                 .ok()
                 .and_then(|v| v.parse().ok())
@@ -55,6 +96,27 @@ impl AppConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(8 * 1024), // 8KB default
+            compression_enabled: env::var("ENABLE_COMPRESSION")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+            compression_min_size: env::var("COMPRESSION_MIN_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1024),
+            request_decompression_enabled: env::var("ENABLE_REQUEST_DECOMPRESSION")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+            max_decompressed_body_bytes: env::var("MAX_DECOMPRESSED_BODY_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(32 * 1024 * 1024),
+            http_versions: HttpVersions::from_env(),
+            http2_max_concurrent_streams: env::var("HTTP2_MAX_CONCURRENT_STREAMS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256),
         };
 
         info!(
@@ -62,12 +124,32 @@ impl AppConfig {
             config.port, config.host
         );
         debug!(
-            "Advanced settings: workers={}, max_conn={}, buffer_size={}",
-            config.worker_threads, config.max_connections, config.buffer_size
+            "Advanced settings: workers={}, blocking_threads={}, stack_size={}, max_conn={}, buffer_size={}",
+            config.worker_threads,
+            config.max_blocking_threads,
+            config.thread_stack_size,
+            config.max_connections,
+            config.buffer_size
+        );
+        debug!(
+            "HTTP settings: versions={:?}, h2_max_streams={}",
+            config.http_versions, config.http2_max_concurrent_streams
         );
 
         config
     }
+
+    /// Build the tokio runtime the server runs on. The runtime can't be resized
+    /// once started, so these settings only apply when set before startup.
+    pub fn build_runtime(&self) -> std::io::Result<tokio::runtime::Runtime> {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(self.worker_threads.max(1))
+            .max_blocking_threads(self.max_blocking_threads.max(1))
+            .thread_stack_size(self.thread_stack_size)
+            .thread_name("gateway-worker")
+            .enable_all()
+            .build()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -75,6 +157,11 @@ pub struct TelemetryConfig {
     pub debug_mode: bool,
     pub elasticsearch_enabled: bool,
     pub cloudwatch_enabled: bool,
+    pub prometheus_enabled: bool,
+    pub noveum_enabled: bool,
+    pub anomaly_detection_enabled: bool,
+    pub request_log_enabled: bool,
+    pub otel_tracing_enabled: bool,
 }
 
 impl Default for TelemetryConfig {
@@ -89,6 +176,21 @@ impl Default for TelemetryConfig {
             cloudwatch_enabled: std::env::var("ENABLE_CLOUDWATCH")
                 .map(|v| v.parse().unwrap_or(false))
                 .unwrap_or(false),
+            prometheus_enabled: std::env::var("ENABLE_PROMETHEUS")
+                .map(|v| v.parse().unwrap_or(false))
+                .unwrap_or(false),
+            noveum_enabled: std::env::var("ENABLE_NOVEUM")
+                .map(|v| v.parse().unwrap_or(false))
+                .unwrap_or(false),
+            anomaly_detection_enabled: std::env::var("ENABLE_ANOMALY_DETECTION")
+                .map(|v| v.parse().unwrap_or(false))
+                .unwrap_or(false),
+            request_log_enabled: std::env::var("ENABLE_REQUEST_LOG")
+                .map(|v| v.parse().unwrap_or(false))
+                .unwrap_or(false),
+            otel_tracing_enabled: std::env::var("ENABLE_OTEL_TRACING")
+                .map(|v| v.parse().unwrap_or(false))
+                .unwrap_or(false),
         }
     }
 }
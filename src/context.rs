@@ -1,3 +1,4 @@
+use crate::identity;
 use axum::http::HeaderMap;
 use serde_json::Value;
 
@@ -17,3 +18,63 @@ impl RequestContext {
         }
     }
 }
+
+/// Who a proxied request is for, resolved once in `handlers::proxy_request`.
+///
+/// Travels in the request extensions to the proxy, which fills in the model,
+/// and back in the response extensions to the telemetry middleware.
+#[derive(Debug, Clone, Default)]
+pub struct GatewayRequestContext {
+    /// Gateway request ID; also the ID of the request's log documents.
+    /// Always generated here, so clients can't pick or collide with document IDs
+    pub request_id: String,
+    /// Conversation the request belongs to, from `x-thread-id`
+    pub thread_id: Option<String>,
+    /// The client's own ID for the message, from `x-message-id`; the log's `id` when set
+    pub message_id: Option<String>,
+    pub provider: String,
+    pub model: Option<String>,
+    pub stream: bool,
+    pub tenant_id: Option<String>,
+    pub project_id: Option<String>,
+    pub org_id: Option<String>,
+    pub user_id: Option<String>,
+    pub experiment_id: Option<String>,
+    /// Variant the gateway assigned when `experiment_id` is one of its own experiments
+    pub experiment_variant: Option<String>,
+}
+
+impl GatewayRequestContext {
+    pub fn new(provider: &str, headers: &HeaderMap) -> Self {
+        let identity = identity::from_headers(headers);
+        Self {
+            request_id: format!("msg_{}", uuid::Uuid::new_v4().simple()),
+            thread_id: client_id(headers, "x-thread-id"),
+            message_id: client_id(headers, "x-message-id"),
+            provider: provider.to_string(),
+            project_id: identity.project_id,
+            org_id: identity.org_id,
+            user_id: identity.user_id,
+            experiment_id: identity.experiment_id,
+            ..Default::default()
+        }
+    }
+
+    /// Fill in what the request body says about the model and streaming
+    pub fn observe_body(&mut self, body: &Value) {
+        self.model = body.get("model").and_then(Value::as_str).map(String::from);
+        self.stream = body.get("stream").and_then(Value::as_bool).unwrap_or(false);
+    }
+}
+
+/// Longest client-supplied ID kept; longer values are ignored rather than truncated
+const MAX_CLIENT_ID_LEN: usize = 256;
+
+/// An ID header from the client, if it's usable as a log document ID
+fn client_id(headers: &HeaderMap, name: &str) -> Option<String> {
+    let value = headers.get(name)?.to_str().ok()?.trim();
+    let usable = !value.is_empty()
+        && value.len() <= MAX_CLIENT_ID_LEN
+        && !value.chars().any(|c| c.is_control() || c == '/');
+    usable.then(|| value.to_string())
+}
@@ -0,0 +1,159 @@
+use crate::{
+    admin::require_admin_key,
+    telemetry::{
+        stats::{aggregate, percentile, StatsQuery, StatsSample},
+        MetricsRegistry,
+    },
+};
+use axum::{
+    extract::State,
+    middleware::from_fn,
+    response::{Html, IntoResponse},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DurationRound, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::debug;
+
+/// The dashboard page; it asks for the admin key and polls the summary API
+const INDEX_HTML: &str = include_str!("dashboard/index.html");
+
+/// Rows kept per breakdown table, by cost then request count
+const MAX_ROWS: usize = 20;
+
+/// Built-in dashboard over the in-memory aggregates. The page itself is
+/// public; the data behind it needs the admin key like `/admin/*`.
+pub fn router(registry: Arc<MetricsRegistry>) -> Router {
+    let api = Router::new()
+        .route("/dashboard/api/summary", get(summary))
+        .with_state(registry)
+        .layer(from_fn(require_admin_key));
+
+    Router::new().route("/dashboard", get(index)).merge(api)
+}
+
+#[utoipa::path(
+    get,
+    path = "/dashboard",
+    tag = "gateway",
+    responses((status = 200, description = "Dashboard page", content_type = "text/html"))
+)]
+pub(crate) async fn index() -> impl IntoResponse {
+    Html(INDEX_HTML)
+}
+
+/// One row of a breakdown table
+#[derive(Debug, Serialize)]
+struct Breakdown {
+    key: String,
+    requests: u64,
+    error_rate: f64,
+    latency_p95_ms: f64,
+    total_tokens: u64,
+    cost: f64,
+}
+
+/// One minute of traffic
+#[derive(Debug, Serialize)]
+struct Bucket {
+    timestamp: String,
+    requests: u64,
+    errors: u64,
+    latency_p50_ms: f64,
+    latency_p95_ms: f64,
+}
+
+/// Everything the dashboard renders, in one call
+#[utoipa::path(
+    get,
+    path = "/dashboard/api/summary",
+    tag = "admin",
+    security(("admin_key" = [])),
+    responses((status = 200, description = "Totals, per-minute series and breakdowns by provider, model and project", body = Value))
+)]
+pub(crate) async fn summary(State(registry): State<Arc<MetricsRegistry>>) -> impl IntoResponse {
+    let store = registry.stats();
+    let samples = store.samples(&StatsQuery::default());
+    debug!("Building dashboard summary from {} samples", samples.len());
+
+    let totals = aggregate(&samples);
+    let mut latencies: Vec<f64> = samples.iter().map(|s| s.latency_ms).collect();
+    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let now = Utc::now();
+    let last_minute = samples
+        .iter()
+        .filter(|s| now - s.timestamp <= chrono::Duration::seconds(60))
+        .count();
+
+    Json(json!({
+        "generated_at": now.to_rfc3339(),
+        "window_secs": store.window().as_secs(),
+        "totals": {
+            "requests": totals.requests,
+            "errors": totals.errors,
+            "error_rate": totals.error_rate,
+            "requests_per_second": last_minute as f64 / 60.0,
+            "latency_p50_ms": totals.latency_p50_ms,
+            "latency_p95_ms": totals.latency_p95_ms,
+            "latency_p99_ms": percentile(&latencies, 0.99),
+            "total_tokens": totals.total_tokens,
+            "cost": totals.cost,
+        },
+        "timeseries": timeseries(&samples),
+        "by_provider": breakdown(&samples, |s| s.provider.clone()),
+        "by_model": breakdown(&samples, |s| format!("{}/{}", s.provider, s.model)),
+        "by_project": breakdown(&samples, |s| s.project_id.clone().unwrap_or_else(|| "(none)".to_string())),
+    }))
+}
+
+fn breakdown(samples: &[StatsSample], key: impl Fn(&StatsSample) -> String) -> Vec<Breakdown> {
+    let mut groups: BTreeMap<String, Vec<StatsSample>> = BTreeMap::new();
+    for sample in samples {
+        groups.entry(key(sample)).or_default().push(sample.clone());
+    }
+    let mut rows: Vec<Breakdown> = groups
+        .into_iter()
+        .map(|(key, samples)| {
+            let stats = aggregate(&samples);
+            Breakdown {
+                key,
+                requests: stats.requests,
+                error_rate: stats.error_rate,
+                latency_p95_ms: stats.latency_p95_ms,
+                total_tokens: stats.total_tokens,
+                cost: stats.cost,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| b.cost.total_cmp(&a.cost).then(b.requests.cmp(&a.requests)));
+    rows.truncate(MAX_ROWS);
+    rows
+}
+
+fn timeseries(samples: &[StatsSample]) -> Vec<Bucket> {
+    let mut buckets: BTreeMap<_, Vec<StatsSample>> = BTreeMap::new();
+    for sample in samples {
+        let minute = sample
+            .timestamp
+            .duration_trunc(chrono::Duration::minutes(1))
+            .unwrap_or(sample.timestamp);
+        buckets.entry(minute).or_default().push(sample.clone());
+    }
+    buckets
+        .into_iter()
+        .map(|(minute, samples)| {
+            let stats = aggregate(&samples);
+            Bucket {
+                timestamp: minute.to_rfc3339(),
+                requests: stats.requests,
+                errors: stats.errors,
+                latency_p50_ms: stats.latency_p50_ms,
+                latency_p95_ms: stats.latency_p95_ms,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,59 @@
+use axum::{
+    body::Body,
+    http::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::error::AppError;
+
+/// Retry-After sent while draining; the retry should land on another replica
+const DRAIN_RETRY_AFTER_SECS: u64 = 1;
+
+/// How long the gateway keeps accepting connections after SIGTERM/Ctrl+C,
+/// answering new requests with 503 so load balancers and client SDKs move them
+/// to other replicas, from `SHUTDOWN_DRAIN_SECS`. Requests already in flight
+/// run to completion either way.
+static DRAIN_PERIOD: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(
+        std::env::var("SHUTDOWN_DRAIN_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0),
+    )
+});
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+pub fn is_draining() -> bool {
+    DRAINING.load(Ordering::Relaxed)
+}
+
+/// Enter the drain phase and wait it out before the listener closes
+pub async fn drain() {
+    if DRAIN_PERIOD.is_zero() {
+        return;
+    }
+    info!("Draining for {:?} before closing the listener", *DRAIN_PERIOD);
+    DRAINING.store(true, Ordering::Relaxed);
+    tokio::time::sleep(*DRAIN_PERIOD).await;
+}
+
+/// Rejects new requests with 503 and `Retry-After` while the gateway drains,
+/// health checks included, so load balancers stop routing here
+pub async fn drain_middleware(request: Request<Body>, next: Next) -> Response {
+    if !is_draining() {
+        return next.run(request).await;
+    }
+    debug!("Rejecting {} while draining", request.uri().path());
+    AppError::Unavailable {
+        message: "The gateway is restarting; retry the request".to_string(),
+        code: "gateway_draining",
+        retry_after_secs: DRAIN_RETRY_AFTER_SECS,
+    }
+    .into_response()
+}
@@ -1,6 +1,6 @@
 use aws_sigv4::http_request::SigningError;
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -9,6 +9,11 @@ use http::status::InvalidStatusCode;
 use serde_json::json;
 use std::{convert::Infallible, io};
 
+/// Response extension on errors from failing to reach the provider (connection
+/// errors, timeouts), as opposed to errors the gateway raised itself
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderUnreachable;
+
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error("Request to provider failed: {0}")]
@@ -50,6 +55,9 @@ pub enum AppError {
     #[error("AWS params error: {0}")]
     AwsParamsError(String),
 
+    #[error("Request signing error: {0}")]
+    SigningError(String),
+
     #[error("Invalid header value: {0}")]
     InvalidHeaderValue(#[from] InvalidHeaderValue),
 
@@ -70,11 +78,117 @@ pub enum AppError {
     
     #[error("JSON serialize error: {0}")]
     JsonSerializeError(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    #[error("Invalid provider response: {0}")]
+    InvalidProviderResponse(String),
+
+    #[error("Provider unavailable: {0}")]
+    ProviderUnavailable(String),
+
+    #[error("Endpoint {path} is not supported by {provider}")]
+    UnsupportedEndpoint {
+        path: String,
+        provider: String,
+        supported: Vec<String>,
+        known: bool,
+    },
+
+    #[error("Method {method} is not allowed on {path}")]
+    MethodNotAllowed {
+        method: String,
+        path: String,
+        allowed: Vec<String>,
+    },
+
+    #[error("Service unavailable: {message}")]
+    Unavailable {
+        message: String,
+        code: &'static str,
+        retry_after_secs: u64,
+    },
+
+    #[error("Model {model} does not exist on {provider}")]
+    UnknownModel {
+        model: String,
+        provider: String,
+        suggestions: Vec<String>,
+    },
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message) = match &self {
+            // OpenAI-style error that also tells the client what the provider can serve
+            AppError::UnsupportedEndpoint { path, provider, supported, known } => {
+                let status = if *known {
+                    StatusCode::NOT_IMPLEMENTED
+                } else {
+                    StatusCode::NOT_FOUND
+                };
+                let body = Json(json!({
+                    "error": {
+                        "message": format!("{} does not support {}", provider, path),
+                        "type": "invalid_request_error",
+                        "param": null,
+                        "code": "unsupported_endpoint",
+                        "supported_endpoints": supported,
+                    }
+                }));
+                return (status, body).into_response();
+            }
+            // 405s say which methods the endpoint does take
+            AppError::MethodNotAllowed { method, path, allowed } => {
+                let body = Json(json!({
+                    "error": {
+                        "message": format!("{} does not accept {} requests", path, method),
+                        "type": "invalid_request_error",
+                        "param": null,
+                        "code": "method_not_allowed",
+                    }
+                }));
+                let allow = allowed.join(", ");
+                return (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, allow)], body).into_response();
+            }
+            // Temporary unavailability is answered like OpenAI's overload errors, so
+            // client SDKs retry after the Retry-After delay
+            AppError::Unavailable { message, code, retry_after_secs } => {
+                let body = Json(json!({
+                    "error": {
+                        "message": message,
+                        "type": "server_error",
+                        "param": null,
+                        "code": code,
+                    }
+                }));
+                let retry_after = retry_after_secs.max(&1).to_string();
+                return (StatusCode::SERVICE_UNAVAILABLE, [(header::RETRY_AFTER, retry_after)], body).into_response();
+            }
+            // Nonexistent models are answered like OpenAI does, with close matches to try instead
+            AppError::UnknownModel { model, provider, suggestions } => {
+                let mut message = format!("The model `{}` does not exist on {}.", model, provider);
+                if !suggestions.is_empty() {
+                    message.push_str(&format!(" Did you mean: {}?", suggestions.join(", ")));
+                }
+                let body = Json(json!({
+                    "error": {
+                        "message": message,
+                        "type": "invalid_request_error",
+                        "param": "model",
+                        "code": "model_not_found",
+                        "suggestions": suggestions,
+                    }
+                }));
+                return (StatusCode::NOT_FOUND, body).into_response();
+            }
             AppError::ReqwestError(e) => (
                 StatusCode::BAD_GATEWAY,
                 format!("Provider request failed: {}", e),
@@ -83,6 +197,10 @@ impl IntoResponse for AppError {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Internal server error: {}", e),
             ),
+            AppError::AxumError(e) if exceeds_body_limit(e) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Request body is too large".to_string(),
+            ),
             AppError::AxumError(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Server error: {}", e),
@@ -122,6 +240,10 @@ impl IntoResponse for AppError {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("AWS params build error: {}", e),
             ),
+            AppError::SigningError(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Request signing error: {}", e),
+            ),
             AppError::InvalidHeaderValue(e) => (
                 StatusCode::BAD_REQUEST,
                 format!("Invalid header value: {}", e),
@@ -147,6 +269,11 @@ impl IntoResponse for AppError {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("JSON serialize error: {}", e),
             ),
+            AppError::Forbidden(e) => (StatusCode::FORBIDDEN, e.clone()),
+            AppError::RateLimited(e) => (StatusCode::TOO_MANY_REQUESTS, e.clone()),
+            AppError::BudgetExceeded(e) => (StatusCode::PAYMENT_REQUIRED, e.clone()),
+            AppError::InvalidProviderResponse(e) => (StatusCode::BAD_GATEWAY, e.clone()),
+            AppError::ProviderUnavailable(e) => (StatusCode::SERVICE_UNAVAILABLE, e.clone()),
         };
 
         let body = Json(json!({
@@ -156,8 +283,24 @@ impl IntoResponse for AppError {
             }
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if matches!(self, AppError::ReqwestError(_)) {
+            response.extensions_mut().insert(ProviderUnreachable);
+        }
+        response
+    }
+}
+
+/// Whether reading the body failed because it passed the request body limit
+fn exceeds_body_limit(e: &axum::Error) -> bool {
+    let mut source = std::error::Error::source(e);
+    while let Some(error) = source {
+        if error.is::<http_body_util::LengthLimitError>() {
+            return true;
+        }
+        source = error.source();
     }
+    false
 }
 
 impl From<Infallible> for AppError {
@@ -0,0 +1,211 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info};
+
+use crate::error::AppError;
+use crate::identity;
+
+/// Placeholder in a variant's prompt template replaced by the client's own system prompt
+const SYSTEM_PLACEHOLDER: &str = "{{system}}";
+
+/// An experiment comparing variants on the traffic for one model
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExperimentConfig {
+    /// Recorded as the request's `experiment_id`
+    pub name: String,
+    /// Requested model the experiment runs on
+    pub model: String,
+    /// Only requests to this provider are eligible; any provider when unset
+    #[serde(default)]
+    pub provider: Option<String>,
+    pub variants: Vec<Variant>,
+}
+
+/// One arm of an experiment; unset fields leave the request as the client sent it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    /// Relative share of users
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// System prompt for the variant; `{{system}}` stands for the client's system prompt
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl ExperimentConfig {
+    fn applies_to(&self, provider: &str, model: &str) -> bool {
+        self.model == model && self.provider.as_deref().map_or(true, |p| p.eq_ignore_ascii_case(provider))
+    }
+
+    /// The variant a user is in; the same user always lands in the same variant
+    fn variant_for(&self, user_id: &str) -> Option<&Variant> {
+        let total: u64 = self.variants.iter().map(|v| u64::from(v.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let digest = Sha256::digest(format!("{}:{}", self.name, user_id).as_bytes());
+        let mut bucket = u64::from_be_bytes(digest[..8].try_into().ok()?) % total;
+        for variant in &self.variants {
+            let weight = u64::from(variant.weight);
+            if bucket < weight {
+                return Some(variant);
+            }
+            bucket -= weight;
+        }
+        None
+    }
+}
+
+/// Which experiment variant served a request; carried in request extensions into the request context
+#[derive(Debug, Clone)]
+pub struct ExperimentAssignment {
+    pub experiment: String,
+    pub variant: String,
+}
+
+/// Experiments from `EXPERIMENTS`, e.g.
+/// `[{"name": "concise-prompt", "model": "gpt-4o", "variants": [{"name": "control"}, {"name": "concise", "prompt_template": "Answer briefly. {{system}}"}]}]`
+static EXPERIMENTS: Lazy<Vec<ExperimentConfig>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("EXPERIMENTS") else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<ExperimentConfig>>(&raw) {
+        Ok(experiments) => {
+            info!("Loaded {} experiments", experiments.len());
+            experiments
+        }
+        Err(e) => {
+            error!("Failed to parse EXPERIMENTS: {}", e);
+            Vec::new()
+        }
+    }
+});
+
+/// The template with the client's system prompt in place of the placeholder. A prompt
+/// sent as content blocks stays blocks, so fields such as `cache_control` survive,
+/// with the template's text before and after them as text blocks of their own.
+fn fill_template(template: &str, client: Option<&Value>) -> Value {
+    let Some(Value::Array(blocks)) = client else {
+        let client = client.and_then(Value::as_str).unwrap_or_default();
+        return json!(template.replace(SYSTEM_PLACEHOLDER, client).trim());
+    };
+    let (before, after, included) = match template.split_once(SYSTEM_PLACEHOLDER) {
+        Some((before, after)) => (before, after.replace(SYSTEM_PLACEHOLDER, ""), true),
+        None => (template, String::new(), false),
+    };
+    let text_block = |text: &str| (!text.trim().is_empty()).then(|| json!({ "type": "text", "text": text.trim() }));
+    let mut filled: Vec<Value> = text_block(before).into_iter().collect();
+    if included {
+        filled.extend(blocks.iter().cloned());
+    }
+    filled.extend(text_block(after.as_str()));
+    Value::Array(filled)
+}
+
+/// Put the variant's system prompt in place of the client's, keeping the client's where the template asks for it.
+/// `native_messages` is set for Anthropic Messages requests, which carry the system prompt as a top-level field;
+/// OpenAI-format bodies keep it as a `system` or `developer` message, whichever provider they go to.
+fn apply_prompt_template(json: &mut Value, native_messages: bool, template: &str) {
+    let Some(object) = json.as_object_mut() else {
+        return;
+    };
+
+    if native_messages {
+        let prompt = fill_template(template, object.get("system"));
+        object.insert("system".to_string(), prompt);
+        return;
+    }
+    let Some(messages) = object.get_mut("messages").and_then(Value::as_array_mut) else {
+        return;
+    };
+    match messages.iter_mut().find(|m| m["role"] == "system" || m["role"] == "developer") {
+        Some(message) => {
+            let prompt = fill_template(template, message.get("content"));
+            message["content"] = prompt;
+        }
+        None => messages.insert(0, json!({ "role": "system", "content": fill_template(template, None) })),
+    }
+}
+
+/// Assigns requests for an experiment's model to a variant by hashing `x-user-id`, then
+/// applies the variant's provider, model and prompt. Runs outside the routing layer so a
+/// variant can name a routing pool, and requests without a user id are left alone.
+pub async fn experiment_middleware(request: Request<Body>, next: Next) -> Response {
+    if EXPERIMENTS.is_empty() {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    let Some(user_id) = identity::from_headers(&parts.headers).user_id else {
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::AxumError(e).into_response(),
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let provider = parts
+        .headers
+        .get("x-provider")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("openai")
+        .to_string();
+    let Some((experiment, variant)) = json
+        .get("model")
+        .and_then(Value::as_str)
+        .and_then(|model| EXPERIMENTS.iter().find(|e| e.applies_to(&provider, model)))
+        .and_then(|experiment| Some((experiment, experiment.variant_for(&user_id)?)))
+    else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    debug!("User {} is in variant {} of experiment {}", user_id, variant.name, experiment.name);
+    if let Some(target) = &variant.provider {
+        let Ok(value) = HeaderValue::from_str(target) else {
+            return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+        };
+        parts.headers.insert("x-provider", value);
+    }
+    if let Some(model) = &variant.model {
+        json["model"] = Value::String(model.clone());
+    }
+    if let Some(template) = &variant.prompt_template {
+        let native_messages = parts.uri.path().ends_with("/v1/messages");
+        apply_prompt_template(&mut json, native_messages, template);
+    }
+    parts.extensions.insert(ExperimentAssignment {
+        experiment: experiment.name.clone(),
+        variant: variant.name.clone(),
+    });
+
+    let body = serde_json::to_vec(&json).map(Bytes::from).unwrap_or(bytes);
+    parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
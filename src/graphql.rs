@@ -0,0 +1,236 @@
+use crate::telemetry::{
+    stats::{aggregate, StatsQuery, StatsSample},
+    MetricsRegistry,
+};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, Schema, SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{extract::State, routing::post, Router};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::debug;
+
+pub type UsageSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Upper bound on the number of raw requests returned by a single query
+const MAX_REQUESTS: usize = 1000;
+
+/// Filters shared by all usage queries
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct UsageFilter {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub org_id: Option<String>,
+    pub project_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub key_fingerprint: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl From<UsageFilter> for StatsQuery {
+    fn from(filter: UsageFilter) -> Self {
+        Self {
+            provider: filter.provider,
+            model: filter.model,
+            org_id: filter.org_id,
+            project_id: filter.project_id,
+            tenant_id: filter.tenant_id,
+            key_fingerprint: filter.key_fingerprint,
+            canary: None,
+            since: filter.since,
+            until: filter.until,
+        }
+    }
+}
+
+/// Dimensions aggregates can be grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum GroupBy {
+    Provider,
+    Model,
+    Org,
+    Project,
+    KeyFingerprint,
+}
+
+/// A single completed request from the in-memory usage window
+#[derive(Debug, Clone, SimpleObject)]
+pub struct UsageRequest {
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    pub org_id: Option<String>,
+    pub project_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub key_fingerprint: Option<String>,
+    pub latency_ms: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+    pub is_error: bool,
+}
+
+impl From<StatsSample> for UsageRequest {
+    fn from(sample: StatsSample) -> Self {
+        Self {
+            timestamp: sample.timestamp,
+            provider: sample.provider,
+            model: sample.model,
+            org_id: sample.org_id,
+            project_id: sample.project_id,
+            tenant_id: sample.tenant_id,
+            key_fingerprint: sample.key_fingerprint,
+            latency_ms: sample.latency_ms,
+            input_tokens: sample.input_tokens,
+            output_tokens: sample.output_tokens,
+            cost: sample.cost,
+            is_error: sample.is_error,
+        }
+    }
+}
+
+/// Aggregated usage; key fields are only set for the dimensions grouped by
+#[derive(Debug, Clone, Default, SimpleObject)]
+pub struct UsageAggregate {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub org_id: Option<String>,
+    pub project_id: Option<String>,
+    pub key_fingerprint: Option<String>,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+    pub cost: f64,
+}
+
+impl UsageAggregate {
+    fn from_samples(samples: &[StatsSample]) -> Self {
+        let stats = aggregate(samples);
+        Self {
+            requests: stats.requests,
+            errors: stats.errors,
+            error_rate: stats.error_rate,
+            latency_p50_ms: stats.latency_p50_ms,
+            latency_p95_ms: stats.latency_p95_ms,
+            input_tokens: stats.input_tokens,
+            output_tokens: stats.output_tokens,
+            total_tokens: stats.total_tokens,
+            cost: stats.cost,
+            ..Default::default()
+        }
+    }
+}
+
+type GroupKey = (
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+fn group_key(sample: &StatsSample, group_by: &[GroupBy]) -> GroupKey {
+    let pick = |dimension: GroupBy, value: Option<&String>| {
+        group_by.contains(&dimension).then(|| value.cloned()).flatten()
+    };
+    (
+        pick(GroupBy::Provider, Some(&sample.provider)),
+        pick(GroupBy::Model, Some(&sample.model)),
+        pick(GroupBy::Org, sample.org_id.as_ref()),
+        pick(GroupBy::Project, sample.project_id.as_ref()),
+        pick(GroupBy::KeyFingerprint, sample.key_fingerprint.as_ref()),
+    )
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Raw requests in the usage window, newest first
+    async fn requests(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<UsageFilter>,
+        #[graphql(default = 100)] limit: usize,
+    ) -> Vec<UsageRequest> {
+        let registry = ctx.data_unchecked::<Arc<MetricsRegistry>>();
+        let query = StatsQuery::from(filter.unwrap_or_default());
+        registry
+            .stats()
+            .samples(&query)
+            .into_iter()
+            .rev()
+            .take(limit.min(MAX_REQUESTS))
+            .map(UsageRequest::from)
+            .collect()
+    }
+
+    /// Aggregates over the usage window, grouped by the requested dimensions
+    async fn aggregates(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<UsageFilter>,
+        #[graphql(default)] group_by: Vec<GroupBy>,
+    ) -> Vec<UsageAggregate> {
+        let registry = ctx.data_unchecked::<Arc<MetricsRegistry>>();
+        let query = StatsQuery::from(filter.unwrap_or_default());
+
+        let mut groups: BTreeMap<GroupKey, Vec<StatsSample>> = BTreeMap::new();
+        for sample in registry.stats().samples(&query) {
+            groups.entry(group_key(&sample, &group_by)).or_default().push(sample);
+        }
+        debug!("GraphQL aggregates produced {} groups", groups.len());
+
+        groups
+            .into_iter()
+            .map(|((provider, model, org_id, project_id, key_fingerprint), samples)| UsageAggregate {
+                provider,
+                model,
+                org_id,
+                project_id,
+                key_fingerprint,
+                ..UsageAggregate::from_samples(&samples)
+            })
+            .collect()
+    }
+
+    /// Length of the in-memory usage window in seconds
+    async fn window_secs(&self, ctx: &Context<'_>) -> u64 {
+        ctx.data_unchecked::<Arc<MetricsRegistry>>()
+            .stats()
+            .window()
+            .as_secs()
+    }
+}
+
+/// Router exposing the usage schema at `/admin/graphql`
+pub fn router(registry: Arc<MetricsRegistry>) -> Router {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(registry)
+        .finish();
+
+    Router::new()
+        .route("/admin/graphql", post(graphql_handler))
+        .with_state(schema)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/graphql",
+    tag = "admin",
+    security(("admin_key" = [])),
+    request_body(content = Value, description = "GraphQL request: `{\"query\": \"{ aggregates(groupBy: [PROVIDER]) { provider requests cost } }\"}`"),
+    responses((status = 200, description = "Usage aggregates over the in-memory window", body = Value))
+)]
+pub(crate) async fn graphql_handler(State(schema): State<UsageSchema>, request: GraphQLRequest) -> GraphQLResponse {
+    schema.execute(request.into_inner()).await.into()
+}
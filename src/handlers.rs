@@ -1,4 +1,12 @@
-use crate::{config::AppConfig, proxy::proxy_request_to_provider};
+use crate::{
+    config::{AppConfig, TelemetryConfig},
+    context::GatewayRequestContext,
+    error::AppError,
+    experiments::ExperimentAssignment,
+    proxy::proxy_request_to_provider,
+    telemetry::ResourceInfo,
+    tenancy::{self, TenantContext, TENANTS},
+};
 use axum::{
     body::Body,
     extract::{ConnectInfo, State},
@@ -6,16 +14,65 @@ use axum::{
     response::IntoResponse,
     Json,
 };
-use serde_json::json;
+use metrics_exporter_prometheus::PrometheusHandle;
+use serde_json::{json, Value};
 use std::{net::SocketAddr, sync::Arc};
 use tracing::{debug, error, info, Instrument};
-use uuid;
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "gateway",
+    responses((status = 200, description = "The gateway is up", body = Value))
+)]
 pub async fn health_check() -> impl IntoResponse {
     info!("Health check endpoint called");
     Json(json!({ "status": "healthy", "version": env!("CARGO_PKG_VERSION") }))
 }
 
+/// Build and deployment details, for checking what a running gateway was built from
+#[utoipa::path(
+    get,
+    path = "/version",
+    tag = "gateway",
+    responses((status = 200, description = "Version, git SHA, enabled features and resource attributes", body = Value))
+)]
+pub async fn version() -> impl IntoResponse {
+    let telemetry = TelemetryConfig::default();
+    let features: Vec<&str> = [
+        (cfg!(feature = "mock-providers"), "mock-providers"),
+        (telemetry.elasticsearch_enabled, "elasticsearch"),
+        (telemetry.prometheus_enabled, "prometheus"),
+        (telemetry.noveum_enabled, "noveum"),
+        (telemetry.cloudwatch_enabled, "cloudwatch"),
+        (telemetry.anomaly_detection_enabled, "anomaly-detection"),
+        (telemetry.request_log_enabled, "request-log"),
+        (telemetry.otel_tracing_enabled, "otel-tracing"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| enabled.then_some(name))
+    .collect();
+
+    Json(json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_sha": option_env!("GIT_SHA").unwrap_or("unknown"),
+        "features": features,
+        "resource": ResourceInfo::current(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "gateway",
+    responses((status = 200, description = "Prometheus exposition format; served when ENABLE_PROMETHEUS=true", content_type = "text/plain"))
+)]
+pub async fn prometheus_metrics(State(handle): State<PrometheusHandle>) -> impl IntoResponse {
+    debug!("Prometheus metrics endpoint called");
+    handle.render()
+}
+
 pub async fn proxy_request(
     State(config): State<Arc<AppConfig>>,
     headers: HeaderMap,
@@ -31,25 +88,50 @@ pub async fn proxy_request(
         .map(|ConnectInfo(addr)| addr.to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
+    // Everything below reads the caller's identity from here rather than the headers
+    let mut context = GatewayRequestContext::new(provider, &headers);
+    if let Some(assignment) = request.extensions().get::<ExperimentAssignment>() {
+        context.experiment_id = Some(assignment.experiment.clone());
+        context.experiment_variant = Some(assignment.variant.clone());
+    }
+
+    // Resolve the tenant from the gateway key and apply its policy and credentials
+    let resolved = async {
+        let Some(tenant) = TENANTS.resolve(&headers)? else {
+            return Ok(None);
+        };
+        // Rejections are still attributed to the tenant
+        context.tenant_id = Some(tenant.id().to_string());
+        tenant.authorize_provider(provider)?;
+        let usage = tenant.admit().await?;
+        Ok::<_, AppError>(Some((tenant, usage)))
+    }
+    .instrument(tracing::info_span!("auth", provider = provider))
+    .await;
+    let tenant = match resolved {
+        Ok(tenant) => tenant,
+        Err(e) => {
+            debug!("Rejected request for provider {}: {}", provider, e);
+            let mut response = e.into_response();
+            response.extensions_mut().insert(context);
+            return response;
+        }
+    };
+    let soft_limits = tenant
+        .as_ref()
+        .map(|(tenant, usage)| tenant.soft_limit_warnings(usage))
+        .unwrap_or_default();
+    if let Some((tenant, _)) = tenant {
+        tenant.apply_provider_headers(provider, request.headers_mut());
+        request.extensions_mut().insert(TenantContext(tenant));
+    }
+    request.extensions_mut().insert(context.clone());
+
     let path = request.uri().path();
     let method = request.method().as_str();
-    
-    // Extract potentially useful headers for logging
-    let organization = headers
-        .get("x-organisation-id")
-        .or_else(|| headers.get("x-organization-id"))  // Try both British and American spelling
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("none");
-        
-    let project = headers
-        .get("x-project-id")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("none");
-
-    let user = headers
-        .get("x-user-id")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("none");
+    let organization = context.org_id.as_deref().unwrap_or("none");
+    let project = context.project_id.as_deref().unwrap_or("none");
+    let user = context.user_id.as_deref().unwrap_or("none");
 
     // Log request at info level for operational visibility
     info!(
@@ -60,6 +142,7 @@ pub async fn proxy_request(
         org_id = %organization,
         project_id = %project,
         user_id = %user,
+        request_id = %context.request_id,
         "Received API request"
     );
 
@@ -96,14 +179,15 @@ pub async fn proxy_request(
         let elapsed = start_time.elapsed();
         
         match result {
-            Ok(response) => {
+            Ok(mut response) => {
+                tenancy::add_soft_limit_headers(response.headers_mut(), &soft_limits);
                 let status = response.status().as_u16();
                 
                 // Extract x-request-id header from response for tracking
                 let request_id = response.headers()
                     .get("x-request-id")
                     .and_then(|h| h.to_str().ok())
-                    .unwrap_or("none");
+                    .unwrap_or(&context.request_id);
                 
                 // Also try to get provider-specific request ID
                 let provider_request_id = match provider_clone.as_str() {
@@ -135,19 +219,19 @@ pub async fn proxy_request(
                 response
             },
             Err(e) => {
-                // For errors, generate a unique ID to help with debugging
-                let error_id = format!("err-{}", uuid::Uuid::new_v4().to_string()[..8].to_string());
-                
                 error!(
                     provider = %provider_clone,
                     path = %path_clone,
                     method = %method_clone,
                     error = %e,
                     latency_ms = %elapsed.as_millis(),
-                    request_id = %error_id,
+                    request_id = %context.request_id,
                     "Request failed"
                 );
-                e.into_response()
+                let mut response = e.into_response();
+                tenancy::add_soft_limit_headers(response.headers_mut(), &soft_limits);
+                response.extensions_mut().insert(context);
+                response
             }
         }
     }
@@ -0,0 +1,86 @@
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tracing::{error, info};
+
+/// Caller identity from the tracking headers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identity {
+    pub project_id: Option<String>,
+    pub org_id: Option<String>,
+    pub user_id: Option<String>,
+    pub experiment_id: Option<String>,
+}
+
+/// Header names read for each identity field, in order of preference
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdentityHeaders {
+    pub project_id: Vec<String>,
+    pub org_id: Vec<String>,
+    pub user_id: Vec<String>,
+    pub experiment_id: Vec<String>,
+}
+
+impl IdentityHeaders {
+    fn standard() -> Self {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            project_id: names(&["x-project-id"]),
+            // Both American and British spellings are accepted
+            org_id: names(&["x-organization-id", "x-organisation-id"]),
+            user_id: names(&["x-user-id"]),
+            experiment_id: names(&["x-experiment-id"]),
+        }
+    }
+
+    fn extend(&mut self, other: IdentityHeaders) {
+        let lower = |names: Vec<String>| names.into_iter().map(|name| name.trim().to_lowercase());
+        self.project_id.extend(lower(other.project_id));
+        self.org_id.extend(lower(other.org_id));
+        self.user_id.extend(lower(other.user_id));
+        self.experiment_id.extend(lower(other.experiment_id));
+    }
+}
+
+/// Standard header names plus the deployment's alternatives from `IDENTITY_HEADERS`,
+/// e.g. `{"project_id": ["x-team-id"], "user_id": ["x-end-user-id"]}`
+static HEADERS: Lazy<IdentityHeaders> = Lazy::new(|| {
+    let mut headers = IdentityHeaders::standard();
+    let Ok(raw) = std::env::var("IDENTITY_HEADERS") else {
+        return headers;
+    };
+    match serde_json::from_str::<IdentityHeaders>(&raw) {
+        Ok(alternatives) => {
+            info!("Loaded alternative identity headers: {:?}", alternatives);
+            headers.extend(alternatives);
+        }
+        Err(e) => error!("Failed to parse IDENTITY_HEADERS: {}", e),
+    }
+    headers
+});
+
+/// Read the caller's identity, taking the first configured header present for each field
+pub fn from_headers(headers: &HeaderMap) -> Identity {
+    let first = |names: &[String]| {
+        names
+            .iter()
+            .find_map(|name| headers.get(name.as_str()).and_then(|h| h.to_str().ok()))
+            .map(String::from)
+    };
+    Identity {
+        project_id: first(&HEADERS.project_id),
+        org_id: first(&HEADERS.org_id),
+        user_id: first(&HEADERS.user_id),
+        experiment_id: first(&HEADERS.experiment_id),
+    }
+}
+
+/// Every header name an identity field can come from
+pub fn header_names() -> impl Iterator<Item = &'static str> {
+    let headers: &'static IdentityHeaders = &HEADERS;
+    [&headers.project_id, &headers.org_id, &headers.user_id, &headers.experiment_id]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+}
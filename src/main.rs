@@ -1,33 +1,60 @@
 use axum::{
-    middleware::from_fn_with_state,
+    middleware::{from_fn, from_fn_with_state},
     routing::{any, get},
     Router,
     extract::connect_info::ConnectInfo,
     Extension,
 };
 use hyper::{Body, Request};
+use clap::Parser;
 use std::{
     net::SocketAddr,
+    process::ExitCode,
     sync::Arc,
     time::Duration,
 };
 use tokio::signal;
 use tower_http::{
+    compression::{
+        predicate::{NotForContentType, Predicate, SizeAbove},
+        CompressionLayer,
+    },
     cors::{Any, CorsLayer},
+    decompression::RequestDecompressionLayer,
+    limit::RequestBodyLimitLayer,
 };
 use tracing::{debug, error, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use colored::*;
 
+mod admin;
+mod breaker;
+mod chaos;
+mod cli;
+mod cluster;
 mod config;
 mod context;
+mod dashboard;
+mod drain;
 mod error;
+mod experiments;
+mod graphql;
 mod handlers;
+mod identity;
+mod maintenance;
+#[cfg(feature = "mock-providers")]
+mod mock;
+mod openapi;
 mod providers;
 mod proxy;
+mod routing;
+mod server;
+mod shared_state;
 mod telemetry;
+mod tenancy;
 
 use crate::{
+    cli::{Cli, Command},
     config::{AppConfig, TelemetryConfig},
     context::RequestContext,
     handlers::{health_check, proxy_request},
@@ -36,11 +63,64 @@ use crate::{
         metrics_middleware, 
         ConsolePlugin,
         plugins::elasticsearch::ElasticsearchPlugin,
+        plugins::prometheus::PrometheusPlugin,
+        plugins::noveum::{NoveumConfig, NoveumPlugin},
+        plugins::sqlite::{RequestLogConfig, SqlitePlugin},
+        anomaly::{AnomalyConfig, AnomalyDetector},
+        schema,
+        ResourceInfo,
     },
 };
 
-#[tokio::main]
-async fn main() {
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    dotenv::dotenv().ok();
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            // The runtime is sized from the config, so it's built here rather than by #[tokio::main].
+            // OTLP export needs the runtime before the global subscriber can be installed, so
+            // warnings from loading the config go to a console-only subscriber meanwhile
+            let startup_logs = tracing_subscriber::registry()
+                .with(log_filter())
+                .with(tracing_subscriber::fmt::layer().compact());
+            let (config, runtime) = tracing::subscriber::with_default(startup_logs, || {
+                let config = Arc::new(AppConfig::new());
+                let runtime = config.build_runtime().expect("Failed to build the tokio runtime");
+                (config, runtime)
+            });
+            runtime.block_on(serve(config));
+            ExitCode::SUCCESS
+        }
+        Command::ValidateConfig => cli::validate_config(),
+        Command::CheckProviders => command_runtime().block_on(cli::check_providers()),
+        Command::Replay {
+            file,
+            gateway_url,
+            headers,
+        } => command_runtime().block_on(cli::replay(file, gateway_url, headers)),
+        Command::ExportPricing {
+            provider,
+            models,
+            format,
+        } => command_runtime().block_on(cli::export_pricing(provider, models, format)),
+    }
+}
+
+/// Log filter from `RUST_LOG`, defaulting to `info`
+fn log_filter() -> tracing_subscriber::EnvFilter {
+    tracing_subscriber::EnvFilter::new(std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()))
+}
+
+/// Default runtime for the one-shot CLI commands
+fn command_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build the tokio runtime")
+}
+
+async fn serve(config: Arc<AppConfig>) {
     // Display startup animation
     let frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
     print!("\n    Starting Noveum AI Gateway ");
@@ -70,28 +150,25 @@ async fn main() {
 
     // Initialize tracing
     info!("Initializing tracing system");
+    let otel_layer = telemetry::otel::layer();
+    let exporting_traces = otel_layer.is_some();
     tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
-        ))
+        .with(log_filter())
         .with(tracing_subscriber::fmt::layer().compact())
+        .with(otel_layer)
         .init();
+    if exporting_traces {
+        info!("Exporting request traces over OTLP");
+    }
 
-    // Load configuration
-    info!("Loading application configuration");
-    let config = Arc::new(AppConfig::new());
     debug!(
         "Configuration loaded: port={}, host={}, worker_threads={}",
         config.port, config.host, config.worker_threads
     );
-
-    // Optimize tokio runtime
     info!(
-        "Configuring tokio runtime with {} worker threads",
-        config.worker_threads
+        "Tokio runtime running {} worker threads (up to {} blocking threads, {} byte stacks)",
+        config.worker_threads, config.max_blocking_threads, config.thread_stack_size
     );
-    std::env::set_var("TOKIO_WORKER_THREADS", config.worker_threads.to_string());
-    std::env::set_var("TOKIO_THREAD_STACK_SIZE", (2 * 1024 * 1024).to_string());
 
     // Setup CORS
     debug!("Setting up CORS layer with 1-hour max age");
@@ -101,12 +178,19 @@ async fn main() {
         .allow_headers(Any)
         .max_age(Duration::from_secs(3600));
 
+    #[cfg(feature = "mock-providers")]
+    mock::start_if_enabled().await;
+
     let telemetry_config = TelemetryConfig::default();
     debug!(
         "Telemetry configuration: debug_mode={}",
         telemetry_config.debug_mode
     );
     let metrics_registry = Arc::new(MetricsRegistry::new(telemetry_config.debug_mode));
+    proxy::canary::spawn_evaluator(metrics_registry.stats());
+    // Leader-only jobs below wait for the election when replicas are clustered
+    cluster::spawn();
+    providers::catalog::spawn_refresh();
 
     // Register exporters based on configuration
     if telemetry_config.debug_mode {
@@ -135,6 +219,16 @@ async fn main() {
             elasticsearch_index
         ) {
             Ok(plugin) => {
+                let schema_version = schema::version_from_env("ELASTICSEARCH_SCHEMA_VERSION");
+                let plugin = plugin.with_schema_version(schema_version);
+                let per_environment = std::env::var("ELASTICSEARCH_INDEX_PER_ENVIRONMENT")
+                    .map(|v| v.parse().unwrap_or(false))
+                    .unwrap_or(false);
+                let plugin = if per_environment {
+                    plugin.with_environment_suffix(&ResourceInfo::current().deployment_environment)
+                } else {
+                    plugin
+                };
                 metrics_registry.register_exporter(Box::new(plugin)).await;
                 info!("Elasticsearch exporter registered successfully");
             },
@@ -144,19 +238,129 @@ async fn main() {
         }
     }
 
+    let mut prometheus_handle = None;
+    if telemetry_config.prometheus_enabled {
+        debug!("Registering Prometheus exporter");
+        match PrometheusPlugin::new() {
+            Ok(plugin) => {
+                prometheus_handle = Some(plugin.handle());
+                metrics_registry.register_exporter(Box::new(plugin)).await;
+                info!("Prometheus exporter registered successfully");
+            },
+            Err(e) => {
+                error!("Failed to initialize Prometheus exporter: {}", e);
+            }
+        }
+    }
+
+    if telemetry_config.noveum_enabled {
+        debug!("Registering Noveum exporter");
+        match NoveumConfig::from_env() {
+            Some(config) => match NoveumPlugin::new(config) {
+                Ok(plugin) => {
+                    metrics_registry.register_exporter(Box::new(plugin)).await;
+                    info!("Noveum exporter registered successfully");
+                },
+                Err(e) => {
+                    error!("Failed to initialize Noveum exporter: {}", e);
+                }
+            },
+            None => error!("ENABLE_NOVEUM is set but NOVEUM_API_KEY is missing; Noveum exporter disabled"),
+        }
+    }
+
+    if telemetry_config.request_log_enabled {
+        debug!("Registering SQLite request log");
+        match SqlitePlugin::new(RequestLogConfig::from_env()) {
+            Ok(plugin) => {
+                metrics_registry.register_exporter(Box::new(plugin)).await;
+                info!("SQLite request log registered successfully");
+            },
+            Err(e) => {
+                error!("Failed to open the SQLite request log: {}", e);
+            }
+        }
+    }
+
+    if telemetry_config.anomaly_detection_enabled {
+        debug!("Registering anomaly detector");
+        let detector = AnomalyDetector::new(AnomalyConfig::default());
+        detector.spawn();
+        metrics_registry.register_exporter(Box::new(detector)).await;
+    }
+
     debug!("Registering middleware for metrics collection and telemetry");
     
     // Register request handlers and middleware
     info!("Registering request handlers and API routes");
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/health", get(handlers::health_check))
         .route("/v1/*path", any(handlers::proxy_request))
+        // Stability AI's image API is versioned separately
+        .route("/v2beta/*path", any(handlers::proxy_request))
+        // Inside the metrics layer so injected faults show up in telemetry
+        .layer(from_fn(chaos::chaos_middleware))
         .layer(from_fn_with_state(
             metrics_registry.clone(),
             metrics_middleware,
         ))
+        // Outside the metrics layer so its own rejections aren't counted as provider failures,
+        // inside maintenance and routing so it sees the provider actually called
+        .layer(from_fn(breaker::breaker_middleware))
+        // Between metrics and routing so pool targets under maintenance are rerouted too
+        .layer(from_fn(maintenance::maintenance_middleware))
+        // Outside the metrics layer so telemetry sees the provider a pool routed to
+        .layer(from_fn(routing::routing_middleware))
+        // Outside routing so an experiment variant can name a routing pool
+        .layer(from_fn(experiments::experiment_middleware))
+        // Outermost, so nothing new starts once shutdown begins; health checks fail too
+        .layer(from_fn(drain::drain_middleware))
         .with_state(config.clone())
-        .layer(cors);
+        .merge(admin::router(metrics_registry.clone()))
+        .merge(dashboard::router(metrics_registry.clone()));
+
+    // Build info and the API spec sit outside the metrics middleware; they aren't proxied traffic
+    app = app
+        .route("/version", get(handlers::version))
+        .route("/openapi.json", get(openapi::openapi_json));
+
+    // The scrape endpoint sits outside the metrics middleware so scrapes are not recorded
+    if let Some(handle) = prometheus_handle {
+        app = app.merge(
+            Router::new()
+                .route("/metrics", get(handlers::prometheus_metrics))
+                .with_state(handle),
+        );
+    }
+
+    // Compress larger buffered responses per Accept-Encoding; SSE must stay uncompressed
+    // so each event reaches the client as soon as it is written
+    if config.compression_enabled {
+        debug!("Enabling response compression above {} bytes", config.compression_min_size);
+        let predicate = SizeAbove::new(config.compression_min_size)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE);
+        app = app.layer(CompressionLayer::new().compress_when(predicate));
+    }
+
+    // Inflate gzip/zstd request bodies before telemetry and provider translation see them;
+    // other encodings are rejected with 415. The limit sits inside, so it counts inflated bytes
+    if config.request_decompression_enabled {
+        debug!(
+            "Enabling gzip/zstd request decompression up to {} bytes",
+            config.max_decompressed_body_bytes
+        );
+        app = app
+            .layer(RequestBodyLimitLayer::new(config.max_decompressed_body_bytes))
+            .layer(RequestDecompressionLayer::new());
+    }
+
+    // OPTIONS and HEAD on proxy routes are answered here and never reach a provider;
+    // this wraps CORS so preflights list only the route's allowed methods
+    let app = app
+        .layer(cors)
+        .layer(from_fn(providers::routes::method_middleware));
 
     // Start server with optimized TCP settings
     let addr = std::net::SocketAddr::from(([0, 0, 0, 0], config.port));
@@ -188,16 +392,12 @@ async fn main() {
     println!();
 
     debug!("Starting server with graceful shutdown");
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal())
-    .await
-    .unwrap_or_else(|e| {
-        error!("Server error: {}", e);
-        std::process::exit(1);
-    });
+    let shutdown = async {
+        shutdown_signal().await;
+        drain::drain().await;
+    };
+    server::serve(listener, app, &config, shutdown).await;
+    telemetry::otel::shutdown();
 }
 
 async fn shutdown_signal() {
@@ -0,0 +1,332 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, error, info};
+
+use crate::error::AppError;
+use crate::providers::bedrock::DEFAULT_REGION;
+use crate::proxy::header_policy;
+
+/// Longest a scheduled window can run; each request scans back this many minutes for a start
+const MAX_DURATION_MINUTES: u32 = 7 * 24 * 60;
+
+const MONTHS: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Start times of a recurring window in five-field cron syntax (minute, hour,
+/// day of month, month, day of week), evaluated in UTC, e.g. `0 2 * * sun`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = String;
+
+    fn try_from(expr: String) -> Result<Self, Self::Error> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(format!(
+                "schedule `{}` needs five fields: minute hour day-of-month month day-of-week",
+                expr
+            ));
+        };
+        let weekdays = field(weekday, 0, 7, WEEKDAYS)?;
+        Ok(Schedule {
+            minutes: field(minute, 0, 59, &[])?,
+            hours: field(hour, 0, 23, &[])?,
+            days: field(day, 1, 31, &[])?,
+            months: field(month, 1, 12, MONTHS)?,
+            // Sunday is both 0 and 7
+            weekdays: (weekdays | weekdays >> 7) & 0x7f,
+            days_restricted: *day != "*",
+            weekdays_restricted: *weekday != "*",
+        })
+    }
+}
+
+/// Bitmask of the values a cron field matches: `*`, numbers or names, `a-b` ranges,
+/// `/n` steps and comma-separated lists
+fn field(spec: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in `{}`", part))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start, min, max, names)?, value(end, min, max, names)?)
+        } else {
+            let start = value(range, min, max, names)?;
+            // `5/15` runs from 5 to the end of the field
+            (start, if step > 1 { max } else { start })
+        };
+        if start > end {
+            return Err(format!("empty range `{}`", part));
+        }
+        for v in (start..=end).step_by(step) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+fn value(token: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let lower = token.to_ascii_lowercase();
+    let value = match names.iter().position(|name| *name == lower) {
+        Some(index) => index as u32 + min,
+        None => token.parse().map_err(|_| format!("invalid value `{}`", token))?,
+    };
+    if value < min || value > max {
+        return Err(format!("`{}` is outside {}-{}", token, min, max));
+    }
+    Ok(value)
+}
+
+impl Schedule {
+    fn matches(&self, time: DateTime<Utc>) -> bool {
+        let has = |mask: u64, value: u32| mask & (1 << value) != 0;
+        let day = has(self.days, time.day());
+        let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+        // As in cron, a restricted day of month and day of week are alternatives
+        let date = if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        };
+        date && has(self.minutes, time.minute()) && has(self.hours, time.hour()) && has(self.months, time.month())
+    }
+}
+
+/// Where a provider's traffic goes during its maintenance
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fallback {
+    pub provider: String,
+    /// Model for the fallback provider; the request's own model when unset
+    #[serde(default)]
+    pub model: Option<String>,
+    /// AWS region (`x-aws-region`) for the fallback, e.g. another Bedrock region
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Environment variable holding the fallback provider's API key, sent as a bearer token.
+    /// Required when the fallback is a different provider; the client's key is never sent to another vendor.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+/// A period during which a provider doesn't get traffic: recurring (`schedule`
+/// plus `duration_minutes`) or one-off (`start` to `end`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaintenanceWindow {
+    pub provider: String,
+    /// Only requests to this AWS region (`x-aws-region`, else `AWS_REGION`), for regional Bedrock maintenance
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    #[serde(default = "default_duration")]
+    pub duration_minutes: u32,
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
+    /// Requests are rejected with 503 during the window when unset
+    #[serde(default)]
+    pub fallback: Option<Fallback>,
+}
+
+fn default_duration() -> u32 {
+    60
+}
+
+impl MaintenanceWindow {
+    /// Whether the window can be used as configured
+    pub fn validate(&self) -> Result<(), String> {
+        if self.schedule.is_none() && (self.start.is_none() || self.end.is_none()) {
+            return Err(format!("window for {} needs a schedule or start and end", self.provider));
+        }
+        if let Some(fallback) = &self.fallback {
+            if self.is_cross_provider(fallback) && fallback.api_key_env.is_none() {
+                return Err(format!(
+                    "fallback from {} to {} needs api_key_env",
+                    self.provider, fallback.provider
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_cross_provider(&self, fallback: &Fallback) -> bool {
+        !self.provider.eq_ignore_ascii_case(&fallback.provider)
+    }
+
+    /// When the window ends, if `now` falls inside it
+    fn active_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if start <= now && now < end {
+                return Some(end);
+            }
+        }
+        let schedule = self.schedule.as_ref()?;
+        let minute = now.duration_trunc(Duration::minutes(1)).ok()?;
+        let duration = self.duration_minutes.min(MAX_DURATION_MINUTES);
+        (0..duration)
+            .map(|ago| minute - Duration::minutes(ago.into()))
+            .find(|start| schedule.matches(*start))
+            .map(|start| start + Duration::minutes(duration.into()))
+    }
+
+    fn covers(&self, provider: &str, region: Option<&str>) -> bool {
+        self.provider.eq_ignore_ascii_case(provider)
+            && self
+                .region
+                .as_deref()
+                .map_or(true, |window| region.is_some_and(|region| window.eq_ignore_ascii_case(region)))
+    }
+}
+
+/// Windows from `MAINTENANCE_WINDOWS`, e.g.
+/// `[{"provider": "bedrock", "region": "us-east-1", "schedule": "0 2 * * sun", "duration_minutes": 120, "fallback": {"provider": "bedrock", "region": "us-west-2"}}]`
+static WINDOWS: Lazy<Vec<MaintenanceWindow>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("MAINTENANCE_WINDOWS") else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<MaintenanceWindow>>(&raw) {
+        Ok(windows) => {
+            let windows: Vec<_> = windows
+                .into_iter()
+                .filter(|window| match window.validate() {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error!("Ignoring maintenance window: {}", e);
+                        false
+                    }
+                })
+                .collect();
+            info!("Loaded {} maintenance windows", windows.len());
+            windows
+        }
+        Err(e) => {
+            error!("Failed to parse MAINTENANCE_WINDOWS: {}", e);
+            Vec::new()
+        }
+    }
+});
+
+/// Reroutes requests for a provider in a maintenance window to the window's
+/// fallback by setting `x-provider`, the body's model, region and key,
+/// or rejects them with 503 and `Retry-After` when it has none. Runs after
+/// routing, so pool targets are covered, and outside the metrics layer, so
+/// telemetry records the provider that served the request.
+pub async fn maintenance_middleware(request: Request<Body>, next: Next) -> Response {
+    if WINDOWS.is_empty() {
+        return next.run(request).await;
+    }
+    let Some(provider) = request
+        .headers()
+        .get("x-provider")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string)
+    else {
+        return next.run(request).await;
+    };
+    let region = request
+        .headers()
+        .get("x-aws-region")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| provider.eq_ignore_ascii_case("bedrock").then(|| {
+            std::env::var("AWS_REGION").unwrap_or_else(|_| DEFAULT_REGION.to_string())
+        }));
+
+    let now = Utc::now();
+    let Some((window, until)) = WINDOWS
+        .iter()
+        .filter(|window| window.covers(&provider, region.as_deref()))
+        .find_map(|window| window.active_until(now).map(|until| (window, until)))
+    else {
+        return next.run(request).await;
+    };
+
+    let Some(fallback) = &window.fallback else {
+        debug!("Rejecting request to {} during its maintenance window", provider);
+        let mut response = AppError::ProviderUnavailable(format!(
+            "{} is under maintenance until {}",
+            provider,
+            until.to_rfc3339()
+        ))
+        .into_response();
+        let retry_after = (until - now).num_seconds().max(1);
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    };
+    debug!(
+        "Rerouting request from {} to {}{} during maintenance",
+        provider,
+        fallback.provider,
+        fallback.region.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default()
+    );
+
+    let (mut parts, body) = request.into_parts();
+    let Ok(fallback_provider) = HeaderValue::from_str(&fallback.provider) else {
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+    // The client's credentials are for the provider it addressed
+    if window.is_cross_provider(fallback) {
+        header_policy::strip_credentials(&mut parts.headers);
+    }
+    parts.headers.insert("x-provider", fallback_provider);
+    if let Some(region) = fallback.region.as_deref().and_then(|r| HeaderValue::from_str(r).ok()) {
+        parts.headers.insert("x-aws-region", region);
+    }
+    if let Some(key) = fallback.api_key_env.as_ref().and_then(|name| std::env::var(name).ok()) {
+        match HeaderValue::from_str(&format!("Bearer {}", key)) {
+            Ok(value) => {
+                parts.headers.insert(header::AUTHORIZATION, value);
+            }
+            Err(e) => return AppError::InvalidHeaderValue(e).into_response(),
+        }
+    }
+
+    let Some(model) = &fallback.model else {
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::AxumError(e).into_response(),
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    if json.get("model").is_none() {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    }
+    json["model"] = Value::String(model.clone());
+
+    let body = serde_json::to_vec(&json).map(Bytes::from).unwrap_or(bytes);
+    parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
@@ -0,0 +1,558 @@
+//! Built-in mock upstream for contract tests (feature `mock-providers`).
+//!
+//! With `MOCK_PROVIDERS=true` the gateway starts a local server that answers
+//! in the wire formats of OpenAI (and the OpenAI-compatible providers),
+//! Anthropic and Bedrock, and sends every upstream request there instead of
+//! the real API. Scenarios are selected through the model name:
+//!
+//! - `*error-400*`, `*error-429*`, `*error-500*`: provider-style error with that status
+//! - `*stream-error*`: streams a couple of deltas, then a provider error event
+//! - `*stream-drop*`: streams a couple of deltas, then drops the connection (OpenAI only)
+//! - `*tool-use*`: non-streaming reply that calls the `get_weather` tool
+//! - `*echo-system*`: non-streaming Anthropic reply quoting the request's `system` text
+//!
+//! The Anthropic mock also rejects message lists the Messages API would,
+//! such as non-alternating roles or unanswered `tool_use` blocks.
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use futures::stream::{self, StreamExt};
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use std::{convert::Infallible, time::Duration};
+use tracing::{error, info, warn};
+
+use crate::providers::event_stream::encode_message;
+
+/// Text every mock completion replies with
+pub const MOCK_REPLY: &str = "Hello from the mock provider.";
+
+/// Reasoning returned alongside the reply for `reasoning` models
+pub const MOCK_REASONING: &str = "The user wants a short greeting, so reply briefly.";
+
+/// Deltas sent before a `stream-error` or `stream-drop` scenario fails
+const DELTAS_BEFORE_ERROR: usize = 2;
+
+static MOCK_URL: OnceCell<String> = OnceCell::new();
+
+/// Start the mock server if `MOCK_PROVIDERS` is set
+pub async fn start_if_enabled() {
+    let enabled = std::env::var("MOCK_PROVIDERS")
+        .map(|v| v.parse().unwrap_or(false))
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+
+    let port: u16 = std::env::var("MOCK_PROVIDERS_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let listener = match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Failed to bind mock provider server: {}", e);
+            return;
+        }
+    };
+    let addr = match listener.local_addr() {
+        Ok(addr) => addr,
+        Err(e) => {
+            error!("Failed to read mock provider address: {}", e);
+            return;
+        }
+    };
+
+    let _ = MOCK_URL.set(format!("http://{}", addr));
+    warn!("MOCK_PROVIDERS is enabled; all provider traffic goes to the mock server at {}", addr);
+
+    tokio::spawn(async move {
+        let app = Router::new().fallback(handle);
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("Mock provider server stopped: {}", e);
+        }
+    });
+}
+
+/// Base URL replacing the provider's real API while the mock server runs
+pub fn upstream_base_url(provider: &str) -> Option<String> {
+    MOCK_URL.get().map(|url| format!("{}/{}", url, provider))
+}
+
+async fn handle(uri: Uri, body: Bytes) -> Response {
+    let path = uri.path();
+    let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    let stream = request.get("stream").and_then(Value::as_bool).unwrap_or(false);
+    let input_tokens = count_words(request.get("messages").unwrap_or(&Value::Null));
+    info!("Mock provider received {}", path);
+
+    if path.contains("/converse") {
+        // Bedrock carries the model in the path: /model/{model}/converse[-stream]
+        let model = path
+            .split('/')
+            .skip_while(|segment| *segment != "model")
+            .nth(1)
+            .unwrap_or("mock-model")
+            .to_string();
+        let scenario = Scenario::from_model(&model);
+        return if path.ends_with("/converse-stream") {
+            bedrock_stream(scenario, input_tokens)
+        } else {
+            bedrock_response(scenario, input_tokens)
+        };
+    }
+
+    let model = request
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or("mock-model")
+        .to_string();
+    let scenario = Scenario::from_model(&model);
+
+    if path.ends_with("/messages") {
+        if let Some(problem) = anthropic_request_problem(&request) {
+            warn!("Mock Anthropic rejected request: {}", problem);
+            return anthropic_invalid_request(&problem);
+        }
+        if stream {
+            anthropic_stream(&model, scenario, input_tokens)
+        } else {
+            anthropic_response(&model, scenario, &request, input_tokens)
+        }
+    } else if path.ends_with("/chat/completions") {
+        let include_usage = request
+            .pointer("/stream_options/include_usage")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if stream {
+            openai_stream(&model, scenario, input_tokens, include_usage)
+        } else {
+            openai_response(&model, scenario, input_tokens)
+        }
+    } else {
+        openai_error(StatusCode::NOT_FOUND, &format!("Mock provider has no route for {}", path))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scenario {
+    Success,
+    Error(StatusCode),
+    StreamError,
+    StreamDrop,
+    ToolUse,
+    Reasoning,
+    EchoSystem,
+}
+
+impl Scenario {
+    fn from_model(model: &str) -> Self {
+        if model.contains("stream-error") {
+            Self::StreamError
+        } else if model.contains("stream-drop") {
+            Self::StreamDrop
+        } else if model.contains("tool-use") {
+            Self::ToolUse
+        } else if model.contains("reasoning") {
+            Self::Reasoning
+        } else if model.contains("echo-system") {
+            Self::EchoSystem
+        } else if model.contains("error-400") {
+            Self::Error(StatusCode::BAD_REQUEST)
+        } else if model.contains("error-429") {
+            Self::Error(StatusCode::TOO_MANY_REQUESTS)
+        } else if model.contains("error-500") {
+            Self::Error(StatusCode::INTERNAL_SERVER_ERROR)
+        } else {
+            Self::Success
+        }
+    }
+}
+
+fn count_words(value: &Value) -> u64 {
+    match value {
+        Value::String(s) => s.split_whitespace().count() as u64,
+        Value::Array(items) => items.iter().map(count_words).sum(),
+        Value::Object(map) => map.values().map(count_words).sum(),
+        _ => 0,
+    }
+}
+
+fn output_tokens() -> u64 {
+    MOCK_REPLY.split_whitespace().count() as u64
+}
+
+/// The reply split into streaming deltas, keeping the separating spaces
+fn deltas() -> Vec<String> {
+    let words: Vec<&str> = MOCK_REPLY.split(' ').collect();
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| if i == 0 { word.to_string() } else { format!(" {}", word) })
+        .collect()
+}
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+/// Serve pre-built chunks with a short pause between them, like a real stream
+fn streaming_response(content_type: &'static str, chunks: Vec<Bytes>) -> Response {
+    let body = stream::iter(chunks).then(|chunk| async move {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        Ok::<_, Infallible>(chunk)
+    });
+    let mut response = Response::new(Body::from_stream(body));
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+/// Serve chunks like `streaming_response`, then fail the body so the connection is cut
+fn dropped_stream(content_type: &'static str, chunks: Vec<Bytes>) -> Response {
+    let body = stream::iter(chunks.into_iter().map(Ok))
+        .chain(stream::once(async {
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "mock connection dropped"))
+        }))
+        .then(|chunk| async move {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            chunk
+        });
+    let mut response = Response::new(Body::from_stream(body));
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+fn json_response(body: Value) -> Response {
+    (StatusCode::OK, axum::Json(body)).into_response()
+}
+
+// OpenAI and OpenAI-compatible providers
+
+fn openai_error(status: StatusCode, message: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": if status == StatusCode::TOO_MANY_REQUESTS { "rate_limit_exceeded" } else { "server_error" },
+            "param": null,
+            "code": status.as_u16().to_string()
+        }
+    });
+    (status, axum::Json(body)).into_response()
+}
+
+/// The assistant message, carrying DeepSeek-style reasoning for the `reasoning` scenario
+fn openai_message(scenario: Scenario) -> Value {
+    let mut message = json!({ "role": "assistant", "content": MOCK_REPLY });
+    if scenario == Scenario::Reasoning {
+        message["reasoning_content"] = json!(MOCK_REASONING);
+    }
+    message
+}
+
+fn openai_response(model: &str, scenario: Scenario, input_tokens: u64) -> Response {
+    if let Scenario::Error(status) = scenario {
+        return openai_error(status, "Mock provider error");
+    }
+    json_response(json!({
+        "id": format!("chatcmpl-mock-{}", uuid::Uuid::new_v4().simple()),
+        "object": "chat.completion",
+        "created": unix_now(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": openai_message(scenario),
+            "logprobs": null,
+            "finish_reason": "stop"
+        }],
+        "usage": {
+            "prompt_tokens": input_tokens,
+            "completion_tokens": output_tokens(),
+            "total_tokens": input_tokens + output_tokens()
+        }
+    }))
+}
+
+fn openai_stream(model: &str, scenario: Scenario, input_tokens: u64, include_usage: bool) -> Response {
+    if let Scenario::Error(status) = scenario {
+        return openai_error(status, "Mock provider error");
+    }
+
+    let id = format!("chatcmpl-mock-{}", uuid::Uuid::new_v4().simple());
+    let created = unix_now();
+    let chunk = |delta: Value, finish_reason: Value| {
+        let value = json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [{ "index": 0, "delta": delta, "logprobs": null, "finish_reason": finish_reason }]
+        });
+        Bytes::from(format!("data: {}\n\n", value))
+    };
+
+    let mut chunks = vec![chunk(json!({ "role": "assistant", "content": "" }), Value::Null)];
+    if scenario == Scenario::Reasoning {
+        chunks.push(chunk(json!({ "reasoning_content": MOCK_REASONING }), Value::Null));
+    }
+    for (i, delta) in deltas().into_iter().enumerate() {
+        if scenario == Scenario::StreamError && i == DELTAS_BEFORE_ERROR {
+            let error = json!({ "error": { "message": "Mock stream interrupted", "type": "server_error" } });
+            chunks.push(Bytes::from(format!("data: {}\n\n", error)));
+            return streaming_response("text/event-stream", chunks);
+        }
+        if scenario == Scenario::StreamDrop && i == DELTAS_BEFORE_ERROR {
+            return dropped_stream("text/event-stream", chunks);
+        }
+        chunks.push(chunk(json!({ "content": delta }), Value::Null));
+    }
+    chunks.push(chunk(json!({}), json!("stop")));
+
+    if include_usage {
+        let usage = json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [],
+            "usage": {
+                "prompt_tokens": input_tokens,
+                "completion_tokens": output_tokens(),
+                "total_tokens": input_tokens + output_tokens()
+            }
+        });
+        chunks.push(Bytes::from(format!("data: {}\n\n", usage)));
+    }
+    chunks.push(Bytes::from("data: [DONE]\n\n"));
+    streaming_response("text/event-stream", chunks)
+}
+
+// Anthropic
+
+fn anthropic_error(status: StatusCode) -> Response {
+    let error_type = match status {
+        StatusCode::TOO_MANY_REQUESTS => "rate_limit_error",
+        StatusCode::BAD_REQUEST => "invalid_request_error",
+        _ => "api_error",
+    };
+    let body = json!({
+        "type": "error",
+        "error": { "type": error_type, "message": "Mock provider error" }
+    });
+    (status, axum::Json(body)).into_response()
+}
+
+fn anthropic_invalid_request(message: &str) -> Response {
+    let body = json!({
+        "type": "error",
+        "error": { "type": "invalid_request_error", "message": message }
+    });
+    (StatusCode::BAD_REQUEST, axum::Json(body)).into_response()
+}
+
+/// What the Messages API would reject about a request's stop sequences or message list
+fn anthropic_request_problem(request: &Value) -> Option<String> {
+    if request.get("stop_sequences").map_or(false, |stops| !stops.is_array()) {
+        return Some("stop_sequences: Input should be a valid list".to_string());
+    }
+    let messages = request.get("messages")?.as_array()?;
+    let mut expected_role = "user";
+    let mut open_tool_uses: Vec<&str> = Vec::new();
+    for (index, message) in messages.iter().enumerate() {
+        let role = message["role"].as_str().unwrap_or_default();
+        if role != expected_role {
+            return Some(format!("messages.{}: expected role {}, got {:?}", index, expected_role, role));
+        }
+        let blocks = message["content"].as_array().map(Vec::as_slice).unwrap_or_default();
+        if role == "user" {
+            for block in blocks.iter().filter(|b| b["type"] == "tool_result") {
+                let id = block["tool_use_id"].as_str().unwrap_or_default();
+                if !open_tool_uses.contains(&id) {
+                    return Some(format!("messages.{}: tool_result for unknown tool_use {:?}", index, id));
+                }
+            }
+            open_tool_uses.clear();
+        } else {
+            if !open_tool_uses.is_empty() {
+                return Some(format!("messages.{}: tool_use blocks without tool_result", index));
+            }
+            open_tool_uses = blocks
+                .iter()
+                .filter(|b| b["type"] == "tool_use")
+                .filter_map(|b| b["id"].as_str())
+                .collect();
+        }
+        expected_role = if role == "user" { "assistant" } else { "user" };
+    }
+    None
+}
+
+fn anthropic_response(model: &str, scenario: Scenario, request: &Value, input_tokens: u64) -> Response {
+    if let Scenario::Error(status) = scenario {
+        return anthropic_error(status);
+    }
+    if scenario == Scenario::ToolUse {
+        return json_response(json!({
+            "id": format!("msg_mock_{}", uuid::Uuid::new_v4().simple()),
+            "type": "message",
+            "role": "assistant",
+            "model": model,
+            "content": [{
+                "type": "tool_use",
+                "id": "toolu_mock_weather",
+                "name": "get_weather",
+                "input": { "city": "London" }
+            }],
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "usage": { "input_tokens": input_tokens, "output_tokens": output_tokens() }
+        }));
+    }
+    let text = match (scenario, request.get("system")) {
+        (Scenario::EchoSystem, Some(Value::String(system))) => system.clone(),
+        (Scenario::EchoSystem, Some(Value::Array(blocks))) => {
+            blocks.iter().filter_map(|b| b["text"].as_str()).collect::<Vec<_>>().join("\n")
+        }
+        _ => MOCK_REPLY.to_string(),
+    };
+    json_response(json!({
+        "id": format!("msg_mock_{}", uuid::Uuid::new_v4().simple()),
+        "type": "message",
+        "role": "assistant",
+        "model": model,
+        "content": [{ "type": "text", "text": text }],
+        "stop_reason": "end_turn",
+        "stop_sequence": null,
+        "usage": { "input_tokens": input_tokens, "output_tokens": output_tokens() }
+    }))
+}
+
+fn anthropic_stream(model: &str, scenario: Scenario, input_tokens: u64) -> Response {
+    if let Scenario::Error(status) = scenario {
+        return anthropic_error(status);
+    }
+
+    let event = |name: &str, data: Value| Bytes::from(format!("event: {}\ndata: {}\n\n", name, data));
+    let mut chunks = vec![
+        event(
+            "message_start",
+            json!({
+                "type": "message_start",
+                "message": {
+                    "id": format!("msg_mock_{}", uuid::Uuid::new_v4().simple()),
+                    "type": "message",
+                    "role": "assistant",
+                    "model": model,
+                    "content": [],
+                    "stop_reason": null,
+                    "usage": { "input_tokens": input_tokens, "output_tokens": 1 }
+                }
+            }),
+        ),
+        event(
+            "content_block_start",
+            json!({ "type": "content_block_start", "index": 0, "content_block": { "type": "text", "text": "" } }),
+        ),
+    ];
+
+    for (i, delta) in deltas().into_iter().enumerate() {
+        if scenario == Scenario::StreamError && i == DELTAS_BEFORE_ERROR {
+            chunks.push(event(
+                "error",
+                json!({ "type": "error", "error": { "type": "overloaded_error", "message": "Mock stream interrupted" } }),
+            ));
+            return streaming_response("text/event-stream", chunks);
+        }
+        chunks.push(event(
+            "content_block_delta",
+            json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": delta } }),
+        ));
+    }
+
+    chunks.push(event("content_block_stop", json!({ "type": "content_block_stop", "index": 0 })));
+    chunks.push(event(
+        "message_delta",
+        json!({
+            "type": "message_delta",
+            "delta": { "stop_reason": "end_turn", "stop_sequence": null },
+            "usage": { "output_tokens": output_tokens() }
+        }),
+    ));
+    chunks.push(event("message_stop", json!({ "type": "message_stop" })));
+    streaming_response("text/event-stream", chunks)
+}
+
+// Bedrock Converse
+
+fn bedrock_error(status: StatusCode) -> Response {
+    let error_type = match status {
+        StatusCode::TOO_MANY_REQUESTS => "ThrottlingException",
+        StatusCode::BAD_REQUEST => "ValidationException",
+        _ => "InternalServerException",
+    };
+    let mut response = (status, axum::Json(json!({ "message": "Mock provider error" }))).into_response();
+    response
+        .headers_mut()
+        .insert("x-amzn-errortype", HeaderValue::from_static(error_type));
+    response
+}
+
+fn bedrock_usage(input_tokens: u64) -> Value {
+    json!({
+        "inputTokens": input_tokens,
+        "outputTokens": output_tokens(),
+        "totalTokens": input_tokens + output_tokens()
+    })
+}
+
+fn bedrock_response(scenario: Scenario, input_tokens: u64) -> Response {
+    if let Scenario::Error(status) = scenario {
+        return bedrock_error(status);
+    }
+    json_response(json!({
+        "output": {
+            "message": { "role": "assistant", "content": [{ "text": MOCK_REPLY }] }
+        },
+        "stopReason": "end_turn",
+        "usage": bedrock_usage(input_tokens),
+        "metrics": { "latencyMs": 5 }
+    }))
+}
+
+fn bedrock_stream(scenario: Scenario, input_tokens: u64) -> Response {
+    if let Scenario::Error(status) = scenario {
+        return bedrock_error(status);
+    }
+
+    let mut chunks = vec![encode_message("event", "messageStart", &json!({ "role": "assistant" }))];
+    for (i, delta) in deltas().into_iter().enumerate() {
+        if scenario == Scenario::StreamError && i == DELTAS_BEFORE_ERROR {
+            chunks.push(encode_message(
+                "exception",
+                "throttlingException",
+                &json!({ "message": "Mock stream interrupted" }),
+            ));
+            return streaming_response("application/vnd.amazon.eventstream", chunks);
+        }
+        chunks.push(encode_message(
+            "event",
+            "contentBlockDelta",
+            &json!({ "contentBlockIndex": 0, "delta": { "text": delta } }),
+        ));
+    }
+    chunks.push(encode_message("event", "contentBlockStop", &json!({ "contentBlockIndex": 0 })));
+    chunks.push(encode_message("event", "messageStop", &json!({ "stopReason": "end_turn" })));
+    chunks.push(encode_message(
+        "event",
+        "metadata",
+        &json!({ "usage": bedrock_usage(input_tokens), "metrics": { "latencyMs": 5 } }),
+    ));
+    streaming_response("application/vnd.amazon.eventstream", chunks)
+}
@@ -0,0 +1,159 @@
+use axum::{response::IntoResponse, Json};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+    IntoParams, Modify, OpenApi, ToSchema,
+};
+
+/// Error body returned by the gateway itself, in OpenAI's error format. Only
+/// describes what `AppError` renders, so it's never built directly.
+#[derive(Serialize, ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+#[derive(Serialize, ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorDetail {
+    pub message: String,
+    /// `invalid_request_error` for request problems, otherwise the error variant
+    #[serde(rename = "type")]
+    pub error_type: String,
+    /// Offending request field, when there is one
+    pub param: Option<String>,
+    /// Machine-readable reason, e.g. `unsupported_endpoint` or `model_not_found`
+    pub code: Option<String>,
+    /// Endpoints the provider does support, with `unsupported_endpoint`
+    pub supported_endpoints: Option<Vec<String>>,
+    /// Closest listed models, with `model_not_found`
+    pub suggestions: Option<Vec<String>>,
+}
+
+/// Request headers the gateway reads; everything else is passed to the provider
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header, rename_all = "kebab-case")]
+#[allow(dead_code)]
+struct GatewayHeaders {
+    /// Provider to route to (`openai`, `anthropic`, `groq`, ...), or a routing pool name
+    x_provider: Option<String>,
+    /// Conversation id; pins routing pools and becomes the log's `thread_id`
+    x_thread_id: Option<String>,
+    /// Client message id, used as the log's `id` instead of a generated `msg_*`
+    x_message_id: Option<String>,
+    x_organization_id: Option<String>,
+    x_project_id: Option<String>,
+    x_user_id: Option<String>,
+    /// Tenant key, when multi-tenancy is configured
+    x_gateway_key: Option<String>,
+    /// Scheduling priority: `interactive`, `normal` or `batch`
+    x_priority: Option<String>,
+    /// Reject the request when its estimated cost exceeds this many dollars
+    x_max_cost: Option<String>,
+    /// Truncate the oldest messages instead of failing on context overflow
+    x_auto_truncate: Option<String>,
+    /// History compression when the prompt overflows: `middle-out`, `summarize` or `off`
+    x_history_compression: Option<String>,
+    /// JSON object merged into the translated upstream body
+    x_provider_options: Option<String>,
+    /// End a stream after this many seconds
+    x_max_stream_duration: Option<String>,
+    /// Opaque JSON carried into telemetry
+    x_gateway_metadata: Option<String>,
+}
+
+/// Declares a proxied endpoint; the actual handler is the `/v1/*path` catch-all
+macro_rules! proxy_path {
+    ($name:ident, $method:ident, $path:tt, $summary:tt) => {
+        #[utoipa::path(
+            $method,
+            path = $path,
+            tag = "proxy",
+            summary = $summary,
+            params(GatewayHeaders),
+            request_body(content = Value, description = "Provider request in OpenAI format, or the provider's native format"),
+            responses(
+                (status = 200, description = "Provider response, or `text/event-stream` when `stream` is set", body = Value),
+                (status = 400, description = "Invalid request or unsupported endpoint", body = ErrorResponse),
+                (status = 401, description = "Missing API key", body = ErrorResponse),
+                (status = 402, description = "Budget exceeded", body = ErrorResponse),
+                (status = 404, description = "Model not offered by the provider", body = ErrorResponse),
+                (status = 429, description = "Rate limited by the gateway", body = ErrorResponse),
+                (status = 502, description = "Invalid provider response", body = ErrorResponse),
+            )
+        )]
+        #[allow(dead_code)]
+        fn $name() {}
+    };
+}
+
+proxy_path!(chat_completions, post, "/v1/chat/completions", "Chat completion");
+proxy_path!(completions, post, "/v1/completions", "Text completion");
+proxy_path!(messages, post, "/v1/messages", "Anthropic messages");
+proxy_path!(embeddings, post, "/v1/embeddings", "Embeddings");
+proxy_path!(rerank, post, "/v1/rerank", "Rerank documents");
+proxy_path!(image_generations, post, "/v1/images/generations", "Image generation");
+proxy_path!(audio_speech, post, "/v1/audio/speech", "Text to speech");
+proxy_path!(audio_transcriptions, post, "/v1/audio/transcriptions", "Speech to text");
+proxy_path!(models, get, "/v1/models", "List the provider's models");
+
+/// Registers the `x-admin-key` scheme the admin routes refer to
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "admin_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("x-admin-key"))),
+            );
+        }
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Noveum AI Gateway",
+        description = "OpenAI-compatible gateway in front of multiple LLM providers. Any `/v1/*` path supported by the selected provider is proxied; the common ones are listed here."
+    ),
+    paths(
+        chat_completions,
+        completions,
+        messages,
+        embeddings,
+        rerank,
+        image_generations,
+        audio_speech,
+        audio_transcriptions,
+        models,
+        crate::handlers::health_check,
+        crate::handlers::version,
+        crate::handlers::prometheus_metrics,
+        crate::admin::stats,
+        crate::admin::schema_registry,
+        crate::admin::canaries,
+        crate::admin::limits,
+        crate::admin::cluster_status,
+        crate::admin::test_provider,
+        crate::admin::tail,
+        crate::admin::requests,
+        crate::graphql::graphql_handler,
+        crate::dashboard::index,
+        crate::dashboard::summary,
+    ),
+    components(schemas(ErrorResponse, ErrorDetail)),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "proxy", description = "Requests forwarded to the provider named in `x-provider`"),
+        (name = "admin", description = "Usage and limiter state; requires `x-admin-key` when `ADMIN_API_KEY` is set"),
+        (name = "gateway", description = "Health, build info, metrics and the dashboard page"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The OpenAPI 3.1 document for this gateway
+pub async fn openapi_json() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
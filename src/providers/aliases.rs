@@ -0,0 +1,64 @@
+use axum::body::Bytes;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::{error, info};
+
+/// A model name rewritten by the alias map; carried in response extensions for telemetry
+#[derive(Debug, Clone)]
+pub struct ModelRewrite {
+    pub original: String,
+    pub rewritten: String,
+}
+
+/// Alias map from `MODEL_ALIASES`, e.g. `{"gpt-4-32k": "gpt-4o", "anthropic:claude-2": "claude-3-5-sonnet-latest"}`.
+///
+/// Keys may be scoped to a provider with a `provider:` prefix; scoped entries
+/// win over unscoped ones.
+static MODEL_ALIASES: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("MODEL_ALIASES") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, String>>(&raw) {
+        Ok(aliases) => {
+            info!("Loaded {} model aliases", aliases.len());
+            aliases
+        }
+        Err(e) => {
+            error!("Failed to parse MODEL_ALIASES: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// Replacement for a model on the given provider, if one is configured
+pub fn resolve(provider: &str, model: &str) -> Option<String> {
+    MODEL_ALIASES
+        .get(&format!("{}:{}", provider.to_lowercase(), model))
+        .or_else(|| MODEL_ALIASES.get(model))
+        .filter(|target| target.as_str() != model)
+        .cloned()
+}
+
+/// Rewrite the `model` field of a JSON body according to the alias map
+pub fn rewrite_model(provider: &str, body: Bytes) -> (Bytes, Option<ModelRewrite>) {
+    if MODEL_ALIASES.is_empty() {
+        return (body, None);
+    }
+    let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+        return (body, None);
+    };
+    let Some(original) = json.get("model").and_then(Value::as_str).map(String::from) else {
+        return (body, None);
+    };
+    let Some(rewritten) = resolve(provider, &original) else {
+        return (body, None);
+    };
+
+    info!("Rewriting model {} to {} for {}", original, rewritten, provider);
+    json["model"] = Value::String(rewritten.clone());
+    match serde_json::to_vec(&json) {
+        Ok(bytes) => (Bytes::from(bytes), Some(ModelRewrite { original, rewritten })),
+        Err(_) => (body, None),
+    }
+}
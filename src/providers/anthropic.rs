@@ -1,14 +1,16 @@
 use super::Provider;
+use super::params::ParamSupport;
 use super::utils::log_tracking_headers;
 use crate::error::AppError;
 use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
 use async_trait::async_trait;
 use axum::http::HeaderMap;
-use serde_json::{Value, json};
-use tracing::{debug, error};
+use serde_json::{Map, Value, json};
+use tracing::{debug, error, info};
 use std::cell::RefCell;
 use axum::{
-    body::{Body, to_bytes},
+    body::{Body, Bytes, to_bytes},
     http::{HeaderValue, Response},
 };
 use chrono;
@@ -39,6 +41,18 @@ impl Provider for AnthropicProvider {
         "anthropic"
     }
 
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &["/v1/chat/completions", "/v1/messages", "/v1/complete", "/v1/models"]
+    }
+
+    fn param_support(&self, param: &str) -> ParamSupport {
+        match param {
+            "stop" => ParamSupport::Rename("stop_sequences"),
+            "top_k" => ParamSupport::Native,
+            _ => ParamSupport::Unsupported,
+        }
+    }
+
     fn transform_path(&self, path: &str) -> String {
         if path.contains("/chat/completions") {
             "/v1/messages".to_string()
@@ -88,6 +102,23 @@ impl Provider for AnthropicProvider {
         Ok(headers)
     }
 
+    async fn prepare_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        // Native /v1/messages bodies go through untouched, apart from OpenAI tier names
+        let Ok(mut request) = serde_json::from_slice::<Value>(&body) else {
+            return Ok(body);
+        };
+        let tier_changed = normalize_service_tier(&mut request);
+        if !is_openai_chat_request(&request) {
+            if tier_changed {
+                return Ok(Bytes::from(serde_json::to_vec(&request)?));
+            }
+            return Ok(body);
+        }
+        let transformed = to_anthropic_request(request)?;
+        debug!("Translated OpenAI chat request for Anthropic: {}", redact::value(&transformed));
+        Ok(Bytes::from(serde_json::to_vec(&transformed)?))
+    }
+
     async fn process_response(&self, response: Response<Body>) -> Result<Response<Body>, AppError> {
         // Clone response parts and body
         let (mut parts, body) = response.into_parts();
@@ -145,7 +176,13 @@ impl Provider for AnthropicProvider {
         
         // Always try to parse the response as JSON
         if let Ok(json) = serde_json::from_slice::<Value>(&bytes) {
-            debug!("Successfully parsed response body as JSON: {:?}", json);
+            // Message Batches and lists are returned as Anthropic sent them
+            if is_batch_or_list(&json) {
+                debug!("Returning Anthropic batch or list response without transformation");
+                return Ok(Response::from_parts(parts, Body::from(bytes)));
+            }
+
+            debug!("Successfully parsed response body as JSON: {}", redact::value(&json));
             
             // If we couldn't find a request_id in the headers, try to extract it from the body as a fallback
             let body_request_id = if request_id.is_none() {
@@ -201,7 +238,7 @@ pub struct AnthropicMetricsExtractor;
 
 impl MetricsExtractor for AnthropicMetricsExtractor {
     fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
-        debug!("Extracting Anthropic metrics from response: {}", response_body);
+        debug!("Extracting Anthropic metrics from response: {}", redact::value(response_body));
         let mut metrics = ProviderMetrics::default();
         
         // Extract usage data
@@ -249,6 +286,23 @@ impl MetricsExtractor for AnthropicMetricsExtractor {
             }
         }
 
+        // Requests may be served on the priority tier when the key has capacity reserved
+        metrics.service_tier = response_body
+            .pointer("/usage/service_tier")
+            .and_then(Value::as_str)
+            .map(String::from);
+
+        // Message Batches are tracked as jobs through creation, polling and cancellation
+        if response_body.get("type").and_then(Value::as_str) == Some("message_batch") {
+            metrics.job_id = response_body.get("id").and_then(Value::as_str).map(String::from);
+            metrics.job_status = response_body.get("processing_status").and_then(Value::as_str).map(String::from);
+            info!(
+                "Anthropic message batch {} is {}",
+                metrics.job_id.as_deref().unwrap_or("unknown"),
+                metrics.job_status.as_deref().unwrap_or("unknown"),
+            );
+        }
+
         // Extract message ID to use as request ID
         if let Some(id) = response_body.get("id").and_then(|v| v.as_str()) {
             debug!("Found Anthropic message ID: {}", id);
@@ -265,7 +319,7 @@ impl MetricsExtractor for AnthropicMetricsExtractor {
     }
     
     fn try_extract_provider_specific_streaming_metrics(&self, chunk: &str) -> Option<ProviderMetrics> {
-        debug!("Attempting to extract metrics from Anthropic streaming chunk: {}", chunk);
+        debug!("Attempting to extract metrics from Anthropic streaming chunk: {}", redact::text(chunk));
         
         // Try to parse the chunk as JSON
         if let Ok(json) = serde_json::from_str::<Value>(chunk) {
@@ -339,6 +393,10 @@ impl MetricsExtractor for AnthropicMetricsExtractor {
         
         None
     }
+
+    fn estimate_cost(&self, model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        Some(calculate_anthropic_cost(model, input_tokens + output_tokens))
+    }
 }
 
 // Helper function for Anthropic-specific cost calculation
@@ -372,6 +430,239 @@ fn calculate_anthropic_cost(model: &str, total_tokens: u32) -> f64 {
     }
 }
 
+/// Map OpenAI `service_tier` values onto Anthropic's `auto` and `standard_only`,
+/// dropping tiers with no Anthropic equivalent such as `flex`. Returns whether the body changed.
+fn normalize_service_tier(request: &mut Value) -> bool {
+    let Some(object) = request.as_object_mut() else {
+        return false;
+    };
+    let Some(tier) = object.get("service_tier").and_then(Value::as_str) else {
+        return false;
+    };
+    let mapped = match tier {
+        "auto" | "standard_only" => return false,
+        "priority" => Some("auto"),
+        "default" | "standard" => Some("standard_only"),
+        _ => None,
+    };
+    match mapped {
+        Some(mapped) => {
+            object.insert("service_tier".to_string(), json!(mapped));
+        }
+        None => {
+            debug!("Dropping service_tier {} which Anthropic doesn't offer", tier);
+            object.remove("service_tier");
+        }
+    }
+    true
+}
+
+/// Whether a response is a Message Batch or a paginated list (`data` array),
+/// neither of which has an OpenAI chat completion equivalent
+fn is_batch_or_list(response: &Value) -> bool {
+    response.get("type").and_then(Value::as_str) == Some("message_batch")
+        || response.get("data").is_some_and(Value::is_array)
+}
+
+/// Whether a body uses OpenAI chat features the Messages API has no direct
+/// equivalent for; plain user/assistant text is valid as-is in both formats
+fn is_openai_chat_request(request: &Value) -> bool {
+    let messages = request.get("messages").and_then(Value::as_array);
+    let openai_message = messages.map_or(false, |messages| {
+        messages.iter().any(|message| {
+            matches!(message["role"].as_str(), Some("system" | "developer" | "tool"))
+                || message.get("tool_calls").is_some()
+                || message["content"]
+                    .as_array()
+                    .map_or(false, |parts| parts.iter().any(|part| part["type"] == "image_url"))
+        })
+    });
+    let openai_tools = request
+        .get("tools")
+        .and_then(Value::as_array)
+        .map_or(false, |tools| tools.iter().any(|tool| tool.get("function").is_some()));
+    let openai_tool_choice = request
+        .get("tool_choice")
+        .map_or(false, |choice| choice.is_string() || choice.get("function").is_some());
+    openai_message || openai_tools || openai_tool_choice
+}
+
+/// Build a Messages API request from an OpenAI chat completion request.
+///
+/// System messages move to `system`, assistant `tool_calls` become `tool_use`
+/// blocks, and `tool` messages become `tool_result` blocks in the following
+/// user turn. Consecutive messages with the same role are merged, since the
+/// Messages API requires user and assistant turns to alternate.
+fn to_anthropic_request(mut request: Value) -> Result<Value, AppError> {
+    let messages = match request.get_mut("messages").map(Value::take) {
+        Some(Value::Array(messages)) => messages,
+        _ => {
+            error!("Invalid request format: messages array not found");
+            return Err(AppError::InvalidRequestFormat);
+        }
+    };
+
+    let mut system = match request.get_mut("system").map(Value::take) {
+        Some(Value::String(text)) => vec![json!({ "type": "text", "text": text })],
+        Some(Value::Array(blocks)) => blocks,
+        _ => vec![],
+    };
+    let mut turns: Vec<(&str, Vec<Value>)> = Vec::new();
+    for message in &messages {
+        let (role, blocks) = match message["role"].as_str().unwrap_or("user") {
+            "system" | "developer" => {
+                system.extend(content_blocks(&message["content"]));
+                continue;
+            }
+            "assistant" => ("assistant", assistant_blocks(message)?),
+            "tool" => ("user", vec![tool_result_block(message)]),
+            _ => ("user", content_blocks(&message["content"])),
+        };
+        match turns.last_mut() {
+            Some((last_role, last_blocks)) if *last_role == role => last_blocks.extend(blocks),
+            _ => turns.push((role, blocks)),
+        }
+    }
+
+    let object = request.as_object_mut().ok_or(AppError::InvalidRequestFormat)?;
+    object.insert(
+        "messages".to_string(),
+        turns
+            .into_iter()
+            .map(|(role, content)| json!({ "role": role, "content": content }))
+            .collect(),
+    );
+    if !system.is_empty() {
+        object.insert("system".to_string(), Value::Array(system));
+    }
+
+    if let Some(Value::Array(tools)) = object.remove("tools") {
+        object.insert("tools".to_string(), tools.into_iter().map(anthropic_tool).collect());
+    }
+    let parallel_tool_calls = object.remove("parallel_tool_calls").and_then(|v| v.as_bool());
+    if let Some(choice) = object.remove("tool_choice") {
+        let mut choice = anthropic_tool_choice(choice);
+        if parallel_tool_calls == Some(false) {
+            if let Some(choice) = choice.as_object_mut() {
+                choice.insert("disable_parallel_tool_use".to_string(), json!(true));
+            }
+        }
+        object.insert("tool_choice".to_string(), choice);
+    } else if parallel_tool_calls == Some(false) && object.contains_key("tools") {
+        object.insert("tool_choice".to_string(), json!({ "type": "auto", "disable_parallel_tool_use": true }));
+    }
+
+    Ok(request)
+}
+
+/// Convert OpenAI message content (a string or a list of parts) into content
+/// blocks. Parts already in Anthropic form (e.g. `document`) pass through.
+fn content_blocks(content: &Value) -> Vec<Value> {
+    let parts = match content {
+        Value::String(text) if text.is_empty() => return vec![],
+        Value::String(text) => return vec![json!({ "type": "text", "text": text })],
+        Value::Array(parts) => parts,
+        _ => return vec![],
+    };
+    parts
+        .iter()
+        .filter_map(|part| match part["type"].as_str() {
+            // Empty text blocks are rejected by the Messages API
+            Some("text") => part["text"]
+                .as_str()
+                .filter(|text| !text.is_empty())
+                .map(|text| json!({ "type": "text", "text": text })),
+            Some("image_url") => part["image_url"]["url"].as_str().map(image_block),
+            _ => Some(part.clone()),
+        })
+        .collect()
+}
+
+/// Inline `data:` URLs as base64 sources; anything else is fetched by URL
+fn image_block(url: &str) -> Value {
+    if let Some((meta, data)) = url.strip_prefix("data:").and_then(|rest| rest.split_once(',')) {
+        let media_type = meta.trim_end_matches(";base64");
+        return json!({
+            "type": "image",
+            "source": { "type": "base64", "media_type": media_type, "data": data }
+        });
+    }
+    json!({ "type": "image", "source": { "type": "url", "url": url } })
+}
+
+/// An assistant message's text followed by a `tool_use` block per tool call
+fn assistant_blocks(message: &Value) -> Result<Vec<Value>, AppError> {
+    let mut blocks = content_blocks(&message["content"]);
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        let function = &call["function"];
+        // Arguments are a JSON-encoded string in OpenAI's format and an object in Anthropic's
+        let input = match &function["arguments"] {
+            Value::String(arguments) if arguments.trim().is_empty() => json!({}),
+            Value::String(arguments) => serde_json::from_str(arguments).map_err(|e| {
+                AppError::RequestError(format!(
+                    "Arguments of tool call {} are not valid JSON: {}",
+                    call["id"], e
+                ))
+            })?,
+            Value::Null => json!({}),
+            arguments => arguments.clone(),
+        };
+        blocks.push(json!({
+            "type": "tool_use",
+            "id": call["id"],
+            "name": function["name"],
+            "input": input
+        }));
+    }
+    Ok(blocks)
+}
+
+/// A `tool` message as the `tool_result` block answering its tool call
+fn tool_result_block(message: &Value) -> Value {
+    let content = match &message["content"] {
+        Value::String(text) => json!(text),
+        content => json!(content_blocks(content)),
+    };
+    json!({
+        "type": "tool_result",
+        "tool_use_id": message["tool_call_id"],
+        "content": content
+    })
+}
+
+/// `{"type": "function", "function": {...}}` to `{"name", "description", "input_schema"}`;
+/// tools already in Anthropic form (including server tools) pass through
+fn anthropic_tool(tool: Value) -> Value {
+    let Some(function) = tool.get("function") else {
+        return tool;
+    };
+    let mut converted = Map::new();
+    converted.insert("name".to_string(), function["name"].clone());
+    if let Some(description) = function.get("description") {
+        converted.insert("description".to_string(), description.clone());
+    }
+    converted.insert(
+        "input_schema".to_string(),
+        function
+            .get("parameters")
+            .cloned()
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+    );
+    Value::Object(converted)
+}
+
+fn anthropic_tool_choice(choice: Value) -> Value {
+    match choice.as_str() {
+        Some("required") => json!({ "type": "any" }),
+        Some("none") => json!({ "type": "none" }),
+        Some(_) => json!({ "type": "auto" }),
+        None => match choice.pointer("/function/name") {
+            Some(name) => json!({ "type": "tool", "name": name }),
+            None => choice,
+        },
+    }
+}
+
 // Convert Anthropic API response format to OpenAI format
 fn transform_anthropic_to_openai_format(anthropic_response: Value) -> Value {
     // Extract content from Anthropic's array-based content structure
@@ -388,6 +679,25 @@ fn transform_anthropic_to_openai_format(anthropic_response: Value) -> Value {
         // Fallback if content structure is different
         anthropic_response.get("content").and_then(|c| c.as_str()).unwrap_or("").to_string()
     };
+
+    // tool_use blocks become OpenAI tool calls, with the input re-encoded as a JSON string
+    let tool_calls: Vec<Value> = anthropic_response
+        .get("content")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|block| block["type"] == "tool_use")
+        .map(|block| {
+            json!({
+                "id": block["id"],
+                "type": "function",
+                "function": {
+                    "name": block["name"],
+                    "arguments": block["input"].to_string()
+                }
+            })
+        })
+        .collect();
     
     // Map Anthropic usage fields to OpenAI format
     let usage = {
@@ -422,6 +732,7 @@ fn transform_anthropic_to_openai_format(anthropic_response: Value) -> Value {
         Some("end_turn") => "stop",
         Some("max_tokens") => "length",
         Some("stop_sequence") => "stop",
+        Some("tool_use") => "tool_calls",
         Some(reason) => reason,
         None => "stop" // Default
     };
@@ -446,6 +757,14 @@ fn transform_anthropic_to_openai_format(anthropic_response: Value) -> Value {
         "system_fingerprint": format!("anthropic-{}", anthropic_response.get("model").and_then(|m| m.as_str()).unwrap_or("claude"))
     });
     
+    if !tool_calls.is_empty() {
+        let message = &mut transformed["choices"][0]["message"];
+        if content.is_empty() {
+            message["content"] = Value::Null;
+        }
+        message["tool_calls"] = json!(tool_calls);
+    }
+    
     // Handle the seed value - convert to string if present to avoid Elasticsearch long integer overflow
     if let Some(seed) = anthropic_response.get("seed") {
         if let Some(choices) = transformed.get_mut("choices").and_then(|c| c.as_array_mut()) {
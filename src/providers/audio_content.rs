@@ -0,0 +1,39 @@
+use serde_json::{json, Value};
+
+/// Keys whose object value carries base64 audio in `data`: `input_audio` content
+/// parts in requests, and `audio` on completion messages and stream deltas
+const AUDIO_KEYS: &[&str] = &["input_audio", "audio"];
+
+/// Replace base64 audio in a chat body with a placeholder giving its size.
+///
+/// Audio passes through to the provider untouched; this is for copies of the
+/// body that would otherwise be megabytes of base64, such as telemetry
+/// captures and prompt token estimates. Returns whether anything was replaced.
+pub fn elide_audio(value: &mut Value) -> bool {
+    match value {
+        Value::Object(map) => {
+            let mut elided = false;
+            for (key, field) in map.iter_mut() {
+                if AUDIO_KEYS.contains(&key.as_str()) {
+                    if let Some(data) = field.get_mut("data").filter(|data| data.is_string()) {
+                        let bytes = data.as_str().map_or(0, str::len);
+                        *data = json!(format!("[base64 audio: {} bytes]", bytes));
+                        elided = true;
+                        continue;
+                    }
+                }
+                elided |= elide_audio(field);
+            }
+            elided
+        }
+        Value::Array(items) => items.iter_mut().fold(false, |elided, item| elide_audio(item) | elided),
+        _ => false,
+    }
+}
+
+/// A copy of `value` without its base64 audio, for estimating its prompt tokens
+pub fn without_audio(value: &Value) -> Value {
+    let mut value = value.clone();
+    elide_audio(&mut value);
+    value
+}
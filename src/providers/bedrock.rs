@@ -1,8 +1,13 @@
 use std::env;
 use super::Provider;
+use super::params::ParamSupport;
+use super::stream_events::{sse_body, StreamEvent};
 use super::utils::log_tracking_headers;
 use crate::error::AppError;
+use crate::proxy::signing::{RequestSigner, SigV4Signer};
+use crate::proxy::AwsCredentials;
 use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
 use async_trait::async_trait;
 use aws_event_stream_parser::{parse_message, Message};
 use axum::{
@@ -10,6 +15,7 @@ use axum::{
     http::{HeaderMap, HeaderValue, Response, StatusCode},
 };
 use futures_util::StreamExt;
+use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use serde_json::{json, Value};
 use std::sync::Arc;
@@ -18,12 +24,31 @@ use tracing::{debug, error, warn};
 use uuid;
 
 /// Constants for default values
-const DEFAULT_REGION: &str = "us-east-1";
+pub(crate) const DEFAULT_REGION: &str = "us-east-1";
 const DEFAULT_MODEL: &str = "amazon.titan-text-premier-v1:0";
 const DEFAULT_FALLBACK_MODEL: &str = "mistral.mistral-7b-instruct-v0:2";
 const DEFAULT_MAX_TOKENS: u64 = 1000;
 const DEFAULT_TEMPERATURE: f64 = 0.7;
 const DEFAULT_TOP_P: f64 = 1.0;
+const DEFAULT_GUARDRAIL_VERSION: &str = "DRAFT";
+
+/// Roles a client may name in `x-aws-role-arn` while signing with the gateway's own
+/// credentials, from `BEDROCK_ALLOWED_ROLE_ARNS` (comma-separated) plus `BEDROCK_ROLE_ARN`
+static ALLOWED_ROLE_ARNS: Lazy<Vec<String>> = Lazy::new(|| {
+    env::var("BEDROCK_ALLOWED_ROLE_ARNS")
+        .unwrap_or_default()
+        .split(',')
+        .chain(env::var("BEDROCK_ROLE_ARN").as_deref().unwrap_or_default().split(','))
+        .map(str::trim)
+        .filter(|arn| !arn.is_empty())
+        .map(String::from)
+        .collect()
+});
+
+/// Whether the client sent its own AWS keys rather than relying on the gateway's
+fn client_credentials(headers: &HeaderMap) -> bool {
+    headers.contains_key("x-aws-access-key-id") && headers.contains_key("x-aws-secret-access-key")
+}
 
 /// BedrockProvider handles AWS Bedrock API integration
 #[derive(Clone)]
@@ -36,6 +61,8 @@ pub struct BedrockProvider {
     first_chunk: Arc<RwLock<bool>>,
     aws_key: Option<Arc<RwLock<String>>>,
     aws_secret: Option<Arc<RwLock<String>>>,
+    guardrail_config: Arc<RwLock<Option<Value>>>,
+    stop_reason: Arc<RwLock<Option<String>>>,
 }
 
 impl BedrockProvider {
@@ -62,9 +89,44 @@ impl BedrockProvider {
             aws_secret: env::var("AWS_SECRET_ACCESS_KEY")
                 .ok()
                 .map(|key| Arc::new(RwLock::new(key))),
+            guardrail_config: Arc::new(RwLock::new(None)),
+            stop_reason: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// Resolves the guardrail to apply from the request headers or body.
+    ///
+    /// A native `guardrailConfig` object in the body wins, followed by the
+    /// `x-aws-guardrail-id`/`x-aws-guardrail-version` headers and finally an
+    /// OpenAI-style `guardrail_config: {"id", "version", "trace"}` body field.
+    fn resolve_guardrail_config(&self, headers: &HeaderMap, body: &Value) -> Option<Value> {
+        if let Some(config) = body.get("guardrailConfig").filter(|c| c.is_object()) {
+            return Some(config.clone());
+        }
+
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(String::from);
+        let field = |name: &str| {
+            body.get("guardrail_config")
+                .and_then(|c| c.get(name))
+                .and_then(Value::as_str)
+                .map(String::from)
+        };
+
+        let id = header("x-aws-guardrail-id").or_else(|| field("id"))?;
+        let version = header("x-aws-guardrail-version")
+            .or_else(|| field("version"))
+            .unwrap_or_else(|| DEFAULT_GUARDRAIL_VERSION.to_string());
+        let trace = header("x-aws-guardrail-trace")
+            .or_else(|| field("trace"))
+            .unwrap_or_else(|| "enabled".to_string());
+
+        Some(json!({
+            "guardrailIdentifier": id,
+            "guardrailVersion": version,
+            "trace": trace
+        }))
+    }
+
     fn get_model_name(&self, path: &str) -> String {
         path.split('/')
             .last()
@@ -73,10 +135,16 @@ impl BedrockProvider {
     }
 
     fn transform_request_body(&self, body: Value) -> Result<Value, AppError> {
-        debug!("Transforming request body: {:#?}", body);
+        debug!("Transforming request body: {:#}", redact::value(&body));
+
+        let guardrail_config = self.guardrail_config.read().clone();
 
         // Return early if already in correct format
         if body.get("inferenceConfig").is_some() {
+            let mut body = body;
+            if let (Some(config), Some(obj)) = (guardrail_config, body.as_object_mut()) {
+                obj.entry("guardrailConfig").or_insert(config);
+            }
             return Ok(body);
         }
 
@@ -93,19 +161,20 @@ impl BedrockProvider {
 
         for msg in messages {
             let role = msg["role"].as_str().unwrap_or("user");
-            let content = msg["content"].as_str().unwrap_or_default();
+            let content = content_blocks(&msg["content"])?;
 
             if role == "system" {
-                system_messages.push(json!({ "text": content }));
+                // System prompts only take text blocks
+                system_messages.extend(content.into_iter().filter(|block| block.get("text").is_some()));
             } else {
                 transformed_messages.push(json!({
                     "role": role,
-                    "content": [{ "text": content }]
+                    "content": content
                 }));
             }
         }
 
-        let transformed = json!({
+        let mut transformed = json!({
             "messages": transformed_messages,
             "system": system_messages,
             "inferenceConfig": {
@@ -121,11 +190,31 @@ impl BedrockProvider {
             }
         });
 
-        debug!("Transformed body: {:#?}", transformed);
+        // `stop` accepts a single string or a list, Converse only takes a list
+        let stop_sequences = match body.get("stop") {
+            Some(Value::String(stop)) => vec![json!(stop)],
+            Some(Value::Array(stops)) => stops.iter().filter(|s| s.is_string()).cloned().collect(),
+            _ => vec![],
+        };
+        if !stop_sequences.is_empty() {
+            transformed["inferenceConfig"]["stopSequences"] = json!(stop_sequences);
+        }
+
+        // top_k is model specific and has no Converse equivalent
+        if let Some(top_k) = body.get("top_k").and_then(Value::as_u64) {
+            transformed["additionalModelRequestFields"] = json!({ "top_k": top_k });
+        }
+
+        if let Some(config) = guardrail_config {
+            debug!("Applying Bedrock guardrail: {}", config);
+            transformed["guardrailConfig"] = config;
+        }
+
+        debug!("Transformed body: {:#}", redact::value(&transformed));
         Ok(transformed)
     }
 
-    fn transform_bedrock_chunk(&self, chunk: Bytes) -> Result<Bytes, AppError> {
+    fn transform_bedrock_chunk(&self, chunk: Bytes) -> Result<Vec<StreamEvent>, AppError> {
         debug!("Processing chunk of size: {}", chunk.len());
         let mut remaining = chunk.as_ref();
         let mut response_events = Vec::new();
@@ -143,16 +232,17 @@ impl BedrockProvider {
             }
         }
 
-        Ok(Bytes::from(response_events.join("")))
+        Ok(response_events)
     }
 
-    fn process_message<'a>(&self, data: &'a [u8]) -> Result<(&'a [u8], Vec<String>), AppError> {
+    fn process_message<'a>(&self, data: &'a [u8]) -> Result<(&'a [u8], Vec<StreamEvent>), AppError> {
         let (rest, message) =
             parse_message(data).map_err(|e| AppError::EventStreamError(e.to_string()))?;
 
         let event_type = self.get_event_type(&message);
         let events = match event_type.as_deref() {
             Some("contentBlockDelta") => self.handle_content_block(&message)?,
+            Some("messageStop") => self.handle_message_stop(&message)?,
             Some("metadata") => self.handle_metadata(&message)?,
             _ => {
                 debug!("Skipping event type: {:?}", event_type);
@@ -184,7 +274,7 @@ impl BedrockProvider {
     /// For the first chunk, this includes the "role": "assistant" field in the delta.
     /// For all chunks, this includes the same system_fingerprint and required OpenAI fields
     /// for compatibility with OpenAI SDKs.
-    fn handle_content_block(&self, message: &Message) -> Result<Vec<String>, AppError> {
+    fn handle_content_block(&self, message: &Message) -> Result<Vec<StreamEvent>, AppError> {
         let body_str = String::from_utf8(message.body.to_vec())?;
         let json: Value = serde_json::from_str(&body_str)?;
 
@@ -194,27 +284,36 @@ impl BedrockProvider {
             .and_then(Value::as_str)
         {
             let response = self.create_delta_response(delta);
-            Ok(vec![format!("data: {}\n\n", response.to_string())])
+            Ok(vec![StreamEvent::chunk(response)])
         } else {
             Ok(vec![])
         }
     }
 
+    /// Records the stop reason so the final chunk can report it, e.g. a guardrail intervention.
+    fn handle_message_stop(&self, message: &Message) -> Result<Vec<StreamEvent>, AppError> {
+        let body_str = String::from_utf8(message.body.to_vec())?;
+        let json: Value = serde_json::from_str(&body_str)?;
+
+        if let Some(reason) = json.get("stopReason").and_then(Value::as_str) {
+            debug!("Bedrock stream stopped with reason: {}", reason);
+            *self.stop_reason.write() = Some(reason.to_string());
+        }
+        Ok(vec![])
+    }
+
     /// Handles metadata chunks from Bedrock (typically the final chunk) and transforms them 
     /// to the OpenAI streaming format.
     ///
     /// The final chunk includes usage information and a finish_reason of "stop".
     /// This also includes the [DONE] marker required by OpenAI's streaming protocol.
-    fn handle_metadata(&self, message: &Message) -> Result<Vec<String>, AppError> {
+    fn handle_metadata(&self, message: &Message) -> Result<Vec<StreamEvent>, AppError> {
         let body_str = String::from_utf8(message.body.to_vec())?;
         let json: Value = serde_json::from_str(&body_str)?;
 
         if let Some(usage) = json.get("usage") {
-            let final_message = self.create_final_response(usage);
-            Ok(vec![format!(
-                "data: {}\ndata: [DONE]\n\n",
-                final_message.to_string()
-            )])
+            let final_message = self.create_final_response(usage, json.get("trace"));
+            Ok(vec![StreamEvent::chunk(final_message), StreamEvent::Done])
         } else {
             Ok(vec![])
         }
@@ -256,7 +355,7 @@ impl BedrockProvider {
         })
     }
 
-    fn create_final_response(&self, usage: &Value) -> Value {
+    fn create_final_response(&self, usage: &Value, trace: Option<&Value>) -> Value {
         // Extract usage data and transform to OpenAI format
         let input_tokens = usage.get("inputTokens").and_then(Value::as_u64).unwrap_or(0);
         let output_tokens = usage.get("outputTokens").and_then(Value::as_u64).unwrap_or(0);
@@ -269,7 +368,10 @@ impl BedrockProvider {
             "total_tokens": total_tokens
         });
         
-        json!({
+        let stop_reason = self.stop_reason.read().clone();
+        let finish_reason = map_stop_reason(stop_reason.as_deref().unwrap_or("end_turn"));
+
+        let mut response = json!({
             "id": "chatcmpl-bedrock",
             "object": "chat.completion.chunk",
             "created": chrono::Utc::now().timestamp(),
@@ -277,12 +379,17 @@ impl BedrockProvider {
             "choices": [{
                 "index": 0,
                 "delta": {},
-                "finish_reason": "stop"
+                "finish_reason": finish_reason
             }],
             "usage": transformed_usage,
             "service_tier": "default",
             "system_fingerprint": self.system_fingerprint.read().clone()
-        })
+        });
+
+        if let Some(guardrail) = guardrail_result(stop_reason.as_deref(), trace) {
+            response["guardrail"] = guardrail;
+        }
+        response
     }
 
     // Helper method to transform Bedrock response to OpenAI format
@@ -319,15 +426,11 @@ impl BedrockProvider {
             .unwrap_or("stop");
             
         // Map Bedrock finish reason to OpenAI format
-        let openai_finish_reason = match finish_reason {
-            "end_turn" => "stop",
-            "max_tokens" => "length",
-            "stop_sequence" => "stop",
-            _ => "stop",
-        };
+        let openai_finish_reason = map_stop_reason(finish_reason);
+        let guardrail = guardrail_result(Some(finish_reason), bedrock_response.get("trace"));
         
         // Create OpenAI format response
-        let openai_response = json!({
+        let mut openai_response = json!({
             "metrics":metrics,
             "id": format!("chatcmpl-{}", uuid::Uuid::new_v4().to_string().replace("-", "").chars().take(10).collect::<String>()),
             "object": "chat.completion",
@@ -361,9 +464,69 @@ impl BedrockProvider {
             "service_tier": "default",
             "system_fingerprint": format!("fp_{}", uuid::Uuid::new_v4().to_string().replace("-", "").chars().take(10).collect::<String>())
         });
+
+        if let Some(guardrail) = guardrail {
+            openai_response["guardrail"] = guardrail;
+        }
         
         Ok(openai_response)
     }
+
+    /// Region to sign for: `x-aws-region`, else the provider's configured region
+    fn signing_region(&self, headers: &HeaderMap) -> Option<String> {
+        let region = headers
+            .get("x-aws-region")
+            .and_then(|h| h.to_str().ok())
+            .map(String::from)
+            .unwrap_or_else(|| self.region.read().clone());
+        Some(region)
+    }
+
+    /// Explicit credentials and region, from the request headers or static env keys
+    fn signing_credentials(&self, headers: &HeaderMap) -> Option<(AwsCredentials, String)> {
+        let region = self.signing_region(headers)?;
+
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(String::from);
+
+        // Client keys and the gateway's own are never mixed; temporary credentials
+        // take their session token from the same place as their keys
+        let (access_key, secret_key, session_token) = if client_credentials(headers) {
+            (
+                header("x-aws-access-key-id")?,
+                header("x-aws-secret-access-key")?,
+                header("x-aws-session-token"),
+            )
+        } else {
+            (
+                self.aws_key.as_ref().map(|arc| arc.read().clone())?,
+                self.aws_secret.as_ref().map(|arc| arc.read().clone())?,
+                env::var("AWS_SESSION_TOKEN").ok(),
+            )
+        };
+
+        debug!( //
+            "AWS credentials - Access Key: {} s: {}, session token: {}, Region: {}", 
+            mask_key(access_key.as_str()), mask_key(secret_key.as_str()),
+            session_token.is_some(), region,
+        );
+        Some((
+            AwsCredentials {
+                access_key,
+                secret_key,
+                session_token,
+            },
+            region,
+        ))
+    }
+
+    /// Role to assume via STS before signing, from `x-aws-role-arn` or `BEDROCK_ROLE_ARN`
+    fn assume_role_arn(&self, headers: &HeaderMap) -> Option<String> {
+        headers
+            .get("x-aws-role-arn")
+            .and_then(|v| v.to_str().ok().map(|s| s.to_owned()))
+            .or_else(|| env::var("BEDROCK_ROLE_ARN").ok())
+            .filter(|arn| !arn.trim().is_empty())
+    }
 }
 
 #[async_trait]
@@ -376,6 +539,15 @@ impl Provider for BedrockProvider {
         "bedrock"
     }
 
+    fn param_support(&self, param: &str) -> ParamSupport {
+        match param {
+            // Mapped onto the Converse request by transform_request_body
+            "stop" | "top_k" => ParamSupport::Native,
+            // `n` is fanned out by the proxy for non-streaming requests
+            _ => ParamSupport::Unsupported,
+        }
+    }
+
     async fn before_request(&self, headers: &HeaderMap, body: &Bytes) -> Result<(), AppError> {
         // Extract and set the model from the request body before any other processing
         if let Ok(request_body) = serde_json::from_slice::<Value>(body) {
@@ -397,6 +569,9 @@ impl Provider for BedrockProvider {
             // Reset the first_chunk flag for a new request
             debug!("Resetting first_chunk flag for new request");
             *self.first_chunk.write() = true;
+            *self.stop_reason.write() = None;
+
+            *self.guardrail_config.write() = self.resolve_guardrail_config(headers, &request_body);
         }
 
         // Extract and set the region from the request headers
@@ -428,6 +603,17 @@ impl Provider for BedrockProvider {
         Ok(Bytes::from(serde_json::to_vec(&transformed_body)?))
     }
 
+    fn prepare_raw_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        // Converse takes the model and streaming in the path, which before_request read from these
+        let mut request_body: Value = serde_json::from_slice(&body)?;
+        let Some(object) = request_body.as_object_mut() else {
+            return Ok(body);
+        };
+        object.remove("model");
+        object.remove("stream");
+        Ok(Bytes::from(serde_json::to_vec(&request_body)?))
+    }
+
     fn process_headers(&self, headers: &HeaderMap) -> Result<HeaderMap, AppError> {
         let mut final_headers = HeaderMap::new();
 
@@ -440,6 +626,19 @@ impl Provider for BedrockProvider {
             http::header::HeaderValue::from_static("application/json"),
         );
 
+        // A role is assumed with whatever identity signs the request. With the gateway's
+        // own credentials that would let any caller act as any role the gateway may
+        // assume, so only configured roles are accepted then.
+        if let Some(role_arn) = headers.get("x-aws-role-arn").and_then(|v| v.to_str().ok()) {
+            if !client_credentials(headers) && !ALLOWED_ROLE_ARNS.iter().any(|allowed| allowed == role_arn.trim()) {
+                warn!("Rejecting x-aws-role-arn {} that is not in BEDROCK_ALLOWED_ROLE_ARNS", role_arn);
+                return Err(AppError::Forbidden(format!(
+                    "Role {} may not be assumed with the gateway's credentials",
+                    role_arn
+                )));
+            }
+        }
+
         // Preserve AWS specific headers
         for (key, value) in headers {
             if key.as_str().starts_with("x-aws-") {
@@ -450,39 +649,13 @@ impl Provider for BedrockProvider {
         Ok(final_headers)
     }
 
-    fn requires_signing(&self) -> bool {
-        true
-    }
-    
-
-
-    fn get_signing_credentials(&self, headers: &HeaderMap) -> Option<(String, String, String)> {
-        let region = headers
-            .get("x-aws-region")
-            .and_then(|h| h.to_str().ok())
-            .map(String::from)
-            .unwrap_or_else(|| self.region.read().clone());
-
-        let access_key = headers
-            .get("x-aws-access-key-id")
-            .and_then(|v| v.to_str().ok().map(|s| s.to_owned()))
-            .or_else(|| self.aws_key.as_ref().map(|arc| arc.read().clone()))?;
-
-        let secret_key = headers
-            .get("x-aws-secret-access-key")
-            .and_then(|v| v.to_str().ok().map(|s| s.to_owned()))
-            .or_else(|| self.aws_secret.as_ref().map(|arc| arc.read().clone()))?;
-        
-        debug!( //
-            "AWS credentials - Access Key: {} s: {}, Region: {}", 
-            mask_key(access_key.as_str()), mask_key(secret_key.as_str()), region,
-        );
-        Some((access_key, secret_key, region))
-    }
-
-    fn get_signing_host(&self) -> String {
-        let region = self.region.read().clone();
-        format!("bedrock-runtime.{}.amazonaws.com", region)
+    fn request_signer(&self, headers: &HeaderMap) -> Option<Box<dyn RequestSigner>> {
+        Some(Box::new(SigV4Signer {
+            service: "bedrock",
+            credentials: self.signing_credentials(headers),
+            region: self.signing_region(headers),
+            role_arn: self.assume_role_arn(headers),
+        }))
     }
 
     async fn process_response(&self, response: Response<Body>) -> Result<Response<Body>, AppError> {
@@ -506,9 +679,9 @@ impl Provider for BedrockProvider {
         {
             debug!("Processing Bedrock event stream response");
 
-            // Create transformed stream
+            // Create transformed stream; the parsed events are also handed to telemetry
             let provider = self.clone();
-            let stream = response
+            let events = response
                 .into_body()
                 .into_data_stream()
                 .map(move |chunk| match chunk {
@@ -533,7 +706,7 @@ impl Provider for BedrockProvider {
                 // CORS headers
                 .header("access-control-allow-origin", "*")
                 .header("access-control-allow-methods", "POST, OPTIONS")
-                .header("access-control-allow-headers", "content-type, x-provider, x-aws-access-key-id, x-aws-secret-access-key, x-aws-region")
+                .header("access-control-allow-headers", "content-type, x-provider, x-aws-access-key-id, x-aws-secret-access-key, x-aws-region, x-aws-guardrail-id, x-aws-guardrail-version")
                 .header("access-control-expose-headers", "*")
                 // SSE specific headers for better client compatibility
                 .header("x-accel-buffering", "no")
@@ -544,8 +717,10 @@ impl Provider for BedrockProvider {
                 builder = builder.header("x-request-id", id);
             }
 
+            let (body, tap) = sse_body(events);
             Ok(builder
-                .body(Body::from_stream(stream))
+                .extension(tap)
+                .body(body)
                 .unwrap())
         } else {
             // For non-streaming responses, transform the body to OpenAI format
@@ -571,11 +746,11 @@ impl Provider for BedrockProvider {
             let bedrock_response: Value = serde_json::from_slice(&bytes)
                 .map_err(|e| AppError::JsonParseError(e.to_string()))?;
             
-            debug!("Original Bedrock response: {:?}", bedrock_response);
+            debug!("Original Bedrock response: {}", redact::value(&bedrock_response));
             
             // Transform to OpenAI format
             let openai_response = self.transform_bedrock_to_openai_format(bedrock_response)?;
-            debug!("Transformed to OpenAI format: {:?}", openai_response);
+            debug!("Transformed to OpenAI format: {}", redact::value(&openai_response));
             
             // Create new response with transformed body
             let transformed_body = serde_json::to_vec(&openai_response)
@@ -600,7 +775,7 @@ impl Provider for BedrockProvider {
             builder = builder
                 .header("access-control-allow-origin", "*")
                 .header("access-control-allow-methods", "POST, OPTIONS")
-                .header("access-control-allow-headers", "content-type, x-provider, x-aws-access-key-id, x-aws-secret-access-key, x-aws-region")
+                .header("access-control-allow-headers", "content-type, x-provider, x-aws-access-key-id, x-aws-secret-access-key, x-aws-region, x-aws-guardrail-id, x-aws-guardrail-version")
                 .header("access-control-expose-headers", "*");
             
             // Add the request ID header if we have one
@@ -620,7 +795,7 @@ pub struct BedrockMetricsExtractor;
 
 impl MetricsExtractor for BedrockMetricsExtractor {
     fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
-        debug!("Extracting Bedrock metrics from response: {}", response_body);
+        debug!("Extracting Bedrock metrics from response: {}", redact::value(response_body));
         let mut metrics = ProviderMetrics::default();
         
         // Try extracting token information from Bedrock format first
@@ -650,6 +825,16 @@ impl MetricsExtractor for BedrockMetricsExtractor {
                 metrics.input_tokens, metrics.output_tokens, metrics.total_tokens);
         }
 
+        // Guardrail outcome added by the response normalization
+        if let Some(action) = response_body
+            .get("guardrail")
+            .and_then(|g| g.get("action"))
+            .and_then(Value::as_str)
+        {
+            debug!("Found Bedrock guardrail action: {}", action);
+            metrics.guardrail_action = Some(action.to_string());
+        }
+
         if let Some(model) = response_body.get("model").and_then(|v| v.as_str()) {
             debug!("Found Bedrock model: {}", model);
             metrics.model = model.to_string();
@@ -679,43 +864,170 @@ impl MetricsExtractor for BedrockMetricsExtractor {
     // Override with Bedrock-specific streaming metrics extraction
     fn try_extract_provider_specific_streaming_metrics(&self, chunk: &str) -> Option<ProviderMetrics> {
         debug!("Attempting Bedrock-specific streaming metrics extraction for chunk");
-        
+
         // Try to parse the chunk as JSON
-        if let Ok(json) = serde_json::from_str::<Value>(chunk) {
-            // Check for indicators that this is a final message with metrics
-            if json.get("usage").is_some() {
-                debug!("Found usage in Bedrock streaming chunk, extracting complete metrics");
-                return Some(self.extract_metrics(&json));
-            }
-            
-            // For ongoing chunks, extract what we can
-            let mut partial_metrics = ProviderMetrics::default();
-            
-            // Try to extract model information if available
-            if let Some(model) = json.get("model").and_then(|m| m.as_str()) {
-                partial_metrics.model = model.to_string();
-            }
-            
-            // Try to extract request ID from various possible locations
-            if let Some(request_id) = json.get("id").and_then(|v| v.as_str()) {
-                debug!("Found request ID in Bedrock streaming chunk: {}", request_id);
-                partial_metrics.request_id = Some(request_id.to_string());
-            } else if let Some(request_id) = json.get("requestId").and_then(|v| v.as_str()) {
-                debug!("Found requestId in Bedrock streaming chunk: {}", request_id);
-                partial_metrics.request_id = Some(request_id.to_string());
-            }
-            
-            // Return partial metrics if we found anything useful
-            if !partial_metrics.model.is_empty() || partial_metrics.request_id.is_some() {
-                debug!("Returning partial Bedrock metrics from streaming chunk");
-                return Some(partial_metrics);
-            }
+        let json = serde_json::from_str::<Value>(chunk).ok()?;
+        self.streaming_metrics(&json)
+    }
+
+    // Bedrock streams are translated into events by the provider, so no re-parse is needed
+    fn extract_streaming_event_metrics(&self, chunk: &Value) -> Option<ProviderMetrics> {
+        self.streaming_metrics(chunk)
+            .or_else(|| self.common_streaming_metrics(chunk))
+    }
+
+    fn estimate_cost(&self, model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        Some(calculate_bedrock_cost(model, input_tokens + output_tokens))
+    }
+}
+
+impl BedrockMetricsExtractor {
+    fn streaming_metrics(&self, json: &Value) -> Option<ProviderMetrics> {
+        // Check for indicators that this is a final message with metrics
+        if json.get("usage").is_some() {
+            debug!("Found usage in Bedrock streaming chunk, extracting complete metrics");
+            return Some(self.extract_metrics(json));
         }
-        
+
+        // For ongoing chunks, extract what we can
+        let mut partial_metrics = ProviderMetrics::default();
+
+        // Try to extract model information if available
+        if let Some(model) = json.get("model").and_then(|m| m.as_str()) {
+            partial_metrics.model = model.to_string();
+        }
+
+        // Try to extract request ID from various possible locations
+        if let Some(request_id) = json.get("id").and_then(|v| v.as_str()) {
+            debug!("Found request ID in Bedrock streaming chunk: {}", request_id);
+            partial_metrics.request_id = Some(request_id.to_string());
+        } else if let Some(request_id) = json.get("requestId").and_then(|v| v.as_str()) {
+            debug!("Found requestId in Bedrock streaming chunk: {}", request_id);
+            partial_metrics.request_id = Some(request_id.to_string());
+        }
+
+        // Return partial metrics if we found anything useful
+        if !partial_metrics.model.is_empty() || partial_metrics.request_id.is_some() {
+            debug!("Returning partial Bedrock metrics from streaming chunk");
+            return Some(partial_metrics);
+        }
+
         None
     }
 }
 
+/// Translate OpenAI message content (a string or multimodal parts) into Converse content blocks
+fn content_blocks(content: &Value) -> Result<Vec<Value>, AppError> {
+    let parts = match content {
+        Value::String(text) => return Ok(vec![json!({ "text": text })]),
+        Value::Array(parts) => parts,
+        Value::Null => return Ok(vec![json!({ "text": "" })]),
+        other => return Ok(vec![json!({ "text": other.to_string() })]),
+    };
+
+    let mut blocks = Vec::with_capacity(parts.len());
+    for (index, part) in parts.iter().enumerate() {
+        match part["type"].as_str() {
+            Some("text") => blocks.push(json!({ "text": part["text"].as_str().unwrap_or_default() })),
+            Some("image_url") => {
+                let url = part["image_url"]["url"].as_str().unwrap_or_default();
+                blocks.push(image_block(url)?);
+            }
+            Some("file") => blocks.push(document_block(&part["file"], index)?),
+            other => warn!("Skipping unsupported content part for Bedrock: {:?}", other),
+        }
+    }
+    Ok(blocks)
+}
+
+/// Media type and base64 payload of a `data:` URL
+fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let media_type = header.strip_suffix(";base64")?;
+    Some((media_type, data))
+}
+
+fn image_block(url: &str) -> Result<Value, AppError> {
+    if url.starts_with("s3://") {
+        let format = url.rsplit('.').next().map_or("png", image_format);
+        return Ok(json!({ "image": { "format": format, "source": { "s3Location": { "uri": url } } } }));
+    }
+    let (media_type, data) = parse_data_url(url).ok_or_else(|| {
+        AppError::RequestError("Bedrock images must be base64 data URLs or s3:// URIs".to_string())
+    })?;
+    let format = image_format(media_type.trim_start_matches("image/"));
+    Ok(json!({ "image": { "format": format, "source": { "bytes": data } } }))
+}
+
+fn image_format(subtype: &str) -> &'static str {
+    match subtype.to_lowercase().as_str() {
+        "jpg" | "jpeg" => "jpeg",
+        "gif" => "gif",
+        "webp" => "webp",
+        _ => "png",
+    }
+}
+
+/// OpenAI `file` part (`file_data` data URL plus `filename`) as a Converse document block
+fn document_block(file: &Value, index: usize) -> Result<Value, AppError> {
+    let file_data = file["file_data"].as_str().unwrap_or_default();
+    let (media_type, data) = parse_data_url(file_data).ok_or_else(|| {
+        AppError::RequestError("Bedrock documents must be sent as base64 file_data".to_string())
+    })?;
+    let filename = file["filename"].as_str().unwrap_or_default();
+    let extension = filename.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+
+    let format = match (media_type, extension.as_deref()) {
+        ("application/pdf", _) | (_, Some("pdf")) => "pdf",
+        ("text/csv", _) | (_, Some("csv")) => "csv",
+        ("application/msword", _) | (_, Some("doc")) => "doc",
+        ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", _) | (_, Some("docx")) => "docx",
+        ("application/vnd.ms-excel", _) | (_, Some("xls")) => "xls",
+        ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", _) | (_, Some("xlsx")) => "xlsx",
+        ("text/html", _) | (_, Some("html" | "htm")) => "html",
+        ("text/markdown", _) | (_, Some("md")) => "md",
+        _ => "txt",
+    };
+
+    // Converse names allow letters, digits, single spaces, hyphens, parentheses and brackets
+    let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '(' | ')' | '[' | ']') { c } else { '-' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let name = if name.is_empty() { format!("document-{}", index + 1) } else { name };
+
+    Ok(json!({ "document": { "format": format, "name": name, "source": { "bytes": data } } }))
+}
+
+/// Maps a Bedrock Converse stop reason to the OpenAI finish_reason
+fn map_stop_reason(stop_reason: &str) -> &'static str {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "guardrail_intervened" | "content_filtered" => "content_filter",
+        _ => "stop",
+    }
+}
+
+/// Builds the normalized `guardrail` object from the stop reason and guardrail trace, if any
+fn guardrail_result(stop_reason: Option<&str>, trace: Option<&Value>) -> Option<Value> {
+    let trace = trace.and_then(|t| t.get("guardrail")).cloned();
+    let intervened = stop_reason == Some("guardrail_intervened");
+    if trace.is_none() && !intervened {
+        return None;
+    }
+
+    Some(json!({
+        "action": if intervened { "INTERVENED" } else { "NONE" },
+        "trace": trace
+    }))
+}
+
 // Helper function for Bedrock-specific cost calculation
 fn calculate_bedrock_cost(model: &str, total_tokens: u32) -> f64 {
     match model {
@@ -0,0 +1,375 @@
+use super::create_provider;
+use crate::cluster;
+use crate::error::AppError;
+use crate::shared_state;
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// How a provider's model list is requested and read
+struct ListEndpoint {
+    provider: &'static str,
+    path: &'static str,
+    auth: Auth,
+}
+
+enum Auth {
+    Bearer,
+    /// Anthropic's `x-api-key` with a pinned API version
+    AnthropicKey,
+    /// Google's `x-goog-api-key`; as a `key` query parameter it would show up in request errors
+    GoogleKey,
+}
+
+/// Providers whose model lists can be fetched; the key comes from `<PROVIDER>_API_KEY`
+const ENDPOINTS: &[ListEndpoint] = &[
+    ListEndpoint { provider: "openai", path: "/v1/models", auth: Auth::Bearer },
+    ListEndpoint { provider: "groq", path: "/v1/models", auth: Auth::Bearer },
+    ListEndpoint { provider: "together", path: "/v1/models", auth: Auth::Bearer },
+    ListEndpoint { provider: "fireworks", path: "/models", auth: Auth::Bearer },
+    ListEndpoint { provider: "anthropic", path: "/v1/models?limit=1000", auth: Auth::AnthropicKey },
+    ListEndpoint { provider: "google-ai", path: "/v1beta/models?pageSize=1000", auth: Auth::GoogleKey },
+];
+
+/// Most close matches suggested for an unknown model
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Requests are checked against the cached lists when `MODEL_VALIDATION=true`
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("MODEL_VALIDATION")
+        .map(|v| v.parse().unwrap_or(false))
+        .unwrap_or(false)
+});
+
+static REFRESH_INTERVAL: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(
+        std::env::var("MODEL_REFRESH_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600),
+    )
+});
+
+/// Client for model list calls; the shared proxy client only speaks HTTP/2
+static CATALOG_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(20))
+        .build()
+        .expect("Failed to create model catalog HTTP client")
+});
+
+/// Shared-state key the cluster leader publishes the model lists under
+const SHARED_KEY: &str = "catalog";
+
+#[derive(Serialize, Deserialize)]
+struct ModelList {
+    models: HashSet<String>,
+    fetched_at: DateTime<Utc>,
+}
+
+/// Last successfully fetched model list per provider
+static CATALOG: Lazy<RwLock<HashMap<&'static str, ModelList>>> = Lazy::new(Default::default);
+
+fn api_key(provider: &str) -> Option<String> {
+    std::env::var(format!("{}_API_KEY", provider.to_uppercase().replace('-', "_")))
+        .ok()
+        .filter(|key| !key.is_empty())
+}
+
+async fn fetch(endpoint: &ListEndpoint, api_key: &str) -> Result<HashSet<String>, AppError> {
+    let url = format!("{}{}", create_provider(endpoint.provider)?.base_url(), endpoint.path);
+    let request = match endpoint.auth {
+        Auth::Bearer => CATALOG_CLIENT.get(&url).bearer_auth(api_key),
+        Auth::AnthropicKey => CATALOG_CLIENT
+            .get(&url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01"),
+        Auth::GoogleKey => CATALOG_CLIENT.get(&url).header("x-goog-api-key", api_key),
+    };
+    let body: Value = request.send().await?.error_for_status()?.json().await?;
+
+    // OpenAI-style `data`, Google's `models`, or Together's bare array
+    let entries = body
+        .get("data")
+        .or_else(|| body.get("models"))
+        .unwrap_or(&body)
+        .as_array()
+        .ok_or_else(|| AppError::InvalidProviderResponse(format!("unexpected {} model list", endpoint.provider)))?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| entry.get("id").or_else(|| entry.get("name")).and_then(Value::as_str))
+        .map(|id| id.strip_prefix("models/").unwrap_or(id).to_string())
+        .collect())
+}
+
+/// Refetch every provider's model list that has an API key configured,
+/// keeping the previous list when a fetch fails
+pub async fn refresh() {
+    for endpoint in ENDPOINTS {
+        let Some(key) = api_key(endpoint.provider) else {
+            continue;
+        };
+        match fetch(endpoint, &key).await {
+            Ok(models) if !models.is_empty() => {
+                debug!("Fetched {} models for {}", models.len(), endpoint.provider);
+                CATALOG.write().insert(
+                    endpoint.provider,
+                    ModelList {
+                        models,
+                        fetched_at: Utc::now(),
+                    },
+                );
+            }
+            Ok(_) => warn!("{} returned an empty model list; keeping the cached one", endpoint.provider),
+            Err(e) => warn!("Failed to refresh {} model list: {}", endpoint.provider, e),
+        }
+    }
+}
+
+/// Whether a provider accepted the configured key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthStatus {
+    Ok,
+    /// The provider rejected the key (401 or 403)
+    Unauthorized,
+    /// No `<PROVIDER>_API_KEY` is configured
+    NoCredentials,
+    /// The provider couldn't be reached or timed out
+    Unreachable,
+    Error,
+}
+
+/// Outcome of listing one provider's models with its configured key
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderCheck {
+    pub provider: &'static str,
+    pub auth: AuthStatus,
+    /// Models listed when the call succeeded
+    pub models: Option<usize>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Check a provider's credentials and connectivity by listing its models,
+/// caching the list when it comes back
+async fn check(endpoint: &ListEndpoint) -> ProviderCheck {
+    let mut check = ProviderCheck {
+        provider: endpoint.provider,
+        auth: AuthStatus::NoCredentials,
+        models: None,
+        latency_ms: 0,
+        error: None,
+        checked_at: Utc::now(),
+    };
+    let Some(key) = api_key(endpoint.provider) else {
+        return check;
+    };
+
+    let started = Instant::now();
+    let result = fetch(endpoint, &key).await;
+    check.latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(models) => {
+            check.auth = AuthStatus::Ok;
+            check.models = Some(models.len());
+            CATALOG.write().insert(
+                endpoint.provider,
+                ModelList {
+                    models,
+                    fetched_at: Utc::now(),
+                },
+            );
+        }
+        Err(e) => {
+            check.auth = match &e {
+                AppError::ReqwestError(err)
+                    if err
+                        .status()
+                        .is_some_and(|s| s == StatusCode::UNAUTHORIZED || s == StatusCode::FORBIDDEN) =>
+                {
+                    AuthStatus::Unauthorized
+                }
+                AppError::ReqwestError(err) if err.is_connect() || err.is_timeout() => AuthStatus::Unreachable,
+                _ => AuthStatus::Error,
+            };
+            warn!("Credential check for {} failed: {}", endpoint.provider, e);
+            check.error = Some(e.to_string());
+        }
+    }
+    check
+}
+
+/// Check one provider; None when it has no model list to test against
+pub async fn check_provider(provider: &str) -> Option<ProviderCheck> {
+    let endpoint = ENDPOINTS.iter().find(|e| e.provider.eq_ignore_ascii_case(provider))?;
+    Some(check(endpoint).await)
+}
+
+/// Check every provider with a model list endpoint
+pub async fn check_providers() -> Vec<ProviderCheck> {
+    let mut checks = Vec::new();
+    for endpoint in ENDPOINTS {
+        checks.push(check(endpoint).await);
+    }
+    checks
+}
+
+/// Cached model ids for a provider, sorted
+pub fn models(provider: &str) -> Vec<String> {
+    let mut models: Vec<String> = CATALOG
+        .read()
+        .get(provider.to_lowercase().as_str())
+        .map(|list| list.models.iter().cloned().collect())
+        .unwrap_or_default();
+    models.sort();
+    models
+}
+
+/// Providers whose model lists can be fetched
+pub fn listable_providers() -> impl Iterator<Item = &'static str> {
+    ENDPOINTS.iter().map(|endpoint| endpoint.provider)
+}
+
+/// Publish the cached model lists for the rest of the cluster
+async fn publish(shared: &shared_state::SharedState) {
+    let lists: HashMap<&str, Value> = CATALOG
+        .read()
+        .iter()
+        .filter_map(|(provider, list)| Some((*provider, serde_json::to_value(list).ok()?)))
+        .collect();
+    if let Err(e) = shared.put_json(SHARED_KEY, &lists, *REFRESH_INTERVAL * 2).await {
+        warn!("Failed to publish model lists: {}", e);
+    }
+}
+
+/// Adopt the model lists published by the cluster leader; false when there are none
+async fn load_published(shared: &shared_state::SharedState) -> bool {
+    let lists: HashMap<String, ModelList> = match shared.get_json(SHARED_KEY).await {
+        Ok(Some(lists)) => lists,
+        Ok(None) => return false,
+        Err(e) => {
+            warn!("Failed to load published model lists: {}", e);
+            return false;
+        }
+    };
+    let mut catalog = CATALOG.write();
+    for (provider, list) in lists {
+        if let Some(endpoint) = ENDPOINTS.iter().find(|e| e.provider == provider) {
+            catalog.insert(endpoint.provider, list);
+        }
+    }
+    true
+}
+
+/// Periodically refresh the model lists when validation is enabled. In a
+/// cluster the leader fetches and publishes them and the other replicas load
+/// its copy, fetching their own only until one is published.
+pub fn spawn_refresh() {
+    if !*ENABLED {
+        return;
+    }
+    info!("Model validation enabled; refreshing model lists every {:?}", *REFRESH_INTERVAL);
+    tokio::spawn(async {
+        let mut ticker = tokio::time::interval(*REFRESH_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let shared = shared_state::get().await;
+            match shared {
+                Some(shared) if !cluster::is_leader() => {
+                    if !load_published(shared).await {
+                        refresh().await;
+                    }
+                }
+                _ => {
+                    refresh().await;
+                    if let Some(shared) = shared {
+                        publish(shared).await;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Reject a request for a model the provider doesn't list. Providers without
+/// a cached list (no key configured, or never fetched) are not checked.
+pub fn validate(provider: &str, body: &Bytes) -> Result<(), AppError> {
+    if !*ENABLED {
+        return Ok(());
+    }
+    let catalog = CATALOG.read();
+    let Some(list) = catalog.get(provider.to_lowercase().as_str()) else {
+        return Ok(());
+    };
+    let Some(model) = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|json| json.get("model").and_then(Value::as_str).map(String::from))
+    else {
+        return Ok(());
+    };
+    if is_listed(&model, &list.models) {
+        return Ok(());
+    }
+    debug!("Model {} not in {} list fetched at {}", model, provider, list.fetched_at);
+    Err(AppError::UnknownModel {
+        suggestions: suggestions(&model, &list.models),
+        model,
+        provider: provider.to_string(),
+    })
+}
+
+/// Exact ids, plus `-latest` aliases (Anthropic) for a listed dated snapshot
+fn is_listed(model: &str, models: &HashSet<String>) -> bool {
+    if models.contains(model) {
+        return true;
+    }
+    model
+        .strip_suffix("-latest")
+        .is_some_and(|base| models.iter().any(|m| m.starts_with(base)))
+}
+
+/// Closest listed models by edit distance, ignoring ones too different to be typos
+fn suggestions(model: &str, models: &HashSet<String>) -> Vec<String> {
+    let wanted = model.to_lowercase();
+    let max_distance = (wanted.len() / 3).max(2);
+    let mut scored: Vec<(usize, &String)> = models
+        .iter()
+        .map(|candidate| {
+            let candidate_lower = candidate.to_lowercase();
+            // A listed model that extends the requested name (e.g. a dated snapshot) is a strong match
+            let distance = if candidate_lower.starts_with(&wanted) {
+                0
+            } else {
+                edit_distance(&wanted, &candidate_lower)
+            };
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    scored.sort();
+    scored.into_iter().take(MAX_SUGGESTIONS).map(|(_, m)| m.clone()).collect()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
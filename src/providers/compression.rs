@@ -0,0 +1,299 @@
+use super::audio_content;
+use crate::telemetry::provider_metrics::ProviderMetrics;
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Request header choosing the compression strategy for this call
+const COMPRESSION_HEADER: &str = "x-history-compression";
+
+const DEFAULT_SUMMARY_URL: &str = "https://api.openai.com/v1/chat/completions";
+const DEFAULT_SUMMARY_MODEL: &str = "gpt-4o-mini";
+
+/// Output tokens reserved when the request doesn't set `max_tokens`
+const DEFAULT_OUTPUT_RESERVE: u32 = 4096;
+
+/// Context windows for common models, matched by longest prefix
+const CONTEXT_WINDOWS: &[(&str, u32)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    ("claude", 200_000),
+    ("anthropic.claude", 200_000),
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini", 1_048_576),
+    ("llama-3.1", 131_072),
+    ("llama-3.3", 131_072),
+    ("llama3", 8_192),
+    ("meta.llama3-1", 131_072),
+    ("mixtral-8x7b", 32_768),
+    ("accounts/fireworks/models/llama-v3p1", 131_072),
+    ("meta-llama/Llama-3.3", 131_072),
+];
+
+/// How an over-long history is shortened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionMode {
+    Off,
+    /// Drop whole turns from the middle, keeping the opening and latest turns
+    MiddleOut,
+    /// Replace the dropped turns with a summary from a small model
+    Summarize,
+}
+
+impl CompressionMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().replace('_', "-").as_str() {
+            "off" | "false" | "none" => Some(Self::Off),
+            "middle-out" | "true" => Some(Self::MiddleOut),
+            "summarize" | "summarise" => Some(Self::Summarize),
+            _ => None,
+        }
+    }
+}
+
+/// Gateway-wide mode from `HISTORY_COMPRESSION`
+static DEFAULT_MODE: Lazy<CompressionMode> = Lazy::new(|| {
+    std::env::var("HISTORY_COMPRESSION")
+        .ok()
+        .and_then(|v| CompressionMode::parse(&v))
+        .unwrap_or(CompressionMode::Off)
+});
+
+/// Context window overrides from `MODEL_CONTEXT_WINDOWS`, e.g. `{"my-finetune": 32768}`
+static WINDOW_OVERRIDES: Lazy<HashMap<String, u32>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("MODEL_CONTEXT_WINDOWS") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, u32>>(&raw) {
+        Ok(windows) => {
+            info!("Loaded context windows for {} models", windows.len());
+            windows
+        }
+        Err(e) => {
+            error!("Failed to parse MODEL_CONTEXT_WINDOWS: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// Client for summarization calls; the shared proxy client only speaks HTTP/2
+static SUMMARY_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(20))
+        .build()
+        .expect("Failed to create summarization HTTP client")
+});
+
+/// Context window for a model, from the overrides or the built-in table
+pub fn context_window(model: &str) -> Option<u32> {
+    if let Some(window) = WINDOW_OVERRIDES.get(model) {
+        return Some(*window);
+    }
+    CONTEXT_WINDOWS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, window)| *window)
+}
+
+fn requested_mode(headers: &HeaderMap) -> CompressionMode {
+    headers
+        .get(COMPRESSION_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(CompressionMode::parse)
+        .unwrap_or(*DEFAULT_MODE)
+}
+
+fn estimate_tokens(value: &Value) -> u32 {
+    match value {
+        Value::String(text) => ProviderMetrics::estimate_tokens_from_text(text),
+        other => ProviderMetrics::estimate_tokens_from_text(&audio_content::without_audio(other).to_string()),
+    }
+}
+
+/// Shorten a chat body whose estimated prompt no longer fits the model's
+/// context window, leaving every other body untouched
+pub async fn compress_history(provider: &str, headers: &HeaderMap, body: Bytes) -> Bytes {
+    let mode = requested_mode(headers);
+    if mode == CompressionMode::Off {
+        return body;
+    }
+    let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+        return body;
+    };
+    let Some(window) = json.get("model").and_then(Value::as_str).and_then(context_window) else {
+        return body;
+    };
+    let reserve = ["max_tokens", "max_completion_tokens"]
+        .iter()
+        .find_map(|key| json.get(*key).and_then(Value::as_u64))
+        .map_or(DEFAULT_OUTPUT_RESERVE.min(window / 4), |tokens| tokens as u32);
+    let budget = window.saturating_sub(reserve);
+
+    let system_tokens = json.get("system").map_or(0, estimate_tokens);
+    let Some(messages) = json.get_mut("messages").and_then(Value::as_array_mut) else {
+        return body;
+    };
+    let message_tokens: Vec<u32> = messages.iter().map(estimate_tokens).collect();
+    let total: u32 = system_tokens + message_tokens.iter().sum::<u32>();
+    if total <= budget {
+        return body;
+    }
+
+    let Some(dropped) = middle_out(messages, &message_tokens, total - budget) else {
+        warn!("Estimated prompt of {} tokens exceeds the {} token budget but the history can't be shortened", total, budget);
+        return body;
+    };
+
+    let summary = if mode == CompressionMode::Summarize {
+        let removed: Vec<&Value> = dropped.iter().map(|&i| &messages[i]).collect();
+        summarize(&removed).await
+    } else {
+        None
+    };
+
+    let first_dropped = dropped[0];
+    let mut index = 0;
+    messages.retain(|_| {
+        let keep = !dropped.contains(&index);
+        index += 1;
+        keep
+    });
+    info!(
+        "Compressed history for {}: dropped {} messages (~{} tokens over a {} token budget){}",
+        provider,
+        dropped.len(),
+        total - budget,
+        budget,
+        if summary.is_some() { ", replaced with a summary" } else { "" }
+    );
+
+    if let Some(summary) = summary {
+        let note = format!("Summary of earlier conversation:\n{}", summary);
+        // Anthropic takes system text as a top-level field rather than a message
+        if provider.eq_ignore_ascii_case("anthropic") {
+            match json.get_mut("system") {
+                Some(Value::String(existing)) => *existing = format!("{}\n\n{}", existing, note),
+                Some(Value::Array(blocks)) => blocks.push(json!({ "type": "text", "text": note })),
+                _ => json["system"] = Value::String(note),
+            }
+        } else if let Some(messages) = json.get_mut("messages").and_then(Value::as_array_mut) {
+            messages.insert(first_dropped.min(messages.len()), json!({ "role": "system", "content": note }));
+        }
+    }
+
+    serde_json::to_vec(&json).map(Bytes::from).unwrap_or(body)
+}
+
+/// Indices of the turns to drop, working outwards from the middle of the
+/// conversation until `excess` tokens are freed. A turn is a user message and
+/// the assistant and tool messages answering it, so tool calls stay paired.
+fn middle_out(messages: &[Value], tokens: &[u32], excess: u32) -> Option<Vec<usize>> {
+    let role = |i: usize| messages[i].get("role").and_then(Value::as_str).unwrap_or_default();
+    // Anthropic returns tool results in user messages; those continue the turn
+    let is_tool_result = |i: usize| {
+        messages[i]
+            .get("content")
+            .and_then(Value::as_array)
+            .is_some_and(|blocks| blocks.iter().any(|b| b["type"] == "tool_result"))
+    };
+    let mut turns: Vec<Vec<usize>> = Vec::new();
+    for i in 0..messages.len() {
+        match role(i) {
+            "system" | "developer" => {}
+            "user" if !is_tool_result(i) => turns.push(vec![i]),
+            _ => match turns.last_mut() {
+                Some(turn) => turn.push(i),
+                None => turns.push(vec![i]),
+            },
+        }
+    }
+    // The opening turn and the turn being answered always stay
+    if turns.len() < 3 {
+        return None;
+    }
+
+    let middle = &turns[1..turns.len() - 1];
+    let mut order: Vec<usize> = (0..middle.len()).collect();
+    let center = middle.len() / 2;
+    order.sort_by_key(|&i| i.abs_diff(center));
+
+    let mut freed = 0;
+    let mut dropped = Vec::new();
+    for turn in order.into_iter().map(|i| &middle[i]) {
+        freed += turn.iter().map(|&i| tokens[i]).sum::<u32>();
+        dropped.extend_from_slice(turn);
+        if freed >= excess {
+            break;
+        }
+    }
+    if freed < excess {
+        debug!("Dropping every middle turn frees only {} of {} excess tokens", freed, excess);
+    }
+    dropped.sort_unstable();
+    Some(dropped)
+}
+
+/// Summarize dropped messages with a small OpenAI-compatible model; `None` falls back to dropping them
+async fn summarize(messages: &[&Value]) -> Option<String> {
+    let Ok(api_key) = std::env::var("HISTORY_SUMMARY_API_KEY") else {
+        warn!("HISTORY_SUMMARY_API_KEY is not set; compressing history without a summary");
+        return None;
+    };
+    let url = std::env::var("HISTORY_SUMMARY_URL").unwrap_or_else(|_| DEFAULT_SUMMARY_URL.to_string());
+    let model = std::env::var("HISTORY_SUMMARY_MODEL").unwrap_or_else(|_| DEFAULT_SUMMARY_MODEL.to_string());
+
+    let transcript = messages
+        .iter()
+        .map(|m| {
+            let role = m.get("role").and_then(Value::as_str).unwrap_or("unknown");
+            let content = match m.get("content") {
+                Some(Value::String(text)) => text.clone(),
+                Some(other) => other.to_string(),
+                None => m.get("tool_calls").map(Value::to_string).unwrap_or_default(),
+            };
+            format!("{}: {}", role, content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let request = json!({
+        "model": model,
+        "max_tokens": 512,
+        "messages": [
+            {
+                "role": "system",
+                "content": "Summarize this conversation excerpt concisely. Keep facts, decisions, names and open questions needed to continue the conversation.",
+            },
+            { "role": "user", "content": transcript },
+        ],
+    });
+
+    let response = SUMMARY_CLIENT
+        .post(&url)
+        .bearer_auth(api_key)
+        .json(&request)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    let body = match response {
+        Ok(response) => response.json::<Value>().await.ok()?,
+        Err(e) => {
+            warn!("History summarization failed, dropping messages instead: {}", e);
+            return None;
+        }
+    };
+    body.pointer("/choices/0/message/content")
+        .and_then(Value::as_str)
+        .map(String::from)
+}
@@ -0,0 +1,177 @@
+use super::Provider;
+use super::utils::log_tracking_headers;
+use crate::error::AppError;
+use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderValue};
+use serde_json::Value;
+use tracing::{debug, error};
+
+/// Deepgram speech-to-text and text-to-speech
+///
+/// `/v1/listen` takes raw audio (or a JSON `url`) and `/v1/speak` returns audio;
+/// both bodies pass through untouched, with options in the query string.
+pub struct DeepgramProvider {
+    base_url: String,
+}
+
+impl DeepgramProvider {
+    pub fn new() -> Self {
+        Self {
+            base_url: "https://api.deepgram.com".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for DeepgramProvider {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn name(&self) -> &str {
+        "deepgram"
+    }
+
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &["/v1/listen", "/v1/speak", "/v1/models"]
+    }
+
+    fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
+        debug!("Processing Deepgram request headers");
+        let mut headers = HeaderMap::new();
+
+        // Log tracking headers for observability
+        log_tracking_headers(original_headers);
+
+        // Audio uploads are sent as-is, so the client's content type must be kept
+        if let Some(content_type) = original_headers.get(http::header::CONTENT_TYPE) {
+            headers.insert(http::header::CONTENT_TYPE, content_type.clone());
+        }
+
+        // Deepgram expects `Token <key>`; convert a Bearer key for OpenAI-style clients
+        let key = original_headers
+            .get(http::header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|auth| auth.strip_prefix("Bearer ").or_else(|| auth.strip_prefix("Token ")))
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| {
+                error!("No authorization header found for Deepgram request");
+                AppError::MissingApiKey
+            })?;
+        headers.insert(
+            http::header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Token {}", key)).map_err(|_| {
+                error!("Invalid characters in Deepgram authorization header");
+                AppError::InvalidHeader
+            })?,
+        );
+
+        Ok(headers)
+    }
+}
+
+// Deepgram-specific metrics extractor; transcription is billed per minute
+// of audio and speech per character
+pub struct DeepgramMetricsExtractor;
+
+impl MetricsExtractor for DeepgramMetricsExtractor {
+    fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
+        debug!("Extracting Deepgram metrics from response: {}", redact::value(response_body));
+        let mut metrics = ProviderMetrics::default();
+
+        let Some(metadata) = response_body.get("metadata") else {
+            return metrics;
+        };
+
+        if let Some(request_id) = metadata.get("request_id").and_then(Value::as_str) {
+            metrics.request_id = Some(request_id.to_string());
+        }
+
+        // model_info is keyed by model UUID; a single-model request has one entry
+        metrics.model = metadata
+            .get("model_info")
+            .and_then(Value::as_object)
+            .and_then(|info| info.values().next())
+            .and_then(|model| model.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_STT_MODEL)
+            .to_string();
+
+        if let Some(seconds) = metadata.get("duration").and_then(Value::as_f64) {
+            metrics.audio_seconds = Some(seconds);
+            metrics.cost = Some(seconds / 60.0 * stt_price_per_minute(&metrics.model));
+            debug!(
+                "Calculated Deepgram cost: {:?} for model {} and {} seconds of audio",
+                metrics.cost, metrics.model, seconds
+            );
+        }
+
+        metrics
+    }
+
+    fn extract_request_metrics(
+        &self,
+        path: &str,
+        request_body: Option<&Value>,
+        headers: &HeaderMap,
+    ) -> Option<ProviderMetrics> {
+        if !path.starts_with("/v1/speak") {
+            return None;
+        }
+        let mut metrics = ProviderMetrics::default();
+
+        // Text-to-speech reports the billed characters and model in response headers
+        metrics.model = headers
+            .get("dg-model-name")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or(DEFAULT_TTS_MODEL)
+            .to_string();
+        metrics.request_id = headers
+            .get("dg-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+
+        let characters = headers
+            .get("dg-char-count")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u32>().ok())
+            .or_else(|| {
+                request_body
+                    .and_then(|request| request.get("text"))
+                    .and_then(Value::as_str)
+                    .map(|text| text.chars().count() as u32)
+            })?;
+
+        metrics.characters = Some(characters);
+        metrics.cost = Some(characters as f64 * tts_price_per_thousand(&metrics.model) / 1000.0);
+        debug!(
+            "Calculated Deepgram cost: {:?} for model {} and {} characters",
+            metrics.cost, metrics.model, characters
+        );
+
+        Some(metrics)
+    }
+}
+
+const DEFAULT_STT_MODEL: &str = "nova-3";
+const DEFAULT_TTS_MODEL: &str = "aura-2";
+
+// Pre-recorded transcription price per minute of audio in USD
+fn stt_price_per_minute(model: &str) -> f64 {
+    if model.contains("whisper") {
+        0.0048
+    } else {
+        0.0043
+    }
+}
+
+// Price per thousand characters in USD
+fn tts_price_per_thousand(model: &str) -> f64 {
+    if model.starts_with("aura-2") {
+        0.030
+    } else {
+        0.015
+    }
+}
@@ -0,0 +1,127 @@
+use crate::identity;
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::{debug, error, info};
+
+/// Defaults merged into requests that don't set the corresponding field
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DefaultParams {
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u64>,
+    pub metadata: Option<Value>,
+    pub system_prompt: Option<String>,
+    /// `service_tier` for providers that offer tiers (OpenAI, Anthropic), e.g. `flex` for batch-like projects
+    pub service_tier: Option<String>,
+}
+
+impl DefaultParams {
+    /// Fill in fields from `fallback` that this set leaves unset
+    fn or(self, fallback: &DefaultParams) -> DefaultParams {
+        DefaultParams {
+            temperature: self.temperature.or(fallback.temperature),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            metadata: self.metadata.or_else(|| fallback.metadata.clone()),
+            system_prompt: self.system_prompt.or_else(|| fallback.system_prompt.clone()),
+            service_tier: self.service_tier.or_else(|| fallback.service_tier.clone()),
+        }
+    }
+}
+
+/// Per-org defaults with optional per-project overrides
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct OrgDefaults {
+    #[serde(flatten)]
+    params: DefaultParams,
+    #[serde(default)]
+    projects: HashMap<String, DefaultParams>,
+}
+
+/// Defaults from `ORG_DEFAULTS`, keyed by org id
+static ORG_DEFAULTS: Lazy<HashMap<String, OrgDefaults>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("ORG_DEFAULTS") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, OrgDefaults>>(&raw) {
+        Ok(defaults) => {
+            info!("Loaded default parameters for {} orgs", defaults.len());
+            defaults
+        }
+        Err(e) => {
+            error!("Failed to parse ORG_DEFAULTS: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// Defaults for the org/project in the tracking headers, project settings first
+fn defaults_for(headers: &HeaderMap) -> Option<DefaultParams> {
+    let identity = identity::from_headers(headers);
+    let org = ORG_DEFAULTS.get(identity.org_id.as_deref()?)?;
+
+    let project = identity.project_id.as_deref().and_then(|p| org.projects.get(p));
+    Some(match project {
+        Some(project) => project.clone().or(&org.params),
+        None => org.params.clone(),
+    })
+}
+
+/// Merge the org/project defaults into a JSON chat body where fields are absent
+pub fn apply_defaults(provider: &str, headers: &HeaderMap, body: Bytes) -> Bytes {
+    if ORG_DEFAULTS.is_empty() {
+        return body;
+    }
+    let Some(defaults) = defaults_for(headers) else {
+        return body;
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+        return body;
+    };
+    let Some(object) = json.as_object_mut() else {
+        return body;
+    };
+    // Message Batch bodies wrap complete requests, which the provider validates as they are
+    if object.contains_key("requests") {
+        return body;
+    }
+
+    if let Some(temperature) = defaults.temperature {
+        object.entry("temperature").or_insert(json!(temperature));
+    }
+    if let Some(max_tokens) = defaults.max_tokens {
+        object.entry("max_tokens").or_insert(json!(max_tokens));
+    }
+    if let Some(metadata) = defaults.metadata {
+        object.entry("metadata").or_insert(metadata);
+    }
+
+    if let Some(service_tier) = defaults.service_tier {
+        if ["openai", "anthropic"].iter().any(|p| provider.eq_ignore_ascii_case(p)) {
+            object.entry("service_tier").or_insert(json!(service_tier));
+        }
+    }
+
+    if let Some(system_prompt) = defaults.system_prompt {
+        let has_system_message = object
+            .get("messages")
+            .and_then(Value::as_array)
+            .map_or(false, |messages| messages.iter().any(|m| m["role"] == "system"));
+
+        // Anthropic takes the system prompt as a top-level field
+        if provider.eq_ignore_ascii_case("anthropic") {
+            if !has_system_message {
+                object.entry("system").or_insert(json!(system_prompt));
+            }
+        } else if !has_system_message {
+            if let Some(messages) = object.get_mut("messages").and_then(Value::as_array_mut) {
+                messages.insert(0, json!({ "role": "system", "content": system_prompt }));
+            }
+        }
+    }
+
+    debug!("Applied org default parameters for {}", provider);
+    serde_json::to_vec(&json).map(Bytes::from).unwrap_or(body)
+}
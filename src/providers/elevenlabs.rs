@@ -0,0 +1,167 @@
+use super::Provider;
+use super::utils::log_tracking_headers;
+use crate::error::AppError;
+use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderValue};
+use serde_json::Value;
+use tracing::{debug, error};
+
+/// ElevenLabs text-to-speech and speech-to-text
+///
+/// Bodies pass through untouched: text-to-speech takes JSON and returns audio,
+/// speech-to-text takes a multipart upload and returns JSON.
+pub struct ElevenLabsProvider {
+    base_url: String,
+}
+
+impl ElevenLabsProvider {
+    pub fn new() -> Self {
+        Self {
+            base_url: "https://api.elevenlabs.io".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for ElevenLabsProvider {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn name(&self) -> &str {
+        "elevenlabs"
+    }
+
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &["/v1/text-to-speech", "/v1/speech-to-text", "/v1/voices", "/v1/models"]
+    }
+
+    fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
+        debug!("Processing ElevenLabs request headers");
+        let mut headers = HeaderMap::new();
+
+        // Log tracking headers for observability
+        log_tracking_headers(original_headers);
+
+        // Keep the client's content type so multipart boundaries survive
+        if let Some(content_type) = original_headers.get(http::header::CONTENT_TYPE) {
+            headers.insert(http::header::CONTENT_TYPE, content_type.clone());
+        }
+        if let Some(accept) = original_headers.get(http::header::ACCEPT) {
+            headers.insert(http::header::ACCEPT, accept.clone());
+        }
+
+        // ElevenLabs authenticates with xi-api-key; accept a Bearer token as well
+        let key = original_headers
+            .get("xi-api-key")
+            .and_then(|h| h.to_str().ok())
+            .or_else(|| {
+                original_headers
+                    .get(http::header::AUTHORIZATION)
+                    .and_then(|h| h.to_str().ok())
+                    .and_then(|auth| auth.strip_prefix("Bearer "))
+            })
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| {
+                error!("No API key found for ElevenLabs request");
+                AppError::MissingApiKey
+            })?;
+        headers.insert(
+            "xi-api-key",
+            HeaderValue::from_str(key).map_err(|_| {
+                error!("Invalid characters in ElevenLabs API key");
+                AppError::InvalidHeader
+            })?,
+        );
+
+        Ok(headers)
+    }
+}
+
+// ElevenLabs-specific metrics extractor; speech is billed per character
+// and transcription per second of audio
+pub struct ElevenLabsMetricsExtractor;
+
+impl MetricsExtractor for ElevenLabsMetricsExtractor {
+    fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
+        debug!("Extracting ElevenLabs metrics from response: {}", redact::value(response_body));
+        let mut metrics = ProviderMetrics::default();
+
+        // Speech-to-text returns word timings; the last word ends at the audio duration
+        let Some(words) = response_body.get("words").and_then(Value::as_array) else {
+            return metrics;
+        };
+        let seconds = words
+            .iter()
+            .filter_map(|word| word.get("end").and_then(Value::as_f64))
+            .fold(0.0, f64::max);
+
+        metrics.model = response_body
+            .get("model_id")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_STT_MODEL)
+            .to_string();
+        metrics.audio_seconds = Some(seconds);
+        metrics.cost = Some(seconds * STT_PRICE_PER_HOUR / 3600.0);
+        debug!("Calculated ElevenLabs cost: {:?} for {} seconds of audio", metrics.cost, seconds);
+
+        metrics
+    }
+
+    fn extract_request_metrics(
+        &self,
+        path: &str,
+        request_body: Option<&Value>,
+        headers: &HeaderMap,
+    ) -> Option<ProviderMetrics> {
+        if !path.starts_with("/v1/text-to-speech") {
+            return None;
+        }
+        let request = request_body?;
+        let mut metrics = ProviderMetrics::default();
+
+        metrics.model = request
+            .get("model_id")
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_TTS_MODEL)
+            .to_string();
+
+        // Prefer the provider's own count, which reflects its character normalization
+        let characters = headers
+            .get("character-cost")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u32>().ok())
+            .or_else(|| {
+                request
+                    .get("text")
+                    .and_then(Value::as_str)
+                    .map(|text| text.chars().count() as u32)
+            })?;
+
+        metrics.characters = Some(characters);
+        metrics.cost = Some(characters as f64 * tts_price_per_thousand(&metrics.model) / 1000.0);
+        debug!(
+            "Calculated ElevenLabs cost: {:?} for model {} and {} characters",
+            metrics.cost, metrics.model, characters
+        );
+
+        Some(metrics)
+    }
+}
+
+const DEFAULT_TTS_MODEL: &str = "eleven_multilingual_v2";
+const DEFAULT_STT_MODEL: &str = "scribe_v1";
+
+// Price per hour of transcribed audio in USD
+const STT_PRICE_PER_HOUR: f64 = 0.40;
+
+// Price per thousand characters in USD; Flash and Turbo models cost half a credit per character
+fn tts_price_per_thousand(model: &str) -> f64 {
+    if model.contains("flash") || model.contains("turbo") {
+        0.15
+    } else {
+        0.30
+    }
+}
@@ -0,0 +1,90 @@
+use aws_event_stream_parser::parse_message;
+use axum::body::Bytes;
+use serde_json::Value;
+use tracing::debug;
+
+/// Smallest well-formed message: prelude (12 bytes) plus the message CRC
+const MIN_MESSAGE_LEN: usize = 16;
+
+/// Encode one AWS event stream message with string headers and a JSON payload,
+/// as Bedrock frames the events of a native stream
+pub fn encode_message(message_type: &str, event_type: &str, payload: &Value) -> Bytes {
+    let type_header = if message_type == "exception" { ":exception-type" } else { ":event-type" };
+    let mut headers = Vec::new();
+    for (name, value) in [
+        (":message-type", message_type),
+        (type_header, event_type),
+        (":content-type", "application/json"),
+    ] {
+        headers.push(name.len() as u8);
+        headers.extend_from_slice(name.as_bytes());
+        headers.push(7); // string value
+        headers.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        headers.extend_from_slice(value.as_bytes());
+    }
+
+    let payload = payload.to_string().into_bytes();
+    let total_len = (12 + headers.len() + payload.len() + 4) as u32;
+
+    let mut message = Vec::with_capacity(total_len as usize);
+    message.extend_from_slice(&total_len.to_be_bytes());
+    message.extend_from_slice(&(headers.len() as u32).to_be_bytes());
+    let prelude_crc = crc32(&message);
+    message.extend_from_slice(&prelude_crc.to_be_bytes());
+    message.extend_from_slice(&headers);
+    message.extend_from_slice(&payload);
+    let message_crc = crc32(&message);
+    message.extend_from_slice(&message_crc.to_be_bytes());
+    Bytes::from(message)
+}
+
+/// CRC-32 (IEEE), as used by the event stream framing
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Splits a native event stream into whole messages as the bytes arrive,
+/// holding back a message split across chunks
+#[derive(Debug, Default)]
+pub struct EventStreamMessages {
+    pending: Vec<u8>,
+}
+
+impl EventStreamMessages {
+    /// The whole messages this chunk completes, and their JSON payloads
+    pub fn push(&mut self, bytes: &[u8]) -> (Bytes, Vec<Value>) {
+        self.pending.extend_from_slice(bytes);
+        let mut payloads = Vec::new();
+        let mut consumed = 0;
+        while let Some(prefix) = self.pending.get(consumed..consumed + 4) {
+            let len = u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+            if len < MIN_MESSAGE_LEN {
+                // Not an event stream after all; pass the rest through unparsed
+                debug!("Invalid event stream message length {}; forwarding the rest unparsed", len);
+                consumed = self.pending.len();
+                break;
+            }
+            if self.pending.len() - consumed < len {
+                break;
+            }
+            match parse_message(&self.pending[consumed..consumed + len]) {
+                Ok((_, message)) => match serde_json::from_slice(message.body) {
+                    Ok(payload) => payloads.push(payload),
+                    Err(e) => debug!("Skipping event stream message without a JSON payload: {}", e),
+                },
+                Err(e) => debug!("Failed to parse event stream message: {}", e),
+            }
+            consumed += len;
+        }
+        let complete: Vec<u8> = self.pending.drain(..consumed).collect();
+        (Bytes::from(complete), payloads)
+    }
+}
@@ -1,23 +1,35 @@
 use super::Provider;
+use super::logprobs;
 use super::utils::log_tracking_headers;
 use crate::error::AppError;
 use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
 use async_trait::async_trait;
 use axum::{
-    body::{Body, Bytes},
+    body::{to_bytes, Body, Bytes},
     http::{HeaderMap, Response},
 };
+use parking_lot::RwLock;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error};
 
+const IMAGE_GENERATIONS_PATH: &str = "/v1/images/generations";
+
 pub struct FireworksProvider {
     base_url: String,
+    current_model: Arc<RwLock<Option<String>>>,
+    // Set when an OpenAI image request is translated to Fireworks' image API
+    translating_images: Arc<RwLock<bool>>,
 }
 
 impl FireworksProvider {
     pub fn new() -> Self {
         Self {
             base_url: "https://api.fireworks.ai/inference/v1".to_string(),
+            current_model: Arc::new(RwLock::new(None)),
+            translating_images: Arc::new(RwLock::new(false)),
         }
     }
 }
@@ -32,6 +44,28 @@ impl Provider for FireworksProvider {
         "fireworks"
     }
 
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &[
+            "/v1/chat/completions",
+            "/v1/completions",
+            "/v1/embeddings",
+            "/v1/models",
+            IMAGE_GENERATIONS_PATH,
+            "/v1/image_generation",
+            "/v1/workflows",
+        ]
+    }
+
+    async fn before_request(&self, _headers: &HeaderMap, body: &Bytes) -> Result<(), AppError> {
+        if let Ok(request_body) = serde_json::from_slice::<Value>(body) {
+            *self.current_model.write() = request_body
+                .get("model")
+                .and_then(Value::as_str)
+                .map(full_model_name);
+        }
+        Ok(())
+    }
+
     fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
         debug!("Processing Fireworks request headers");
         let mut headers = HeaderMap::new();
@@ -45,10 +79,13 @@ impl Provider for FireworksProvider {
             http::header::HeaderValue::from_static("application/json"),
         );
 
-        headers.insert(
-            http::header::ACCEPT,
-            http::header::HeaderValue::from_static("application/json"),
-        );
+        // Native image routes may ask for raw image bytes instead of base64 JSON
+        let accept = original_headers
+            .get(http::header::ACCEPT)
+            .filter(|v| v.to_str().map_or(false, |v| v.starts_with("image/")))
+            .cloned()
+            .unwrap_or_else(|| http::header::HeaderValue::from_static("application/json"));
+        headers.insert(http::header::ACCEPT, accept);
 
         // Process authentication
         if let Some(auth) = original_headers
@@ -90,13 +127,31 @@ impl Provider for FireworksProvider {
     }
 
     fn transform_path(&self, path: &str) -> String {
+        // Image models are addressed by path rather than by the `model` field
+        if path == IMAGE_GENERATIONS_PATH {
+            if let Some(model) = self.current_model.read().as_deref() {
+                *self.translating_images.write() = true;
+                return image_path(model);
+            }
+        }
+
         // The incoming path is /v1/chat/completions
         // We want to strip the /v1 prefix since it's already in the base_url
-        if path.starts_with("/v1/") {
-            path.trim_start_matches("/v1").to_string()
-        } else {
-            path.to_string()
+        match path.strip_prefix("/v1/") {
+            Some(rest) => format!("/{}", rest),
+            None => path.to_string(),
+        }
+    }
+
+    async fn prepare_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        if !*self.translating_images.read() {
+            return Ok(body);
         }
+        let request: Value = serde_json::from_slice(&body)?;
+        let model = self.current_model.read().clone().unwrap_or_default();
+        let translated = to_fireworks_image_request(&model, &request);
+        debug!("Translated image request for Fireworks model {}: {}", model, translated);
+        Ok(Bytes::from(serde_json::to_vec(&translated)?))
     }
     
     async fn process_response(&self, response: Response<Body>) -> Result<Response<Body>, AppError> {
@@ -110,8 +165,28 @@ impl Provider for FireworksProvider {
         } else {
             debug!("No x-request-id found in Fireworks response headers");
         }
-        
-        Ok(Response::from_parts(parts, body))
+
+        let is_json = parts
+            .headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map_or(false, |ct| ct.contains("application/json"));
+        if !is_json {
+            return Ok(Response::from_parts(parts, body));
+        }
+
+        if *self.translating_images.read() && parts.status.is_success() {
+            let bytes = to_bytes(body, usize::MAX).await?;
+            let images: Value = serde_json::from_slice(&bytes)?;
+            parts.headers.remove(http::header::CONTENT_LENGTH);
+            let translated = to_openai_images_response(&images);
+            return Ok(Response::from_parts(parts, Body::from(serde_json::to_vec(&translated)?)));
+        }
+
+        // Fireworks may return legacy logprobs; convert them to the OpenAI schema
+        let bytes = logprobs::normalize_body(to_bytes(body, usize::MAX).await?);
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+        Ok(Response::from_parts(parts, Body::from(bytes)))
     }
 }
 
@@ -120,7 +195,7 @@ pub struct FireworksMetricsExtractor;
 
 impl MetricsExtractor for FireworksMetricsExtractor {
     fn extract_metrics(&self, response_body: &serde_json::Value) -> ProviderMetrics {
-        debug!("Extracting Fireworks metrics from response: {}", response_body);
+        debug!("Extracting Fireworks metrics from response: {}", redact::value(response_body));
         let mut metrics = ProviderMetrics::default();
         
         // Extract token information from usage field (OpenAI compatible format)
@@ -129,6 +204,10 @@ impl MetricsExtractor for FireworksMetricsExtractor {
             metrics.input_tokens = usage.get("prompt_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
             metrics.output_tokens = usage.get("completion_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
             metrics.total_tokens = usage.get("total_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
+            // Embedding usage has no completion tokens
+            if metrics.output_tokens.is_none() && metrics.input_tokens.is_some() {
+                metrics.output_tokens = Some(0);
+            }
             debug!("Extracted tokens - input: {:?}, output: {:?}, total: {:?}", 
                 metrics.input_tokens, metrics.output_tokens, metrics.total_tokens);
         }
@@ -147,4 +226,159 @@ impl MetricsExtractor for FireworksMetricsExtractor {
         
         metrics
     }
+
+    /// Fireworks appends `usage` to the final chunk of every stream, with no
+    /// `stream_options` needed; earlier chunks carry none
+    fn try_extract_provider_specific_streaming_metrics(&self, chunk: &str) -> Option<ProviderMetrics> {
+        let json = serde_json::from_str::<Value>(chunk).ok()?;
+        if !json.get("usage").map_or(false, Value::is_object) {
+            return None;
+        }
+        debug!("Found usage in final Fireworks streaming chunk");
+        Some(self.extract_metrics(&json))
+    }
+
+    fn extract_request_metrics(
+        &self,
+        path: &str,
+        request_body: Option<&Value>,
+        _headers: &HeaderMap,
+    ) -> Option<ProviderMetrics> {
+        // Images are billed per inference step, so price them from the request
+        let model = if path == IMAGE_GENERATIONS_PATH {
+            full_model_name(request_body?.get("model")?.as_str()?)
+        } else if let Some(rest) = path.strip_prefix("/v1/image_generation/") {
+            rest.to_string()
+        } else if let Some(rest) = path.strip_prefix("/v1/workflows/") {
+            rest.rsplit_once('/').map_or(rest, |(model, _)| model).to_string()
+        } else {
+            return None;
+        };
+
+        let (price_per_step, default_steps) = image_step_pricing(&model);
+        let steps = request_body
+            .and_then(|r| r.get("steps").or_else(|| r.get("num_inference_steps")))
+            .and_then(Value::as_u64)
+            .unwrap_or(default_steps);
+        let images = request_body
+            .and_then(|r| r.get("n").or_else(|| r.get("samples")))
+            .and_then(Value::as_u64)
+            .unwrap_or(1);
+
+        let cost = price_per_step * steps as f64 * images as f64;
+        debug!(
+            "Calculated Fireworks image cost: {} for model {} ({} images x {} steps)",
+            cost, model, images, steps
+        );
+        Some(ProviderMetrics {
+            model,
+            cost: Some(cost),
+            ..Default::default()
+        })
+    }
+}
+
+/// Expand a short model name such as `flux-1-schnell-fp8` to its account path
+fn full_model_name(model: &str) -> String {
+    if model.starts_with("accounts/") {
+        model.to_string()
+    } else {
+        format!("accounts/fireworks/models/{}", model)
+    }
+}
+
+fn is_flux(model: &str) -> bool {
+    model.contains("flux")
+}
+
+/// FLUX models run as workflows; other diffusion models use the image generation API
+fn image_path(model: &str) -> String {
+    if is_flux(model) {
+        format!("/workflows/{}/text_to_image", model)
+    } else {
+        format!("/image_generation/{}", model)
+    }
+}
+
+/// Convert an OpenAI images request into Fireworks' text-to-image body
+fn to_fireworks_image_request(model: &str, request: &Value) -> Value {
+    let mut body = Map::new();
+    if let Some(prompt) = request.get("prompt") {
+        body.insert("prompt".to_string(), prompt.clone());
+    }
+
+    let size = request
+        .get("size")
+        .and_then(Value::as_str)
+        .and_then(|size| size.split_once('x'))
+        .and_then(|(w, h)| Some((w.parse::<u64>().ok()?, h.parse::<u64>().ok()?)));
+
+    if is_flux(model) {
+        if let Some((width, height)) = size {
+            let divisor = gcd(width, height).max(1);
+            body.insert(
+                "aspect_ratio".to_string(),
+                json!(format!("{}:{}", width / divisor, height / divisor)),
+            );
+        }
+        for key in ["guidance_scale", "num_inference_steps", "seed"] {
+            if let Some(value) = request.get(key) {
+                body.insert(key.to_string(), value.clone());
+            }
+        }
+    } else {
+        if let Some((width, height)) = size {
+            body.insert("width".to_string(), json!(width));
+            body.insert("height".to_string(), json!(height));
+        }
+        if let Some(n) = request.get("n") {
+            body.insert("samples".to_string(), n.clone());
+        }
+        for key in ["negative_prompt", "cfg_scale", "steps", "seed"] {
+            if let Some(value) = request.get(key) {
+                body.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+    Value::Object(body)
+}
+
+/// Convert Fireworks image output into the OpenAI images response shape
+///
+/// The image generation API returns an array of `{base64, seed, finishReason}`
+/// and workflows a single object whose `base64` may be a string or an array.
+fn to_openai_images_response(images: &Value) -> Value {
+    let items = match images {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        other => vec![other],
+    };
+    let data: Vec<Value> = items
+        .into_iter()
+        .flat_map(|item| match item.get("base64") {
+            Some(Value::Array(all)) => all.clone(),
+            Some(single) => vec![single.clone()],
+            None => Vec::new(),
+        })
+        .map(|b64| json!({ "b64_json": b64 }))
+        .collect();
+
+    json!({
+        "created": chrono::Utc::now().timestamp(),
+        "data": data,
+    })
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Price per inference step in USD and the model's default step count
+fn image_step_pricing(model: &str) -> (f64, u64) {
+    if model.contains("flux-1-schnell") {
+        (0.00035, 4)
+    } else if model.contains("flux-1-dev") {
+        (0.0005, 28)
+    } else {
+        (0.00013, 30)
+    }
 }
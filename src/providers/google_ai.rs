@@ -0,0 +1,526 @@
+use super::params::ParamSupport;
+use super::stream_events::{sse_body, StreamEvent};
+use super::utils::log_tracking_headers;
+use super::Provider;
+use crate::error::AppError;
+use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{HeaderMap, HeaderValue, Response},
+};
+use futures_util::{stream, StreamExt};
+use parking_lot::RwLock;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+
+/// Google AI Studio (Gemini API) with an API key, as opposed to Vertex AI
+pub struct GoogleAiProvider {
+    base_url: String,
+    current_model: Arc<RwLock<String>>,
+    is_streaming: Arc<RwLock<bool>>,
+}
+
+impl GoogleAiProvider {
+    pub fn new() -> Self {
+        Self {
+            base_url: "https://generativelanguage.googleapis.com".to_string(),
+            current_model: Arc::new(RwLock::new(DEFAULT_MODEL.to_string())),
+            is_streaming: Arc::new(RwLock::new(false)),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for GoogleAiProvider {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn name(&self) -> &str {
+        "google-ai"
+    }
+
+    fn param_support(&self, param: &str) -> ParamSupport {
+        match param {
+            // Mapped onto generationConfig by prepare_request_body
+            "stop" | "frequency_penalty" | "presence_penalty" | "seed" | "top_k" | "n" => ParamSupport::Native,
+            _ => ParamSupport::Unsupported,
+        }
+    }
+
+    async fn before_request(&self, _headers: &HeaderMap, body: &Bytes) -> Result<(), AppError> {
+        if let Ok(request_body) = serde_json::from_slice::<Value>(body) {
+            if let Some(model) = request_body.get("model").and_then(Value::as_str) {
+                // Accept both `gemini-2.0-flash` and the API's `models/gemini-2.0-flash`
+                *self.current_model.write() = model.trim_start_matches("models/").to_string();
+            }
+            *self.is_streaming.write() = request_body.get("stream").and_then(Value::as_bool).unwrap_or(false);
+        }
+        Ok(())
+    }
+
+    fn transform_path(&self, _path: &str) -> String {
+        let model = self.current_model.read();
+        if *self.is_streaming.read() {
+            format!("/v1beta/models/{}:streamGenerateContent?alt=sse", *model)
+        } else {
+            format!("/v1beta/models/{}:generateContent", *model)
+        }
+    }
+
+    fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
+        debug!("Processing Google AI request headers");
+        let mut headers = HeaderMap::new();
+
+        // Log tracking headers for observability
+        log_tracking_headers(original_headers);
+
+        headers.insert(
+            http::header::CONTENT_TYPE,
+            http::header::HeaderValue::from_static("application/json"),
+        );
+
+        // AI Studio keys go in x-goog-api-key; accept them as a Bearer token too
+        let api_key = original_headers
+            .get("x-goog-api-key")
+            .and_then(|h| h.to_str().ok())
+            .or_else(|| {
+                original_headers
+                    .get(http::header::AUTHORIZATION)
+                    .and_then(|h| h.to_str().ok())
+                    .map(|auth| auth.trim_start_matches("Bearer "))
+            })
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| {
+                error!("No API key found for Google AI request");
+                AppError::MissingApiKey
+            })?;
+        headers.insert(
+            "x-goog-api-key",
+            HeaderValue::from_str(api_key).map_err(|_| {
+                error!("Invalid characters in Google AI API key");
+                AppError::InvalidHeader
+            })?,
+        );
+
+        Ok(headers)
+    }
+
+    async fn prepare_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        let request: Value = serde_json::from_slice(&body)?;
+        let transformed = to_gemini_request(&request)?;
+        debug!("Transformed Google AI request: {}", redact::value(&transformed));
+        Ok(Bytes::from(serde_json::to_vec(&transformed)?))
+    }
+
+    async fn process_response(&self, response: Response<Body>) -> Result<Response<Body>, AppError> {
+        let (mut parts, body) = response.into_parts();
+        let model = self.current_model.read().clone();
+
+        if !parts.status.is_success() {
+            // Reshape `{"error": {"code", "message", "status"}}` into the OpenAI error format
+            let bytes = to_bytes(body, usize::MAX).await?;
+            let Ok(error) = serde_json::from_slice::<Value>(&bytes) else {
+                return Ok(Response::from_parts(parts, Body::from(bytes)));
+            };
+            let error = error.get("error").cloned().unwrap_or(error);
+            let openai_error = json!({
+                "error": {
+                    "message": error.get("message").and_then(Value::as_str).unwrap_or("Google AI request failed"),
+                    "type": error.get("status").and_then(Value::as_str).unwrap_or("api_error"),
+                    "param": null,
+                    "code": error.get("code").cloned().unwrap_or(Value::Null)
+                }
+            });
+            parts.headers.remove(http::header::CONTENT_LENGTH);
+            return Ok(Response::from_parts(parts, Body::from(serde_json::to_vec(&openai_error)?)));
+        }
+
+        let is_streaming = parts
+            .headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map_or(false, |ct| ct.contains("text/event-stream"));
+
+        if is_streaming {
+            debug!("Translating Google AI event stream");
+            let id = chat_completion_id();
+            let created = chrono::Utc::now().timestamp();
+            let mut translator = StreamTranslator::new(id, model, created);
+
+            let events = body
+                .into_data_stream()
+                .map(move |chunk| match chunk {
+                    Ok(bytes) => Ok(translator.push(&bytes)),
+                    Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e)),
+                })
+                .chain(stream::once(async { Ok(vec![StreamEvent::Done]) }));
+
+            let (body, tap) = sse_body(events);
+            parts.headers.remove(http::header::CONTENT_LENGTH);
+            parts.extensions.insert(tap);
+            return Ok(Response::from_parts(parts, body));
+        }
+
+        let bytes = to_bytes(body, usize::MAX).await?;
+        let gemini: Value = serde_json::from_slice(&bytes)?;
+        if let Some(id) = gemini.get("responseId").and_then(Value::as_str) {
+            if let Ok(value) = HeaderValue::from_str(id) {
+                parts.headers.insert("x-request-id", value);
+            }
+        }
+        let transformed = to_openai_response(&gemini, &model);
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+        Ok(Response::from_parts(parts, Body::from(serde_json::to_vec(&transformed)?)))
+    }
+}
+
+/// Build a `generateContent` request from an OpenAI chat completion request
+fn to_gemini_request(request: &Value) -> Result<Value, AppError> {
+    let messages = request
+        .get("messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            error!("Invalid request format: messages array not found");
+            AppError::InvalidRequestFormat
+        })?;
+
+    let mut contents = Vec::new();
+    let mut system_parts = Vec::new();
+    for message in messages {
+        let parts = content_parts(message.get("content"));
+        match message.get("role").and_then(Value::as_str).unwrap_or("user") {
+            "system" | "developer" => system_parts.extend(parts),
+            "assistant" => contents.push(json!({ "role": "model", "parts": parts })),
+            _ => contents.push(json!({ "role": "user", "parts": parts })),
+        }
+    }
+
+    let mut config = Map::new();
+    let mut copy = |from: &str, to: &str| {
+        if let Some(value) = request.get(from).filter(|v| !v.is_null()) {
+            config.insert(to.to_string(), value.clone());
+        }
+    };
+    copy("max_tokens", "maxOutputTokens");
+    copy("max_completion_tokens", "maxOutputTokens");
+    copy("temperature", "temperature");
+    copy("top_p", "topP");
+    copy("top_k", "topK");
+    copy("presence_penalty", "presencePenalty");
+    copy("frequency_penalty", "frequencyPenalty");
+    copy("seed", "seed");
+    copy("n", "candidateCount");
+    match request.get("stop") {
+        Some(Value::String(stop)) => {
+            config.insert("stopSequences".to_string(), json!([stop]));
+        }
+        Some(Value::Array(stops)) => {
+            config.insert("stopSequences".to_string(), json!(stops));
+        }
+        _ => {}
+    }
+    let response_format = request.get("response_format");
+    match response_format.and_then(|f| f.get("type")).and_then(Value::as_str) {
+        Some("json_object") => {
+            config.insert("responseMimeType".to_string(), json!("application/json"));
+        }
+        Some("json_schema") => {
+            config.insert("responseMimeType".to_string(), json!("application/json"));
+            if let Some(schema) = response_format.and_then(|f| f.pointer("/json_schema/schema")) {
+                config.insert("responseSchema".to_string(), gemini_schema(schema));
+            }
+        }
+        _ => {}
+    }
+
+    let mut gemini = json!({ "contents": contents });
+    if !system_parts.is_empty() {
+        gemini["systemInstruction"] = json!({ "parts": system_parts });
+    }
+    if !config.is_empty() {
+        gemini["generationConfig"] = Value::Object(config);
+    }
+    Ok(gemini)
+}
+
+/// A JSON schema as Gemini's `responseSchema` takes it, which rejects the
+/// `additionalProperties` that OpenAI strict mode requires on every object
+fn gemini_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "additionalProperties" | "$schema"))
+                .map(|(key, value)| (key.clone(), gemini_schema(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(gemini_schema).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Convert OpenAI message content (a string or a list of parts) into Gemini parts
+fn content_parts(content: Option<&Value>) -> Vec<Value> {
+    match content {
+        Some(Value::String(text)) => vec![json!({ "text": text })],
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| match part.get("type").and_then(Value::as_str) {
+                Some("text") => Some(json!({ "text": part.get("text").cloned().unwrap_or_default() })),
+                Some("image_url") => {
+                    let url = part.get("image_url").and_then(|i| i.get("url")).and_then(Value::as_str)?;
+                    Some(image_part(url))
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Inline `data:` URLs; anything else is passed as a file reference
+fn image_part(url: &str) -> Value {
+    if let Some((meta, data)) = url.strip_prefix("data:").and_then(|rest| rest.split_once(',')) {
+        let mime_type = meta.trim_end_matches(";base64");
+        return json!({ "inlineData": { "mimeType": mime_type, "data": data } });
+    }
+    json!({ "fileData": { "fileUri": url } })
+}
+
+/// Maps a Gemini finishReason to the OpenAI finish_reason
+fn map_finish_reason(reason: &str) -> &'static str {
+    match reason {
+        "MAX_TOKENS" => "length",
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => "content_filter",
+        _ => "stop",
+    }
+}
+
+fn candidate_text(candidate: &Value) -> String {
+    candidate
+        .get("content")
+        .and_then(|c| c.get("parts"))
+        .and_then(Value::as_array)
+        .map(|parts| {
+            parts
+                .iter()
+                // Thought summaries aren't part of the answer
+                .filter(|p| !p.get("thought").and_then(Value::as_bool).unwrap_or(false))
+                .filter_map(|p| p.get("text").and_then(Value::as_str))
+                .collect::<String>()
+        })
+        .unwrap_or_default()
+}
+
+fn openai_usage(usage: &Value) -> Value {
+    let count = |name: &str| usage.get(name).and_then(Value::as_u64).unwrap_or(0);
+    let thoughts = count("thoughtsTokenCount");
+    json!({
+        "prompt_tokens": count("promptTokenCount"),
+        // Thinking tokens are billed as output, like OpenAI reasoning tokens
+        "completion_tokens": count("candidatesTokenCount") + thoughts,
+        "total_tokens": count("totalTokenCount"),
+        "prompt_tokens_details": { "cached_tokens": count("cachedContentTokenCount") },
+        "completion_tokens_details": { "reasoning_tokens": thoughts }
+    })
+}
+
+fn chat_completion_id() -> String {
+    format!("chatcmpl-{}", uuid::Uuid::new_v4().simple())
+}
+
+fn to_openai_response(gemini: &Value, model: &str) -> Value {
+    let choices: Vec<Value> = gemini
+        .get("candidates")
+        .and_then(Value::as_array)
+        .map(|candidates| {
+            candidates
+                .iter()
+                .enumerate()
+                .map(|(i, candidate)| {
+                    json!({
+                        "index": candidate.get("index").and_then(Value::as_u64).unwrap_or(i as u64),
+                        "message": {
+                            "role": "assistant",
+                            "content": candidate_text(candidate),
+                            "refusal": null
+                        },
+                        "logprobs": null,
+                        "finish_reason": map_finish_reason(
+                            candidate.get("finishReason").and_then(Value::as_str).unwrap_or("STOP")
+                        )
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    json!({
+        "id": chat_completion_id(),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": gemini.get("modelVersion").and_then(Value::as_str).unwrap_or(model),
+        "choices": choices,
+        "usage": openai_usage(gemini.get("usageMetadata").unwrap_or(&Value::Null))
+    })
+}
+
+/// Turns Gemini SSE bytes into OpenAI chunk events, buffering partial lines between reads
+struct StreamTranslator {
+    id: String,
+    model: String,
+    created: i64,
+    buffer: Vec<u8>,
+    /// Choice indexes that have had their `role` delta; there is one per candidate when `n` > 1
+    sent_role: HashSet<u64>,
+}
+
+impl StreamTranslator {
+    fn new(id: String, model: String, created: i64) -> Self {
+        Self { id, model, created, buffer: Vec::new(), sent_role: HashSet::new() }
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Vec<StreamEvent> {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            match serde_json::from_str::<Value>(data.trim()) {
+                Ok(chunk) => events.push(StreamEvent::chunk(self.translate(&chunk))),
+                Err(e) => debug!("Skipping unparseable Google AI stream line: {}", e),
+            }
+        }
+        events
+    }
+
+    fn translate(&mut self, chunk: &Value) -> Value {
+        // A chunk without candidates (e.g. only prompt feedback) still yields an empty delta
+        let candidates = match chunk.get("candidates").and_then(Value::as_array) {
+            Some(candidates) if !candidates.is_empty() => candidates.clone(),
+            _ => vec![Value::Null],
+        };
+        let choices: Vec<Value> = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let index = candidate.get("index").and_then(Value::as_u64).unwrap_or(i as u64);
+                let mut delta = json!({ "content": candidate_text(candidate) });
+                if self.sent_role.insert(index) {
+                    delta["role"] = json!("assistant");
+                }
+                json!({
+                    "index": index,
+                    "delta": delta,
+                    "finish_reason": candidate.get("finishReason").and_then(Value::as_str).map(map_finish_reason)
+                })
+            })
+            .collect();
+        if let Some(model) = chunk.get("modelVersion").and_then(Value::as_str) {
+            self.model = model.to_string();
+        }
+        let finished = choices.iter().all(|choice| !choice["finish_reason"].is_null());
+
+        let mut openai_chunk = json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": choices
+        });
+        // Usage is cumulative on every chunk; report it once, with the final chunk
+        if finished {
+            if let Some(usage) = chunk.get("usageMetadata") {
+                openai_chunk["usage"] = openai_usage(usage);
+            }
+        }
+        openai_chunk
+    }
+}
+
+/// Google AI responses are translated into OpenAI format before telemetry sees them
+pub struct GoogleAiMetricsExtractor;
+
+impl MetricsExtractor for GoogleAiMetricsExtractor {
+    fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
+        debug!("Extracting Google AI metrics from response: {}", redact::value(response_body));
+        let mut metrics = ProviderMetrics::default();
+
+        if let Some(usage) = response_body.get("usage") {
+            let count = |v: Option<&Value>| v.and_then(Value::as_u64).map(|v| v as u32);
+            metrics.input_tokens = count(usage.get("prompt_tokens"));
+            metrics.output_tokens = count(usage.get("completion_tokens"));
+            metrics.total_tokens = count(usage.get("total_tokens"));
+            metrics.cached_tokens = count(usage.pointer("/prompt_tokens_details/cached_tokens"));
+            metrics.reasoning_tokens = count(usage.pointer("/completion_tokens_details/reasoning_tokens"));
+        }
+
+        if let Some(model) = response_body.get("model").and_then(Value::as_str) {
+            metrics.model = model.to_string();
+        }
+        if let Some(id) = response_body.get("id").and_then(Value::as_str) {
+            metrics.request_id = Some(id.to_string());
+        }
+
+        if let (Some(input), Some(output)) = (metrics.input_tokens, metrics.output_tokens) {
+            metrics.cost = Some(calculate_cost(
+                &metrics.model,
+                input,
+                metrics.cached_tokens.unwrap_or(0),
+                output,
+            ));
+        }
+
+        debug!("Final extracted Google AI metrics: {:?}", metrics);
+        metrics
+    }
+
+    // Streams are translated into events by the provider, so no re-parse is needed
+    fn extract_streaming_event_metrics(&self, chunk: &Value) -> Option<ProviderMetrics> {
+        self.common_streaming_metrics(chunk)
+    }
+
+    fn estimate_cost(&self, model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        model_prices(model)?;
+        Some(calculate_cost(model, input_tokens, 0, output_tokens))
+    }
+}
+
+// Per-token prices in USD as (input, cached input, output), for prompts up to 128k/200k tokens
+fn model_prices(model: &str) -> Option<(f64, f64, f64)> {
+    const PER_MILLION: f64 = 1_000_000.0;
+    let prices = match model {
+        m if m.starts_with("gemini-2.5-pro") => (1.25, 0.31, 10.00),
+        m if m.starts_with("gemini-2.5-flash-lite") => (0.10, 0.025, 0.40),
+        m if m.starts_with("gemini-2.5-flash") => (0.30, 0.075, 2.50),
+        m if m.starts_with("gemini-2.0-flash-lite") => (0.075, 0.075, 0.30),
+        m if m.starts_with("gemini-2.0-flash") => (0.10, 0.025, 0.40),
+        m if m.starts_with("gemini-1.5-pro") => (1.25, 0.3125, 5.00),
+        m if m.starts_with("gemini-1.5-flash-8b") => (0.0375, 0.01, 0.15),
+        m if m.starts_with("gemini-1.5-flash") => (0.075, 0.01875, 0.30),
+        _ => return None,
+    };
+    Some((prices.0 / PER_MILLION, prices.1 / PER_MILLION, prices.2 / PER_MILLION))
+}
+
+fn calculate_cost(model: &str, input_tokens: u32, cached_tokens: u32, output_tokens: u32) -> f64 {
+    let Some((input_price, cached_price, output_price)) = model_prices(model) else {
+        debug!("Unknown Google AI model for cost calculation: {}", model);
+        return 0.0;
+    };
+
+    let cached = cached_tokens.min(input_tokens) as f64;
+    let uncached = input_tokens as f64 - cached;
+
+    uncached * input_price + cached * cached_price + output_tokens as f64 * output_price
+}
@@ -1,21 +1,34 @@
 use super::Provider;
+use super::params::ParamSupport;
+use super::reasoning::{self, ReasoningUsage};
+use super::stream_events::{sse_body, StreamEvent};
 use super::utils::log_tracking_headers;
 use crate::error::AppError;
 use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
 use async_trait::async_trait;
-use axum::http::HeaderMap;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{HeaderMap, Response},
+};
+use futures_util::StreamExt;
+use parking_lot::RwLock;
 use serde_json::Value;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error};
 
 pub struct GroqProvider {
     base_url: String,
+    // Set from `x-strip-reasoning` (or `STRIP_REASONING`) for the current request
+    strip_reasoning: Arc<RwLock<bool>>,
 }
 
 impl GroqProvider {
     pub fn new() -> Self {
         Self {
             base_url: "https://api.groq.com/openai".to_string(),
+            strip_reasoning: Arc::new(RwLock::new(false)),
         }
     }
 }
@@ -30,6 +43,23 @@ impl Provider for GroqProvider {
         "groq"
     }
 
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &[
+            "/v1/chat/completions",
+            "/v1/models",
+            "/v1/audio/transcriptions",
+            "/v1/audio/translations",
+        ]
+    }
+
+    fn param_support(&self, param: &str) -> ParamSupport {
+        match param {
+            "frequency_penalty" | "presence_penalty" | "logprobs" | "top_logprobs" | "logit_bias" | "top_k"
+            | "prediction" => ParamSupport::Unsupported,
+            _ => ParamSupport::Native,
+        }
+    }
+
     fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
         debug!("Processing Groq request headers");
         let mut headers = HeaderMap::new();
@@ -63,6 +93,93 @@ impl Provider for GroqProvider {
 
         Ok(headers)
     }
+
+    async fn before_request(&self, headers: &HeaderMap, _body: &Bytes) -> Result<(), AppError> {
+        *self.strip_reasoning.write() = reasoning::strip_requested(headers);
+        Ok(())
+    }
+
+    async fn process_response(&self, response: Response<Body>) -> Result<Response<Body>, AppError> {
+        let (mut parts, body) = response.into_parts();
+        if !parts.status.is_success() {
+            return Ok(Response::from_parts(parts, body));
+        }
+        let content_type = parts
+            .headers
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let strip = *self.strip_reasoning.read();
+        let usage = ReasoningUsage::default();
+
+        if content_type.contains("text/event-stream") {
+            // Reasoning deltas are counted (and stripped if asked) chunk by chunk
+            let mut stream = ReasoningStream::new(usage.clone(), strip);
+            let events = body.into_data_stream().map(move |chunk| match chunk {
+                Ok(bytes) => Ok(stream.push(&bytes)),
+                Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e)),
+            });
+            let (body, tap) = sse_body(events);
+            parts.headers.remove(http::header::CONTENT_LENGTH);
+            parts.extensions.insert(tap);
+            parts.extensions.insert(usage);
+            return Ok(Response::from_parts(parts, body));
+        }
+
+        if !content_type.contains("application/json") {
+            return Ok(Response::from_parts(parts, body));
+        }
+        let bytes = to_bytes(body, usize::MAX).await?;
+        let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+            return Ok(Response::from_parts(parts, Body::from(bytes)));
+        };
+        let stripped = usage.record(&mut json, strip);
+        parts.extensions.insert(usage);
+        if !stripped {
+            return Ok(Response::from_parts(parts, Body::from(bytes)));
+        }
+        debug!("Stripped reasoning from Groq response");
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+        Ok(Response::from_parts(parts, Body::from(serde_json::to_vec(&json)?)))
+    }
+}
+
+/// Parses Groq SSE bytes into chunk events, buffering partial lines between reads
+struct ReasoningStream {
+    usage: ReasoningUsage,
+    strip: bool,
+    buffer: Vec<u8>,
+}
+
+impl ReasoningStream {
+    fn new(usage: ReasoningUsage, strip: bool) -> Self {
+        Self { usage, strip, buffer: Vec::new() }
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Vec<StreamEvent> {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                events.push(StreamEvent::Done);
+                continue;
+            }
+            match serde_json::from_str::<Value>(data) {
+                Ok(mut chunk) => {
+                    self.usage.record(&mut chunk, self.strip);
+                    events.push(StreamEvent::chunk(chunk));
+                }
+                Err(e) => debug!("Skipping unparseable Groq stream line: {}", e),
+            }
+        }
+        events
+    }
 }
 
 // Add Groq-specific metrics extractor
@@ -70,7 +187,7 @@ pub struct GroqMetricsExtractor;
 
 impl MetricsExtractor for GroqMetricsExtractor {
     fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
-        debug!("Extracting Groq metrics from response: {}", response_body);
+        debug!("Extracting Groq metrics from response: {}", redact::value(response_body));
         let mut metrics = ProviderMetrics::default();
         
         // Try to get metrics from x_groq field first
@@ -80,6 +197,7 @@ impl MetricsExtractor for GroqMetricsExtractor {
                 metrics.input_tokens = usage.get("prompt_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                 metrics.output_tokens = usage.get("completion_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                 metrics.total_tokens = usage.get("total_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
+                metrics.reasoning_tokens = reasoning_tokens(usage);
                 
                 // Also capture provider latency from Groq's timing info
                 if let Some(total_time) = usage.get("total_time").and_then(|v| v.as_f64()) {
@@ -95,6 +213,7 @@ impl MetricsExtractor for GroqMetricsExtractor {
                 metrics.input_tokens = usage.get("prompt_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                 metrics.output_tokens = usage.get("completion_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                 metrics.total_tokens = usage.get("total_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
+                metrics.reasoning_tokens = reasoning_tokens(usage);
                 
                 // Also capture provider latency
                 if let Some(total_time) = usage.get("total_time").and_then(|v| v.as_f64()) {
@@ -126,7 +245,7 @@ impl MetricsExtractor for GroqMetricsExtractor {
         if let Ok(json) = serde_json::from_str::<Value>(chunk) {
             // Check if this is the final chunk with usage data
             if let Some(x_groq) = json.get("x_groq") {
-                debug!("Found x_groq field in direct JSON: {}", json);
+                debug!("Found x_groq field in direct JSON: {}", redact::value(&json));
                 if let Some(usage) = x_groq.get("usage") {
                     // Extract token counts from the usage data
                     let mut metrics = ProviderMetrics::default();
@@ -134,6 +253,7 @@ impl MetricsExtractor for GroqMetricsExtractor {
                     metrics.input_tokens = usage.get("prompt_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                     metrics.output_tokens = usage.get("completion_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                     metrics.total_tokens = usage.get("total_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
+                    metrics.reasoning_tokens = reasoning_tokens(usage);
                     
                     // Capture provider latency from Groq's timing info if available
                     if let Some(total_time) = usage.get("total_time").and_then(|v| v.as_f64()) {
@@ -210,6 +330,7 @@ impl MetricsExtractor for GroqMetricsExtractor {
                         metrics.input_tokens = usage.get("prompt_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                         metrics.output_tokens = usage.get("completion_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
                         metrics.total_tokens = usage.get("total_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
+                        metrics.reasoning_tokens = reasoning_tokens(usage);
                         
                         // Capture provider latency if available
                         if let Some(total_time) = usage.get("total_time").and_then(|v| v.as_f64()) {
@@ -256,6 +377,18 @@ impl MetricsExtractor for GroqMetricsExtractor {
         debug!("No usage data found in Groq streaming chunk");
         None
     }
+
+    fn estimate_cost(&self, model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        Some(calculate_groq_cost(model, input_tokens + output_tokens))
+    }
+}
+
+/// Reasoning tokens Groq reports for reasoning models, counted within completion_tokens
+fn reasoning_tokens(usage: &Value) -> Option<u32> {
+    usage
+        .pointer("/completion_tokens_details/reasoning_tokens")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
 }
 
 // Helper function for Groq-specific cost calculation
@@ -0,0 +1,119 @@
+use super::Provider;
+use super::utils::{forward_bearer_auth, log_tracking_headers};
+use crate::error::AppError;
+use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
+use async_trait::async_trait;
+use axum::{body::Bytes, http::HeaderMap};
+use serde_json::Value;
+use tracing::debug;
+
+/// Jina AI embeddings and reranking
+pub struct JinaProvider {
+    base_url: String,
+}
+
+impl JinaProvider {
+    pub fn new() -> Self {
+        Self {
+            base_url: "https://api.jina.ai".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for JinaProvider {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn name(&self) -> &str {
+        "jina"
+    }
+
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &["/v1/embeddings", "/v1/rerank"]
+    }
+
+    fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
+        debug!("Processing Jina request headers");
+        let mut headers = HeaderMap::new();
+
+        // Log tracking headers for observability
+        log_tracking_headers(original_headers);
+
+        headers.insert(
+            http::header::CONTENT_TYPE,
+            http::header::HeaderValue::from_static("application/json"),
+        );
+        forward_bearer_auth("Jina", original_headers, &mut headers)?;
+
+        Ok(headers)
+    }
+
+    async fn prepare_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        // Jina names OpenAI's `encoding_format` `embedding_type`
+        let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+            return Ok(body);
+        };
+        let Some(object) = json.as_object_mut() else {
+            return Ok(body);
+        };
+        let Some(format) = object.remove("encoding_format") else {
+            return Ok(body);
+        };
+        object.entry("embedding_type").or_insert(format);
+        Ok(Bytes::from(serde_json::to_vec(&json)?))
+    }
+}
+
+// Jina-specific metrics extractor
+pub struct JinaMetricsExtractor;
+
+impl MetricsExtractor for JinaMetricsExtractor {
+    fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
+        debug!("Extracting Jina metrics from response: {}", redact::value(response_body));
+        let mut metrics = ProviderMetrics::default();
+
+        if let Some(usage) = response_body.get("usage") {
+            let tokens = usage
+                .get("total_tokens")
+                .or_else(|| usage.get("prompt_tokens"))
+                .and_then(Value::as_u64)
+                .map(|v| v as u32);
+            // Embedding and rerank calls bill input tokens only
+            metrics.input_tokens = tokens;
+            metrics.output_tokens = tokens.map(|_| 0);
+            metrics.total_tokens = tokens;
+        }
+
+        if let Some(model) = response_body.get("model").and_then(Value::as_str) {
+            metrics.model = model.to_string();
+        }
+
+        if let Some(tokens) = metrics.total_tokens {
+            metrics.cost = Some(calculate_cost(&metrics.model, tokens));
+            debug!("Calculated Jina cost: {:?} for model {} and {} tokens", metrics.cost, metrics.model, tokens);
+        }
+
+        metrics
+    }
+}
+
+// Price per million tokens in USD, at pay-as-you-go token pack rates
+fn price_per_million(model: &str) -> Option<f64> {
+    let price = match model {
+        m if m.starts_with("jina-embeddings") || m.starts_with("jina-clip") => 0.05,
+        m if m.starts_with("jina-reranker") || m.starts_with("jina-colbert") => 0.05,
+        _ => return None,
+    };
+    Some(price)
+}
+
+fn calculate_cost(model: &str, tokens: u32) -> f64 {
+    let Some(price) = price_per_million(model) else {
+        debug!("Unknown Jina model for cost calculation: {}", model);
+        return 0.0;
+    };
+    tokens as f64 * price / 1_000_000.0
+}
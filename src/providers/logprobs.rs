@@ -0,0 +1,116 @@
+use axum::body::Bytes;
+use serde_json::{json, Map, Value};
+use tracing::debug;
+
+/// Rewrites OpenAI-style `logprobs: true, top_logprobs: k` into the legacy
+/// integer form (`logprobs: k`) used by Together's chat API.
+pub fn to_legacy_request(body: &mut Value) {
+    let Some(object) = body.as_object_mut() else {
+        return;
+    };
+
+    let top_logprobs = object.remove("top_logprobs").and_then(|v| v.as_u64());
+    match object.get("logprobs") {
+        Some(Value::Bool(true)) => {
+            object.insert("logprobs".to_string(), json!(top_logprobs.unwrap_or(1).max(1)));
+        }
+        Some(Value::Bool(false)) => {
+            object.remove("logprobs");
+        }
+        _ => {}
+    }
+}
+
+/// Converts legacy logprobs (`tokens`/`token_logprobs`/`top_logprobs` arrays)
+/// on every choice into the OpenAI chat schema (`{"content": [...]}`).
+///
+/// Returns true if any choice was rewritten.
+pub fn normalize_response(body: &mut Value) -> bool {
+    let Some(choices) = body.get_mut("choices").and_then(Value::as_array_mut) else {
+        return false;
+    };
+
+    let mut changed = false;
+    for choice in choices {
+        if let Some(logprobs) = choice.get("logprobs").filter(|l| is_legacy(l)) {
+            let normalized = normalize_logprobs(logprobs);
+            choice["logprobs"] = normalized;
+            changed = true;
+        }
+    }
+
+    if changed {
+        debug!("Normalized legacy logprobs to the OpenAI schema");
+    }
+    changed
+}
+
+/// Normalizes the logprobs of a serialized response body, leaving it untouched if nothing changes
+pub fn normalize_body(bytes: Bytes) -> Bytes {
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return bytes;
+    };
+    if !normalize_response(&mut json) {
+        return bytes;
+    }
+    serde_json::to_vec(&json).map(Bytes::from).unwrap_or(bytes)
+}
+
+fn is_legacy(logprobs: &Value) -> bool {
+    logprobs.get("content").is_none() && logprobs.get("tokens").map_or(false, Value::is_array)
+}
+
+fn normalize_logprobs(logprobs: &Value) -> Value {
+    let tokens = logprobs.get("tokens").and_then(Value::as_array).cloned().unwrap_or_default();
+    let token_logprobs = logprobs.get("token_logprobs").and_then(Value::as_array);
+    let top_logprobs = logprobs.get("top_logprobs").and_then(Value::as_array);
+
+    let content: Vec<Value> = tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, token)| {
+            let token = token.as_str()?;
+            let logprob = token_logprobs
+                .and_then(|l| l.get(i))
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0);
+
+            // Legacy top_logprobs is one {token: logprob} map per position
+            let top = top_logprobs
+                .and_then(|t| t.get(i))
+                .and_then(Value::as_object)
+                .map(top_entries)
+                .unwrap_or_default();
+
+            Some(json!({
+                "token": token,
+                "logprob": logprob,
+                "bytes": token.as_bytes(),
+                "top_logprobs": top
+            }))
+        })
+        .collect();
+
+    json!({ "content": content, "refusal": null })
+}
+
+fn top_entries(candidates: &Map<String, Value>) -> Vec<Value> {
+    let mut entries: Vec<Value> = candidates
+        .iter()
+        .map(|(token, logprob)| {
+            json!({
+                "token": token,
+                "logprob": logprob.as_f64().unwrap_or(0.0),
+                "bytes": token.as_bytes()
+            })
+        })
+        .collect();
+
+    // Most likely candidates first, matching OpenAI's ordering
+    entries.sort_by(|a, b| {
+        let a = a["logprob"].as_f64().unwrap_or(f64::MIN);
+        let b = b["logprob"].as_f64().unwrap_or(f64::MIN);
+        b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    entries
+}
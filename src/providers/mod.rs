@@ -1,4 +1,6 @@
 use crate::error::AppError;
+use crate::proxy::signing::{self, RequestSigner};
+use params::ParamSupport;
 use async_trait::async_trait;
 use axum::{
     body::{Body, Bytes},
@@ -14,6 +16,11 @@ pub trait Provider: Send + Sync {
     /// Get the provider's name for logging and identification
     fn name(&self) -> &str;
 
+    /// Endpoints this provider can serve; sub-paths of an entry are allowed too
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &["/v1/chat/completions"]
+    }
+
     /// Transform the request path if needed
     fn transform_path(&self, path: &str) -> String {
         path.to_string()
@@ -27,60 +34,72 @@ pub trait Provider: Send + Sync {
         Ok(body)
     }
 
+    /// Body sent for `x-raw-passthrough` requests, which are already in the
+    /// provider's native format; only fields the gateway reads are removed
+    fn prepare_raw_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        Ok(body)
+    }
+
+    /// How this provider handles an OpenAI-style sampling or stop parameter
+    fn param_support(&self, _param: &str) -> ParamSupport {
+        ParamSupport::Native
+    }
+
     /// Process response before returning to client
     async fn process_response(&self, response: Response<Body>) -> Result<Response<Body>, AppError> {
         Ok(response)
     }
 
-    /// Sign the final request if needed
-    async fn sign_request(
-        &self,
-        method: &str,
-        url: &str,
-        headers: &HeaderMap,
-        body: &[u8],
-    ) -> Result<HeaderMap, AppError> {
-        Ok(headers.clone())
-    }
-
     /// Process any operations needed before the request is sent
     async fn before_request(&self, headers: &HeaderMap, body: &Bytes) -> Result<(), AppError> {
         Ok(())
     }
 
-    /// Check if the provider requires AWS signing
-    fn requires_signing(&self) -> bool {
-        false
-    }
-
-    /// Get AWS signing credentials if available
-    fn get_signing_credentials(&self, _headers: &HeaderMap) -> Option<(String, String, String)> {
-        None
-    }
-
-    /// Get the signing host for the provider
-    fn get_signing_host(&self) -> String {
-        self.base_url()
-            .replace("https://", "")
-            .replace("http://", "")
+    /// Signer applied to the final request; by default the one configured in
+    /// `REQUEST_SIGNERS`, and `None` sends the processed headers as-is
+    fn request_signer(&self, _headers: &HeaderMap) -> Option<Box<dyn RequestSigner>> {
+        signing::configured_signer(self.name())
     }
 }
 
 // Use pub instead of mod to make the modules and their contents public
+pub mod aliases;
 pub mod anthropic;
+pub mod audio_content;
 pub mod bedrock;
+pub mod catalog;
+pub mod compression;
+pub mod deepgram;
+pub mod defaults;
+pub mod elevenlabs;
+pub mod event_stream;
 pub mod fireworks;
+pub mod google_ai;
 pub mod groq;
+pub mod jina;
+pub mod logprobs;
 pub mod openai;
+pub mod params;
+pub mod reasoning;
+pub mod routes;
+pub mod stability;
+pub mod stream_events;
 pub mod together;
 pub mod utils;
+pub mod voyage;
 
 pub use anthropic::AnthropicProvider;
 pub use bedrock::BedrockProvider;
+pub use deepgram::DeepgramProvider;
+pub use elevenlabs::ElevenLabsProvider;
 pub use fireworks::FireworksProvider;
+pub use google_ai::GoogleAiProvider;
 pub use groq::GroqProvider;
+pub use jina::JinaProvider;
 pub use openai::OpenAIProvider;
+pub use stability::StabilityProvider;
 pub use together::TogetherProvider;
+pub use voyage::VoyageProvider;
 
 /// Factory function to create provider instances
 pub fn create_provider(provider_name: &str) -> Result<Box<dyn Provider>, AppError> {
@@ -91,6 +110,12 @@ pub fn create_provider(provider_name: &str) -> Result<Box<dyn Provider>, AppErro
         "fireworks" => Ok(Box::new(FireworksProvider::new())),
         "together" => Ok(Box::new(TogetherProvider::new())),
         "bedrock" => Ok(Box::new(BedrockProvider::new())),
+        "google-ai" => Ok(Box::new(GoogleAiProvider::new())),
+        "voyage" => Ok(Box::new(VoyageProvider::new())),
+        "jina" => Ok(Box::new(JinaProvider::new())),
+        "elevenlabs" => Ok(Box::new(ElevenLabsProvider::new())),
+        "deepgram" => Ok(Box::new(DeepgramProvider::new())),
+        "stability" => Ok(Box::new(StabilityProvider::new())),
         unknown => {
             error!("Attempted to use unsupported provider: {}", unknown);
             Err(AppError::UnsupportedProvider)
@@ -1,12 +1,14 @@
 use super::Provider;
+use super::params::ParamSupport;
 use super::utils::log_tracking_headers;
 use crate::error::AppError;
 use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
 use async_trait::async_trait;
 use axum::http::HeaderMap;
 use serde_json::Value;
 use std::time::Duration;
-use tracing::{debug, error};
+use tracing::{debug, error, info};
 
 pub struct OpenAIProvider {
     base_url: String,
@@ -30,6 +32,34 @@ impl Provider for OpenAIProvider {
         "openai"
     }
 
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &[
+            "/v1/chat/completions",
+            "/v1/completions",
+            "/v1/embeddings",
+            "/v1/models",
+            "/v1/moderations",
+            "/v1/images/generations",
+            "/v1/images/edits",
+            "/v1/images/variations",
+            "/v1/audio/speech",
+            "/v1/audio/transcriptions",
+            "/v1/audio/translations",
+            "/v1/files",
+            "/v1/uploads",
+            "/v1/fine_tuning/jobs",
+            "/v1/batches",
+            "/v1/responses",
+        ]
+    }
+
+    fn param_support(&self, param: &str) -> ParamSupport {
+        match param {
+            "top_k" => ParamSupport::Unsupported,
+            _ => ParamSupport::Native,
+        }
+    }
+
     fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
         debug!("Processing OpenAI request headers");
         let mut headers = HeaderMap::new();
@@ -37,10 +67,13 @@ impl Provider for OpenAIProvider {
         // Log tracking headers for observability
         log_tracking_headers(original_headers);
 
-        // Add content type
+        // Keep the client's content type so multipart uploads (files, audio) survive
         headers.insert(
             http::header::CONTENT_TYPE,
-            http::header::HeaderValue::from_static("application/json"),
+            original_headers
+                .get(http::header::CONTENT_TYPE)
+                .cloned()
+                .unwrap_or_else(|| http::header::HeaderValue::from_static("application/json")),
         );
 
         // Process authentication
@@ -70,16 +103,62 @@ pub struct OpenAIMetricsExtractor;
 
 impl MetricsExtractor for OpenAIMetricsExtractor {
     fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
-        debug!("Extracting OpenAI metrics from response: {}", response_body);
+        debug!("Extracting OpenAI metrics from response: {}", redact::value(response_body));
         let mut metrics = ProviderMetrics::default();
+
+        // Fine-tuning job objects are returned on create, retrieve and cancel
+        if response_body.get("object").and_then(Value::as_str) == Some("fine_tuning.job") {
+            metrics.job_id = response_body.get("id").and_then(Value::as_str).map(String::from);
+            metrics.job_status = response_body.get("status").and_then(Value::as_str).map(String::from);
+            info!(
+                "Fine-tuning job {} is {} (base model {})",
+                metrics.job_id.as_deref().unwrap_or("unknown"),
+                metrics.job_status.as_deref().unwrap_or("unknown"),
+                response_body.get("model").and_then(Value::as_str).unwrap_or("unknown")
+            );
+        }
         
         if let Some(usage) = response_body.get("usage") {
             debug!("Found usage data: {:?}", usage);
             metrics.input_tokens = usage.get("prompt_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
             metrics.output_tokens = usage.get("completion_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
             metrics.total_tokens = usage.get("total_tokens").and_then(|v| v.as_u64()).map(|v| v as u32);
-            debug!("Extracted tokens - input: {:?}, output: {:?}, total: {:?}", 
-                metrics.input_tokens, metrics.output_tokens, metrics.total_tokens);
+
+            // Cached prompt tokens and hidden reasoning tokens are reported as breakdowns
+            metrics.cached_tokens = usage.get("prompt_tokens_details")
+                .and_then(|d| d.get("cached_tokens"))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32);
+            metrics.reasoning_tokens = usage.get("completion_tokens_details")
+                .and_then(|d| d.get("reasoning_tokens"))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32);
+            // Audio models report spoken input and output as separately priced breakdowns
+            metrics.audio_input_tokens = usage.get("prompt_tokens_details")
+                .and_then(|d| d.get("audio_tokens"))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+                .filter(|&tokens| tokens > 0);
+            metrics.audio_output_tokens = usage.get("completion_tokens_details")
+                .and_then(|d| d.get("audio_tokens"))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+                .filter(|&tokens| tokens > 0);
+            // Predicted outputs report how much of the `prediction` the completion used
+            metrics.accepted_prediction_tokens = usage.get("completion_tokens_details")
+                .and_then(|d| d.get("accepted_prediction_tokens"))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+                .filter(|&tokens| tokens > 0);
+            metrics.rejected_prediction_tokens = usage.get("completion_tokens_details")
+                .and_then(|d| d.get("rejected_prediction_tokens"))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+                .filter(|&tokens| tokens > 0);
+            debug!("Extracted tokens - input: {:?}, output: {:?}, total: {:?}, cached: {:?}, reasoning: {:?}, audio in/out: {:?}/{:?}", 
+                metrics.input_tokens, metrics.output_tokens, metrics.total_tokens,
+                metrics.cached_tokens, metrics.reasoning_tokens,
+                metrics.audio_input_tokens, metrics.audio_output_tokens);
         }
 
         if let Some(model) = response_body.get("model").and_then(|v| v.as_str()) {
@@ -87,10 +166,26 @@ impl MetricsExtractor for OpenAIMetricsExtractor {
             metrics.model = model.to_string();
         }
 
-        if let (Some(total_tokens), Some(model)) = (metrics.total_tokens, response_body.get("model")) {
-            metrics.cost = Some(calculate_cost(model.as_str().unwrap_or(""), total_tokens));
-            debug!("Calculated cost: {:?} for model {} and {} tokens", 
-                metrics.cost, metrics.model, total_tokens);
+        // The tier that actually served the request, which may differ from the one asked for
+        metrics.service_tier = response_body.get("service_tier").and_then(Value::as_str).map(String::from);
+
+        if let (Some(input_tokens), Some(output_tokens)) = (metrics.input_tokens, metrics.output_tokens) {
+            metrics.cost = Some(calculate_cost(
+                &metrics.model,
+                input_tokens,
+                metrics.cached_tokens.unwrap_or(0),
+                output_tokens,
+                metrics.audio_input_tokens.unwrap_or(0),
+                metrics.audio_output_tokens.unwrap_or(0),
+            ) * tier_multiplier(metrics.service_tier.as_deref()));
+            debug!("Calculated cost: {:?} for model {} ({} input, {} output tokens)", 
+                metrics.cost, metrics.model, input_tokens, output_tokens);
+        }
+
+        // Rejected predicted tokens are billed as output although they never reach the response
+        if let Some(rejected) = metrics.rejected_prediction_tokens {
+            metrics.rejected_prediction_cost = model_prices(&metrics.model)
+                .map(|(_, _, output)| rejected as f64 * output * tier_multiplier(metrics.service_tier.as_deref()));
         }
 
         debug!("Final extracted metrics: {:?}", metrics);
@@ -99,7 +194,7 @@ impl MetricsExtractor for OpenAIMetricsExtractor {
     
     // Override with OpenAI-specific streaming metrics extraction
     fn try_extract_provider_specific_streaming_metrics(&self, chunk: &str) -> Option<ProviderMetrics> {
-        debug!("Attempting to extract metrics from OpenAI streaming chunk: {}", chunk);
+        debug!("Attempting to extract metrics from OpenAI streaming chunk: {}", redact::text(chunk));
         if let Ok(json) = serde_json::from_str::<Value>(chunk) {
             // If we have usage data, extract full metrics
             if json.get("usage").is_some() {
@@ -124,13 +219,84 @@ impl MetricsExtractor for OpenAIMetricsExtractor {
         debug!("No usage data found in OpenAI streaming chunk");
         None
     }
+
+    fn estimate_cost(&self, model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        model_prices(model)?;
+        Some(calculate_cost(model, input_tokens, 0, output_tokens, 0, 0))
+    }
+}
+
+// Per-token prices in USD as (input, cached input, output)
+fn model_prices(model: &str) -> Option<(f64, f64, f64)> {
+    const PER_MILLION: f64 = 1_000_000.0;
+    let prices = match model {
+        // o-series reasoning models
+        m if m.starts_with("o1-mini") || m.starts_with("o3-mini") => (1.10, 0.55, 4.40),
+        m if m.starts_with("o1") => (15.00, 7.50, 60.00),
+
+        // GPT-4o family
+        m if m.contains("gpt-4o-mini") => (0.15, 0.075, 0.60),
+        m if m.contains("gpt-4o") => (2.50, 1.25, 10.00),
+
+        // Legacy GPT-4 and GPT-3.5 models (no prompt caching)
+        m if m.contains("gpt-4-turbo") => (10.00, 10.00, 30.00),
+        m if m.contains("gpt-4") => (30.00, 30.00, 60.00),
+        m if m.contains("gpt-3.5") => (0.50, 0.50, 1.50),
+        _ => return None,
+    };
+    Some((prices.0 / PER_MILLION, prices.1 / PER_MILLION, prices.2 / PER_MILLION))
 }
 
-// Helper function to calculate cost based on model and tokens
-fn calculate_cost(model: &str, total_tokens: u32) -> f64 {
-    match model {
-        m if m.contains("gpt-4") => (total_tokens as f64) * 0.00003,
-        m if m.contains("gpt-3.5") => (total_tokens as f64) * 0.000002,
-        _ => 0.0,
+// Price relative to the standard tier; flex processing is billed at half price
+fn tier_multiplier(service_tier: Option<&str>) -> f64 {
+    match service_tier {
+        Some("flex") => 0.5,
+        _ => 1.0,
     }
 }
+
+// Per-token audio prices in USD as (input, output), for models that accept or produce audio
+fn audio_prices(model: &str) -> Option<(f64, f64)> {
+    const PER_MILLION: f64 = 1_000_000.0;
+    let prices = match model {
+        m if m.contains("gpt-4o-mini-audio") => (10.00, 20.00),
+        // The first gpt-4o-audio snapshot launched at the higher rate
+        m if m.contains("gpt-4o-audio-preview-2024-10-01") => (100.00, 200.00),
+        m if m.contains("gpt-4o-audio") => (40.00, 80.00),
+        _ => return None,
+    };
+    Some((prices.0 / PER_MILLION, prices.1 / PER_MILLION))
+}
+
+// Helper function to calculate cost based on model and tokens.
+// Cached prompt tokens are billed at the discounted cached rate. Reasoning tokens
+// are already included in completion_tokens and are billed as output tokens.
+// Audio tokens are part of prompt_tokens and completion_tokens but billed at the
+// model's audio rates.
+fn calculate_cost(
+    model: &str,
+    input_tokens: u32,
+    cached_tokens: u32,
+    output_tokens: u32,
+    audio_input_tokens: u32,
+    audio_output_tokens: u32,
+) -> f64 {
+    let Some((input_price, cached_price, output_price)) = model_prices(model) else {
+        debug!("Unknown OpenAI model for cost calculation: {}", model);
+        return 0.0;
+    };
+    let (audio_input_price, audio_output_price) = audio_prices(model).unwrap_or((input_price, output_price));
+
+    let audio_input = audio_input_tokens.min(input_tokens);
+    let audio_output = audio_output_tokens.min(output_tokens);
+    let text_input = input_tokens - audio_input;
+    let cached = cached_tokens.min(text_input) as f64;
+    let uncached = text_input as f64 - cached;
+    let text_output = (output_tokens - audio_output) as f64;
+
+    uncached * input_price
+        + cached * cached_price
+        + audio_input as f64 * audio_input_price
+        + text_output * output_price
+        + audio_output as f64 * audio_output_price
+}
@@ -0,0 +1,172 @@
+use super::Provider;
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use serde_json::{Map, Value};
+use tracing::{debug, warn};
+
+/// Sampling and stop parameters covered by the translation matrix
+pub const TRANSLATED_PARAMS: &[&str] = &[
+    "stop",
+    "frequency_penalty",
+    "presence_penalty",
+    "seed",
+    "logprobs",
+    "top_logprobs",
+    "logit_bias",
+    "top_k",
+    "n",
+    "prediction",
+];
+
+/// Body field (and request header) carrying provider-specific options keyed by
+/// provider name, e.g. `{"groq": {"service_tier": "flex"}}`
+pub const PROVIDER_OPTIONS_FIELD: &str = "x-provider-options";
+
+/// How a provider handles an OpenAI-style request parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamSupport {
+    /// Sent as-is; the provider (or its body transform) understands it
+    Native,
+    /// Sent under a different name
+    Rename(&'static str),
+    /// Dropped before the request goes upstream and reported back to the client
+    Unsupported,
+}
+
+/// Applies the provider's parameter matrix to a JSON request body.
+///
+/// Returns the rewritten body and the names of the parameters that were
+/// dropped. Non-JSON bodies are returned unchanged.
+pub fn translate_params(provider: &dyn Provider, body: Bytes) -> (Bytes, Vec<String>) {
+    let mut json: Value = match serde_json::from_slice(&body) {
+        Ok(json) => json,
+        Err(_) => return (body, Vec::new()),
+    };
+    let Some(object) = json.as_object_mut() else {
+        return (body, Vec::new());
+    };
+
+    let mut dropped = Vec::new();
+    let mut changed = false;
+    for &param in TRANSLATED_PARAMS {
+        if !object.contains_key(param) {
+            continue;
+        }
+        match provider.param_support(param) {
+            ParamSupport::Native => {}
+            ParamSupport::Rename(target) => {
+                if let Some(value) = object.remove(param) {
+                    debug!("Renaming parameter {} to {} for {}", param, target, provider.name());
+                    // `stop` accepts a single string, the renamed fields only take a list
+                    let value = match value {
+                        Value::String(stop) if param == "stop" => Value::Array(vec![Value::String(stop)]),
+                        value => value,
+                    };
+                    object.entry(target).or_insert(value);
+                    changed = true;
+                }
+            }
+            ParamSupport::Unsupported => {
+                // A single choice is what every provider returns anyway
+                if let Some(value) = object.remove(param).filter(|v| !(param == "n" && v.as_u64() == Some(1))) {
+                    debug!("Dropping parameter {}={} for {}", param, value, provider.name());
+                    dropped.push(param.to_string());
+                }
+                changed = true;
+            }
+        }
+    }
+
+    if !dropped.is_empty() {
+        warn!(
+            "Dropped parameters unsupported by {}: {}",
+            provider.name(),
+            dropped.join(", ")
+        );
+    }
+
+    if !changed {
+        return (body, dropped);
+    }
+    match serde_json::to_vec(&json) {
+        Ok(bytes) => (Bytes::from(bytes), dropped),
+        Err(_) => (body, dropped),
+    }
+}
+
+/// Value for the warning header listing parameters the provider could not honour
+pub fn dropped_params_warning(provider: &str, dropped: &[String]) -> String {
+    format!("unsupported parameters dropped for {}: {}", provider, dropped.join(", "))
+}
+
+/// Remove the provider options namespace from the body, returning the options
+/// for this provider. Options in the body override those in the header.
+pub fn take_provider_options(provider: &str, headers: &HeaderMap, body: Bytes) -> (Bytes, Option<Map<String, Value>>) {
+    let from_header = headers
+        .get(PROVIDER_OPTIONS_FIELD)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|raw| match serde_json::from_str::<Value>(raw) {
+            Ok(options) => Some(options),
+            Err(e) => {
+                warn!("Ignoring invalid {} header: {}", PROVIDER_OPTIONS_FIELD, e);
+                None
+            }
+        });
+
+    let mut json: Option<Value> = serde_json::from_slice(&body).ok();
+    let from_body = json
+        .as_mut()
+        .and_then(Value::as_object_mut)
+        .and_then(|object| object.remove(PROVIDER_OPTIONS_FIELD));
+
+    let removed = from_body.is_some();
+
+    let mut options = Map::new();
+    for namespace in [from_header, from_body].into_iter().flatten() {
+        let Some(entry) = namespace
+            .as_object()
+            .and_then(|by_provider| by_provider.iter().find(|(name, _)| name.eq_ignore_ascii_case(provider)))
+        else {
+            continue;
+        };
+        match entry.1 {
+            Value::Object(provider_options) => merge_into(&mut options, provider_options),
+            other => warn!("Ignoring {} options for {}: expected an object, got {}", PROVIDER_OPTIONS_FIELD, provider, other),
+        }
+    }
+
+    // The namespace itself never goes upstream, whichever provider it names
+    let body = match json.filter(|_| removed) {
+        Some(json) => serde_json::to_vec(&json).map(Bytes::from).unwrap_or(body),
+        None => body,
+    };
+    (body, (!options.is_empty()).then_some(options))
+}
+
+/// Merge provider options into the prepared upstream body, after the provider's
+/// own translation so fields it doesn't know about survive
+pub fn merge_provider_options(provider: &str, body: Bytes, options: &Map<String, Value>) -> Bytes {
+    let Ok(Value::Object(mut object)) = serde_json::from_slice::<Value>(&body) else {
+        warn!("Cannot apply {} to a non-JSON {} request body", PROVIDER_OPTIONS_FIELD, provider);
+        return body;
+    };
+    debug!(
+        "Merging provider options into {} request: {}",
+        provider,
+        options.keys().cloned().collect::<Vec<_>>().join(", ")
+    );
+    merge_into(&mut object, options);
+    serde_json::to_vec(&object).map(Bytes::from).unwrap_or(body)
+}
+
+/// Deep-merge `source` into `target`; nested objects are merged, anything else replaced
+fn merge_into(target: &mut Map<String, Value>, source: &Map<String, Value>) {
+    for (key, value) in source {
+        match (target.get_mut(key), value) {
+            (Some(Value::Object(existing)), Value::Object(nested)) => merge_into(existing, nested),
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Request header choosing whether reasoning text reaches the client
+const STRIP_HEADER: &str = "x-strip-reasoning";
+
+/// Strip reasoning unless a request asks for it, read once from `STRIP_REASONING`
+static STRIP_BY_DEFAULT: Lazy<bool> = Lazy::new(|| {
+    std::env::var("STRIP_REASONING")
+        .map(|v| v.parse().unwrap_or(false))
+        .unwrap_or(false)
+});
+
+/// Fields reasoning models stream their reasoning in: `reasoning_content`
+/// (DeepSeek style) or `reasoning` (Groq's `reasoning_format: parsed`)
+const REASONING_FIELDS: &[&str] = &["reasoning_content", "reasoning"];
+
+/// Whether reasoning should be removed from this request's response
+pub fn strip_requested(headers: &HeaderMap) -> bool {
+    headers
+        .get(STRIP_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map_or(*STRIP_BY_DEFAULT, |v| v.eq_ignore_ascii_case("true") || v == "1")
+}
+
+/// Response extension counting the reasoning text a provider passed through
+/// or stripped, so telemetry can report reasoning tokens when the provider's
+/// usage doesn't break them out
+#[derive(Debug, Clone, Default)]
+pub struct ReasoningUsage(Arc<AtomicUsize>);
+
+impl ReasoningUsage {
+    /// Estimated reasoning tokens, at ~4 characters per token; None if there was no reasoning
+    pub fn tokens(&self) -> Option<u32> {
+        let chars = self.0.load(Ordering::Relaxed);
+        (chars > 0).then(|| (chars as f64 / 4.0).ceil() as u32)
+    }
+
+    /// Count the reasoning in a completion's `message`s or a chunk's `delta`s,
+    /// removing it when `strip` is set. Returns whether anything was removed.
+    pub fn record(&self, body: &mut Value, strip: bool) -> bool {
+        let mut stripped = false;
+        let choices = body.get_mut("choices").and_then(Value::as_array_mut);
+        for choice in choices.into_iter().flatten() {
+            let key = if choice.get("delta").is_some() { "delta" } else { "message" };
+            let Some(part) = choice.get_mut(key).and_then(Value::as_object_mut) else {
+                continue;
+            };
+            for field in REASONING_FIELDS {
+                let Some(text) = part.get(*field).and_then(Value::as_str) else {
+                    continue;
+                };
+                self.0.fetch_add(text.chars().count(), Ordering::Relaxed);
+                if strip {
+                    part.remove(*field);
+                    stripped = true;
+                }
+            }
+        }
+        stripped
+    }
+}
@@ -0,0 +1,176 @@
+use super::{create_provider, Provider};
+use crate::error::AppError;
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::debug;
+
+/// OpenAI-compatible endpoints the gateway knows about across all providers
+pub const KNOWN_ENDPOINTS: &[&str] = &[
+    "/v1/chat/completions",
+    "/v1/completions",
+    "/v1/embeddings",
+    "/v1/multimodalembeddings",
+    "/v1/rerank",
+    "/v1/models",
+    "/v1/moderations",
+    "/v1/images/generations",
+    "/v1/images/edits",
+    "/v1/images/variations",
+    "/v1/audio/speech",
+    "/v1/audio/transcriptions",
+    "/v1/audio/translations",
+    "/v1/text-to-speech",
+    "/v1/speech-to-text",
+    "/v1/voices",
+    "/v1/listen",
+    "/v1/speak",
+    "/v2beta/stable-image",
+    "/v1/files",
+    "/v1/uploads",
+    "/v1/fine_tuning/jobs",
+    "/v1/fine-tunes",
+    "/v1/endpoints",
+    "/v1/hardware",
+    "/v1/batches",
+    "/v1/responses",
+    "/v1/messages",
+    "/v1/messages/batches",
+];
+
+/// Methods for endpoints missing from [`ROUTE_METHODS`]
+const DEFAULT_METHODS: &[&str] = &["POST"];
+
+/// Methods each endpoint (and its sub-resources) accepts. The most specific
+/// matching entry wins.
+pub const ROUTE_METHODS: &[(&str, &[&str])] = &[
+    ("/v1/models", &["GET", "DELETE"]),
+    ("/v1/files", &["GET", "POST", "DELETE"]),
+    ("/v1/fine_tuning/jobs", &["GET", "POST"]),
+    ("/v1/fine-tunes", &["GET", "POST"]),
+    ("/v1/batches", &["GET", "POST"]),
+    ("/v1/responses", &["GET", "POST", "DELETE"]),
+    ("/v1/endpoints", &["GET", "POST", "PATCH", "DELETE"]),
+    ("/v1/hardware", &["GET"]),
+    ("/v1/voices", &["GET", "POST", "DELETE"]),
+    ("/v1/messages/batches", &["GET", "POST", "DELETE"]),
+    ("/v2beta/results", &["GET"]),
+];
+
+/// Whether `path` is `endpoint` itself or a sub-resource of it (e.g. `/v1/files/{id}`)
+fn matches(endpoint: &str, path: &str) -> bool {
+    path == endpoint
+        || path
+            .strip_prefix(endpoint)
+            .map_or(false, |rest| rest.starts_with('/'))
+}
+
+/// Methods a request to `path` may use
+pub fn allowed_methods(path: &str) -> &'static [&'static str] {
+    ROUTE_METHODS
+        .iter()
+        .filter(|(endpoint, _)| matches(endpoint, path))
+        .max_by_key(|(endpoint, _)| endpoint.len())
+        .map_or(DEFAULT_METHODS, |(_, methods)| *methods)
+}
+
+fn methods_header(methods: &[&str]) -> HeaderValue {
+    HeaderValue::from_str(&methods.join(", ")).expect("method names are valid header values")
+}
+
+/// `Allow` for `path`: its methods plus the ones the gateway answers itself
+fn allow_list(path: &str) -> Vec<&'static str> {
+    let mut methods = allowed_methods(path).to_vec();
+    methods.extend(["HEAD", "OPTIONS"]);
+    methods
+}
+
+/// Reject requests for endpoints the provider can't serve, or with a method
+/// the endpoint doesn't accept.
+///
+/// Endpoints the gateway knows but the provider lacks yield a 501; anything
+/// else yields a 404. Both list the provider's supported endpoints. Wrong
+/// methods yield a 405 with an `Allow` header.
+pub fn check_route(provider: &dyn Provider, method: &Method, path: &str) -> Result<(), AppError> {
+    check_endpoint(provider, path)?;
+
+    if !allowed_methods(path).contains(&method.as_str()) {
+        debug!("Rejecting {} {} for provider {}", method, path, provider.name());
+        return Err(AppError::MethodNotAllowed {
+            method: method.to_string(),
+            path: path.to_string(),
+            allowed: allow_list(path).iter().map(|s| s.to_string()).collect(),
+        });
+    }
+    Ok(())
+}
+
+fn check_endpoint(provider: &dyn Provider, path: &str) -> Result<(), AppError> {
+    let supported = provider.supported_endpoints();
+    if supported.iter().any(|endpoint| matches(endpoint, path)) {
+        return Ok(());
+    }
+
+    let known = KNOWN_ENDPOINTS.iter().any(|endpoint| matches(endpoint, path));
+    debug!(
+        "Rejecting {} for provider {} (known endpoint: {})",
+        path,
+        provider.name(),
+        known
+    );
+    Err(AppError::UnsupportedEndpoint {
+        path: path.to_string(),
+        provider: provider.name().to_string(),
+        supported: supported.iter().map(|s| s.to_string()).collect(),
+        known,
+    })
+}
+
+/// Whether the path is on a route the gateway proxies to providers
+pub fn is_proxy_path(path: &str) -> bool {
+    path.starts_with("/v1/") || path.starts_with("/v2beta/")
+}
+
+/// Answer OPTIONS and HEAD on proxy routes in the gateway; neither is ever
+/// forwarded to a provider.
+///
+/// This sits outside the CORS layer, which answers every OPTIONS request as a
+/// preflight; its allowed methods are narrowed here to the route's. HEAD
+/// reports whether the provider (`x-provider`) serves the route, with the
+/// route's methods in `Allow`.
+pub async fn method_middleware(request: Request<Body>, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    if !is_proxy_path(&path) {
+        return next.run(request).await;
+    }
+
+    match *request.method() {
+        Method::OPTIONS => {
+            let mut response = next.run(request).await;
+            let headers = response.headers_mut();
+            if headers.contains_key(header::ACCESS_CONTROL_ALLOW_METHODS) {
+                headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, methods_header(allowed_methods(&path)));
+            }
+            headers.insert(header::ALLOW, methods_header(&allow_list(&path)));
+            response
+        }
+        Method::HEAD => match head(request.headers(), &path) {
+            Ok(response) => response,
+            Err(e) => e.into_response(),
+        },
+        _ => next.run(request).await,
+    }
+}
+
+fn head(headers: &HeaderMap, path: &str) -> Result<Response, AppError> {
+    let provider = headers
+        .get("x-provider")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("openai");
+    let provider = create_provider(provider)?;
+    check_endpoint(provider.as_ref(), path)?;
+    Ok((StatusCode::OK, [(header::ALLOW, methods_header(&allow_list(path)))]).into_response())
+}
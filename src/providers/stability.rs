@@ -0,0 +1,124 @@
+use super::Provider;
+use super::utils::{forward_bearer_auth, log_tracking_headers};
+use crate::error::AppError;
+use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use async_trait::async_trait;
+use axum::http::{HeaderMap, HeaderValue};
+use serde_json::Value;
+use tracing::debug;
+
+/// Stability AI image generation and editing
+///
+/// The Stable Image API takes multipart form uploads, so bodies pass through
+/// untouched. Responses are raw images or JSON with a base64 image depending on
+/// the client's `Accept` header.
+pub struct StabilityProvider {
+    base_url: String,
+}
+
+impl StabilityProvider {
+    pub fn new() -> Self {
+        Self {
+            base_url: "https://api.stability.ai".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for StabilityProvider {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn name(&self) -> &str {
+        "stability"
+    }
+
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &["/v2beta/stable-image", "/v2beta/results"]
+    }
+
+    fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
+        debug!("Processing Stability request headers");
+        let mut headers = HeaderMap::new();
+
+        // Log tracking headers for observability
+        log_tracking_headers(original_headers);
+
+        // Keep the client's content type so multipart boundaries survive
+        if let Some(content_type) = original_headers.get(http::header::CONTENT_TYPE) {
+            headers.insert(http::header::CONTENT_TYPE, content_type.clone());
+        }
+        // Stability requires an Accept header to pick between raw image bytes and JSON
+        headers.insert(
+            http::header::ACCEPT,
+            original_headers
+                .get(http::header::ACCEPT)
+                .cloned()
+                .unwrap_or_else(|| HeaderValue::from_static("image/*")),
+        );
+        forward_bearer_auth("Stability", original_headers, &mut headers)?;
+
+        Ok(headers)
+    }
+}
+
+// Stability-specific metrics extractor; every call produces one image and is
+// billed a fixed number of credits for its endpoint
+pub struct StabilityMetricsExtractor;
+
+impl MetricsExtractor for StabilityMetricsExtractor {
+    fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
+        // Image responses carry no usage block; cost comes from the endpoint instead
+        debug!(
+            "Stability response finish_reason: {:?}",
+            response_body.get("finish_reason").and_then(Value::as_str)
+        );
+        ProviderMetrics::default()
+    }
+
+    fn extract_request_metrics(
+        &self,
+        path: &str,
+        _request_body: Option<&Value>,
+        _headers: &HeaderMap,
+    ) -> Option<ProviderMetrics> {
+        let operation = path.strip_prefix("/v2beta/stable-image/")?;
+        let Some(credits) = credits_per_image(operation) else {
+            debug!("Unknown Stability operation for cost calculation: {}", operation);
+            return None;
+        };
+
+        let cost = credits * USD_PER_CREDIT;
+        debug!("Calculated Stability cost: {} for {} ({} credits)", cost, operation, credits);
+        Some(ProviderMetrics {
+            model: operation.to_string(),
+            cost: Some(cost),
+            ..Default::default()
+        })
+    }
+}
+
+const USD_PER_CREDIT: f64 = 0.01;
+
+// Credits charged per generated image. The SD3 endpoint takes its model as a
+// form field the gateway doesn't parse, so it's priced as SD3.5 Large.
+fn credits_per_image(operation: &str) -> Option<f64> {
+    let credits = match operation {
+        "generate/ultra" => 8.0,
+        "generate/core" => 3.0,
+        "generate/sd3" => 6.5,
+        "edit/erase" | "edit/inpaint" => 3.0,
+        "edit/outpaint" | "edit/search-and-replace" => 4.0,
+        "edit/search-and-recolor" => 5.0,
+        "edit/remove-background" => 2.0,
+        "edit/replace-background-and-relight" => 8.0,
+        "upscale/fast" => 1.0,
+        "upscale/conservative" | "upscale/creative" => 25.0,
+        "control/sketch" | "control/structure" => 3.0,
+        "control/style" => 4.0,
+        "control/style-transfer" => 8.0,
+        _ => return None,
+    };
+    Some(credits)
+}
@@ -0,0 +1,74 @@
+use axum::body::{Body, Bytes};
+use futures_util::{Stream, StreamExt};
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// A streamed event already parsed (or built) by the provider.
+///
+/// Providers that translate streams emit these once; the same events are
+/// serialized for the client and handed to telemetry, so the middleware
+/// doesn't have to re-parse the SSE bytes.
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    /// An OpenAI-style `chat.completion.chunk`
+    Chunk(Arc<Value>),
+    /// End of stream marker (`data: [DONE]`)
+    Done,
+}
+
+impl StreamEvent {
+    pub fn chunk(value: Value) -> Self {
+        StreamEvent::Chunk(Arc::new(value))
+    }
+
+    fn write_sse(&self, out: &mut String) {
+        match self {
+            StreamEvent::Chunk(value) => {
+                out.push_str("data: ");
+                out.push_str(&value.to_string());
+                out.push_str("\n\n");
+            }
+            StreamEvent::Done => out.push_str("data: [DONE]\n\n"),
+        }
+    }
+}
+
+/// Response extension giving telemetry the events behind a streamed body
+#[derive(Clone)]
+pub struct StreamTap(Arc<Mutex<Option<mpsc::UnboundedReceiver<StreamEvent>>>>);
+
+impl StreamTap {
+    /// Take the event receiver; only the first caller gets it
+    pub fn take(&self) -> Option<mpsc::UnboundedReceiver<StreamEvent>> {
+        self.0.lock().take()
+    }
+}
+
+/// Serialize batches of events into an SSE body, teeing every event to the returned tap.
+///
+/// Each event is sent to the tap before its bytes are yielded, so a consumer
+/// reading the body can drain the matching events as each chunk arrives.
+pub fn sse_body<S, E>(events: S) -> (Body, StreamTap)
+where
+    S: Stream<Item = Result<Vec<StreamEvent>, E>> + Send + 'static,
+    E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+{
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let tap = StreamTap(Arc::new(Mutex::new(Some(receiver))));
+
+    let bytes = events.map(move |batch| {
+        batch.map(|events| {
+            let mut out = String::new();
+            for event in events {
+                event.write_sse(&mut out);
+                // Nobody may be listening (e.g. telemetry disabled for this route)
+                let _ = sender.send(event);
+            }
+            Bytes::from(out)
+        })
+    });
+
+    (Body::from_stream(bytes), tap)
+}
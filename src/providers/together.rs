@@ -1,11 +1,14 @@
 use super::Provider;
+use super::logprobs;
+use super::openai::OpenAIMetricsExtractor;
 use super::utils::log_tracking_headers;
 use crate::error::AppError;
+use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
 use async_trait::async_trait;
 use axum::http::HeaderMap;
 use tracing::{debug, error};
 use axum::{
-    body::{Body, to_bytes},
+    body::{Body, Bytes, to_bytes},
     http::{HeaderValue, Response},
 };
 use serde_json::Value;
@@ -33,6 +36,21 @@ impl Provider for TogetherProvider {
         "together"
     }
 
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &[
+            "/v1/chat/completions",
+            "/v1/completions",
+            "/v1/embeddings",
+            "/v1/models",
+            "/v1/images/generations",
+            // Platform management: fine-tuning, training files and dedicated endpoints
+            "/v1/fine-tunes",
+            "/v1/files",
+            "/v1/endpoints",
+            "/v1/hardware",
+        ]
+    }
+
     fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
         debug!("Processing Together request headers");
         let mut headers = HeaderMap::new();
@@ -40,10 +58,13 @@ impl Provider for TogetherProvider {
         // Log tracking headers for observability
         log_tracking_headers(original_headers);
 
-        // Add content type
+        // Keep the client's content type so multipart file uploads survive
         headers.insert(
             http::header::CONTENT_TYPE,
-            http::header::HeaderValue::from_static("application/json"),
+            original_headers
+                .get(http::header::CONTENT_TYPE)
+                .cloned()
+                .unwrap_or_else(|| http::header::HeaderValue::from_static("application/json")),
         );
 
         // Process authentication
@@ -81,6 +102,18 @@ impl Provider for TogetherProvider {
         Ok(headers)
     }
 
+    async fn prepare_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        // Together expects the legacy integer form of `logprobs`
+        let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+            return Ok(body);
+        };
+        if json.get("logprobs").is_none() {
+            return Ok(body);
+        }
+        logprobs::to_legacy_request(&mut json);
+        Ok(Bytes::from(serde_json::to_vec(&json)?))
+    }
+
     async fn process_response(&self, response: Response<Body>) -> Result<Response<Body>, AppError> {
         // Clone response parts and body
         let (mut parts, body) = response.into_parts();
@@ -109,8 +142,9 @@ impl Provider for TogetherProvider {
             return Ok(Response::from_parts(parts, body));
         }
         
-        // For regular responses
-        let bytes = to_bytes(body, usize::MAX).await?;
+        // For regular responses, converting any legacy logprobs to the OpenAI schema
+        let bytes = logprobs::normalize_body(to_bytes(body, usize::MAX).await?);
+        parts.headers.remove(http::header::CONTENT_LENGTH);
         
         // Check if we already have a request ID
         let has_request_id = parts.headers.get("x-request-id").is_some();
@@ -145,3 +179,27 @@ impl Provider for TogetherProvider {
         Ok(Response::from_parts(parts, Body::from(bytes)))
     }
 }
+
+// Together responses use the OpenAI usage format
+pub struct TogetherMetricsExtractor;
+
+impl MetricsExtractor for TogetherMetricsExtractor {
+    fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
+        OpenAIMetricsExtractor.extract_metrics(response_body)
+    }
+
+    /// Together sends `"usage": null` on every chunk but the last, which
+    /// carries the stream's token counts
+    fn try_extract_provider_specific_streaming_metrics(&self, chunk: &str) -> Option<ProviderMetrics> {
+        let json = serde_json::from_str::<Value>(chunk).ok()?;
+        if !json.get("usage").map_or(false, Value::is_object) {
+            return None;
+        }
+        debug!("Found usage in final Together streaming chunk");
+        Some(self.extract_metrics(&json))
+    }
+
+    fn estimate_cost(&self, model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        OpenAIMetricsExtractor.estimate_cost(model, input_tokens, output_tokens)
+    }
+}
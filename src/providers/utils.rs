@@ -1,14 +1,7 @@
-use axum::http::HeaderMap;
-use tracing::debug;
-
-/// List of tracking headers that should be preserved and logged
-pub const TRACKING_HEADERS: [&str; 5] = [
-    "x-project-id", 
-    "x-organization-id",
-    "x-organisation-id", // British spelling
-    "x-user-id",
-    "x-experiment-id"
-];
+use crate::error::AppError;
+use crate::identity;
+use axum::http::{HeaderMap, HeaderValue};
+use tracing::{debug, error};
 
 /// Utility function to log tracking headers for observability
 /// 
@@ -18,9 +11,38 @@ pub const TRACKING_HEADERS: [&str; 5] = [
 /// # Arguments
 /// * `headers` - The original request headers
 pub fn log_tracking_headers(headers: &HeaderMap) {
-    for header in &TRACKING_HEADERS {
-        if let Some(value) = headers.get(*header).and_then(|h| h.to_str().ok()) {
+    for header in identity::header_names() {
+        if let Some(value) = headers.get(header).and_then(|h| h.to_str().ok()) {
             debug!("{}: {}", header, value);
         }
     }
-} 
\ No newline at end of file
+} 
+/// Copy the client's Bearer token into the upstream headers
+///
+/// # Arguments
+/// * `provider` - Provider name, for logging
+/// * `original_headers` - The original request headers
+/// * `headers` - The headers being built for the upstream request
+pub fn forward_bearer_auth(
+    provider: &str,
+    original_headers: &HeaderMap,
+    headers: &mut HeaderMap,
+) -> Result<(), AppError> {
+    let Some(auth) = original_headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .filter(|auth| auth.len() > "Bearer ".len())
+    else {
+        error!("No authorization header found for {} request", provider);
+        return Err(AppError::MissingApiKey);
+    };
+
+    headers.insert(
+        http::header::AUTHORIZATION,
+        HeaderValue::from_str(auth).map_err(|_| {
+            error!("Invalid characters in {} authorization header", provider);
+            AppError::InvalidHeader
+        })?,
+    );
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use super::Provider;
+use super::utils::{forward_bearer_auth, log_tracking_headers};
+use crate::error::AppError;
+use crate::telemetry::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use crate::telemetry::redact;
+use async_trait::async_trait;
+use axum::{body::Bytes, http::HeaderMap};
+use serde_json::Value;
+use tracing::debug;
+
+/// Voyage AI embeddings and reranking
+pub struct VoyageProvider {
+    base_url: String,
+}
+
+impl VoyageProvider {
+    pub fn new() -> Self {
+        Self {
+            base_url: "https://api.voyageai.com".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for VoyageProvider {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    fn name(&self) -> &str {
+        "voyage"
+    }
+
+    fn supported_endpoints(&self) -> &'static [&'static str] {
+        &["/v1/embeddings", "/v1/multimodalembeddings", "/v1/rerank"]
+    }
+
+    fn process_headers(&self, original_headers: &HeaderMap) -> Result<HeaderMap, AppError> {
+        debug!("Processing Voyage request headers");
+        let mut headers = HeaderMap::new();
+
+        // Log tracking headers for observability
+        log_tracking_headers(original_headers);
+
+        headers.insert(
+            http::header::CONTENT_TYPE,
+            http::header::HeaderValue::from_static("application/json"),
+        );
+        forward_bearer_auth("Voyage", original_headers, &mut headers)?;
+
+        Ok(headers)
+    }
+
+    async fn prepare_request_body(&self, body: Bytes) -> Result<Bytes, AppError> {
+        let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+            return Ok(body);
+        };
+        let Some(object) = json.as_object_mut() else {
+            return Ok(body);
+        };
+
+        // OpenAI's `dimensions` is `output_dimension` here, and floats are the default
+        // encoding (Voyage only accepts `base64` or null)
+        let mut changed = false;
+        if let Some(dimensions) = object.remove("dimensions") {
+            object.entry("output_dimension").or_insert(dimensions);
+            changed = true;
+        }
+        if object.get("encoding_format").and_then(Value::as_str) == Some("float") {
+            object.remove("encoding_format");
+            changed = true;
+        }
+        if !changed {
+            return Ok(body);
+        }
+        Ok(Bytes::from(serde_json::to_vec(&json)?))
+    }
+}
+
+// Voyage-specific metrics extractor; usage only reports total_tokens
+pub struct VoyageMetricsExtractor;
+
+impl MetricsExtractor for VoyageMetricsExtractor {
+    fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics {
+        debug!("Extracting Voyage metrics from response: {}", redact::value(response_body));
+        let mut metrics = ProviderMetrics::default();
+
+        if let Some(tokens) = response_body
+            .get("usage")
+            .and_then(|u| u.get("total_tokens"))
+            .and_then(Value::as_u64)
+        {
+            // Embedding and rerank calls bill input tokens only
+            metrics.input_tokens = Some(tokens as u32);
+            metrics.output_tokens = Some(0);
+            metrics.total_tokens = Some(tokens as u32);
+        }
+
+        if let Some(model) = response_body.get("model").and_then(Value::as_str) {
+            metrics.model = model.to_string();
+        }
+
+        if let Some(tokens) = metrics.total_tokens {
+            metrics.cost = Some(calculate_cost(&metrics.model, tokens));
+            debug!("Calculated Voyage cost: {:?} for model {} and {} tokens", metrics.cost, metrics.model, tokens);
+        }
+
+        metrics
+    }
+}
+
+// Price per million tokens in USD
+fn price_per_million(model: &str) -> Option<f64> {
+    let price = match model {
+        "voyage-3-large" | "voyage-code-3" | "voyage-multimodal-3" => 0.18,
+        "voyage-3.5" | "voyage-3" => 0.06,
+        "voyage-3.5-lite" | "voyage-3-lite" => 0.02,
+        m if m.starts_with("voyage-finance") || m.starts_with("voyage-law") || m.starts_with("voyage-code") => 0.12,
+        m if m.starts_with("rerank-2-lite") || m.starts_with("rerank-2.5-lite") => 0.02,
+        m if m.starts_with("rerank-") => 0.05,
+        _ => return None,
+    };
+    Some(price)
+}
+
+fn calculate_cost(model: &str, tokens: u32) -> f64 {
+    let Some(price) = price_per_million(model) else {
+        debug!("Unknown Voyage model for cost calculation: {}", model);
+        return 0.0;
+    };
+    tokens as f64 * price / 1_000_000.0
+}
@@ -0,0 +1,143 @@
+use crate::error::AppError;
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::{BehaviorVersion, Region};
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_credential_types::Credentials;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use tokio::sync::OnceCell;
+use tracing::{debug, error, info};
+
+/// Session name reported to STS for assumed roles
+const ROLE_SESSION_NAME: &str = "noveum-ai-gateway";
+
+/// Refresh cached credentials this long before they expire
+const EXPIRY_BUFFER: Duration = Duration::from_secs(5 * 60);
+
+/// AWS credentials used to sign a request
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key: String,
+    pub secret_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key", &"** redacted **")
+            .field("secret_key", &"** redacted **")
+            .field("session_token", &self.session_token.as_ref().map(|_| "** redacted **"))
+            .finish()
+    }
+}
+
+impl From<&Credentials> for AwsCredentials {
+    fn from(credentials: &Credentials) -> Self {
+        Self {
+            access_key: credentials.access_key_id().to_string(),
+            secret_key: credentials.secret_access_key().to_string(),
+            session_token: credentials.session_token().map(String::from),
+        }
+    }
+}
+
+// The default chain (env, shared config/profile, web identity/IRSA, ECS and EC2 IMDS)
+// is resolved once; the credentials it yields are cached separately below.
+static DEFAULT_CHAIN: OnceCell<Option<SharedCredentialsProvider>> = OnceCell::const_new();
+static CACHED: Lazy<Mutex<Option<Credentials>>> = Lazy::new(|| Mutex::new(None));
+
+// Assumed-role credentials keyed by role ARN and the base access key that assumed it
+static ASSUMED_ROLES: Lazy<Mutex<HashMap<String, Credentials>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+async fn default_chain() -> Option<SharedCredentialsProvider> {
+    DEFAULT_CHAIN
+        .get_or_init(|| async {
+            info!("Loading AWS default credential provider chain");
+            let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+            config.credentials_provider()
+        })
+        .await
+        .clone()
+}
+
+/// Whether credentials are still usable without a refresh
+pub(crate) fn is_fresh(credentials: &Credentials) -> bool {
+    match credentials.expiry() {
+        Some(expiry) => expiry
+            .duration_since(SystemTime::now())
+            .map(|remaining| remaining > EXPIRY_BUFFER)
+            .unwrap_or(false),
+        None => true,
+    }
+}
+
+/// Resolve credentials through the AWS default provider chain, with caching
+pub async fn default_chain_credentials() -> Result<AwsCredentials, AppError> {
+    if let Some(credentials) = CACHED.lock().as_ref().filter(|c| is_fresh(c)) {
+        debug!("Using cached AWS credentials from the default chain");
+        return Ok(AwsCredentials::from(credentials));
+    }
+
+    let provider = default_chain().await.ok_or_else(|| {
+        error!("No AWS credential provider available in the default chain");
+        AppError::MissingApiKey
+    })?;
+
+    let credentials = provider.provide_credentials().await.map_err(|e| {
+        error!("Failed to resolve AWS credentials from the default chain: {}", e);
+        AppError::MissingApiKey
+    })?;
+
+    debug!(
+        "Resolved AWS credentials from the default chain (expires: {:?})",
+        credentials.expiry()
+    );
+    let resolved = AwsCredentials::from(&credentials);
+    *CACHED.lock() = Some(credentials);
+    Ok(resolved)
+}
+
+/// Assume `role_arn` via STS using `base` as the calling identity, with caching
+pub async fn assume_role(
+    role_arn: &str,
+    base: &AwsCredentials,
+    region: &str,
+) -> Result<AwsCredentials, AppError> {
+    let cache_key = format!("{}|{}", role_arn, base.access_key);
+    if let Some(credentials) = ASSUMED_ROLES.lock().get(&cache_key).filter(|c| is_fresh(c)) {
+        debug!("Using cached credentials for assumed role {}", role_arn);
+        return Ok(AwsCredentials::from(credentials));
+    }
+
+    info!("Assuming AWS role {} via STS", role_arn);
+    let base_credentials = Credentials::new(
+        base.access_key.clone(),
+        base.secret_key.clone(),
+        base.session_token.clone(),
+        None,
+        "assume-role-base",
+    );
+
+    let provider = AssumeRoleProvider::builder(role_arn)
+        .session_name(ROLE_SESSION_NAME)
+        .region(Region::new(region.to_string()))
+        .build_from_provider(base_credentials)
+        .await;
+
+    let credentials = provider.provide_credentials().await.map_err(|e| {
+        error!("Failed to assume AWS role {}: {}", role_arn, e);
+        AppError::AwsParamsError(format!("Failed to assume role {}: {}", role_arn, e))
+    })?;
+
+    debug!(
+        "Assumed role {} (expires: {:?})",
+        role_arn,
+        credentials.expiry()
+    );
+    let resolved = AwsCredentials::from(&credentials);
+    ASSUMED_ROLES.lock().insert(cache_key, credentials);
+    Ok(resolved)
+}
@@ -0,0 +1,71 @@
+use crate::context::GatewayRequestContext;
+use crate::error::AppError;
+use crate::providers::audio_content;
+use crate::telemetry::pricing;
+use crate::telemetry::provider_metrics::{get_metrics_extractor, MetricsExtractor, ProviderMetrics};
+use crate::tenancy::Tenant;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tracing::debug;
+
+/// Output tokens assumed when the request doesn't set `max_tokens`
+const DEFAULT_OUTPUT_ESTIMATE: u32 = 1024;
+
+/// Body fields holding the prompt, across chat, completion and embedding APIs
+const PROMPT_FIELDS: &[&str] = &["system", "messages", "prompt", "input", "contents"];
+
+/// Estimates below this many USD are not reserved, from `BUDGET_RESERVATION_MIN_USD`
+static RESERVATION_MIN_USD: Lazy<f64> = Lazy::new(|| {
+    std::env::var("BUDGET_RESERVATION_MIN_USD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0.01)
+});
+
+/// Reserve a request's estimated cost against its tenant's budget before it is
+/// forwarded. The reservation is keyed by the gateway-generated request ID in
+/// the request's context and settled against the actual cost once the
+/// request's telemetry is recorded.
+pub async fn reserve(tenant: &Tenant, context: &GatewayRequestContext, body: &Value) -> Result<(), AppError> {
+    if tenant.config.budget_usd.is_none() {
+        return Ok(());
+    }
+    let Some(model) = context.model.as_deref() else {
+        return Ok(());
+    };
+    let Some(estimate) = estimate_cost(tenant, &context.provider, model, body) else {
+        return Ok(());
+    };
+    if estimate < *RESERVATION_MIN_USD {
+        return Ok(());
+    }
+    tenant.reserve(&context.request_id, estimate).await
+}
+
+/// Worst-case cost of a request: its estimated prompt plus every output token it
+/// may generate, at the tenant's negotiated price when it has one
+fn estimate_cost(tenant: &Tenant, provider: &str, model: &str, body: &Value) -> Option<f64> {
+    let input_tokens: u32 = PROMPT_FIELDS
+        .iter()
+        .filter_map(|field| body.get(*field))
+        .map(|value| match value {
+            Value::String(text) => ProviderMetrics::estimate_tokens_from_text(text),
+            other => ProviderMetrics::estimate_tokens_from_text(&audio_content::without_audio(other).to_string()),
+        })
+        .sum();
+    let output_tokens = ["max_tokens", "max_completion_tokens", "max_output_tokens"]
+        .iter()
+        .find_map(|key| body.get(*key).and_then(Value::as_u64))
+        .map_or(DEFAULT_OUTPUT_ESTIMATE, |tokens| tokens.min(u64::from(u32::MAX)) as u32);
+
+    let list_cost = get_metrics_extractor(provider).estimate_cost(model, input_tokens, output_tokens);
+    let cost = match pricing::find_override(&tenant.config.pricing, provider, model) {
+        Some(price) => price.price(list_cost, Some(input_tokens), None, Some(output_tokens)),
+        None => list_cost,
+    };
+    debug!(
+        "Estimated {} request at {} input / {} output tokens: {:?} USD",
+        model, input_tokens, output_tokens, cost
+    );
+    cost
+}
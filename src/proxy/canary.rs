@@ -0,0 +1,290 @@
+use crate::cluster;
+use crate::shared_state::{self, SharedState};
+use crate::telemetry::stats::{aggregate, StatsQuery, StatsStore};
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// How often canaries are compared against their baseline
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Shared-state key holding the canaries the cluster leader rolled back
+const SHARED_KEY: &str = "canary:rollbacks";
+
+/// How long the cluster remembers a rollback
+const SHARED_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A config change tried on a slice of traffic before rollout
+#[derive(Debug, Clone, Deserialize)]
+pub struct CanaryConfig {
+    pub name: String,
+    /// Only requests to this provider are eligible; any provider when unset
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Requested model the change applies to
+    pub model: String,
+    /// Model canary traffic is sent to instead
+    #[serde(default)]
+    pub target_model: Option<String>,
+    /// Body fields set on canary traffic, e.g. `{"reasoning_effort": "low"}`
+    #[serde(default)]
+    pub overrides: Map<String, Value>,
+    /// Share of eligible traffic, 0-100
+    pub percent: f64,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// Requests each arm needs in the window before it's judged
+    #[serde(default = "default_min_requests")]
+    pub min_requests: u64,
+    /// Allowed rise in error rate over the baseline, as a fraction (0.05 = 5 points)
+    #[serde(default = "default_max_error_rate_increase")]
+    pub max_error_rate_increase: f64,
+    /// Allowed rise in p95 latency over the baseline, as a fraction (0.5 = 50%)
+    #[serde(default = "default_max_latency_increase")]
+    pub max_latency_increase: f64,
+}
+
+fn default_window_secs() -> u64 {
+    300
+}
+
+fn default_min_requests() -> u64 {
+    20
+}
+
+fn default_max_error_rate_increase() -> f64 {
+    0.05
+}
+
+fn default_max_latency_increase() -> f64 {
+    0.5
+}
+
+impl CanaryConfig {
+    fn applies_to(&self, provider: &str, model: &str) -> bool {
+        self.model == model && self.provider.as_deref().map_or(true, |p| p.eq_ignore_ascii_case(provider))
+    }
+}
+
+/// Which arm of a canary a request was served by; carried in response extensions for telemetry
+#[derive(Debug, Clone)]
+pub struct CanaryAssignment {
+    pub name: String,
+    pub arm: &'static str,
+}
+
+impl CanaryAssignment {
+    /// Cohort label recorded in telemetry and the stats window, e.g. `gpt-41-rollout:canary`
+    pub fn label(&self) -> String {
+        format!("{}:{}", self.name, self.arm)
+    }
+}
+
+/// Why a canary was switched off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rollback {
+    pub reason: String,
+    pub rolled_back_at: DateTime<Utc>,
+}
+
+/// Canaries from `CANARY_CONFIG`
+static CANARIES: Lazy<Vec<CanaryConfig>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("CANARY_CONFIG") else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<CanaryConfig>>(&raw) {
+        Ok(canaries) => {
+            info!("Loaded {} canary configs", canaries.len());
+            canaries
+        }
+        Err(e) => {
+            error!("Failed to parse CANARY_CONFIG: {}", e);
+            Vec::new()
+        }
+    }
+});
+
+/// Canaries rolled back since startup, by name
+static ROLLED_BACK: Lazy<RwLock<HashMap<String, Rollback>>> = Lazy::new(Default::default);
+
+/// Put a request in a canary's canary or baseline arm, applying the change to canary traffic
+pub fn assign(provider: &str, body: Bytes) -> (Bytes, Option<CanaryAssignment>) {
+    if CANARIES.is_empty() {
+        return (body, None);
+    }
+    let Ok(mut json) = serde_json::from_slice::<Value>(&body) else {
+        return (body, None);
+    };
+    let Some(model) = json.get("model").and_then(Value::as_str) else {
+        return (body, None);
+    };
+    let Some(canary) = CANARIES.iter().find(|c| c.applies_to(provider, model)) else {
+        return (body, None);
+    };
+
+    // Once rolled back, every request gets the baseline config and leaves the cohorts
+    if ROLLED_BACK.read().contains_key(&canary.name) {
+        return (body, None);
+    }
+
+    let in_canary = rand::thread_rng().gen_range(0.0..100.0) < canary.percent;
+    let assignment = CanaryAssignment {
+        name: canary.name.clone(),
+        arm: if in_canary { "canary" } else { "baseline" },
+    };
+    if !in_canary {
+        return (body, Some(assignment));
+    }
+
+    debug!("Routing {} request for {} through canary {}", provider, model, canary.name);
+    if let Some(target) = &canary.target_model {
+        json["model"] = Value::String(target.clone());
+    }
+    if let Some(object) = json.as_object_mut() {
+        for (key, value) in &canary.overrides {
+            object.insert(key.clone(), value.clone());
+        }
+    }
+    match serde_json::to_vec(&json) {
+        Ok(bytes) => (Bytes::from(bytes), Some(assignment)),
+        Err(_) => (body, None),
+    }
+}
+
+/// Compare every live canary against its baseline and roll back regressions,
+/// returning whether any was rolled back
+pub fn evaluate(stats: &StatsStore) -> bool {
+    let mut rolled_back = false;
+    for canary in CANARIES.iter() {
+        if ROLLED_BACK.read().contains_key(&canary.name) {
+            continue;
+        }
+        let since = Utc::now() - chrono::Duration::seconds(canary.window_secs as i64);
+        let arm = |arm: &str| {
+            aggregate(&stats.samples(&StatsQuery {
+                canary: Some(format!("{}:{}", canary.name, arm)),
+                since: Some(since),
+                ..Default::default()
+            }))
+        };
+        let (canary_stats, baseline) = (arm("canary"), arm("baseline"));
+        if canary_stats.requests < canary.min_requests || baseline.requests < canary.min_requests {
+            debug!(
+                "Canary {} has {}/{} requests, waiting for {}",
+                canary.name, canary_stats.requests, baseline.requests, canary.min_requests
+            );
+            continue;
+        }
+
+        let error_rise = canary_stats.error_rate - baseline.error_rate;
+        let latency_limit = baseline.latency_p95_ms * (1.0 + canary.max_latency_increase);
+        let reason = if error_rise > canary.max_error_rate_increase {
+            Some(format!(
+                "error rate {:.1}% vs baseline {:.1}%",
+                canary_stats.error_rate * 100.0,
+                baseline.error_rate * 100.0
+            ))
+        } else if canary_stats.latency_p95_ms > latency_limit {
+            Some(format!(
+                "p95 latency {:.0}ms vs baseline {:.0}ms",
+                canary_stats.latency_p95_ms, baseline.latency_p95_ms
+            ))
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            warn!("Rolling back canary {}: {}", canary.name, reason);
+            ROLLED_BACK.write().insert(
+                canary.name.clone(),
+                Rollback {
+                    reason,
+                    rolled_back_at: Utc::now(),
+                },
+            );
+            rolled_back = true;
+        }
+    }
+    rolled_back
+}
+
+/// Share this replica's rollbacks with the rest of the cluster
+async fn publish(shared: &SharedState) {
+    let rollbacks = ROLLED_BACK.read().clone();
+    if let Err(e) = shared.put_json(SHARED_KEY, &rollbacks, SHARED_TTL).await {
+        warn!("Failed to publish canary rollbacks: {}", e);
+    }
+}
+
+/// Adopt the rollbacks published by the cluster leader
+async fn load_published(shared: &SharedState) {
+    let rollbacks: HashMap<String, Rollback> = match shared.get_json(SHARED_KEY).await {
+        Ok(Some(rollbacks)) => rollbacks,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Failed to load published canary rollbacks: {}", e);
+            return;
+        }
+    };
+    let mut rolled_back = ROLLED_BACK.write();
+    for (name, rollback) in rollbacks {
+        if !rolled_back.contains_key(&name) {
+            warn!("Canary {} was rolled back by the cluster leader: {}", name, rollback.reason);
+            rolled_back.insert(name, rollback);
+        }
+    }
+}
+
+/// Periodically evaluate canaries against the in-memory aggregates. In a
+/// cluster only the leader evaluates, on its own traffic, and the other
+/// replicas adopt the rollbacks it publishes.
+pub fn spawn_evaluator(stats: Arc<StatsStore>) {
+    if CANARIES.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(EVALUATION_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let shared = shared_state::get().await;
+            if let Some(shared) = shared {
+                load_published(shared).await;
+            }
+            if !cluster::is_leader() {
+                continue;
+            }
+            if evaluate(&stats) {
+                if let Some(shared) = shared {
+                    publish(shared).await;
+                }
+            }
+        }
+    });
+}
+
+/// Configured canaries and whether each is still live, for the admin API
+pub fn status() -> Vec<Value> {
+    let rolled_back = ROLLED_BACK.read();
+    CANARIES
+        .iter()
+        .map(|canary| {
+            serde_json::json!({
+                "name": canary.name,
+                "provider": canary.provider,
+                "model": canary.model,
+                "target_model": canary.target_model,
+                "percent": canary.percent,
+                "state": if rolled_back.contains_key(&canary.name) { "rolled_back" } else { "active" },
+                "rollback": rolled_back.get(&canary.name),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,168 @@
+use crate::{
+    config::AppConfig,
+    error::AppError,
+    providers::create_provider,
+    telemetry::error_class::{self, ErrorClass},
+};
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{request::Parts, HeaderMap, Response},
+};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::{collections::HashMap, fmt, sync::Arc};
+use tracing::{debug, error, info};
+
+use super::forward_request;
+
+/// Request header opting in to retries after a context-length error
+const AUTO_TRUNCATE_HEADER: &str = "x-auto-truncate";
+
+/// Remediation attempts before the provider's error is returned as is
+const MAX_ATTEMPTS: usize = 3;
+
+/// Long-context fallbacks from `LONG_CONTEXT_FALLBACKS`, keyed by `provider:model`
+/// or bare model name, e.g. `{"gpt-4o-mini": "gpt-4.1-mini"}`
+static FALLBACK_MODELS: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("LONG_CONTEXT_FALLBACKS") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, String>>(&raw) {
+        Ok(fallbacks) => {
+            info!("Loaded {} long-context fallback models", fallbacks.len());
+            fallbacks
+        }
+        Err(e) => {
+            error!("Failed to parse LONG_CONTEXT_FALLBACKS: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// How a request was changed to fit the model's context window; the metrics
+/// middleware reads this from the response extensions
+#[derive(Debug, Clone, Default)]
+pub struct ContextRemediation {
+    pub dropped_messages: usize,
+    pub fallback_model: Option<String>,
+}
+
+impl fmt::Display for ContextRemediation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.fallback_model, self.dropped_messages) {
+            (Some(model), 0) => write!(f, "fallback_model:{}", model),
+            (Some(model), dropped) => write!(f, "fallback_model:{},truncated:{}", model, dropped),
+            (None, dropped) => write!(f, "truncated:{}", dropped),
+        }
+    }
+}
+
+/// Whether the client asked for context-length errors to be remediated
+pub fn requested(headers: &HeaderMap) -> bool {
+    headers
+        .get(AUTO_TRUNCATE_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Forward the request, retrying on context-length errors with a long-context
+/// fallback model if one is configured and then with the oldest messages dropped
+pub async fn forward_with_remediation(
+    config: Arc<AppConfig>,
+    provider_name: &str,
+    parts: &Parts,
+    body_bytes: Bytes,
+) -> Result<Response<Body>, AppError> {
+    let mut body_bytes = body_bytes;
+    let mut remediation = ContextRemediation::default();
+
+    for attempt in 0..=MAX_ATTEMPTS {
+        let provider = create_provider(provider_name)?;
+        let response = forward_request(config.clone(), provider, parts, body_bytes.clone()).await?;
+
+        let status = response.status().as_u16();
+        if attempt == MAX_ATTEMPTS || !matches!(status, 400 | 413 | 422) {
+            return Ok(finish(response, &remediation));
+        }
+
+        // Only error bodies are buffered, so streamed successes pass straight through
+        let (response_parts, body) = response.into_parts();
+        let error_body = to_bytes(body, usize::MAX).await.unwrap_or_default();
+        let error_json = serde_json::from_slice::<Value>(&error_body).ok();
+        let response = Response::from_parts(response_parts, Body::from(error_body));
+        if error_class::classify(status, error_json.as_ref()) != Some(ErrorClass::ContextLength) {
+            return Ok(finish(response, &remediation));
+        }
+
+        let Ok(mut request) = serde_json::from_slice::<Value>(&body_bytes) else {
+            return Ok(finish(response, &remediation));
+        };
+        if remediation.fallback_model.is_none() && switch_to_fallback(provider_name, &mut request, &mut remediation) {
+            info!("Retrying context-length failure with fallback model {:?}", remediation.fallback_model);
+        } else if let Some(dropped) = drop_oldest_messages(&mut request) {
+            remediation.dropped_messages += dropped;
+            info!("Retrying context-length failure without the oldest {} messages", dropped);
+        } else {
+            debug!("Nothing left to truncate; returning the context-length error");
+            return Ok(finish(response, &remediation));
+        }
+        body_bytes = Bytes::from(serde_json::to_vec(&request)?);
+    }
+
+    unreachable!("the final attempt always returns")
+}
+
+fn finish(mut response: Response<Body>, remediation: &ContextRemediation) -> Response<Body> {
+    if remediation.fallback_model.is_some() || remediation.dropped_messages > 0 {
+        response.extensions_mut().insert(remediation.clone());
+    }
+    response
+}
+
+fn switch_to_fallback(provider_name: &str, request: &mut Value, remediation: &mut ContextRemediation) -> bool {
+    let Some(model) = request.get("model").and_then(Value::as_str) else {
+        return false;
+    };
+    let Some(fallback) = FALLBACK_MODELS
+        .get(&format!("{}:{}", provider_name.to_lowercase(), model))
+        .or_else(|| FALLBACK_MODELS.get(model))
+        .filter(|fallback| fallback.as_str() != model)
+        .cloned()
+    else {
+        return false;
+    };
+    request["model"] = Value::String(fallback.clone());
+    remediation.fallback_model = Some(fallback);
+    true
+}
+
+/// Drop the oldest quarter (at least one) of the conversation, keeping system
+/// messages and the latest message, then any assistant or tool messages left
+/// leading the history so it still starts with a user turn
+fn drop_oldest_messages(request: &mut Value) -> Option<usize> {
+    let messages = request.get_mut("messages").and_then(Value::as_array_mut)?;
+    let is_system = |m: &Value| matches!(m.get("role").and_then(Value::as_str), Some("system" | "developer"));
+    let conversation: Vec<usize> = (0..messages.len()).filter(|&i| !is_system(&messages[i])).collect();
+    if conversation.len() < 2 {
+        return None;
+    }
+
+    let droppable = conversation.len() - 1;
+    let mut drop_count = droppable.div_ceil(4);
+    // Don't leave an assistant reply or tool result without the turn that prompted it
+    while drop_count < droppable
+        && messages[conversation[drop_count]].get("role").and_then(Value::as_str) != Some("user")
+    {
+        drop_count += 1;
+    }
+
+    let dropped: Vec<usize> = conversation[..drop_count].to_vec();
+    let mut index = 0;
+    messages.retain(|_| {
+        let keep = !dropped.contains(&index);
+        index += 1;
+        keep
+    });
+    Some(dropped.len())
+}
@@ -0,0 +1,167 @@
+use crate::error::AppError;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{request::Parts, Extensions, HeaderMap, HeaderValue, Response, StatusCode},
+    response::IntoResponse,
+};
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{debug, info};
+
+/// Headers that identify the caller; requests only coalesce when they match
+const IDENTITY_HEADERS: &[&str] = &["authorization", "x-api-key", "x-aws-access-key-id", "x-gateway-key"];
+
+/// Set on responses that were served from another request's upstream call
+pub const DEDUPLICATED_HEADER: &str = "x-gateway-deduplicated";
+
+/// Marks a response shared from another request so it isn't billed twice
+#[derive(Debug, Clone, Copy)]
+pub struct Deduplicated;
+
+/// A completed response kept in memory so every coalesced caller gets a copy
+struct BufferedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    extensions: Extensions,
+    body: Bytes,
+}
+
+impl BufferedResponse {
+    async fn read(response: Response<Body>) -> Self {
+        let (parts, body) = response.into_parts();
+        let body = match to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(e) => {
+                let error = AppError::AxumError(e);
+                return Self::read_error(error.into_response(), parts.extensions).await;
+            }
+        };
+        Self {
+            status: parts.status,
+            headers: parts.headers,
+            extensions: parts.extensions,
+            body,
+        }
+    }
+
+    async fn read_error(response: Response<Body>, extensions: Extensions) -> Self {
+        let (parts, body) = response.into_parts();
+        Self {
+            status: parts.status,
+            headers: parts.headers,
+            extensions,
+            body: to_bytes(body, usize::MAX).await.unwrap_or_default(),
+        }
+    }
+
+    fn to_response(&self) -> Response<Body> {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        *response.extensions_mut() = self.extensions.clone();
+        response
+    }
+}
+
+type SharedResponse = Shared<BoxFuture<'static, Arc<BufferedResponse>>>;
+
+struct InFlight {
+    started: Instant,
+    response: SharedResponse,
+}
+
+/// How long after the first request an identical one is coalesced; disabled when zero
+static WINDOW: Lazy<Duration> = Lazy::new(|| {
+    let window = std::env::var("DEDUP_WINDOW_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_millis)
+        .unwrap_or_default();
+    if !window.is_zero() {
+        info!("Coalescing identical requests within {:?}", window);
+    }
+    window
+});
+
+static IN_FLIGHT: Lazy<Mutex<HashMap<String, InFlight>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Dedup key for a request, or None if it shouldn't be coalesced.
+///
+/// Only non-streaming POSTs are eligible; streams can't be shared once started.
+pub(super) fn dedup_key(provider: &str, parts: &Parts, body: &Bytes) -> Option<String> {
+    if WINDOW.is_zero() || parts.method != http::Method::POST {
+        return None;
+    }
+    let streaming = serde_json::from_slice::<Value>(body)
+        .ok()?
+        .get("stream")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if streaming {
+        return None;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(provider.as_bytes());
+    hasher.update(parts.uri.path().as_bytes());
+    for &name in IDENTITY_HEADERS {
+        hasher.update(name.as_bytes());
+        if let Some(value) = parts.headers.get(name) {
+            hasher.update(value.as_bytes());
+        }
+    }
+    // The same body asked for natively gets a differently shaped response
+    hasher.update([u8::from(super::raw_passthrough::requested(&parts.headers))]);
+    hasher.update(body);
+    Some(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Run the request, or join an identical one already running within the window
+pub(super) async fn coalesce<F>(key: String, request: F) -> Result<Response<Body>, AppError>
+where
+    F: Future<Output = Result<Response<Body>, AppError>> + Send + 'static,
+{
+    let (shared, leader) = {
+        let mut in_flight = IN_FLIGHT.lock();
+        in_flight.retain(|_, entry| entry.started.elapsed() < *WINDOW);
+
+        match in_flight.get(&key) {
+            Some(entry) => (entry.response.clone(), false),
+            None => {
+                let response = async move {
+                    let response = request.await.unwrap_or_else(IntoResponse::into_response);
+                    Arc::new(BufferedResponse::read(response).await)
+                }
+                .boxed()
+                .shared();
+                in_flight.insert(
+                    key,
+                    InFlight {
+                        started: Instant::now(),
+                        response: response.clone(),
+                    },
+                );
+                (response, true)
+            }
+        }
+    };
+
+    let mut response = shared.await.to_response();
+    if !leader {
+        debug!("Served request from a coalesced upstream call");
+        response.extensions_mut().insert(Deduplicated);
+        response
+            .headers_mut()
+            .insert(DEDUPLICATED_HEADER, HeaderValue::from_static("true"));
+    }
+    Ok(response)
+}
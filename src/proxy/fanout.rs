@@ -0,0 +1,127 @@
+use super::forward_request;
+use crate::{
+    config::AppConfig,
+    error::AppError,
+    providers::{create_provider, params::ParamSupport, Provider},
+};
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{request::Parts, Response},
+};
+use futures_util::future::join_all;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Upper bound on parallel upstream requests issued for a single `n > 1` request
+const MAX_FANOUT_CHOICES: u64 = 16;
+
+/// Usage counters summed across the fanned-out responses
+const USAGE_FIELDS: &[&str] = &["prompt_tokens", "completion_tokens", "total_tokens"];
+
+/// Number of choices to fan out for, if the provider can't honour `n` itself.
+///
+/// Streaming requests are left alone; `n` is then dropped by the parameter
+/// translation and reported in the warning header.
+pub(super) fn fanout_choices(provider: &dyn Provider, body: &Bytes) -> Result<Option<u64>, AppError> {
+    if provider.param_support("n") != ParamSupport::Unsupported {
+        return Ok(None);
+    }
+
+    let Ok(json) = serde_json::from_slice::<Value>(body) else {
+        return Ok(None);
+    };
+    let choices = json.get("n").and_then(Value::as_u64).unwrap_or(1);
+    let streaming = json.get("stream").and_then(Value::as_bool).unwrap_or(false);
+    if choices <= 1 || streaming {
+        return Ok(None);
+    }
+
+    if choices > MAX_FANOUT_CHOICES {
+        return Err(AppError::RequestError(format!(
+            "n={} exceeds the maximum of {} for {}",
+            choices,
+            MAX_FANOUT_CHOICES,
+            provider.name()
+        )));
+    }
+    Ok(Some(choices))
+}
+
+/// Issue one upstream request per choice in parallel and merge the results
+pub(super) async fn fan_out(
+    config: Arc<AppConfig>,
+    provider_name: &str,
+    parts: &Parts,
+    body: Bytes,
+    choices: u64,
+) -> Result<Response<Body>, AppError> {
+    debug!("Fanning out n={} request to {}", choices, provider_name);
+
+    let mut json: Value = serde_json::from_slice(&body)?;
+    if let Some(object) = json.as_object_mut() {
+        object.remove("n");
+    }
+    let single_body = Bytes::from(serde_json::to_vec(&json)?);
+
+    // Each request gets its own provider instance since providers keep per-request state
+    let requests = (0..choices).map(|_| {
+        let config = config.clone();
+        let body = single_body.clone();
+        async move {
+            let provider = create_provider(provider_name)?;
+            forward_request(config, provider, parts, body).await
+        }
+    });
+
+    let mut merged: Option<(http::response::Parts, Value)> = None;
+    let mut merged_choices = Vec::new();
+    let mut usage = json!({});
+
+    for response in join_all(requests).await {
+        let response = response?;
+
+        // Surface the first upstream failure as-is
+        if !response.status().is_success() {
+            warn!("Fan-out request to {} failed with {}", provider_name, response.status());
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| AppError::AxumError(e.into()))?;
+        let value: Value = serde_json::from_slice(&bytes)?;
+
+        for choice in value
+            .get("choices")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let mut choice = choice.clone();
+            choice["index"] = json!(merged_choices.len());
+            merged_choices.push(choice);
+        }
+
+        if let Some(response_usage) = value.get("usage") {
+            for &field in USAGE_FIELDS {
+                let total = usage.get(field).and_then(Value::as_u64).unwrap_or(0)
+                    + response_usage.get(field).and_then(Value::as_u64).unwrap_or(0);
+                usage[field] = json!(total);
+            }
+        }
+
+        if merged.is_none() {
+            merged = Some((parts, value));
+        }
+    }
+
+    let (mut parts, mut body) = merged.ok_or(AppError::InvalidRequestFormat)?;
+    debug!("Merged {} choices from {}", merged_choices.len(), provider_name);
+    body["choices"] = json!(merged_choices);
+    body["usage"] = usage;
+
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, Body::from(serde_json::to_vec(&body)?)))
+}
@@ -0,0 +1,209 @@
+use crate::error::AppError;
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderValue, Response, StatusCode},
+};
+use futures_util::{stream, StreamExt};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::{debug, error, info};
+
+/// Upstream response headers kept in fixtures; everything else is dropped
+const KEPT_HEADERS: &[&str] = &["content-type", "x-request-id", "request-id", "x-amzn-requestid"];
+
+/// Request body fields replaced before a request is written to disk
+const SECRET_FIELDS: &[&str] = &["api_key", "apiKey", "secret", "password", "authorization", "access_token"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    Off,
+    /// Forward to the provider and save each upstream response
+    Record,
+    /// Serve saved responses without contacting the provider
+    Replay,
+}
+
+static MODE: Lazy<FixtureMode> = Lazy::new(|| {
+    let mode = match std::env::var("FIXTURE_MODE").unwrap_or_default().to_lowercase().as_str() {
+        "record" => FixtureMode::Record,
+        "replay" => FixtureMode::Replay,
+        _ => FixtureMode::Off,
+    };
+    if mode != FixtureMode::Off {
+        info!("Provider fixtures in {:?} mode under {}", mode, FIXTURE_DIR.display());
+    }
+    mode
+});
+
+static FIXTURE_DIR: Lazy<PathBuf> =
+    Lazy::new(|| PathBuf::from(std::env::var("FIXTURE_DIR").unwrap_or_else(|_| "fixtures".to_string())));
+
+pub fn mode() -> FixtureMode {
+    *MODE
+}
+
+/// One chunk of a recorded body: text when it is UTF-8, hex otherwise (e.g. AWS event streams)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Chunk {
+    Text(String),
+    Hex(String),
+}
+
+impl Chunk {
+    fn from_bytes(bytes: &Bytes) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Chunk::Text(text.to_string()),
+            Err(_) => Chunk::Hex(bytes.iter().map(|b| format!("{:02x}", b)).collect()),
+        }
+    }
+
+    fn into_bytes(self) -> Result<Bytes, AppError> {
+        match self {
+            Chunk::Text(text) => Ok(Bytes::from(text)),
+            Chunk::Hex(hex) => (0..hex.len())
+                .step_by(2)
+                .map(|i| {
+                    hex.get(i..i + 2)
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .ok_or_else(|| AppError::RequestError("Corrupt hex chunk in fixture".to_string()))
+                })
+                .collect::<Result<Vec<u8>, _>>()
+                .map(Bytes::from),
+        }
+    }
+}
+
+/// A recorded upstream exchange, keyed by a hash of the translated request
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    provider: String,
+    method: String,
+    path: String,
+    request: Value,
+    status: u16,
+    headers: Vec<(String, String)>,
+    chunks: Vec<Chunk>,
+}
+
+fn fixture_path(provider: &str, method: &str, path: &str, body: &[u8]) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    let hash: String = hasher.finalize().iter().take(16).map(|b| format!("{:02x}", b)).collect();
+    FIXTURE_DIR.join(provider).join(format!("{}.json", hash))
+}
+
+fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) {
+                    *field = Value::String("[REDACTED]".to_string());
+                } else {
+                    strip_secrets(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+/// Serve a previously recorded response for this request
+pub(super) fn replay(provider: &str, method: &str, path: &str, body: &[u8]) -> Result<Response<Body>, AppError> {
+    let file = fixture_path(provider, method, path, body);
+    let raw = std::fs::read_to_string(&file).map_err(|_| {
+        error!("No fixture recorded at {}", file.display());
+        AppError::RequestError(format!("No recorded fixture for this {} request ({})", provider, file.display()))
+    })?;
+    let fixture: Fixture = serde_json::from_str(&raw)?;
+    debug!("Replaying fixture {}", file.display());
+
+    let mut builder = Response::builder().status(StatusCode::from_u16(fixture.status)?);
+    for (name, value) in &fixture.headers {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            builder = builder.header(name.as_str(), value);
+        }
+    }
+
+    let chunks = fixture
+        .chunks
+        .into_iter()
+        .map(Chunk::into_bytes)
+        .collect::<Result<Vec<_>, _>>()?;
+    let body = Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, std::io::Error>)));
+    builder
+        .body(body)
+        .map_err(|e| AppError::HttpError(e.to_string()))
+}
+
+/// Pass the response through unchanged, writing it to a fixture once the body completes
+pub(super) fn record(
+    provider: &str,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    response: Response<Body>,
+) -> Response<Body> {
+    let file = fixture_path(provider, method, path, body);
+    let mut request = serde_json::from_slice(body).unwrap_or(Value::Null);
+    strip_secrets(&mut request);
+
+    let (parts, response_body) = response.into_parts();
+    let mut fixture = Fixture {
+        provider: provider.to_string(),
+        method: method.to_string(),
+        path: path.to_string(),
+        request,
+        status: parts.status.as_u16(),
+        headers: parts
+            .headers
+            .iter()
+            .filter(|(name, _)| KEPT_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        chunks: Vec::new(),
+    };
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let capture = captured.clone();
+    let passthrough = response_body.into_data_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            capture.lock().push(Chunk::from_bytes(bytes));
+        }
+        chunk
+    });
+
+    let save = stream::once(async move {
+        fixture.chunks = std::mem::take(&mut *captured.lock());
+        if let Err(e) = write_fixture(&file, &fixture) {
+            error!("Failed to write fixture {}: {}", file.display(), e);
+        } else {
+            debug!("Recorded fixture {}", file.display());
+        }
+        None
+    })
+    .filter_map(|chunk: Option<Result<Bytes, axum::Error>>| async move { chunk });
+
+    Response::from_parts(parts, Body::from_stream(passthrough.chain(save)))
+}
+
+fn write_fixture(file: &Path, fixture: &Fixture) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_vec_pretty(fixture)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    std::fs::write(file, json)
+}
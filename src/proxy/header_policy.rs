@@ -0,0 +1,126 @@
+use axum::http::{HeaderMap, HeaderName};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use tracing::{debug, error, info};
+
+/// Headers that only steer the gateway and must never reach a provider
+const INTERNAL_HEADERS: &[&str] = &[
+    "x-gateway-key",
+    "x-admin-key",
+    "x-provider",
+    "x-gateway-metadata",
+    "x-max-cost",
+    "x-max-stream-duration",
+    "x-auto-truncate",
+    "x-history-compression",
+    "x-provider-options",
+    "x-raw-passthrough",
+];
+
+/// AWS credential headers, consumed by the Bedrock signer and meaningless elsewhere
+const AWS_HEADERS: &[&str] = &[
+    "x-aws-access-key-id",
+    "x-aws-secret-access-key",
+    "x-aws-session-token",
+    "x-aws-region",
+    "x-aws-role-arn",
+    "x-aws-guardrail-id",
+    "x-aws-guardrail-version",
+    "x-aws-guardrail-trace",
+];
+
+/// Headers carrying a client's credentials for the provider it addressed
+const CREDENTIAL_HEADERS: &[&str] = &[
+    "authorization",
+    "x-api-key",
+    "api-key",
+    "x-goog-api-key",
+    "xi-api-key",
+    "x-aws-access-key-id",
+    "x-aws-secret-access-key",
+    "x-aws-session-token",
+    "x-aws-role-arn",
+];
+
+/// Drop the client's provider credentials before a request goes to a different
+/// provider than the one the client addressed, so one vendor's key never reaches another
+pub fn strip_credentials(headers: &mut HeaderMap) {
+    for name in CREDENTIAL_HEADERS {
+        headers.remove(*name);
+    }
+}
+
+/// Client headers to forward per provider from `HEADER_PASSTHROUGH`, e.g.
+/// `{"openai": ["openai-organization", "openai-beta"], "*": ["x-trace-id"]}`
+static PASSTHROUGH: Lazy<HashMap<String, Vec<HeaderName>>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("HEADER_PASSTHROUGH") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, Vec<String>>>(&raw) {
+        Ok(config) => {
+            let passthrough: HashMap<_, _> = config
+                .into_iter()
+                .map(|(provider, names)| {
+                    let names = names
+                        .iter()
+                        .filter_map(|name| HeaderName::from_bytes(name.trim().to_lowercase().as_bytes()).ok())
+                        .collect();
+                    (provider.to_lowercase(), names)
+                })
+                .collect();
+            info!("Loaded header passthrough rules for {} providers", passthrough.len());
+            passthrough
+        }
+        Err(e) => {
+            error!("Failed to parse HEADER_PASSTHROUGH: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// Extra headers to strip everywhere, from the comma-separated `HEADER_DENYLIST`
+static DENYLIST: Lazy<Vec<String>> = Lazy::new(|| {
+    std::env::var("HEADER_DENYLIST")
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+});
+
+/// Whether a header must be kept away from this provider (and out of logs)
+pub fn is_denied(provider: &str, name: &str) -> bool {
+    let name = name.to_lowercase();
+    INTERNAL_HEADERS.contains(&name.as_str())
+        || DENYLIST.contains(&name)
+        || (!provider.eq_ignore_ascii_case("bedrock") && AWS_HEADERS.contains(&name.as_str()))
+}
+
+/// Add the configured client headers to the upstream request, then drop
+/// anything on the denylist regardless of how the provider built its headers
+pub fn apply(provider: &str, client_headers: &HeaderMap, upstream: &mut HeaderMap) {
+    let allowed = PASSTHROUGH
+        .get(&provider.to_lowercase())
+        .into_iter()
+        .chain(PASSTHROUGH.get("*"))
+        .flatten();
+    for name in allowed {
+        if upstream.contains_key(name) || is_denied(provider, name.as_str()) {
+            continue;
+        }
+        if let Some(value) = client_headers.get(name) {
+            debug!("Forwarding client header {} to {}", name, provider);
+            upstream.insert(name.clone(), value.clone());
+        }
+    }
+
+    let denied: Vec<HeaderName> = upstream
+        .keys()
+        .filter(|name| is_denied(provider, name.as_str()))
+        .cloned()
+        .collect();
+    for name in denied {
+        debug!("Stripping {} from upstream {} request", name, provider);
+        upstream.remove(&name);
+    }
+}
@@ -2,86 +2,382 @@ use crate::providers::Provider;
 use axum::body::to_bytes;
 use axum::{
     body::{Body, Bytes},
-    http::{HeaderMap, HeaderValue, Request, Response, StatusCode},
+    http::{request::Parts, HeaderMap, HeaderValue, Request, Response, StatusCode},
 };
 use futures_util::StreamExt;
 use reqwest::Method;
-use std::sync::Arc;
-use tracing::{debug, error};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use fixtures::FixtureMode;
+use scheduler::Priority;
+use tracing::{debug, error, field::Empty, info_span, Instrument};
 
-use crate::{config::AppConfig, error::AppError, providers::create_provider};
+use crate::{
+    config::AppConfig,
+    context::GatewayRequestContext,
+    error::AppError,
+    providers::{aliases, catalog, compression, create_provider, defaults, params, routes},
+    telemetry::{
+        fingerprint::fingerprint_from_headers,
+        redact,
+        timing::{RequestStart, UpstreamTimings},
+    },
+    tenancy::TenantContext,
+};
 
+mod aws_credentials;
+mod budget;
+pub use aws_credentials::AwsCredentials;
+pub mod canary;
 mod client;
 pub use client::CLIENT;
-mod signing;
+mod context_remediation;
+pub use context_remediation::ContextRemediation;
+mod dedup;
+pub use dedup::Deduplicated;
+mod fanout;
+mod fixtures;
+pub mod header_policy;
+pub mod rate_limits;
+pub mod raw_passthrough;
+pub mod scheduler;
+pub mod signing;
+pub mod stream_buffer;
+pub mod stream_failover;
+pub mod stream_retry;
+mod validation;
+
+/// Set on responses relayed from a provider: the upstream that served the
+/// request after routing, maintenance, aliasing, canaries and fallbacks
+pub const ROUTED_PROVIDER_HEADER: &str = "x-gateway-routed-provider";
+pub const ROUTED_MODEL_HEADER: &str = "x-gateway-routed-model";
 
 pub async fn proxy_request_to_provider(
     config: Arc<AppConfig>,
     provider_name: &str,
-    mut original_request: Request<Body>,
+    original_request: Request<Body>,
 ) -> Result<Response<Body>, AppError> {
     let provider = create_provider(provider_name)?;
+    routes::check_route(provider.as_ref(), original_request.method(), original_request.uri().path())?;
 
     // Extract body bytes
-    let body = std::mem::replace(original_request.body_mut(), Body::empty());
+    let (mut parts, body) = original_request.into_parts();
     let body_bytes = to_bytes(body, usize::MAX)
         .await
         .map_err(|e| AppError::AxumError(e.into()))?;
 
-    // Call before_request first to set up any provider state
-    provider
-        .before_request(original_request.headers(), &body_bytes)
-        .await?;
+    // The body is parsed once here for the model and stream flag everything downstream reads.
+    // The context carries the gateway-generated request ID that budget reservations are keyed by.
+    let mut context = parts
+        .extensions
+        .remove::<GatewayRequestContext>()
+        .unwrap_or_else(|| GatewayRequestContext::new(provider_name, &parts.headers));
+    let body = serde_json::from_slice::<serde_json::Value>(&body_bytes).unwrap_or_default();
+    context.observe_body(&body);
+    parts.extensions.insert(context.clone());
+
+    // Tenants may be restricted to a set of models, and hold the request's
+    // estimated cost against their budget until it is settled in telemetry
+    let tenant = parts.extensions.get::<TenantContext>().map(|TenantContext(tenant)| tenant.clone());
+    if let Some(tenant) = &tenant {
+        if let Some(model) = &context.model {
+            tenant.authorize_model(model)?;
+        }
+        budget::reserve(tenant, &context, &body).await?;
+    }
+
+    // Identical requests within the dedup window share a single upstream call
+    let response = if let Some(key) = dedup::dedup_key(provider_name, &parts, &body_bytes) {
+        let provider_name = provider_name.to_string();
+        dedup::coalesce(key, async move {
+            dispatch(config, provider, &provider_name, parts, body_bytes).await
+        })
+        .await
+    } else {
+        dispatch(config, provider, provider_name, parts, body_bytes).await
+    };
+    let mut response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            // Failed requests are never charged, so their reservation is released now
+            if let Some(tenant) = &tenant {
+                tenant.settle(Some(&context.request_id), None).await;
+            }
+            return Err(e);
+        }
+    };
+
+    // Telemetry reads the caller's identity back from the response
+    response.extensions_mut().insert(context);
+    Ok(response)
+}
+
+async fn dispatch(
+    config: Arc<AppConfig>,
+    provider: Box<dyn Provider>,
+    provider_name: &str,
+    parts: Parts,
+    body_bytes: Bytes,
+) -> Result<Response<Body>, AppError> {
+    // Fan-out, remediation and stream retries all rewrite or splice the OpenAI format
+    if raw_passthrough::requested(&parts.headers) {
+        return forward_request(config, provider, &parts, body_bytes).await;
+    }
+
+    // Providers without native `n` support get one upstream request per choice
+    if let Some(choices) = fanout::fanout_choices(provider.as_ref(), &body_bytes)? {
+        return fanout::fan_out(config, provider_name, &parts, body_bytes, choices).await;
+    }
+
+    // Clients can opt in to retries that fit an oversized conversation into the context window
+    if context_remediation::requested(&parts.headers) {
+        return context_remediation::forward_with_remediation(config, provider_name, &parts, body_bytes).await;
+    }
+
+    // Streams that fail before their first byte are re-dispatched, since the client has seen nothing yet
+    if stream_retry::applies(provider_name, &body_bytes) {
+        let response = stream_retry::forward(config.clone(), provider, provider_name, &parts, body_bytes.clone()).await?;
+        // Streams that break off mid-generation can be resumed on a fallback provider
+        if let Some(target) = stream_failover::target_for(provider_name, &body_bytes) {
+            return Ok(stream_failover::guard(config, parts, body_bytes, response, target));
+        }
+        return Ok(response);
+    }
+
+    forward_request(config, provider, &parts, body_bytes).await
+}
+
+/// Runs a single request through the provider pipeline and sends it upstream
+pub(crate) async fn forward_request(
+    config: Arc<AppConfig>,
+    provider: Box<dyn Provider>,
+    original_request: &Parts,
+    body_bytes: Bytes,
+) -> Result<Response<Body>, AppError> {
+    let path = original_request.uri.path();
+    let raw = raw_passthrough::requested(&original_request.headers);
+    let (prepared_body, headers, modified_path, dropped_params, canary, model_rewrite, routed_model) = async {
+        // Native bodies skip every rewrite; the provider only reads what it needs to address the request
+        if raw {
+            provider.before_request(&original_request.headers, &body_bytes).await?;
+            let mut headers = provider.process_headers(&original_request.headers)?;
+            header_policy::apply(provider.name(), &original_request.headers, &mut headers);
+            let routed_model = serde_json::from_slice::<serde_json::Value>(&body_bytes)
+                .ok()
+                .and_then(|json| json.get("model")?.as_str().map(String::from));
+            let prepared_body = provider.prepare_raw_request_body(body_bytes)?;
+            debug!("Passing {} request through in its native format", provider.name());
+            return Ok((prepared_body, headers, provider.transform_path(path), Vec::new(), None, None, routed_model));
+        }
+
+        // Canary config changes apply to their share of traffic, on the model the client asked for
+        let (body_bytes, canary) = canary::assign(provider.name(), body_bytes);
+
+        // Deprecated or aliased model names are rewritten before the provider sees the body
+        let (body_bytes, model_rewrite) = aliases::rewrite_model(provider.name(), body_bytes);
+        let routed_model = serde_json::from_slice::<serde_json::Value>(&body_bytes)
+            .ok()
+            .and_then(|json| json.get("model")?.as_str().map(String::from));
+
+        // Tenant model restrictions hold for the model actually sent, not just the one requested
+        if let (Some(TenantContext(tenant)), Some(model)) =
+            (original_request.extensions.get::<TenantContext>(), &routed_model)
+        {
+            tenant.authorize_model(model)?;
+        }
 
-    // Process headers and transform path
-    let headers = provider.process_headers(original_request.headers())?;
-    let path = original_request.uri().path();
-    let modified_path = provider.transform_path(path);
+        // Models missing from the provider's published list fail here, with suggestions, instead of upstream
+        catalog::validate(provider.name(), &body_bytes)?;
 
-    // Prepare request body
-    let prepared_body = provider.prepare_request_body(body_bytes).await?;
+        // Org/project policy defaults fill in whatever the client left out
+        let body_bytes = defaults::apply_defaults(provider.name(), &original_request.headers, body_bytes);
+
+        // Histories that outgrow the model's context window are trimmed or summarized when enabled
+        let body_bytes = compression::compress_history(provider.name(), &original_request.headers, body_bytes).await;
+
+        // Call before_request first to set up any provider state
+        provider
+            .before_request(&original_request.headers, &body_bytes)
+            .await?;
+
+        // Process headers and transform path
+        let mut headers = provider.process_headers(&original_request.headers)?;
+        // Configured client headers go along; gateway-internal ones never do
+        header_policy::apply(provider.name(), &original_request.headers, &mut headers);
+        let modified_path = provider.transform_path(path);
+
+        // Provider-specific options are set aside so the body translation can't strip them
+        let (body_bytes, provider_options) =
+            params::take_provider_options(provider.name(), &original_request.headers, body_bytes);
+
+        // Map sampling/stop parameters onto the provider, dropping what it can't honour
+        let (body_bytes, dropped_params) = params::translate_params(provider.as_ref(), body_bytes);
+
+        // Prepare request body
+        let prepared_body = provider.prepare_request_body(body_bytes).await?;
+        let prepared_body = match &provider_options {
+            Some(options) => params::merge_provider_options(provider.name(), prepared_body, options),
+            None => prepared_body,
+        };
+
+        Ok::<_, AppError>((prepared_body, headers, modified_path, dropped_params, canary, model_rewrite, routed_model))
+    }
+    .instrument(info_span!("transform", provider = provider.name()))
+    .await?;
 
     // Construct final URL
     let query = original_request
-        .uri()
+        .uri
         .query()
         .map(|q| format!("?{}", q))
         .unwrap_or_default();
-    let url = format!("{}{}{}", provider.base_url(), modified_path, query);
-    debug!("Using URL: {}", url);
-
-    // Handle AWS signing if required
-    let final_headers = if provider.requires_signing() {
-        if let Some((access_key, secret_key, region)) = provider.get_signing_credentials(&headers) {
-            signing::sign_aws_request(
-                original_request.method().as_str(),
-                &url,
-                &prepared_body,
-                &access_key,
-                &secret_key,
-                &region,
-                "bedrock",
-            )
-            .await?
-        } else {
-            headers
+    #[cfg(feature = "mock-providers")]
+    let base_url = crate::mock::upstream_base_url(provider.name()).unwrap_or_else(|| provider.base_url());
+    #[cfg(not(feature = "mock-providers"))]
+    let base_url = provider.base_url();
+    let url = format!("{}{}{}", base_url, modified_path, query);
+    debug!("Using URL: {}", redact::url(&url));
+
+    // Hold back traffic the provider has already said it will reject for this key
+    let key_fingerprint = fingerprint_from_headers(&original_request.headers);
+    rate_limits::admit(provider.name(), key_fingerprint.as_deref()).await?;
+
+    // Wait for a provider slot; interactive traffic is served ahead of batch traffic
+    let tenant_priority = original_request
+        .extensions
+        .get::<TenantContext>()
+        .and_then(|TenantContext(tenant)| tenant.config.priority.clone());
+    let priority = Priority::from_headers(&original_request.headers, tenant_priority.as_deref());
+    let (permit, scheduler_wait) = match scheduler::for_provider(provider.name()) {
+        Some(scheduler) => {
+            let (permit, wait) = scheduler.acquire(priority).await;
+            debug!("Acquired {} slot for {} after {:?}", priority.as_str(), provider.name(), wait);
+            (Some(permit), wait)
         }
-    } else {
-        headers
+        None => (None, Duration::ZERO),
+    };
+
+    // Sign with whatever scheme the provider uses (SigV4, OAuth, HMAC, ...) only once a slot
+    // is held, so a long queue can't outlast the signature or a fetched token
+    let final_headers = match provider.request_signer(&headers) {
+        Some(signer) => {
+            debug!("Signing {} request with {}", provider.name(), signer.name());
+            let span = info_span!("sign", provider = provider.name(), scheme = signer.name());
+            signer
+                .sign(signing::SigningRequest {
+                    method: original_request.method.as_str(),
+                    url: &url,
+                    headers: &headers,
+                    body: &prepared_body,
+                })
+                .instrument(span)
+                .await?
+        }
+        None => headers,
     };
 
-    // Send the request with signed headers
-    let response = send_provider_request(
-        original_request.method().clone(),
-        url,
-        final_headers,
-        prepared_body,
-        &provider,
-        config,
-    )
+    // Time spent inside the gateway before the request goes upstream
+    let queue_time = original_request
+        .extensions
+        .get::<RequestStart>()
+        .map(|RequestStart(start)| start.elapsed())
+        .unwrap_or_default();
+    debug!("Request queued in gateway for {:?} before dispatch", queue_time);
+
+    // Send the request with signed headers, or serve/record it as a fixture
+    let method = original_request.method.as_str();
+    let upstream_path = format!("{}{}", modified_path, query);
+    let upstream = info_span!(
+        "upstream",
+        otel.kind = "client",
+        provider = provider.name(),
+        http.response.status_code = Empty,
+        otel.status_code = Empty,
+    );
+    let response = async {
+        match fixtures::mode() {
+            FixtureMode::Replay => fixtures::replay(provider.name(), method, &upstream_path, &prepared_body),
+            mode => {
+                let response = send_provider_request(
+                    original_request.method.clone(),
+                    url,
+                    final_headers,
+                    prepared_body.clone(),
+                    &provider,
+                    config,
+                )
+                .await?;
+                if mode == FixtureMode::Record {
+                    Ok(fixtures::record(provider.name(), method, &upstream_path, &prepared_body, response))
+                } else {
+                    Ok(response)
+                }
+            }
+        }
+    }
+    .instrument(upstream.clone())
     .await?;
+    crate::telemetry::otel::record_status(&upstream, response.status().as_u16());
 
-    provider.process_response(response).await
+    rate_limits::record(provider.name(), key_fingerprint.as_deref(), response.headers());
+
+    // Provider response processing may rebuild the response, so carry the
+    // upstream timings over explicitly
+    let provider_ttfb = response
+        .extensions()
+        .get::<UpstreamTimings>()
+        .map(|timings| timings.provider_ttfb)
+        .unwrap_or_default();
+
+    let mut response = if raw {
+        response
+    } else {
+        let response = provider.process_response(response).await?;
+        // Optionally check the translated output against the OpenAI schema
+        validation::validate_response(provider.name(), path, response).await?
+    };
+    response.extensions_mut().insert(UpstreamTimings {
+        queue_time,
+        provider_ttfb,
+        scheduler_wait,
+    });
+    response.extensions_mut().insert(priority);
+
+    // Keep the provider slot until the body (including any stream) is finished
+    if let Some(permit) = permit {
+        let (parts, body) = response.into_parts();
+        let stream = body.into_data_stream().map(move |chunk| {
+            let _permit = &permit;
+            chunk
+        });
+        response = Response::from_parts(parts, Body::from_stream(stream));
+    }
+
+    if let Some(rewrite) = model_rewrite {
+        response.extensions_mut().insert(rewrite);
+    }
+    if let Some(canary) = canary {
+        response.extensions_mut().insert(canary);
+    }
+
+    if !dropped_params.is_empty() {
+        let warning = params::dropped_params_warning(provider.name(), &dropped_params);
+        if let Ok(value) = HeaderValue::from_str(&warning) {
+            response.headers_mut().insert("x-gateway-warning", value);
+        }
+    }
+
+    // Retries and fallbacks each get a fresh response, so these name whichever upstream answered
+    if let Ok(value) = HeaderValue::from_str(provider.name()) {
+        response.headers_mut().insert(ROUTED_PROVIDER_HEADER, value);
+    }
+    if let Some(value) = routed_model.and_then(|model| HeaderValue::from_str(&model).ok()) {
+        response.headers_mut().insert(ROUTED_MODEL_HEADER, value);
+    }
+
+    Ok(response)
 }
 
 pub async fn send_provider_request(
@@ -108,6 +404,7 @@ pub async fn send_provider_request(
         })
         .collect::<reqwest::header::HeaderMap>();
 
+    let dispatched_at = Instant::now();
     let response = client
         .request(method, url)
         .headers(reqwest_headers)
@@ -115,7 +412,17 @@ pub async fn send_provider_request(
         .send()
         .await?;
 
-    process_response(response, config).await
+    // reqwest resolves `send` once the response headers arrive, which is the
+    // closest observable point to the provider's first byte
+    let provider_ttfb = dispatched_at.elapsed();
+    debug!("Provider responded with headers after {:?}", provider_ttfb);
+
+    let mut response = process_response(response, config).await?;
+    response.extensions_mut().insert(UpstreamTimings {
+        provider_ttfb,
+        ..Default::default()
+    });
+    Ok(response)
 }
 
 async fn process_response(
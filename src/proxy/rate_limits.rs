@@ -0,0 +1,273 @@
+use crate::error::AppError;
+use crate::shared_state;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Limiter state is tracked and enforced unless `RATE_LIMIT_SYNC=false`
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("RATE_LIMIT_SYNC")
+        .map(|v| v.parse().unwrap_or(true))
+        .unwrap_or(true)
+});
+
+/// Requests held back once the provider reports this many or fewer remaining
+static RESERVE_REQUESTS: Lazy<u64> = Lazy::new(|| env_u64("RATE_LIMIT_RESERVE_REQUESTS", 0));
+
+/// Tokens held back once the provider reports this many or fewer remaining
+static RESERVE_TOKENS: Lazy<u64> = Lazy::new(|| env_u64("RATE_LIMIT_RESERVE_TOKENS", 0));
+
+/// Longest a request is delayed waiting for a window to reset before it is shed instead
+static MAX_DELAY: Lazy<Duration> = Lazy::new(|| Duration::from_millis(env_u64("RATE_LIMIT_MAX_DELAY_MS", 5000)));
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Remaining quota in one provider window, as last reported
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Window {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+impl Window {
+    /// Time until the window resets, if it is exhausted below the reserve
+    fn exhausted_for(&self, reserve: u64, now: DateTime<Utc>) -> Option<Duration> {
+        let remaining = self.remaining?;
+        let reset_at = self.reset_at.filter(|reset_at| *reset_at > now)?;
+        (remaining <= reserve).then(|| (reset_at - now).to_std().unwrap_or_default())
+    }
+}
+
+/// Limiter state for one provider key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitState {
+    pub provider: String,
+    pub key_fingerprint: Option<String>,
+    pub requests: Window,
+    pub tokens: Window,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Limiter state by (provider, key fingerprint)
+static LIMITS: Lazy<RwLock<HashMap<(String, Option<String>), LimitState>>> = Lazy::new(Default::default);
+
+/// Key of a provider key's limiter state in the shared state
+fn shared_key((provider, key_fingerprint): &(String, Option<String>)) -> String {
+    format!("ratelimit:{}:{}", provider, key_fingerprint.as_deref().unwrap_or("default"))
+}
+
+/// Publish limiter state for the other replicas, until its windows have reset
+fn share(key: &(String, Option<String>), state: &LimitState) {
+    let ttl = [&state.requests.reset_at, &state.tokens.reset_at]
+        .into_iter()
+        .flatten()
+        .max()
+        .and_then(|reset_at| (*reset_at - state.updated_at).to_std().ok())
+        .unwrap_or(Duration::from_secs(60));
+    let name = shared_key(key);
+    let state = state.clone();
+    tokio::spawn(async move {
+        let Some(shared) = shared_state::get().await else {
+            return;
+        };
+        if let Err(e) = shared.put_json(&name, &state, ttl).await {
+            warn!("Failed to share rate limits for {}: {}", state.provider, e);
+        }
+    });
+}
+
+/// Take another replica's limiter state when it was reported more recently than ours
+async fn sync_shared(key: &(String, Option<String>)) {
+    let Some(shared) = shared_state::get().await else {
+        return;
+    };
+    let state = match shared.get_json::<LimitState>(&shared_key(key)).await {
+        Ok(Some(state)) => state,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Shared rate limits for {} unavailable, using this replica's: {}", key.0, e);
+            return;
+        }
+    };
+    let mut limits = LIMITS.write();
+    if limits.get(key).map_or(true, |local| local.updated_at < state.updated_at) {
+        limits.insert(key.clone(), state);
+    }
+}
+
+/// Header names for the request and token windows, per provider family
+struct HeaderNames {
+    limit: &'static str,
+    remaining: &'static str,
+    reset: &'static str,
+}
+
+const OPENAI_REQUESTS: HeaderNames = HeaderNames {
+    limit: "x-ratelimit-limit-requests",
+    remaining: "x-ratelimit-remaining-requests",
+    reset: "x-ratelimit-reset-requests",
+};
+const OPENAI_TOKENS: HeaderNames = HeaderNames {
+    limit: "x-ratelimit-limit-tokens",
+    remaining: "x-ratelimit-remaining-tokens",
+    reset: "x-ratelimit-reset-tokens",
+};
+const ANTHROPIC_REQUESTS: HeaderNames = HeaderNames {
+    limit: "anthropic-ratelimit-requests-limit",
+    remaining: "anthropic-ratelimit-requests-remaining",
+    reset: "anthropic-ratelimit-requests-reset",
+};
+const ANTHROPIC_TOKENS: HeaderNames = HeaderNames {
+    limit: "anthropic-ratelimit-tokens-limit",
+    remaining: "anthropic-ratelimit-tokens-remaining",
+    reset: "anthropic-ratelimit-tokens-reset",
+};
+
+fn parse_window(headers: &HeaderMap, names: &HeaderNames, now: DateTime<Utc>) -> Option<Window> {
+    let get = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    let remaining = get(names.remaining)?.parse().ok()?;
+    Some(Window {
+        limit: get(names.limit).and_then(|v| v.parse().ok()),
+        remaining: Some(remaining),
+        reset_at: get(names.reset).and_then(|v| parse_reset(v, now)),
+    })
+}
+
+/// Reset times come as durations from OpenAI and Groq (`6m0s`, `1.5s`, `20ms`)
+/// and as RFC 3339 timestamps from Anthropic
+fn parse_reset(value: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Some(at.with_timezone(&Utc));
+    }
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let amount: f64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => amount * 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                amount / 1000.0
+            }
+            'm' => amount * 60.0,
+            's' => amount,
+            _ => return None,
+        };
+    }
+    // A bare number is seconds
+    if !number.is_empty() {
+        total += number.parse::<f64>().ok()?;
+    }
+    Some(now + chrono::Duration::milliseconds((total * 1000.0) as i64))
+}
+
+/// Update the limiter state from an upstream response's rate-limit headers
+pub fn record(provider: &str, key_fingerprint: Option<&str>, headers: &HeaderMap) {
+    if !*ENABLED {
+        return;
+    }
+    let now = Utc::now();
+    let (request_names, token_names) = if provider.eq_ignore_ascii_case("anthropic") {
+        (&ANTHROPIC_REQUESTS, &ANTHROPIC_TOKENS)
+    } else {
+        (&OPENAI_REQUESTS, &OPENAI_TOKENS)
+    };
+    let requests = parse_window(headers, request_names, now);
+    let tokens = parse_window(headers, token_names, now);
+    if requests.is_none() && tokens.is_none() {
+        return;
+    }
+
+    let key = (provider.to_lowercase(), key_fingerprint.map(String::from));
+    let mut limits = LIMITS.write();
+    let state = limits.entry(key.clone()).or_insert_with(|| LimitState {
+        provider: provider.to_lowercase(),
+        key_fingerprint: key_fingerprint.map(String::from),
+        requests: Window::default(),
+        tokens: Window::default(),
+        updated_at: now,
+    });
+    if let Some(requests) = requests {
+        state.requests = requests;
+    }
+    if let Some(tokens) = tokens {
+        state.tokens = tokens;
+    }
+    state.updated_at = now;
+    debug!(
+        "{} rate limits: {:?} requests, {:?} tokens remaining",
+        provider, state.requests.remaining, state.tokens.remaining
+    );
+    share(&key, state);
+}
+
+/// Hold a request back while the provider has reported its key's quota as
+/// spent: wait for the window to reset if that's soon, otherwise shed it with
+/// a 429 rather than let the provider reject it
+pub async fn admit(provider: &str, key_fingerprint: Option<&str>) -> Result<(), AppError> {
+    if !*ENABLED {
+        return Ok(());
+    }
+    let key = (provider.to_lowercase(), key_fingerprint.map(String::from));
+    sync_shared(&key).await;
+    let wait = {
+        let mut limits = LIMITS.write();
+        let Some(state) = limits.get_mut(&key) else {
+            return Ok(());
+        };
+        let now = Utc::now();
+        let wait = [
+            state.requests.exhausted_for(*RESERVE_REQUESTS, now),
+            state.tokens.exhausted_for(*RESERVE_TOKENS, now),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        if wait.is_none() {
+            // Count this request against the window until the provider reports again
+            if let Some(remaining) = state.requests.remaining.as_mut() {
+                *remaining = remaining.saturating_sub(1);
+            }
+        }
+        wait
+    };
+
+    let Some(wait) = wait else {
+        return Ok(());
+    };
+    if wait > *MAX_DELAY {
+        warn!("Shedding {} request: quota exhausted for another {:?}", provider, wait);
+        return Err(AppError::RateLimited(format!(
+            "{} rate limit exhausted; resets in {}s",
+            provider,
+            wait.as_secs_f64().ceil()
+        )));
+    }
+    info!("Delaying {} request {:?} for its rate limit window to reset", provider, wait);
+    tokio::time::sleep(wait).await;
+    Ok(())
+}
+
+/// Current limiter state for every provider key seen, for the admin API
+pub fn snapshot() -> Vec<LimitState> {
+    let mut states: Vec<LimitState> = LIMITS.read().values().cloned().collect();
+    states.sort_by(|a, b| (&a.provider, &a.key_fingerprint).cmp(&(&b.provider, &b.key_fingerprint)));
+    states
+}
@@ -0,0 +1,14 @@
+use axum::http::HeaderMap;
+
+/// Request header that sends the body to the provider, and the response back
+/// to the client, in the provider's native format
+pub const RAW_PASSTHROUGH_HEADER: &str = "x-raw-passthrough";
+
+/// Whether the client asked for the provider's native formats, untranslated
+pub fn requested(headers: &HeaderMap) -> bool {
+    headers
+        .get(RAW_PASSTHROUGH_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false)
+}
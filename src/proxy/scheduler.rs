@@ -0,0 +1,212 @@
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, error, info};
+
+/// Priority class of a request, from `x-priority` or the tenant's configured priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Interactive,
+    Normal,
+    Batch,
+}
+
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::Interactive, Priority::Normal, Priority::Batch];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "interactive" | "high" => Some(Self::Interactive),
+            "normal" | "default" => Some(Self::Normal),
+            "batch" | "low" => Some(Self::Batch),
+            _ => None,
+        }
+    }
+
+    pub fn from_headers(headers: &HeaderMap, fallback: Option<&str>) -> Self {
+        headers
+            .get("x-priority")
+            .and_then(|h| h.to_str().ok())
+            .and_then(Self::parse)
+            .or_else(|| fallback.and_then(Self::parse))
+            .unwrap_or(Self::Normal)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Normal => "normal",
+            Self::Batch => "batch",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Self::Interactive => 0,
+            Self::Normal => 1,
+            Self::Batch => 2,
+        }
+    }
+
+    /// Share of freed slots each class receives while several are waiting
+    fn weight(&self) -> u64 {
+        match self {
+            Self::Interactive => 8,
+            Self::Normal => 3,
+            Self::Batch => 1,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    in_flight: usize,
+    queues: [VecDeque<oneshot::Sender<()>>; 3],
+    served: [u64; 3],
+}
+
+impl State {
+    /// Waiting class with the lowest weighted share of slots served so far
+    fn next_class(&self) -> Option<usize> {
+        Priority::ALL
+            .iter()
+            .filter(|p| !self.queues[p.index()].is_empty())
+            .min_by(|a, b| {
+                let a_share = self.served[a.index()] as f64 / a.weight() as f64;
+                let b_share = self.served[b.index()] as f64 / b.weight() as f64;
+                a_share.partial_cmp(&b_share).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(Priority::index)
+    }
+}
+
+/// Weighted-priority concurrency limiter for one provider
+pub struct Scheduler {
+    max_concurrency: usize,
+    state: Mutex<State>,
+}
+
+/// Held for the lifetime of an upstream request; frees the slot on drop
+pub struct Permit {
+    scheduler: Arc<Scheduler>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+/// Removes a cancelled waiter, returning a slot it was handed but never used
+struct Waiting {
+    scheduler: Arc<Scheduler>,
+    receiver: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if receiver.try_recv().is_ok() {
+                self.scheduler.release();
+            }
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Wait for a slot, returning the permit and how long the request was queued
+    pub async fn acquire(self: &Arc<Self>, priority: Priority) -> (Permit, Duration) {
+        let queued_at = Instant::now();
+        let receiver = {
+            let mut state = self.state.lock();
+            let nobody_waiting = state.queues.iter().all(VecDeque::is_empty);
+            if state.in_flight < self.max_concurrency && nobody_waiting {
+                state.in_flight += 1;
+                return (Permit { scheduler: self.clone() }, Duration::ZERO);
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.queues[priority.index()].push_back(sender);
+            receiver
+        };
+
+        debug!("Queued {} request behind the provider concurrency limit", priority.as_str());
+        let mut waiting = Waiting {
+            scheduler: self.clone(),
+            receiver: Some(receiver),
+        };
+        let granted = match waiting.receiver.as_mut() {
+            Some(receiver) => receiver.await.is_ok(),
+            None => false,
+        };
+        if granted {
+            // The slot is ours now; don't hand it back when the guard drops
+            waiting.receiver = None;
+        } else {
+            error!("Scheduler dropped a waiting request");
+        }
+        (Permit { scheduler: self.clone() }, queued_at.elapsed())
+    }
+
+    /// Hand the slot to the next waiter by weight, or free it
+    fn release(&self) {
+        let mut state = self.state.lock();
+        while let Some(class) = state.next_class() {
+            let Some(sender) = state.queues[class].pop_front() else {
+                continue;
+            };
+            if sender.send(()).is_ok() {
+                state.served[class] += 1;
+                return;
+            }
+        }
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+}
+
+/// Per-provider limits from `PROVIDER_CONCURRENCY` (JSON) with a
+/// `PROVIDER_MAX_CONCURRENCY` fallback; providers without a limit aren't scheduled.
+static SCHEDULERS: Lazy<HashMap<String, Arc<Scheduler>>> = Lazy::new(|| {
+    let mut limits: HashMap<String, usize> = std::env::var("PROVIDER_CONCURRENCY")
+        .ok()
+        .and_then(|raw| {
+            serde_json::from_str(&raw)
+                .map_err(|e| error!("Failed to parse PROVIDER_CONCURRENCY: {}", e))
+                .ok()
+        })
+        .unwrap_or_default();
+
+    if let Some(default) = std::env::var("PROVIDER_MAX_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+    {
+        for provider in ["openai", "anthropic", "groq", "fireworks", "together", "bedrock", "google-ai", "voyage", "jina", "elevenlabs", "deepgram", "stability"] {
+            limits.entry(provider.to_string()).or_insert(default);
+        }
+    }
+
+    limits
+        .into_iter()
+        .filter(|(_, limit)| *limit > 0)
+        .map(|(provider, limit)| {
+            info!("Limiting {} to {} concurrent requests", provider, limit);
+            (provider.to_lowercase(), Arc::new(Scheduler::new(limit)))
+        })
+        .collect()
+});
+
+/// Scheduler for a provider, if it has a concurrency limit
+pub fn for_provider(provider: &str) -> Option<Arc<Scheduler>> {
+    SCHEDULERS.get(&provider.to_lowercase()).cloned()
+}
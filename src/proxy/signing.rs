@@ -1,10 +1,430 @@
+use super::{aws_credentials, AwsCredentials};
 use crate::error::AppError;
+use crate::telemetry::redact;
+use async_trait::async_trait;
 use aws_credential_types::Credentials;
 use aws_sigv4::http_request::{SignableBody, SignableRequest, SigningSettings};
 use aws_sigv4::sign::v4;
-use axum::http::HeaderMap;
-use std::time::SystemTime;
-use tracing::debug;
+use axum::http::{HeaderMap, HeaderValue};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, error, info, warn};
+
+/// Refresh cached OAuth tokens this long before they expire
+const TOKEN_EXPIRY_BUFFER: Duration = Duration::from_secs(5 * 60);
+
+const GCP_METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const AZURE_IMDS_TOKEN_URL: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+const AZURE_DEFAULT_RESOURCE: &str = "https://cognitiveservices.azure.com";
+
+/// The outgoing request a signer sees, after provider translation
+pub struct SigningRequest<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    pub headers: &'a HeaderMap,
+    pub body: &'a [u8],
+}
+
+/// Authenticates a request to a provider right before it is sent upstream.
+///
+/// Providers pick their signer via `Provider::request_signer`, so adding a cloud
+/// provider with its own auth scheme doesn't touch the proxy pipeline.
+#[async_trait]
+pub trait RequestSigner: Send + Sync {
+    /// Name of the scheme, for logging
+    fn name(&self) -> &'static str;
+
+    /// Return the headers to send upstream
+    async fn sign(&self, request: SigningRequest<'_>) -> Result<HeaderMap, AppError>;
+}
+
+/// AWS Signature Version 4, with the default credential chain and optional STS role
+pub struct SigV4Signer {
+    pub service: &'static str,
+    /// Explicit credentials and region; the default chain is used when absent
+    pub credentials: Option<(AwsCredentials, String)>,
+    /// Region used with default-chain credentials
+    pub region: Option<String>,
+    pub role_arn: Option<String>,
+}
+
+#[async_trait]
+impl RequestSigner for SigV4Signer {
+    fn name(&self) -> &'static str {
+        "aws-sigv4"
+    }
+
+    async fn sign(&self, request: SigningRequest<'_>) -> Result<HeaderMap, AppError> {
+        // Explicit credentials (request headers or static env keys) win; otherwise
+        // fall back to the AWS default provider chain
+        let (mut credentials, region) = match self.credentials.clone() {
+            Some(explicit) => explicit,
+            None => {
+                let region = self
+                    .region
+                    .clone()
+                    .ok_or_else(|| AppError::AwsParamsError("No AWS region configured".to_string()))?;
+                debug!("No explicit AWS credentials, using default provider chain");
+                (aws_credentials::default_chain_credentials().await?, region)
+            }
+        };
+
+        // Optionally swap the base identity for an assumed role
+        if let Some(role_arn) = &self.role_arn {
+            credentials = aws_credentials::assume_role(role_arn, &credentials, &region).await?;
+        }
+
+        sign_aws_request(
+            request.method,
+            request.url,
+            request.body,
+            &credentials.access_key,
+            &credentials.secret_key,
+            credentials.session_token.as_deref(),
+            &region,
+            self.service,
+        )
+        .await
+    }
+}
+
+/// Signing scheme named for a provider in `REQUEST_SIGNERS`
+#[derive(Debug, Clone, Copy, Deserialize)]
+enum SignerKind {
+    #[serde(rename = "gcp-oauth")]
+    GcpOAuth,
+    #[serde(rename = "azure-ad")]
+    AzureAd,
+    #[serde(rename = "hmac-sha256")]
+    Hmac,
+}
+
+/// Signers chosen per provider from `REQUEST_SIGNERS`, e.g.
+/// `{"openai": "azure-ad", "together": "hmac-sha256"}`
+static CONFIGURED_SIGNERS: Lazy<HashMap<String, SignerKind>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("REQUEST_SIGNERS") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, SignerKind>>(&raw) {
+        Ok(signers) => {
+            info!("Loaded request signers for {} providers", signers.len());
+            signers.into_iter().map(|(provider, kind)| (provider.to_lowercase(), kind)).collect()
+        }
+        Err(e) => {
+            error!("Failed to parse REQUEST_SIGNERS: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// The signer configured for `provider` in `REQUEST_SIGNERS`, if any
+pub fn configured_signer(provider: &str) -> Option<Box<dyn RequestSigner>> {
+    match CONFIGURED_SIGNERS.get(&provider.to_lowercase())? {
+        SignerKind::GcpOAuth => Some(Box::new(GcpOAuthSigner)),
+        SignerKind::AzureAd => Some(Box::new(AzureAdSigner::from_env())),
+        SignerKind::Hmac => match HmacSigner::from_env() {
+            Some(signer) => Some(Box::new(signer)),
+            None => {
+                warn!("REQUEST_SIGNERS asks for hmac-sha256 on {} but HMAC_SIGNING_SECRET is unset", provider);
+                None
+            }
+        },
+    }
+}
+
+/// Bearer token for Google Cloud APIs.
+///
+/// Uses `GOOGLE_OAUTH_ACCESS_TOKEN` when set, otherwise the GCE/GKE metadata server.
+pub struct GcpOAuthSigner;
+
+#[async_trait]
+impl RequestSigner for GcpOAuthSigner {
+    fn name(&self) -> &'static str {
+        "gcp-oauth"
+    }
+
+    async fn sign(&self, request: SigningRequest<'_>) -> Result<HeaderMap, AppError> {
+        let token = match std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+            Ok(token) if !token.is_empty() => token,
+            _ => {
+                cached_token("gcp:metadata", || async {
+                    let response = TOKEN_CLIENT
+                        .get(GCP_METADATA_TOKEN_URL)
+                        .header("Metadata-Flavor", "Google")
+                        .send()
+                        .await?;
+                    parse_token_response(response).await
+                })
+                .await?
+            }
+        };
+        with_bearer(request.headers, &token)
+    }
+}
+
+/// Azure AD (Entra ID) token for Azure OpenAI / AI services.
+///
+/// Uses the client credentials flow when a client secret is configured,
+/// otherwise the managed identity endpoint.
+pub struct AzureAdSigner {
+    pub tenant_id: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Resource the token is issued for, without the `/.default` suffix
+    pub resource: String,
+}
+
+impl AzureAdSigner {
+    /// Configure from `AZURE_TENANT_ID`, `AZURE_CLIENT_ID`, `AZURE_CLIENT_SECRET` and `AZURE_AD_RESOURCE`
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Self {
+            tenant_id: var("AZURE_TENANT_ID"),
+            client_id: var("AZURE_CLIENT_ID"),
+            client_secret: var("AZURE_CLIENT_SECRET"),
+            resource: var("AZURE_AD_RESOURCE").unwrap_or_else(|| AZURE_DEFAULT_RESOURCE.to_string()),
+        }
+    }
+
+    async fn fetch_token(&self) -> Result<(String, Duration), AppError> {
+        match (&self.tenant_id, &self.client_id, &self.client_secret) {
+            (Some(tenant_id), Some(client_id), Some(client_secret)) => {
+                let url = format!("https://login.microsoftonline.com/{}/oauth2/v2.0/token", tenant_id);
+                let scope = format!("{}/.default", self.resource.trim_end_matches('/'));
+                let form = form_encode(&[
+                    ("grant_type", "client_credentials"),
+                    ("client_id", client_id.as_str()),
+                    ("client_secret", client_secret.as_str()),
+                    ("scope", scope.as_str()),
+                ]);
+                let response = TOKEN_CLIENT
+                    .post(url)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(form)
+                    .send()
+                    .await?;
+                parse_token_response(response).await
+            }
+            _ => {
+                let mut query = vec![("api-version", "2018-02-01"), ("resource", self.resource.as_str())];
+                if let Some(client_id) = &self.client_id {
+                    query.push(("client_id", client_id.as_str()));
+                }
+                let response = TOKEN_CLIENT
+                    .get(format!("{}?{}", AZURE_IMDS_TOKEN_URL, form_encode(&query)))
+                    .header("Metadata", "true")
+                    .send()
+                    .await?;
+                parse_token_response(response).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RequestSigner for AzureAdSigner {
+    fn name(&self) -> &'static str {
+        "azure-ad"
+    }
+
+    async fn sign(&self, request: SigningRequest<'_>) -> Result<HeaderMap, AppError> {
+        let key = format!(
+            "azure:{}:{}:{}",
+            self.tenant_id.as_deref().unwrap_or("managed-identity"),
+            self.client_id.as_deref().unwrap_or_default(),
+            self.resource
+        );
+        let token = cached_token(&key, || self.fetch_token()).await?;
+        with_bearer(request.headers, &token)
+    }
+}
+
+/// HMAC-SHA256 request signature for self-hosted or partner endpoints.
+///
+/// Signs `METHOD\nPATH?QUERY\nTIMESTAMP\nSHA256(BODY)` and sends the hex digest in
+/// `x-signature`, with `x-signature-timestamp` and, if set, `x-signature-key-id`.
+pub struct HmacSigner {
+    pub key_id: Option<String>,
+    pub secret: Vec<u8>,
+}
+
+impl HmacSigner {
+    /// Configure from `HMAC_SIGNING_SECRET` and `HMAC_SIGNING_KEY_ID`; `None` without a secret
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Some(Self {
+            key_id: var("HMAC_SIGNING_KEY_ID"),
+            secret: var("HMAC_SIGNING_SECRET")?.into_bytes(),
+        })
+    }
+}
+
+#[async_trait]
+impl RequestSigner for HmacSigner {
+    fn name(&self) -> &'static str {
+        "hmac-sha256"
+    }
+
+    async fn sign(&self, request: SigningRequest<'_>) -> Result<HeaderMap, AppError> {
+        let url = reqwest::Url::parse(request.url).map_err(|e| AppError::SigningError(e.to_string()))?;
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string();
+        let payload = format!(
+            "{}\n{}\n{}\n{}",
+            request.method,
+            path,
+            timestamp,
+            to_hex(&Sha256::digest(request.body))
+        );
+
+        let mut headers = request.headers.clone();
+        headers.insert("x-signature", HeaderValue::from_str(&to_hex(&hmac_sha256(&self.secret, payload.as_bytes())))?);
+        headers.insert("x-signature-timestamp", HeaderValue::from_str(&timestamp)?);
+        if let Some(key_id) = &self.key_id {
+            headers.insert("x-signature-key-id", HeaderValue::from_str(key_id)?);
+        }
+        Ok(headers)
+    }
+}
+
+/// Token endpoints are plain HTTP/1.1 services, so they get their own client
+static TOKEN_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to create token client")
+});
+
+struct CachedToken {
+    token: String,
+    expires_at: Instant,
+}
+
+static TOKENS: Lazy<Mutex<HashMap<String, CachedToken>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// One lock per cache key, so concurrent requests behind an expired token
+/// wait for a single refresh instead of each calling the token endpoint
+static REFRESHES: Lazy<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cached(key: &str) -> Option<String> {
+    TOKENS
+        .lock()
+        .get(key)
+        .filter(|t| t.expires_at > Instant::now())
+        .map(|t| t.token.clone())
+}
+
+async fn cached_token<F, Fut>(key: &str, fetch: F) -> Result<String, AppError>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<(String, Duration), AppError>>,
+{
+    if let Some(token) = cached(key) {
+        return Ok(token);
+    }
+
+    let refresh = REFRESHES.lock().entry(key.to_string()).or_default().clone();
+    let _guard = refresh.lock().await;
+    // Whoever held the lock before us may have refreshed it already
+    if let Some(token) = cached(key) {
+        return Ok(token);
+    }
+
+    let (token, lifetime) = fetch().await?;
+    info!("Fetched OAuth token for {} valid for {:?}", key, lifetime);
+    TOKENS.lock().insert(
+        key.to_string(),
+        CachedToken {
+            token: token.clone(),
+            expires_at: Instant::now() + lifetime.saturating_sub(TOKEN_EXPIRY_BUFFER),
+        },
+    );
+    Ok(token)
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    /// Seconds; Azure IMDS sends it as a string
+    #[serde(default)]
+    expires_in: Option<serde_json::Value>,
+}
+
+async fn parse_token_response(response: reqwest::Response) -> Result<(String, Duration), AppError> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::SigningError(format!("Token endpoint returned {}: {}", status, body)));
+    }
+    let token: TokenResponse = response.json().await?;
+    let expires_in = token
+        .expires_in
+        .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .unwrap_or(3600);
+    Ok((token.access_token, Duration::from_secs(expires_in)))
+}
+
+fn with_bearer(headers: &HeaderMap, token: &str) -> Result<HeaderMap, AppError> {
+    let mut headers = headers.clone();
+    headers.insert(
+        http::header::AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", token))?,
+    );
+    Ok(headers)
+}
+
+fn form_encode(pairs: &[(&str, &str)]) -> String {
+    let encode = |s: &str| {
+        s.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect::<String>()
+    };
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.iter().map(|b| b ^ 0x36).collect::<Vec<_>>());
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(block.iter().map(|b| b ^ 0x5c).collect::<Vec<_>>());
+    outer.update(inner.finalize());
+    outer.finalize().to_vec()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 
 pub async fn sign_aws_request(
     method: &str,
@@ -12,14 +432,22 @@ pub async fn sign_aws_request(
     body: &[u8],
     access_key: &str,
     secret_key: &str,
+    session_token: Option<&str>,
     region: &str,
     service: &str,
 ) -> Result<HeaderMap, AppError> {
-    debug!("Signing request with method: {}, url: {}", method, url);
+    debug!("Signing request with method: {}, url: {}", method, redact::url(url));
 
-    // Create credentials
-    let identity =
-        Credentials::new(access_key, secret_key, None, None, "signing-credentials").into();
+    // Create credentials; temporary credentials carry a session token that
+    // ends up in the signed x-amz-security-token header
+    let identity = Credentials::new(
+        access_key,
+        secret_key,
+        session_token.map(String::from),
+        None,
+        "signing-credentials",
+    )
+    .into();
 
     // Create signing parameters
     let signing_settings = SigningSettings::default();
@@ -65,6 +493,6 @@ pub async fn sign_aws_request(
         final_headers.insert(key.clone(), value.clone());
     }
 
-    debug!("Final signed headers: {:?}", final_headers);
+    debug!("Final signed headers: {:?}", redact::headers(&final_headers));
     Ok(final_headers)
 }
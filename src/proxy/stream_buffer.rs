@@ -0,0 +1,57 @@
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Chunks queued between an upstream relay and the client, from `STREAM_CHANNEL_CAPACITY`
+static CAPACITY: Lazy<usize> = Lazy::new(|| {
+    std::env::var("STREAM_CHANNEL_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(64)
+});
+
+/// Bytes queued per stream before the relay stops reading the upstream, from `STREAM_BUFFER_BYTES`
+static MAX_BYTES: Lazy<u32> = Lazy::new(|| {
+    std::env::var("STREAM_BUFFER_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(1024 * 1024)
+});
+
+type Item<E> = (Result<Bytes, E>, Option<OwnedSemaphorePermit>);
+
+/// Sending half of a relay channel bounded by both chunk count and buffered bytes.
+///
+/// `send` waits while the client is behind, so the relay stops polling the
+/// upstream instead of queueing its whole output in memory.
+pub struct StreamSender<E> {
+    tx: mpsc::Sender<Item<E>>,
+    budget: Arc<Semaphore>,
+}
+
+impl<E> StreamSender<E> {
+    pub async fn send(&self, item: Result<Bytes, E>) -> Result<(), mpsc::error::SendError<()>> {
+        let permit = match &item {
+            Ok(bytes) => {
+                // A chunk larger than the whole budget waits for an empty buffer instead of forever
+                let cost = u32::try_from(bytes.len()).unwrap_or(u32::MAX).min(*MAX_BYTES);
+                self.budget.clone().acquire_many_owned(cost).await.ok()
+            }
+            Err(_) => None,
+        };
+        self.tx.send((item, permit)).await.map_err(|_| mpsc::error::SendError(()))
+    }
+}
+
+/// A relay channel; the receiving half is a body stream that frees buffer space as the client reads
+pub fn channel<E: Send + 'static>() -> (StreamSender<E>, impl Stream<Item = Result<Bytes, E>> + Send + 'static) {
+    let (tx, rx) = mpsc::channel::<Item<E>>(*CAPACITY);
+    let budget = Arc::new(Semaphore::new(*MAX_BYTES as usize));
+    let body = ReceiverStream::new(rx).map(|(item, _permit)| item);
+    (StreamSender { tx, budget }, body)
+}
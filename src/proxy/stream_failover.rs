@@ -0,0 +1,329 @@
+use crate::{
+    config::AppConfig,
+    error::AppError,
+    providers::{create_provider, stream_events::StreamTap},
+    tenancy::TenantContext,
+};
+use axum::{
+    body::{Body, Bytes},
+    http::{header, request::Parts, HeaderValue, Request, Response},
+};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc};
+use tracing::{debug, error, info, warn};
+
+use super::{forward_request, header_policy, stream_buffer};
+
+/// Instruction appended after the partial answer when the fallback resumes it
+const CONTINUE_PROMPT: &str =
+    "Your previous response was cut off. Continue it exactly where it stopped, without repeating any of it.";
+
+/// Where a provider's broken streams are resumed
+#[derive(Debug, Clone, Deserialize)]
+pub struct FailoverTarget {
+    pub provider: String,
+    /// Model for the fallback provider; the request's own model when unset
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Environment variable holding the fallback provider's API key, sent as a bearer token.
+    /// Required when the fallback is a different provider, unless the tenant has its own
+    /// credentials for it; the client's key is never sent to another vendor.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+impl FailoverTarget {
+    pub(super) fn label(&self) -> String {
+        match &self.model {
+            Some(model) => format!("{}:{}", self.provider, model),
+            None => self.provider.clone(),
+        }
+    }
+
+    fn api_key(&self) -> Option<String> {
+        self.api_key_env.as_ref().and_then(|name| std::env::var(name).ok())
+    }
+
+    /// Whether this target is a different provider than the one the client addressed
+    fn is_cross_provider(&self, parts: &Parts) -> bool {
+        !parts
+            .headers
+            .get("x-provider")
+            .and_then(|h| h.to_str().ok())
+            .is_some_and(|provider| provider.eq_ignore_ascii_case(&self.provider))
+    }
+
+    /// Whether the request can be sent here: the client's credentials only serve the
+    /// provider it addressed, so another vendor needs `api_key_env` or tenant credentials
+    pub(super) fn has_credentials(&self, parts: &Parts) -> bool {
+        !self.is_cross_provider(parts)
+            || self.api_key().is_some()
+            || parts
+                .extensions
+                .get::<TenantContext>()
+                .is_some_and(|TenantContext(tenant)| tenant.has_provider_headers(&self.provider))
+    }
+}
+
+/// Fallbacks from `STREAM_FAILOVER`, keyed by provider, e.g.
+/// `{"openai": {"provider": "groq", "model": "llama-3.3-70b-versatile", "api_key_env": "GROQ_API_KEY"}}`
+static TARGETS: Lazy<HashMap<String, FailoverTarget>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("STREAM_FAILOVER") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, FailoverTarget>>(&raw) {
+        Ok(targets) => {
+            info!("Loaded stream failover targets for {} providers", targets.len());
+            for (provider, target) in &targets {
+                if !provider.eq_ignore_ascii_case(&target.provider) && target.api_key_env.is_none() {
+                    warn!(
+                        "Stream failover from {} to {} has no api_key_env; it only runs for tenants with their own {} credentials",
+                        provider, target.provider, target.provider
+                    );
+                }
+            }
+            targets
+                .into_iter()
+                .map(|(provider, target)| (provider.to_lowercase(), target))
+                .collect()
+        }
+        Err(e) => {
+            error!("Failed to parse STREAM_FAILOVER: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// Response extension recording the fallback a stream was spliced onto; set
+/// only once the splice happens, so telemetry reads it when the stream ends
+#[derive(Clone, Default)]
+pub struct StreamSplice(Arc<Mutex<Option<String>>>);
+
+impl StreamSplice {
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().clone()
+    }
+}
+
+/// Failover target for a streaming request to this provider, if one is configured
+pub(super) fn target_for(provider_name: &str, body: &Bytes) -> Option<&'static FailoverTarget> {
+    let target = TARGETS.get(&provider_name.to_lowercase())?;
+    let streaming = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|json| json.get("stream").and_then(Value::as_bool))
+        .unwrap_or(false);
+    streaming.then_some(target)
+}
+
+/// Relay a streamed response, resuming it on the fallback if it breaks off before finishing
+pub(super) fn guard(
+    config: Arc<AppConfig>,
+    parts: Parts,
+    request_body: Bytes,
+    response: Response<Body>,
+    target: &'static FailoverTarget,
+) -> Response<Body> {
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"));
+    if !response.status().is_success() || !is_stream {
+        return response;
+    }
+
+    let (mut response_parts, body) = response.into_parts();
+    // The spliced body mixes two upstreams, so telemetry parses the bytes rather than one provider's events
+    response_parts.extensions.remove::<StreamTap>();
+    let splice = StreamSplice::default();
+    response_parts.extensions.insert(splice.clone());
+
+    let (tx, rx) = stream_buffer::channel::<axum::Error>();
+    tokio::spawn(async move {
+        let mut progress = Progress::default();
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let bytes = match chunk {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    warn!("Stream broke off mid-generation: {}", e);
+                    break;
+                }
+            };
+            // Only whole events go out, so the fallback never continues a half-written one
+            if let Some(events) = progress.push(&bytes) {
+                if tx.send(Ok(events)).await.is_err() {
+                    debug!("Client disconnected during guarded stream");
+                    return;
+                }
+            }
+        }
+        if progress.finished {
+            if !progress.pending.is_empty() {
+                let _ = tx.send(Ok(Bytes::from(progress.pending))).await;
+            }
+            return;
+        }
+        // Release the broken upstream (and its provider slot) before calling the fallback
+        drop(stream);
+
+        info!(
+            "Resuming broken stream on {} after {} characters of output",
+            target.label(),
+            progress.text.len()
+        );
+        let resumed = match resume(config, &parts, &request_body, &progress.text, target).await {
+            Ok(response) => response.into_body(),
+            Err(e) => {
+                error!("Stream failover to {} failed: {}", target.label(), e);
+                let _ = tx.send(Ok(failure_event(&e))).await;
+                return;
+            }
+        };
+        *splice.0.lock() = Some(target.label());
+
+        let mut stream = resumed.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let failed = chunk.is_err();
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    Response::from_parts(response_parts, Body::from_stream(rx))
+}
+
+/// What the client has been sent so far
+#[derive(Default)]
+struct Progress {
+    /// Bytes after the last complete event
+    pending: Vec<u8>,
+    /// Generated text, for the continuation prompt
+    text: String,
+    /// Whether the upstream signalled the end of the generation
+    finished: bool,
+}
+
+impl Progress {
+    /// Add a chunk, returning the complete events it finishes
+    fn push(&mut self, bytes: &[u8]) -> Option<Bytes> {
+        self.pending.extend_from_slice(bytes);
+        let end = self.pending.windows(2).rposition(|w| w == b"\n\n")? + 2;
+        let events: Vec<u8> = self.pending.drain(..end).collect();
+        for line in String::from_utf8_lossy(&events).lines() {
+            self.observe(line);
+        }
+        Some(Bytes::from(events))
+    }
+
+    fn observe(&mut self, line: &str) {
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            return;
+        };
+        if data == "[DONE]" {
+            self.finished = true;
+            return;
+        }
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+        // OpenAI-style chunks
+        if let Some(choice) = event.pointer("/choices/0") {
+            if let Some(text) = choice.pointer("/delta/content").and_then(Value::as_str) {
+                self.text.push_str(text);
+            }
+            if choice.get("finish_reason").is_some_and(|reason| !reason.is_null()) {
+                self.finished = true;
+            }
+        }
+        // Anthropic-style events
+        match event.get("type").and_then(Value::as_str) {
+            Some("content_block_delta") => {
+                if let Some(text) = event.pointer("/delta/text").and_then(Value::as_str) {
+                    self.text.push_str(text);
+                }
+            }
+            Some("message_stop") => self.finished = true,
+            _ => {}
+        }
+    }
+}
+
+/// Call the fallback with the partial answer and a request to continue it
+pub(super) async fn resume(
+    config: Arc<AppConfig>,
+    parts: &Parts,
+    request_body: &Bytes,
+    partial: &str,
+    target: &FailoverTarget,
+) -> Result<Response<Body>, AppError> {
+    let tenant = parts.extensions.get::<TenantContext>().cloned();
+    if let Some(TenantContext(tenant)) = &tenant {
+        tenant.authorize_provider(&target.provider)?;
+    }
+
+    if !target.has_credentials(parts) {
+        return Err(AppError::ProviderUnavailable(format!(
+            "no credentials for fallback provider {}; set api_key_env",
+            target.provider
+        )));
+    }
+
+    let mut request = Request::new(());
+    *request.method_mut() = parts.method.clone();
+    *request.uri_mut() = parts.uri.clone();
+    *request.headers_mut() = parts.headers.clone();
+    let headers = request.headers_mut();
+    if target.is_cross_provider(parts) {
+        header_policy::strip_credentials(headers);
+    }
+    headers.insert("x-provider", HeaderValue::from_str(&target.provider)?);
+    if let Some(key) = target.api_key() {
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", key))?);
+    }
+    if let Some(TenantContext(tenant)) = &tenant {
+        tenant.apply_provider_headers(&target.provider, headers);
+        request.extensions_mut().insert(TenantContext(tenant.clone()));
+    }
+    let (fallback_parts, ()) = request.into_parts();
+
+    let mut body: Value = serde_json::from_slice(request_body)?;
+    if let Some(model) = &target.model {
+        body["model"] = Value::String(model.clone());
+    }
+    // With nothing generated yet the original request is simply retried
+    if !partial.is_empty() {
+        if let Some(messages) = body.get_mut("messages").and_then(Value::as_array_mut) {
+            messages.push(json!({ "role": "assistant", "content": partial }));
+            messages.push(json!({ "role": "user", "content": CONTINUE_PROMPT }));
+        }
+    }
+
+    let provider = create_provider(&target.provider)?;
+    let response = forward_request(config, provider, &fallback_parts, Bytes::from(serde_json::to_vec(&body)?)).await?;
+    if !response.status().is_success() {
+        return Err(AppError::RequestError(format!(
+            "fallback {} returned {}",
+            target.provider,
+            response.status()
+        )));
+    }
+    Ok(response)
+}
+
+/// SSE error event ending a stream that couldn't be resumed
+fn failure_event(e: &AppError) -> Bytes {
+    let event = json!({
+        "error": {
+            "message": format!("Upstream stream failed and could not be resumed: {}", e),
+            "type": "server_error",
+            "code": "stream_failover_failed",
+        }
+    });
+    Bytes::from(format!("event: error\ndata: {}\n\ndata: [DONE]\n\n", event))
+}
@@ -0,0 +1,165 @@
+use crate::{
+    config::AppConfig,
+    error::AppError,
+    providers::{create_provider, stream_events::StreamTap, Provider},
+};
+use axum::{
+    body::{Body, Bytes},
+    http::{request::Parts, Response},
+};
+use futures_util::{stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::{forward_request, stream_failover};
+
+/// Attempts on the requested provider before a stream that fails ahead of its
+/// first byte moves to the failover target, from `STREAM_RETRY_ATTEMPTS`
+static ATTEMPTS: Lazy<u32> = Lazy::new(|| {
+    std::env::var("STREAM_RETRY_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2)
+        .max(1)
+});
+
+/// Response extension listing the upstream attempts a stream was re-dispatched
+/// past, e.g. `openai: 503 -> openai: ok`; set only when one was retried
+#[derive(Debug, Clone)]
+pub struct RetryChain(pub String);
+
+/// Whether a request is a stream that can be re-dispatched before its first byte
+pub(super) fn applies(provider_name: &str, body: &Bytes) -> bool {
+    let streaming = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|json| json.get("stream").and_then(Value::as_bool))
+        .unwrap_or(false);
+    streaming && (*ATTEMPTS > 1 || stream_failover::target_for(provider_name, body).is_some())
+}
+
+/// Forward a streaming request, retrying it on the same provider and then on
+/// its failover target while it fails before the first byte. Nothing has been
+/// sent to the client until then, so the retries are invisible to it.
+pub(super) async fn forward(
+    config: Arc<AppConfig>,
+    provider: Box<dyn Provider>,
+    provider_name: &str,
+    parts: &Parts,
+    body_bytes: Bytes,
+) -> Result<Response<Body>, AppError> {
+    // Moving to another provider before the first byte is only done with credentials of its own
+    let target = stream_failover::target_for(provider_name, &body_bytes)
+        .filter(|target| target.has_credentials(parts));
+    let mut chain = Vec::new();
+    let mut provider = Some(provider);
+    for attempt in 1..=*ATTEMPTS {
+        let provider = match provider.take() {
+            Some(provider) => provider,
+            None => create_provider(provider_name)?,
+        };
+        let response = forward_request(config.clone(), provider, parts, body_bytes.clone()).await;
+        let failure = match first_byte(response).await {
+            Attempt::Delivered(outcome) => return outcome.map(|r| with_chain(r, chain, provider_name.to_string())),
+            Attempt::Failed(failure) => failure,
+        };
+        warn!(
+            "Stream from {} failed before its first byte (attempt {} of {}): {}",
+            provider_name, attempt, *ATTEMPTS, failure.reason
+        );
+        chain.push(format!("{}: {}", provider_name, failure.reason));
+        if attempt == *ATTEMPTS && target.is_none() {
+            return failure.outcome;
+        }
+    }
+
+    let Some(target) = target else {
+        return Err(AppError::InvalidProviderResponse(format!("Stream retries exhausted: {}", chain.join(" -> "))));
+    };
+    info!("Re-dispatching stream from {} to {} before its first byte", provider_name, target.label());
+    let response = stream_failover::resume(config, parts, &body_bytes, "", target).await;
+    match first_byte(response).await {
+        Attempt::Delivered(outcome) => outcome.map(|mut response| {
+            // The events are the fallback's, not those of the provider telemetry attributes the request to
+            response.extensions_mut().remove::<StreamTap>();
+            with_chain(response, chain, target.label())
+        }),
+        Attempt::Failed(failure) => {
+            chain.push(format!("{}: {}", target.label(), failure.reason));
+            warn!("Stream failed before its first byte on every attempt: {}", chain.join(" -> "));
+            failure.outcome
+        }
+    }
+}
+
+enum Attempt {
+    /// What goes to the client: a stream that has produced data, or an outcome retrying can't change
+    Delivered(Result<Response<Body>, AppError>),
+    Failed(Failure),
+}
+
+/// An attempt that failed before anything could reach the client
+struct Failure {
+    reason: String,
+    /// What the client gets if this was the last attempt
+    outcome: Result<Response<Body>, AppError>,
+}
+
+/// Wait for a successful stream's first byte, handing back a response that
+/// replays it; transport errors, 5xx responses and streams that end or break
+/// before any data are failures worth retrying
+async fn first_byte(response: Result<Response<Body>, AppError>) -> Attempt {
+    let response = match response {
+        Ok(response) => response,
+        Err(AppError::ReqwestError(e)) => {
+            return Attempt::Failed(Failure {
+                reason: format!("connection failed: {}", e),
+                outcome: Err(AppError::ReqwestError(e)),
+            })
+        }
+        // Anything else was decided by the gateway and would fail the same way again
+        Err(e) => return Attempt::Delivered(Err(e)),
+    };
+    if response.status().is_server_error() {
+        return Attempt::Failed(Failure {
+            reason: response.status().as_u16().to_string(),
+            outcome: Ok(response),
+        });
+    }
+    if !response.status().is_success() {
+        return Attempt::Delivered(Ok(response));
+    }
+
+    let (parts, body) = response.into_parts();
+    let mut body = body.into_data_stream();
+    loop {
+        match body.next().await {
+            Some(Ok(chunk)) if chunk.is_empty() => continue,
+            Some(Ok(chunk)) => {
+                let replayed = stream::once(async move { Ok::<_, axum::Error>(chunk) }).chain(body);
+                return Attempt::Delivered(Ok(Response::from_parts(parts, Body::from_stream(replayed))));
+            }
+            Some(Err(e)) => {
+                return Attempt::Failed(Failure {
+                    reason: format!("stream broke before its first byte: {}", e),
+                    outcome: Err(AppError::AxumError(e)),
+                })
+            }
+            None => {
+                return Attempt::Failed(Failure {
+                    reason: "stream ended before its first byte".to_string(),
+                    outcome: Err(AppError::InvalidProviderResponse("Upstream stream ended before any data".to_string())),
+                })
+            }
+        }
+    }
+}
+
+fn with_chain(mut response: Response<Body>, mut chain: Vec<String>, served_by: String) -> Response<Body> {
+    if !chain.is_empty() {
+        chain.push(format!("{}: ok", served_by));
+        response.extensions_mut().insert(RetryChain(chain.join(" -> ")));
+    }
+    response
+}
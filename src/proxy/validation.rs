@@ -0,0 +1,216 @@
+use crate::error::AppError;
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Response},
+};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tracing::{debug, error, info, warn};
+
+/// What to do with a normalized response that doesn't match the OpenAI schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    Off,
+    /// Log violations and return the response unchanged
+    Log,
+    /// Log violations and fail non-streaming requests with 502
+    Strict,
+}
+
+static MODE: Lazy<ValidationMode> = Lazy::new(|| {
+    let mode = match std::env::var("RESPONSE_VALIDATION")
+        .unwrap_or_default()
+        .to_lowercase()
+        .as_str()
+    {
+        "log" => ValidationMode::Log,
+        "strict" => ValidationMode::Strict,
+        _ => ValidationMode::Off,
+    };
+    if mode != ValidationMode::Off {
+        info!("Response validation enabled in {:?} mode", mode);
+    }
+    mode
+});
+
+const FINISH_REASONS: &[&str] = &["stop", "length", "tool_calls", "content_filter", "function_call"];
+
+/// Check chat completion responses against the OpenAI schema after provider translation
+pub(super) async fn validate_response(
+    provider: &str,
+    path: &str,
+    response: Response<Body>,
+) -> Result<Response<Body>, AppError> {
+    let mode = *MODE;
+    if mode == ValidationMode::Off || !path.ends_with("/chat/completions") || !response.status().is_success() {
+        return Ok(response);
+    }
+
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| v.contains("text/event-stream"));
+
+    if is_stream {
+        return Ok(validate_stream(provider.to_string(), response));
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| AppError::AxumError(e.into()))?;
+
+    let violations = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(value) => validate_chat_completion(&value),
+        Err(e) => vec![format!("body is not valid JSON: {}", e)],
+    };
+
+    if !violations.is_empty() {
+        error!(
+            "{} returned a malformed chat completion: {}",
+            provider,
+            violations.join("; ")
+        );
+        if mode == ValidationMode::Strict {
+            return Err(AppError::InvalidProviderResponse(format!(
+                "{} response failed validation: {}",
+                provider,
+                violations.join("; ")
+            )));
+        }
+    } else {
+        debug!("{} response passed validation", provider);
+    }
+
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+/// Validate each `data:` event as it passes through. Headers are already sent,
+/// so violations are only logged, even in strict mode.
+fn validate_stream(provider: String, response: Response<Body>) -> Response<Body> {
+    let (parts, body) = response.into_parts();
+    let mut pending = String::new();
+
+    let stream = body.into_data_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            pending.push_str(&String::from_utf8_lossy(bytes));
+            while let Some(end) = pending.find('\n') {
+                let line: String = pending.drain(..=end).collect();
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data.is_empty() || data == "[DONE]" {
+                    continue;
+                }
+                let violations = match serde_json::from_str::<Value>(data) {
+                    Ok(value) => validate_chat_chunk(&value),
+                    Err(e) => vec![format!("event is not valid JSON: {}", e)],
+                };
+                if !violations.is_empty() {
+                    warn!(
+                        "{} streamed a malformed chat completion chunk: {}",
+                        provider,
+                        violations.join("; ")
+                    );
+                }
+            }
+        }
+        chunk
+    });
+
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+/// Schema violations in a non-streaming `chat.completion` object
+pub fn validate_chat_completion(value: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    check_envelope(value, "chat.completion", &mut violations);
+
+    for (i, choice) in choices(value, &mut violations) {
+        check_index(choice, i, &mut violations);
+        match choice.get("message") {
+            Some(message) if message.is_object() => {
+                if message.get("role").and_then(Value::as_str) != Some("assistant") {
+                    violations.push(format!("choices[{}].message.role must be \"assistant\"", i));
+                }
+                let content = message.get("content");
+                let has_tool_calls = message.get("tool_calls").map_or(false, Value::is_array);
+                if !matches!(content, Some(Value::String(_)) | Some(Value::Null)) && !has_tool_calls {
+                    violations.push(format!("choices[{}].message.content must be a string or null", i));
+                }
+            }
+            _ => violations.push(format!("choices[{}].message must be an object", i)),
+        }
+        check_finish_reason(choice, i, false, &mut violations);
+    }
+
+    if let Some(usage) = value.get("usage").filter(|u| !u.is_null()) {
+        for field in ["prompt_tokens", "completion_tokens", "total_tokens"] {
+            if !usage.get(field).map_or(false, Value::is_u64) {
+                violations.push(format!("usage.{} must be a non-negative integer", field));
+            }
+        }
+    }
+    violations
+}
+
+/// Schema violations in a streamed `chat.completion.chunk` object
+pub fn validate_chat_chunk(value: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    check_envelope(value, "chat.completion.chunk", &mut violations);
+
+    for (i, choice) in choices(value, &mut violations) {
+        check_index(choice, i, &mut violations);
+        if !choice.get("delta").map_or(false, Value::is_object) {
+            violations.push(format!("choices[{}].delta must be an object", i));
+        }
+        check_finish_reason(choice, i, true, &mut violations);
+    }
+    violations
+}
+
+fn check_envelope(value: &Value, object: &str, violations: &mut Vec<String>) {
+    if value.get("object").and_then(Value::as_str) != Some(object) {
+        violations.push(format!("object must be \"{}\"", object));
+    }
+    if !value.get("id").map_or(false, Value::is_string) {
+        violations.push("id must be a string".to_string());
+    }
+    if !value.get("created").map_or(false, Value::is_u64) {
+        violations.push("created must be a unix timestamp".to_string());
+    }
+    if !value.get("model").map_or(false, Value::is_string) {
+        violations.push("model must be a string".to_string());
+    }
+}
+
+fn choices<'a>(value: &'a Value, violations: &mut Vec<String>) -> Vec<(usize, &'a Value)> {
+    match value.get("choices").and_then(Value::as_array) {
+        Some(choices) => choices.iter().enumerate().collect(),
+        None => {
+            violations.push("choices must be an array".to_string());
+            Vec::new()
+        }
+    }
+}
+
+fn check_index(choice: &Value, i: usize, violations: &mut Vec<String>) {
+    if !choice.get("index").map_or(false, Value::is_u64) {
+        violations.push(format!("choices[{}].index must be an integer", i));
+    }
+}
+
+fn check_finish_reason(choice: &Value, i: usize, nullable: bool, violations: &mut Vec<String>) {
+    match choice.get("finish_reason") {
+        Some(Value::String(reason)) if FINISH_REASONS.contains(&reason.as_str()) => {}
+        Some(Value::Null) | None if nullable => {}
+        other => violations.push(format!(
+            "choices[{}].finish_reason {} is not one of {:?}",
+            i,
+            other.map_or("<missing>".to_string(), Value::to_string),
+            FINISH_REASONS
+        )),
+    }
+}
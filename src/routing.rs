@@ -0,0 +1,178 @@
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info};
+
+use crate::error::AppError;
+
+/// Conversation id that keeps a thread on the provider it started with
+const THREAD_HEADER: &str = "x-thread-id";
+
+/// One provider/model a pool can route to
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteTarget {
+    pub provider: String,
+    pub model: String,
+    /// Relative share of new conversations
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Pools of equivalent targets from `ROUTING_POOLS`, keyed by the model name clients send, e.g.
+/// `{"chat-default": [{"provider": "openai", "model": "gpt-4o"}, {"provider": "anthropic", "model": "claude-3-5-sonnet-latest"}]}`
+static POOLS: Lazy<HashMap<String, Vec<RouteTarget>>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("ROUTING_POOLS") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, Vec<RouteTarget>>>(&raw) {
+        Ok(pools) => {
+            let pools: HashMap<_, _> = pools
+                .into_iter()
+                .filter(|(_, targets)| targets.iter().any(|t| t.weight > 0))
+                .collect();
+            info!("Loaded {} routing pools", pools.len());
+            pools
+        }
+        Err(e) => {
+            error!("Failed to parse ROUTING_POOLS: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// How long a conversation stays pinned after its last request
+static PIN_TTL: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(
+        std::env::var("STICKY_PIN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600),
+    )
+});
+
+struct Pin {
+    target: RouteTarget,
+    expires_at: Instant,
+}
+
+/// Pinned targets by (pool, thread id)
+static PINS: Lazy<Mutex<HashMap<(String, String), Pin>>> = Lazy::new(Default::default);
+
+fn pick(targets: &[RouteTarget]) -> RouteTarget {
+    let total: u32 = targets.iter().map(|t| t.weight).sum();
+    let mut roll = rand::thread_rng().gen_range(0..total);
+    for target in targets {
+        if roll < target.weight {
+            return target.clone();
+        }
+        roll -= target.weight;
+    }
+    targets[targets.len() - 1].clone()
+}
+
+/// Target for a new conversation, by weighted rendezvous hashing of its thread
+/// id: every replica pins a thread to the same target without sharing state,
+/// and changing a pool only moves the threads of the targets that changed
+fn pick_for_thread(pool: &str, thread_id: &str, targets: &[RouteTarget]) -> RouteTarget {
+    targets
+        .iter()
+        .filter(|target| target.weight > 0)
+        .map(|target| {
+            let digest = Sha256::digest(format!("{}\0{}\0{}\0{}", pool, thread_id, target.provider, target.model));
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&digest[..8]);
+            // Uniform in (0, 1); -weight / ln(u) wins in proportion to the weight
+            let unit = ((u64::from_be_bytes(bytes) >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+            (-f64::from(target.weight) / unit.ln(), target)
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, target)| target.clone())
+        .unwrap_or_else(|| pick(targets))
+}
+
+/// Target for a request to a pool, reusing the thread's pin while it is fresh
+fn resolve(pool: &str, targets: &[RouteTarget], thread_id: Option<&str>) -> RouteTarget {
+    let Some(thread_id) = thread_id else {
+        return pick(targets);
+    };
+    let now = Instant::now();
+    let key = (pool.to_string(), thread_id.to_string());
+    let mut pins = PINS.lock();
+    if let Some(pin) = pins.get_mut(&key).filter(|pin| pin.expires_at > now) {
+        pin.expires_at = now + *PIN_TTL;
+        debug!("Thread {} stays pinned to {}/{}", thread_id, pin.target.provider, pin.target.model);
+        return pin.target.clone();
+    }
+
+    pins.retain(|_, pin| pin.expires_at > now);
+    let target = pick_for_thread(pool, thread_id, targets);
+    debug!("Pinning thread {} to {}/{}", thread_id, target.provider, target.model);
+    pins.insert(
+        key,
+        Pin {
+            target: target.clone(),
+            expires_at: now + *PIN_TTL,
+        },
+    );
+    target
+}
+
+/// Sends requests for a pool's model to one of its targets by setting `x-provider`
+/// and the body's model. Runs outside the metrics layer so telemetry records the
+/// provider that actually served the request.
+pub async fn routing_middleware(request: Request<Body>, next: Next) -> Response {
+    if POOLS.is_empty() {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    let is_json = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return AppError::AxumError(e).into_response(),
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    let Some((pool, targets)) = json
+        .get("model")
+        .and_then(Value::as_str)
+        .and_then(|model| POOLS.get_key_value(model))
+    else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    let thread_id = parts.headers.get(THREAD_HEADER).and_then(|h| h.to_str().ok());
+    let target = resolve(pool, targets, thread_id);
+    json["model"] = Value::String(target.model.clone());
+    let Ok(provider) = HeaderValue::from_str(&target.provider) else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    parts.headers.insert("x-provider", provider);
+
+    let body = serde_json::to_vec(&json).map(Bytes::from).unwrap_or(bytes);
+    parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
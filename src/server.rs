@@ -0,0 +1,73 @@
+use crate::config::{AppConfig, HttpVersions};
+use axum::Router;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use std::{future::Future, net::SocketAddr};
+use tokio::net::TcpListener;
+use tower_service::Service;
+use tracing::{debug, error, info};
+
+/// Serve the app over HTTP/1.1 and/or cleartext HTTP/2 until `shutdown` resolves.
+///
+/// Unlike `axum::serve` this exposes the h2 settings, so many SSE streams from one
+/// client can be multiplexed over a single connection up to the configured limit.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    config: &AppConfig,
+    shutdown: impl Future<Output = ()>,
+) {
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder = match config.http_versions {
+        HttpVersions::Http1 => builder.http1_only(),
+        HttpVersions::Http2 => builder.http2_only(),
+        HttpVersions::Auto => builder,
+    };
+    builder
+        .http2()
+        .max_concurrent_streams(config.http2_max_concurrent_streams);
+    info!(
+        "Serving {:?} with up to {} concurrent HTTP/2 streams per connection",
+        config.http_versions, config.http2_max_concurrent_streams
+    );
+
+    let mut make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let (socket, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    error!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+        if let Err(e) = socket.set_nodelay(config.tcp_nodelay) {
+            debug!("Failed to set TCP_NODELAY for {}: {}", remote_addr, e);
+        }
+
+        let service = match make_service.call(remote_addr).await {
+            Ok(service) => service,
+            Err(infallible) => match infallible {},
+        };
+        let connection = builder
+            .serve_connection_with_upgrades(TokioIo::new(socket), TowerToHyperService::new(service))
+            .into_owned();
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("Connection from {} closed with error: {}", remote_addr, e);
+            }
+        });
+    }
+
+    debug!("Listener closed, waiting for open connections to finish");
+    graceful.shutdown().await;
+}
@@ -0,0 +1,282 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use redis::aio::ConnectionManager;
+use redis::{RedisResult, Script};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::{error, info};
+
+/// How long after a failed connection attempt Redis is tried again
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Prefix for every key the gateway writes, from `REDIS_KEY_PREFIX`, so
+/// several deployments can share one Redis
+static KEY_PREFIX: Lazy<String> =
+    Lazy::new(|| std::env::var("REDIS_KEY_PREFIX").unwrap_or_else(|_| "noveum-gateway".to_string()));
+
+/// Sliding-window request counter: drops entries older than the window, then
+/// adds this request if the count is below the limit. Returns {admitted, count}.
+static COUNT_REQUEST: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r"
+        local now = tonumber(ARGV[1])
+        local window = tonumber(ARGV[2])
+        redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+        local count = redis.call('ZCARD', KEYS[1])
+        if count >= tonumber(ARGV[3]) then
+            return {0, count}
+        end
+        redis.call('ZADD', KEYS[1], now, ARGV[4])
+        redis.call('PEXPIRE', KEYS[1], window)
+        return {1, count + 1}
+        ",
+    )
+});
+
+/// Budget spent (KEYS[1]) and reservations (KEYS[2], `amount:reserved_at_ms` by
+/// request ID). Drops expired reservations, and with a request ID, estimate and
+/// budget also reserves the estimate if it fits. Returns {admitted, spent, reserved}.
+static BUDGET: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r"
+        local now = tonumber(ARGV[1])
+        local spent = tonumber(redis.call('GET', KEYS[1]) or '0')
+        local reserved = 0
+        local entries = redis.call('HGETALL', KEYS[2])
+        for i = 1, #entries, 2 do
+            local amount, at = string.match(entries[i + 1], '^([^:]+):(.+)$')
+            if now - tonumber(at) >= tonumber(ARGV[2]) then
+                redis.call('HDEL', KEYS[2], entries[i])
+            else
+                reserved = reserved + tonumber(amount)
+            end
+        end
+        local admitted = 1
+        if ARGV[3] then
+            if spent + reserved + tonumber(ARGV[4]) > tonumber(ARGV[5]) then
+                admitted = 0
+            else
+                redis.call('HSET', KEYS[2], ARGV[3], ARGV[4] .. ':' .. ARGV[1])
+            end
+        end
+        return {admitted, tostring(spent), tostring(reserved)}
+        ",
+    )
+});
+
+/// Releases a reservation and adds the actual cost; empty arguments are skipped
+static SETTLE: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r"
+        if ARGV[1] ~= '' then
+            redis.call('HDEL', KEYS[2], ARGV[1])
+        end
+        if ARGV[2] ~= '' then
+            redis.call('INCRBYFLOAT', KEYS[1], ARGV[2])
+        end
+        return 1
+        ",
+    )
+});
+
+/// Takes or renews a lease (KEYS[1]) for ARGV[1], lasting ARGV[2] ms.
+/// Returns 1 if ARGV[1] holds it.
+static HOLD_LOCK: Lazy<Script> = Lazy::new(|| {
+    Script::new(
+        r"
+        if redis.call('GET', KEYS[1]) == ARGV[1] then
+            redis.call('PEXPIRE', KEYS[1], ARGV[2])
+            return 1
+        end
+        if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+            return 1
+        end
+        return 0
+        ",
+    )
+});
+
+/// Limiter, budget and circuit-breaker state shared by every replica through
+/// Redis, so limits hold for the deployment as a whole rather than per replica
+pub struct SharedState {
+    conn: ConnectionManager,
+}
+
+static CLIENT: Lazy<Option<redis::Client>> = Lazy::new(|| {
+    let url = std::env::var("REDIS_URL").ok().filter(|url| !url.is_empty())?;
+    match redis::Client::open(url) {
+        Ok(client) => Some(client),
+        Err(e) => {
+            error!("Invalid REDIS_URL: {}", e);
+            None
+        }
+    }
+});
+
+static STATE: OnceCell<SharedState> = OnceCell::const_new();
+
+/// When connecting last failed, so an unreachable Redis isn't retried on every request
+static LAST_FAILURE: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// The shared state, connecting on first use; `None` when `REDIS_URL` is unset
+/// or Redis can't be reached yet, in which case each replica keeps its own.
+/// A failed connection is retried every `RECONNECT_INTERVAL`.
+pub async fn get() -> Option<&'static SharedState> {
+    if let Some(state) = STATE.get() {
+        return Some(state);
+    }
+    let client = CLIENT.as_ref()?;
+    if LAST_FAILURE.lock().is_some_and(|at| at.elapsed() < RECONNECT_INTERVAL) {
+        return None;
+    }
+    let connected = STATE
+        .get_or_try_init(|| async {
+            let conn = ConnectionManager::new(client.clone()).await?;
+            info!("Sharing limiter, budget and breaker state through Redis (prefix {})", *KEY_PREFIX);
+            Ok::<_, redis::RedisError>(SharedState { conn })
+        })
+        .await;
+    match connected {
+        Ok(state) => Some(state),
+        Err(e) => {
+            error!(
+                "Failed to connect to Redis, limits will be enforced per replica until it is reachable: {}",
+                e
+            );
+            *LAST_FAILURE.lock() = Some(Instant::now());
+            None
+        }
+    }
+}
+
+fn key(name: &str) -> String {
+    format!("{}:{}", *KEY_PREFIX, name)
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+impl SharedState {
+    /// Count a request against a sliding window of `window`, unless `limit`
+    /// requests are already in it. Returns whether it was admitted and the count.
+    pub async fn count_request(&self, name: &str, limit: u32, window: Duration) -> RedisResult<(bool, u64)> {
+        let (admitted, count): (i64, u64) = COUNT_REQUEST
+            .key(key(name))
+            .arg(now_millis())
+            .arg(millis(window))
+            .arg(limit)
+            .arg(uuid::Uuid::new_v4().to_string())
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok((admitted == 1, count))
+    }
+
+    /// USD spent and reserved under `name`, dropping reservations older than `ttl`
+    pub async fn budget_usage(&self, name: &str, ttl: Duration) -> RedisResult<(f64, f64)> {
+        let (_, spent, reserved): (i64, String, String) = BUDGET
+            .key(key(&format!("{}:spent", name)))
+            .key(key(&format!("{}:reservations", name)))
+            .arg(now_millis())
+            .arg(millis(ttl))
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok((parse_usd(&spent), parse_usd(&reserved)))
+    }
+
+    /// Reserve `estimate` under `name` if spent plus reserved stays within
+    /// `budget`. Returns whether it was reserved, and the spent and reserved
+    /// amounts it was checked against.
+    pub async fn reserve(
+        &self,
+        name: &str,
+        request_id: &str,
+        estimate: f64,
+        budget: f64,
+        ttl: Duration,
+    ) -> RedisResult<(bool, f64, f64)> {
+        let (admitted, spent, reserved): (i64, String, String) = BUDGET
+            .key(key(&format!("{}:spent", name)))
+            .key(key(&format!("{}:reservations", name)))
+            .arg(now_millis())
+            .arg(millis(ttl))
+            .arg(request_id)
+            .arg(estimate)
+            .arg(budget)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok((admitted == 1, parse_usd(&spent), parse_usd(&reserved)))
+    }
+
+    /// Release a request's reservation under `name` and add what it cost
+    pub async fn settle(&self, name: &str, request_id: Option<&str>, cost: Option<f64>) -> RedisResult<()> {
+        let _: i64 = SETTLE
+            .key(key(&format!("{}:spent", name)))
+            .key(key(&format!("{}:reservations", name)))
+            .arg(request_id.unwrap_or_default())
+            .arg(cost.map(|cost| cost.to_string()).unwrap_or_default())
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(())
+    }
+
+    /// Take the lock `name` for `holder`, or extend it if `holder` already has
+    /// it, for `ttl`. Returns whether `holder` holds it.
+    pub async fn hold_lock(&self, name: &str, holder: &str, ttl: Duration) -> RedisResult<bool> {
+        let held: i64 = HOLD_LOCK
+            .key(key(name))
+            .arg(holder)
+            .arg(millis(ttl))
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(held == 1)
+    }
+
+    /// Set the marker `name` unless it is already set, expiring after `ttl`.
+    /// Returns whether this call set it.
+    pub async fn set_once(&self, name: &str, ttl: Duration) -> RedisResult<bool> {
+        let set: Option<String> = redis::cmd("SET")
+            .arg(key(name))
+            .arg(1)
+            .arg("NX")
+            .arg("PX")
+            .arg(millis(ttl).max(1))
+            .query_async(&mut self.conn.clone())
+            .await?;
+        Ok(set.is_some())
+    }
+
+    /// Store a value as JSON under `name`, expiring after `ttl`
+    pub async fn put_json<T: Serialize>(&self, name: &str, value: &T, ttl: Duration) -> RedisResult<()> {
+        let json = serde_json::to_string(value).unwrap_or_default();
+        let _: () = redis::cmd("SET")
+            .arg(key(name))
+            .arg(json)
+            .arg("PX")
+            .arg(millis(ttl).max(1))
+            .query_async(&mut self.conn.clone())
+            .await?;
+        Ok(())
+    }
+
+    /// Remove the value under `name`
+    pub async fn delete(&self, name: &str) -> RedisResult<()> {
+        let _: i64 = redis::cmd("DEL").arg(key(name)).query_async(&mut self.conn.clone()).await?;
+        Ok(())
+    }
+
+    /// A value stored with `put_json`, if present and still readable
+    pub async fn get_json<T: DeserializeOwned>(&self, name: &str) -> RedisResult<Option<T>> {
+        let json: Option<String> = redis::cmd("GET").arg(key(name)).query_async(&mut self.conn.clone()).await?;
+        Ok(json.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+}
+
+fn parse_usd(value: &str) -> f64 {
+    value.parse().unwrap_or(0.0)
+}
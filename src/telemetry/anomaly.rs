@@ -0,0 +1,247 @@
+use super::{metrics::MetricsExporter, RequestMetrics};
+use crate::cluster;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Tuning for the anomaly analyzer, read from the environment
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// How often the current window is compared against the baseline
+    pub interval: Duration,
+    /// Deviation (in standard deviations) above the baseline that raises an event
+    pub z_threshold: f64,
+    /// Windows with fewer requests are folded into the baseline but never flagged
+    pub min_samples: u64,
+    /// Windows observed before a baseline is trusted
+    pub warmup_windows: u32,
+    /// Weight of the newest window in the exponentially weighted baseline
+    pub baseline_alpha: f64,
+    pub webhook_url: Option<String>,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        fn env<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            interval: Duration::from_secs(env("ANOMALY_INTERVAL_SECS", 60)),
+            z_threshold: env("ANOMALY_Z_THRESHOLD", 3.0),
+            min_samples: env("ANOMALY_MIN_SAMPLES", 20),
+            warmup_windows: env("ANOMALY_WARMUP_WINDOWS", 5),
+            baseline_alpha: env("ANOMALY_BASELINE_ALPHA", 0.1),
+            webhook_url: std::env::var("ANOMALY_WEBHOOK_URL").ok().filter(|u| !u.is_empty()),
+        }
+    }
+}
+
+/// Warning emitted when a provider/model deviates from its baseline
+#[derive(Debug, Clone, Serialize)]
+pub struct AnomalyEvent {
+    pub provider: String,
+    pub model: String,
+    /// `latency_ms` or `error_rate`
+    pub metric: &'static str,
+    pub observed: f64,
+    pub baseline: f64,
+    pub z_score: f64,
+    pub requests: u64,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Requests seen for one provider/model during the current interval
+#[derive(Default)]
+struct Window {
+    requests: u64,
+    errors: u64,
+    latency_sum_ms: f64,
+}
+
+/// Exponentially weighted mean and variance of a per-window value
+#[derive(Default)]
+struct Baseline {
+    mean: f64,
+    variance: f64,
+    windows: u32,
+}
+
+impl Baseline {
+    fn update(&mut self, value: f64, alpha: f64) {
+        if self.windows == 0 {
+            self.mean = value;
+        } else {
+            let diff = value - self.mean;
+            let increment = alpha * diff;
+            self.mean += increment;
+            self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+        }
+        self.windows += 1;
+    }
+
+    /// Standard deviations above the baseline; `min_std` keeps a perfectly
+    /// stable baseline from flagging tiny changes
+    fn z_score(&self, value: f64, min_std: f64) -> f64 {
+        (value - self.mean) / self.variance.sqrt().max(min_std)
+    }
+}
+
+#[derive(Default)]
+struct Series {
+    window: Window,
+    latency: Baseline,
+    error_rate: Baseline,
+}
+
+/// Tracks rolling latency and error-rate baselines per provider/model and
+/// reports windows that spike beyond the configured z-score.
+///
+/// Registered as a metrics exporter to receive completed requests; the
+/// comparison itself runs on a background task started with [`spawn`](Self::spawn).
+#[derive(Clone)]
+pub struct AnomalyDetector {
+    config: Arc<AnomalyConfig>,
+    series: Arc<Mutex<HashMap<(String, String), Series>>>,
+    client: reqwest::Client,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            series: Arc::new(Mutex::new(HashMap::new())),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Start the periodic analysis loop
+    pub fn spawn(&self) {
+        let detector = self.clone();
+        info!(
+            "Starting anomaly detection (interval {:?}, z-score threshold {})",
+            self.config.interval, self.config.z_threshold
+        );
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(detector.config.interval);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                for event in detector.analyze() {
+                    detector.report(event).await;
+                }
+            }
+        });
+    }
+
+    fn record(&self, metrics: &RequestMetrics) {
+        let mut series = self.series.lock();
+        let entry = series
+            .entry((metrics.provider.clone(), metrics.model.clone()))
+            .or_default();
+        entry.window.requests += 1;
+        entry.window.latency_sum_ms += metrics.total_latency.as_secs_f64() * 1000.0;
+        if metrics.status_code >= 500 || metrics.status_code == 429 || metrics.error_count > 0 {
+            entry.window.errors += 1;
+        }
+    }
+
+    /// Close the current window for every series, returning the deviations found
+    fn analyze(&self) -> Vec<AnomalyEvent> {
+        let config = &self.config;
+        let mut events = Vec::new();
+        let mut series = self.series.lock();
+
+        for ((provider, model), entry) in series.iter_mut() {
+            let window = std::mem::take(&mut entry.window);
+            if window.requests == 0 {
+                continue;
+            }
+
+            let latency = window.latency_sum_ms / window.requests as f64;
+            let error_rate = window.errors as f64 / window.requests as f64;
+            let trusted = entry.latency.windows >= config.warmup_windows
+                && window.requests >= config.min_samples;
+
+            if trusted {
+                let checks = [
+                    ("latency_ms", latency, &entry.latency, entry.latency.mean * 0.05),
+                    ("error_rate", error_rate, &entry.error_rate, 0.01),
+                ];
+                for (metric, observed, baseline, min_std) in checks {
+                    let z_score = baseline.z_score(observed, min_std);
+                    if z_score >= config.z_threshold {
+                        events.push(AnomalyEvent {
+                            provider: provider.clone(),
+                            model: model.clone(),
+                            metric,
+                            observed,
+                            baseline: baseline.mean,
+                            z_score,
+                            requests: window.requests,
+                            detected_at: Utc::now(),
+                        });
+                    }
+                }
+            }
+
+            entry.latency.update(latency, config.baseline_alpha);
+            entry.error_rate.update(error_rate, config.baseline_alpha);
+        }
+
+        debug!("Anomaly analysis over {} series found {} events", series.len(), events.len());
+        events
+    }
+
+    async fn report(&self, event: AnomalyEvent) {
+        // Replicas see the same spike; one of them alerts per interval
+        let alert = format!("anomaly:{}:{}:{}", event.provider, event.model, event.metric);
+        if !cluster::claim(&alert, self.config.interval).await {
+            debug!("Anomaly for {}/{} already reported by another replica", event.provider, event.model);
+            return;
+        }
+        warn!(
+            provider = %event.provider,
+            model = %event.model,
+            metric = event.metric,
+            observed = event.observed,
+            baseline = event.baseline,
+            z_score = event.z_score,
+            "Anomaly detected: {} for {}/{} is {:.2} against a baseline of {:.2}",
+            event.metric, event.provider, event.model, event.observed, event.baseline
+        );
+
+        let Some(url) = &self.config.webhook_url else {
+            return;
+        };
+        match self.client.post(url).json(&event).send().await {
+            Ok(response) if !response.status().is_success() => {
+                error!("Anomaly webhook returned {}", response.status());
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to deliver anomaly webhook: {}", e),
+        }
+    }
+}
+
+#[async_trait]
+impl MetricsExporter for AnomalyDetector {
+    async fn export_metrics(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
+        self.record(&metrics);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "anomaly"
+    }
+}
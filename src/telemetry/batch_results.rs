@@ -0,0 +1,149 @@
+use super::provider_metrics::MetricsExtractor;
+use super::RequestMetrics;
+use crate::shared_state;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Message Batches are billed at half the price of the same requests made directly
+const BATCH_DISCOUNT: f64 = 0.5;
+
+/// How long a recorded batch is remembered: Anthropic keeps results for 29 days
+const RECORDED_TTL: Duration = Duration::from_secs(29 * 24 * 60 * 60);
+
+/// Most batches remembered at once; the oldest are forgotten first
+const MAX_RECORDED: usize = 10_000;
+
+/// Batches whose items have been recorded by this process, and when, so fetching
+/// the results again doesn't count their usage and cost twice
+static RECORDED: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Remember the batch as recorded, unless it already was. With `REDIS_URL` the
+/// mark is shared, so budgets kept in Redis aren't charged again for results
+/// fetched on another replica or after a restart.
+async fn mark_recorded(batch_id: &str) -> bool {
+    if let Some(shared) = shared_state::get().await {
+        match shared.set_once(&format!("batch-recorded:{}", batch_id), RECORDED_TTL).await {
+            Ok(marked) => return marked,
+            Err(e) => warn!("Failed to mark batch {} as recorded in Redis, checking locally: {}", batch_id, e),
+        }
+    }
+    mark_recorded_locally(batch_id)
+}
+
+fn mark_recorded_locally(batch_id: &str) -> bool {
+    let mut recorded = RECORDED.lock();
+    let now = Instant::now();
+    recorded.retain(|_, at| now.duration_since(*at) < RECORDED_TTL);
+    if recorded.contains_key(batch_id) {
+        return false;
+    }
+    if recorded.len() >= MAX_RECORDED {
+        let oldest = recorded.iter().min_by_key(|(_, at)| **at).map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            recorded.remove(&oldest);
+        }
+    }
+    recorded.insert(batch_id.to_string(), now);
+    true
+}
+
+/// The batch ID when `path` fetches a Message Batch's results,
+/// i.e. `/v1/messages/batches/{id}/results`
+pub fn results_batch_id<'a>(provider: &str, path: &'a str) -> Option<&'a str> {
+    if provider != "anthropic" {
+        return None;
+    }
+    path.strip_prefix("/v1/messages/batches/")?
+        .strip_suffix("/results")
+        .filter(|id| !id.is_empty() && !id.contains('/'))
+}
+
+/// One record per item of a fetched results file (JSONL), carrying the
+/// identity of the request that fetched it. Returns nothing for a batch whose
+/// results were already recorded.
+pub async fn item_metrics(
+    batch_id: &str,
+    fetch: &RequestMetrics,
+    body: &[u8],
+    extractor: &dyn MetricsExtractor,
+) -> Vec<RequestMetrics> {
+    if !mark_recorded(batch_id).await {
+        debug!("Results of batch {} were already recorded", batch_id);
+        return Vec::new();
+    }
+
+    let items: Vec<RequestMetrics> = body
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .filter_map(|line| match serde_json::from_slice::<Value>(line) {
+            Ok(item) => Some(item),
+            Err(e) => {
+                warn!("Skipping unparseable line in results of batch {}: {}", batch_id, e);
+                None
+            }
+        })
+        .map(|item| item_record(batch_id, fetch, &item, extractor))
+        .collect();
+    debug!("Recording {} items from results of batch {}", items.len(), batch_id);
+    items
+}
+
+fn item_record(batch_id: &str, fetch: &RequestMetrics, item: &Value, extractor: &dyn MetricsExtractor) -> RequestMetrics {
+    let custom_id = item.get("custom_id").and_then(Value::as_str).unwrap_or("unknown");
+    let result = item.get("result").cloned().unwrap_or_default();
+    // "succeeded", "errored", "canceled" or "expired"
+    let result_type = result.get("type").and_then(Value::as_str).unwrap_or("unknown").to_string();
+    let message = result.get("message").filter(|_| result_type == "succeeded");
+    let provider_metrics = message.map(|message| extractor.extract_metrics(message)).unwrap_or_default();
+    let error_type = match result_type.as_str() {
+        "succeeded" => None,
+        "errored" => Some(
+            result
+                .pointer("/error/error/type")
+                .or_else(|| result.pointer("/error/type"))
+                .and_then(Value::as_str)
+                .unwrap_or("batch_item_errored")
+                .to_string(),
+        ),
+        other => Some(format!("batch_item_{}", other)),
+    };
+
+    RequestMetrics {
+        provider: fetch.provider.clone(),
+        path: fetch.path.clone(),
+        method: fetch.method.clone(),
+        model: provider_metrics.model,
+        total_latency: Duration::ZERO,
+        input_tokens: provider_metrics.input_tokens,
+        output_tokens: provider_metrics.output_tokens,
+        total_tokens: provider_metrics.total_tokens,
+        cached_tokens: provider_metrics.cached_tokens,
+        cost: provider_metrics.cost.map(|cost| cost * BATCH_DISCOUNT),
+        status_code: fetch.status_code,
+        provider_status_code: fetch.provider_status_code,
+        error_count: u32::from(error_type.is_some()),
+        provider_error_count: u32::from(error_type.is_some()),
+        provider_error_type: error_type.clone(),
+        error_type,
+        job_id: Some(batch_id.to_string()),
+        job_status: Some(result_type),
+        project_id: fetch.project_id.clone(),
+        org_id: fetch.org_id.clone(),
+        user_id: fetch.user_id.clone(),
+        experiment_id: fetch.experiment_id.clone(),
+        tenant_id: fetch.tenant_id.clone(),
+        key_fingerprint: fetch.key_fingerprint.clone(),
+        request_metadata: fetch.request_metadata.clone(),
+        custom_attributes: fetch.custom_attributes.clone(),
+        provider_request_id: provider_metrics.request_id,
+        // Stable per item, so the documents of a re-recorded batch overwrite rather than duplicate
+        id: Some(format!("{}:{}", batch_id, custom_id)),
+        thread_id: fetch.thread_id.clone(),
+        response_body: message.cloned(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,93 @@
+use super::RequestMetrics;
+use crate::providers::audio_content;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use tracing::debug;
+
+/// Largest response body a request log keeps, from `MAX_CAPTURED_RESPONSE_BYTES`
+/// (default 1 MiB, 0 for no limit). Clients always get the full response.
+static MAX_BYTES: Lazy<usize> = Lazy::new(|| {
+    std::env::var("MAX_CAPTURED_RESPONSE_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1024 * 1024)
+});
+
+/// Shrink an oversized captured response to fit the cap.
+///
+/// A completion becomes `{"truncated": true, "original_bytes", "body"}` holding
+/// the first bytes of its JSON. Streamed chunks are kept until the cap is
+/// reached and followed by a `{"truncated": true, ...}` marker for the rest.
+pub fn apply(metrics: &mut RequestMetrics) {
+    let max = *MAX_BYTES;
+    if max == 0 {
+        return;
+    }
+    if let Some(body) = metrics.response_body.as_mut() {
+        truncate_body(body, max);
+    }
+    if let Some(chunks) = metrics.streamed_data.as_mut() {
+        truncate_chunks(chunks, max);
+    }
+}
+
+/// Replace base64 audio in captured bodies with its size; it is unreadable in a
+/// log and would otherwise dominate the document
+pub fn elide_audio(metrics: &mut RequestMetrics) {
+    let mut elided = false;
+    for body in [&mut metrics.request_body, &mut metrics.response_body].into_iter().flatten() {
+        elided |= audio_content::elide_audio(body);
+    }
+    for chunk in metrics.streamed_data.iter_mut().flatten() {
+        elided |= audio_content::elide_audio(chunk);
+    }
+    if elided {
+        debug!("Elided base64 audio from captured bodies");
+    }
+}
+
+fn truncate_body(body: &mut Value, max: usize) {
+    let text = body.to_string();
+    if text.len() <= max {
+        return;
+    }
+    debug!("Truncating captured response body from {} to {} bytes", text.len(), max);
+    *body = json!({
+        "truncated": true,
+        "original_bytes": text.len(),
+        "body": prefix(&text, max),
+    });
+}
+
+fn truncate_chunks(chunks: &mut Vec<Value>, max: usize) {
+    let sizes: Vec<usize> = chunks.iter().map(|chunk| chunk.to_string().len()).collect();
+    let total: usize = sizes.iter().sum();
+    if total <= max {
+        return;
+    }
+    let mut used = 0;
+    let kept = sizes
+        .iter()
+        .take_while(|size| {
+            used += **size;
+            used <= max
+        })
+        .count();
+    debug!("Keeping {} of {} captured stream chunks ({} bytes)", kept, chunks.len(), total);
+    let dropped = chunks.len() - kept;
+    chunks.truncate(kept);
+    chunks.push(json!({
+        "truncated": true,
+        "original_bytes": total,
+        "dropped_chunks": dropped,
+    }));
+}
+
+/// The longest prefix of `text` within `max` bytes that ends on a char boundary
+fn prefix(text: &str, max: usize) -> &str {
+    let mut end = max.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
@@ -0,0 +1,123 @@
+use super::RequestMetrics;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tracing::{error, info};
+
+/// How much of the prompt and response a request log keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureProfile {
+    /// Request and response bodies as sent and received
+    #[default]
+    Full,
+    /// Bodies keep their structure but message text is replaced by its hash
+    Redacted,
+    /// No bodies at all; only the request metadata and usage
+    MetadataOnly,
+}
+
+impl CaptureProfile {
+    fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.trim().to_lowercase())).ok()
+    }
+}
+
+/// Fields whose string values are message text rather than structure
+const CONTENT_KEYS: &[&str] = &[
+    "content",
+    "text",
+    "prompt",
+    "input",
+    "system",
+    "arguments",
+    "reasoning_content",
+    "thinking",
+    "partial_json",
+    "query",
+    "documents",
+];
+
+/// Gateway-wide profile from `CAPTURE_PROFILE`
+static DEFAULT_PROFILE: Lazy<CaptureProfile> = Lazy::new(|| {
+    std::env::var("CAPTURE_PROFILE")
+        .ok()
+        .and_then(|v| CaptureProfile::parse(&v))
+        .unwrap_or_default()
+});
+
+/// Per-project profiles from `CAPTURE_PROFILES`, e.g. `{"proj-health": "metadata-only"}`
+static PROJECT_PROFILES: Lazy<HashMap<String, CaptureProfile>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("CAPTURE_PROFILES") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, CaptureProfile>>(&raw) {
+        Ok(profiles) => {
+            info!("Loaded capture profiles for {} projects", profiles.len());
+            profiles
+        }
+        Err(e) => {
+            error!("Failed to parse CAPTURE_PROFILES: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// Capture profile for a project, falling back to the gateway default
+pub fn profile_for(project_id: Option<&str>) -> CaptureProfile {
+    project_id
+        .and_then(|id| PROJECT_PROFILES.get(id))
+        .copied()
+        .unwrap_or(*DEFAULT_PROFILE)
+}
+
+/// Strip or hash the bodies of a request log according to its project's profile.
+///
+/// Applied once before metrics reach the stats, tail, console and exporters,
+/// so every consumer sees the same view.
+pub fn apply(metrics: &mut RequestMetrics) {
+    match profile_for(metrics.project_id.as_deref()) {
+        CaptureProfile::Full => {}
+        CaptureProfile::Redacted => {
+            for body in [&mut metrics.request_body, &mut metrics.response_body].into_iter().flatten() {
+                redact(body, false);
+            }
+            for chunk in metrics.streamed_data.iter_mut().flatten() {
+                redact(chunk, false);
+            }
+            // The text around a moderated match is response content too
+            metrics.moderation_span = None;
+        }
+        CaptureProfile::MetadataOnly => {
+            metrics.request_body = None;
+            metrics.response_body = None;
+            metrics.streamed_data = None;
+            metrics.moderation_span = None;
+        }
+    }
+}
+
+/// Replace every string under a content key with its hash
+fn redact(value: &mut Value, in_content: bool) {
+    match value {
+        Value::String(text) if in_content => *text = hash_text(text),
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, in_content)),
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                // Content blocks nest structure (type, role) inside text-bearing fields
+                let is_content = CONTENT_KEYS.contains(&key.as_str()) || (in_content && key != "type");
+                redact(child, is_content);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Truncated SHA-256, so identical prompts can still be grouped
+fn hash_text(text: &str) -> String {
+    let digest = Sha256::digest(text.as_bytes());
+    let hex: String = digest.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", hex)
+}
@@ -0,0 +1,211 @@
+use super::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+/// Request header capping the estimated cost of a streamed response, in USD
+const MAX_COST_HEADER: &str = "x-max-cost";
+
+/// Enforcement reason recorded in telemetry when the cap ends a stream
+pub const MAX_COST_REASON: &str = "max_cost";
+
+/// The cost cap requested for this call, if any
+pub fn requested_cap(headers: &HeaderMap) -> Option<f64> {
+    headers
+        .get(MAX_COST_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|cap| cap.is_finite() && *cap > 0.0)
+}
+
+/// Whether the provider can price the request's model, so a cap on it would be enforced
+pub fn can_price(extractor: &dyn MetricsExtractor, request_body: Option<&Value>) -> bool {
+    let model = request_body
+        .and_then(|r| r.get("model"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    extractor.estimate_cost(model, 1, 1).is_some()
+}
+
+/// Running cost estimate for a stream that hasn't reported usage yet.
+///
+/// Prompt tokens are estimated once from the request and output tokens from the
+/// text generated so far, then priced with the provider's `estimate_cost`.
+pub struct CostMeter {
+    cap: Option<f64>,
+    model: String,
+    input_tokens: u32,
+    output_chars: usize,
+    estimate: Option<f64>,
+}
+
+impl CostMeter {
+    pub fn new(cap: Option<f64>, request_body: Option<&Value>) -> Self {
+        let model = request_body
+            .and_then(|r| r.get("model"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let input_tokens = request_body
+            .and_then(|r| r.get("messages").or_else(|| r.get("prompt")))
+            .map(|prompt| ProviderMetrics::estimate_tokens_from_text(&prompt.to_string()))
+            .unwrap_or(0);
+        Self {
+            cap,
+            model,
+            input_tokens,
+            output_chars: 0,
+            estimate: None,
+        }
+    }
+
+    /// Add a streamed chunk to the running estimate
+    pub fn record(&mut self, chunk: &Value, extractor: &dyn MetricsExtractor) {
+        // Price against the provider's resolved model name once it's known
+        if let Some(model) = chunk.get("model").and_then(Value::as_str) {
+            if model != self.model {
+                self.model = model.to_string();
+            }
+        }
+        self.output_chars += generated_chars(chunk);
+
+        let output_tokens = (self.output_chars as f64 / 4.0).ceil() as u32;
+        self.estimate = extractor.estimate_cost(&self.model, self.input_tokens, output_tokens);
+    }
+
+    /// Add a streamed event to the running estimate from its raw bytes, without
+    /// parsing it; for callers that only forward the stream
+    pub fn record_raw(&mut self, data: &[u8], extractor: &dyn MetricsExtractor) {
+        let chars = raw_generated_chars(data);
+        if chars == 0 && self.estimate.is_some() {
+            return;
+        }
+        self.output_chars += chars;
+
+        let output_tokens = (self.output_chars as f64 / 4.0).ceil() as u32;
+        self.estimate = extractor.estimate_cost(&self.model, self.input_tokens, output_tokens);
+    }
+
+    /// Whether the running estimate has reached the requested cap
+    pub fn exceeded(&self) -> bool {
+        match (self.cap, self.estimate) {
+            (Some(cap), Some(estimate)) => estimate >= cap,
+            _ => false,
+        }
+    }
+
+    /// Metrics for a stream ended by the cap, since usage never arrives
+    pub fn metrics(&self) -> ProviderMetrics {
+        let output_tokens = (self.output_chars as f64 / 4.0).ceil() as u32;
+        ProviderMetrics {
+            model: self.model.clone(),
+            input_tokens: Some(self.input_tokens),
+            output_tokens: Some(output_tokens),
+            total_tokens: Some(self.input_tokens + output_tokens),
+            cost: self.estimate,
+            ..Default::default()
+        }
+    }
+
+    /// Final chunk and terminator sent in place of the rest of the stream
+    pub fn stop_chunk(&self) -> Bytes {
+        info!(
+            "Ending stream for {} at estimated cost {:?} (cap {:?})",
+            self.model, self.estimate, self.cap
+        );
+        let chunk = json!({
+            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+            "object": "chat.completion.chunk",
+            "created": chrono::Utc::now().timestamp(),
+            "model": self.model,
+            "choices": [{ "index": 0, "delta": {}, "finish_reason": "length" }],
+        });
+        debug!("Sending cost cap stop chunk: {}", chunk);
+        Bytes::from(format!("data: {}\n\ndata: [DONE]\n\n", chunk))
+    }
+}
+
+/// Characters of generated text (content, reasoning or tool arguments) in a chunk
+fn generated_chars(chunk: &Value) -> usize {
+    let openai: usize = chunk
+        .get("choices")
+        .and_then(Value::as_array)
+        .map(|choices| {
+            choices
+                .iter()
+                .filter_map(|choice| choice.get("delta"))
+                .map(|delta| {
+                    let text = ["content", "reasoning_content"]
+                        .iter()
+                        .filter_map(|key| delta.get(*key).and_then(Value::as_str))
+                        .map(|s| s.chars().count())
+                        .sum::<usize>();
+                    let tools = delta
+                        .get("tool_calls")
+                        .and_then(Value::as_array)
+                        .map(|calls| {
+                            calls
+                                .iter()
+                                .filter_map(|call| call.pointer("/function/arguments").and_then(Value::as_str))
+                                .map(|s| s.chars().count())
+                                .sum::<usize>()
+                        })
+                        .unwrap_or(0);
+                    text + tools
+                })
+                .sum()
+        })
+        .unwrap_or(0);
+
+    // Anthropic native stream events carry text in delta.text
+    let anthropic = chunk
+        .pointer("/delta/text")
+        .and_then(Value::as_str)
+        .map_or(0, |t| t.chars().count());
+
+    openai + anthropic
+}
+
+/// String fields whose values are generated text, in either stream format
+const RAW_TEXT_KEYS: [&[u8]; 4] = [b"\"content\":", b"\"reasoning_content\":", b"\"arguments\":", b"\"text\":"];
+
+/// Characters of generated text in a raw event, found by scanning for the
+/// string values of [`RAW_TEXT_KEYS`]; an escape sequence counts as one character
+fn raw_generated_chars(data: &[u8]) -> usize {
+    let mut chars = 0;
+    for key in RAW_TEXT_KEYS {
+        let mut rest = data;
+        while let Some(at) = rest.windows(key.len()).position(|w| w == key) {
+            rest = rest[at + key.len()..].trim_ascii_start();
+            let Some(value) = rest.strip_prefix(b"\"") else {
+                continue;
+            };
+            let (count, end) = json_string_chars(value);
+            chars += count;
+            rest = &value[end..];
+        }
+    }
+    chars
+}
+
+/// Characters in a JSON string body up to its closing quote, and where that quote is
+fn json_string_chars(value: &[u8]) -> (usize, usize) {
+    let mut chars = 0;
+    let mut i = 0;
+    while i < value.len() {
+        match value[i] {
+            b'"' => return (chars, i),
+            b'\\' if value.get(i + 1) == Some(&b'u') => i += 6,
+            b'\\' => i += 2,
+            // UTF-8 continuation bytes belong to the character before them
+            b if b & 0xC0 == 0x80 => {
+                i += 1;
+                continue;
+            }
+            _ => i += 1,
+        }
+        chars += 1;
+    }
+    (chars, value.len())
+}
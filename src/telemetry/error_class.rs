@@ -0,0 +1,107 @@
+use serde_json::Value;
+
+/// Normalized error categories recorded as `error_type` / `provider_error_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Auth,
+    RateLimit,
+    ContextLength,
+    ContentFilter,
+    Timeout,
+    Network,
+    Server,
+    InvalidRequest,
+}
+
+impl ErrorClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::Auth => "auth",
+            ErrorClass::RateLimit => "rate_limit",
+            ErrorClass::ContextLength => "context_length",
+            ErrorClass::ContentFilter => "content_filter",
+            ErrorClass::Timeout => "timeout",
+            ErrorClass::Network => "network",
+            ErrorClass::Server => "server",
+            ErrorClass::InvalidRequest => "invalid_request",
+        }
+    }
+}
+
+const CONTEXT_LENGTH_HINTS: &[&str] = &[
+    "context_length",
+    "context length",
+    "context window",
+    "maximum context",
+    "prompt is too long",
+    "input is too long",
+    "too many tokens",
+    "max_tokens_exceeded",
+];
+const CONTENT_FILTER_HINTS: &[&str] = &[
+    "content_filter",
+    "content filter",
+    "content_policy",
+    "content policy",
+    "guardrail",
+    "safety",
+    "moderation",
+];
+const RATE_LIMIT_HINTS: &[&str] = &["rate_limit", "rate limit", "throttl", "quota", "resource_exhausted", "too many requests"];
+const AUTH_HINTS: &[&str] = &["authentication", "unauthorized", "unauthenticated", "permission", "invalid_api_key", "api key", "access denied"];
+const TIMEOUT_HINTS: &[&str] = &["timeout", "timed out", "deadline_exceeded", "deadline exceeded"];
+const NETWORK_HINTS: &[&str] = &["error sending request", "connection", "network", "dns", "tls handshake"];
+
+/// Classify an error response from its status code and (OpenAI, Anthropic,
+/// Google or Bedrock shaped) error body. Successful responses are only
+/// classified when they carry an `error` object, as streamed error events do.
+pub fn classify(status: u16, body: Option<&Value>) -> Option<ErrorClass> {
+    let has_error = body.and_then(|b| b.get("error")).is_some_and(|e| !e.is_null());
+    if status < 400 && !has_error {
+        return None;
+    }
+    let text = body.map(error_text).unwrap_or_default();
+    let mentions = |hints: &[&str]| hints.iter().any(|hint| text.contains(hint));
+
+    // These arrive as 400s, so the body decides before the status does
+    let class = if mentions(CONTEXT_LENGTH_HINTS) {
+        ErrorClass::ContextLength
+    } else if mentions(CONTENT_FILTER_HINTS) {
+        ErrorClass::ContentFilter
+    } else if status == 429 || mentions(RATE_LIMIT_HINTS) {
+        ErrorClass::RateLimit
+    } else if status == 401 || status == 403 || mentions(AUTH_HINTS) {
+        ErrorClass::Auth
+    } else if status == 408 || status == 504 || mentions(TIMEOUT_HINTS) {
+        ErrorClass::Timeout
+    } else if mentions(NETWORK_HINTS) {
+        ErrorClass::Network
+    } else if status >= 500 || status < 400 {
+        ErrorClass::Server
+    } else {
+        ErrorClass::InvalidRequest
+    };
+    Some(class)
+}
+
+/// Lowercased type, code, status and message fields of an error body
+fn error_text(body: &Value) -> String {
+    let error = body.get("error").unwrap_or(body);
+    let mut parts = Vec::new();
+    if let Value::String(message) = error {
+        parts.push(message.to_lowercase());
+    }
+    // Bedrock reports the exception name in `__type`
+    for key in ["type", "code", "status", "message", "__type"] {
+        match error.get(key) {
+            Some(Value::String(s)) => parts.push(s.to_lowercase()),
+            Some(Value::Number(n)) => parts.push(n.to_string()),
+            _ => {}
+        }
+    }
+    // Some providers put the message beside the error object rather than in it
+    if let (Some(_), Some(Value::String(message))) = (body.get("error"), body.get("message")) {
+        parts.push(message.to_lowercase());
+    }
+    parts.join(" ")
+}
@@ -0,0 +1,176 @@
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::{debug, error, info};
+
+/// What to record for the value a rule's path selects
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractOp {
+    /// The value itself; objects and arrays are stored as JSON strings
+    #[default]
+    Value,
+    /// Character count of a string, or element count of an array or object
+    Length,
+    /// Whether the path selects anything
+    Exists,
+}
+
+/// A config-defined telemetry attribute taken from the request body
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExtractRule {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub op: ExtractOp,
+}
+
+/// Rules from `TELEMETRY_EXTRACT_RULES`, e.g.
+/// `[{"name": "first_message_length", "path": "$.messages[0].content", "op": "length"}]`
+static RULES: Lazy<Vec<(ExtractRule, Vec<Segment>)>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("TELEMETRY_EXTRACT_RULES") else {
+        return Vec::new();
+    };
+    let rules = match serde_json::from_str::<Vec<ExtractRule>>(&raw) {
+        Ok(rules) => rules,
+        Err(e) => {
+            error!("Failed to parse TELEMETRY_EXTRACT_RULES: {}", e);
+            return Vec::new();
+        }
+    };
+    let compiled: Vec<_> = rules
+        .into_iter()
+        .filter_map(|rule| match parse_path(&rule.path) {
+            Some(segments) => Some((rule, segments)),
+            None => {
+                error!("Ignoring extraction rule {} with invalid path {}", rule.name, rule.path);
+                None
+            }
+        })
+        .collect();
+    info!("Loaded {} telemetry extraction rules", compiled.len());
+    compiled
+});
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+/// Parse a JSONPath subset: `$`, `.key`, `['key']`, `[n]` (negative counts from the end) and `[*]`
+fn parse_path(path: &str) -> Option<Vec<Segment>> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    // Allow a bare leading key, e.g. `session_id`
+    let normalized = if path.is_empty() || path.starts_with(['.', '[']) {
+        path.to_string()
+    } else {
+        format!(".{}", path)
+    };
+
+    let mut rest = normalized.as_str();
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return None;
+            }
+            segments.push(Segment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']')?;
+            let inner = after[..end].trim();
+            segments.push(if inner == "*" {
+                Segment::Wildcard
+            } else if let Some(key) = inner
+                .strip_prefix('\'')
+                .and_then(|k| k.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')))
+            {
+                Segment::Key(key.to_string())
+            } else {
+                Segment::Index(inner.parse().ok()?)
+            });
+            rest = &after[end + 1..];
+        } else {
+            return None;
+        }
+    }
+    Some(segments)
+}
+
+/// Values the path selects; a wildcard can select several
+fn select<'a>(value: &'a Value, segments: &[Segment]) -> Vec<&'a Value> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return vec![value];
+    };
+    match segment {
+        Segment::Key(key) => value.get(key).map(|v| select(v, rest)).unwrap_or_default(),
+        Segment::Index(index) => {
+            let Some(items) = value.as_array() else {
+                return Vec::new();
+            };
+            let index = if *index < 0 { items.len() as i64 + index } else { *index };
+            usize::try_from(index)
+                .ok()
+                .and_then(|i| items.get(i))
+                .map(|v| select(v, rest))
+                .unwrap_or_default()
+        }
+        Segment::Wildcard => match value {
+            Value::Array(items) => items.iter().flat_map(|v| select(v, rest)).collect(),
+            Value::Object(map) => map.values().flat_map(|v| select(v, rest)).collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn apply(op: ExtractOp, selected: &[&Value], wildcard: bool) -> Option<Value> {
+    match op {
+        ExtractOp::Exists => Some(Value::Bool(!selected.is_empty())),
+        ExtractOp::Length if wildcard => Some(Value::from(selected.len())),
+        ExtractOp::Length => {
+            let length = match selected.first()? {
+                Value::String(s) => s.chars().count(),
+                Value::Array(items) => items.len(),
+                Value::Object(map) => map.len(),
+                _ => return None,
+            };
+            Some(Value::from(length))
+        }
+        ExtractOp::Value => {
+            let value = if wildcard {
+                Value::Array(selected.iter().map(|v| (*v).clone()).collect())
+            } else {
+                (*selected.first()?).clone()
+            };
+            Some(match value {
+                Value::Object(_) | Value::Array(_) => Value::String(value.to_string()),
+                scalar => scalar,
+            })
+        }
+    }
+}
+
+/// Evaluate the configured rules against a request body
+pub fn extract(body: Option<&Value>) -> Option<Map<String, Value>> {
+    let body = body?;
+    if RULES.is_empty() {
+        return None;
+    }
+
+    let mut attributes = Map::new();
+    for (rule, segments) in RULES.iter() {
+        let selected = select(body, segments);
+        let wildcard = segments.iter().any(|s| matches!(s, Segment::Wildcard));
+        if let Some(value) = apply(rule.op, &selected, wildcard) {
+            attributes.insert(rule.name.clone(), value);
+        }
+    }
+    debug!("Extracted {} custom telemetry attributes", attributes.len());
+
+    (!attributes.is_empty()).then_some(attributes)
+}
@@ -0,0 +1,266 @@
+use super::cost_meter::CostMeter;
+use super::error_class::{self, ErrorClass};
+use super::metrics::MetricsRegistry;
+use super::middleware::reports_usage;
+use super::provider_metrics::{MetricsExtractor, ProviderMetrics};
+use super::stream_interrupt::StreamFormat;
+use super::timing::{tokens_per_second, ChunkTimer};
+use super::RequestMetrics;
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tracing::debug;
+
+/// Zero-copy forwarding is used when eligible unless `STREAM_FAST_PATH=false`
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("STREAM_FAST_PATH")
+        .map(|v| v.parse().unwrap_or(true))
+        .unwrap_or(true)
+});
+
+/// Longest partial SSE line carried between chunks before it's given up on
+const MAX_PENDING_LINE: usize = 64 * 1024;
+
+pub fn enabled() -> bool {
+    *ENABLED
+}
+
+/// Hand the upstream byte stream to the client unchanged, watching it only for
+/// sizes, chunk timings, the usage event, error events and the generated text
+/// length. Chunks are never accumulated, and only the usage and error events
+/// are parsed; the generated text is measured from the raw bytes so a stream
+/// that breaks before its usage event is still priced from an estimate, as on
+/// the regular path. The request is recorded when the stream ends or the
+/// client goes away.
+///
+/// With `close_broken` a stream that fails partway is ended with an error event
+/// in that format rather than cut off.
+pub fn forward<S, E>(
+    upstream: S,
+    metrics: RequestMetrics,
+    extractor: Box<dyn MetricsExtractor>,
+    registry: Arc<MetricsRegistry>,
+    start: Instant,
+    stream_start: Instant,
+    close_broken: Option<StreamFormat>,
+) -> Observed<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+{
+    let cost_meter = CostMeter::new(None, metrics.request_body.as_ref());
+    Observed {
+        upstream,
+        close_broken,
+        closed: false,
+        state: Some(State {
+            cost_meter,
+            metrics,
+            extractor,
+            registry,
+            start,
+            stream_start,
+            timer: ChunkTimer::default(),
+            usage: ProviderMetrics::default(),
+            pending: Vec::new(),
+            done_seen: false,
+            error_seen: false,
+        }),
+    }
+}
+
+pub struct Observed<S> {
+    upstream: S,
+    close_broken: Option<StreamFormat>,
+    /// The gateway ended the stream itself; nothing more is read from upstream
+    closed: bool,
+    state: Option<State>,
+}
+
+struct State {
+    /// Estimate used when the stream ends without a usage event
+    cost_meter: CostMeter,
+    metrics: RequestMetrics,
+    extractor: Box<dyn MetricsExtractor>,
+    registry: Arc<MetricsRegistry>,
+    start: Instant,
+    stream_start: Instant,
+    timer: ChunkTimer,
+    usage: ProviderMetrics,
+    /// Trailing partial line, so a usage event split across chunks is still seen
+    pending: Vec<u8>,
+    done_seen: bool,
+    /// The provider sent an error event
+    error_seen: bool,
+}
+
+impl State {
+    fn observe(&mut self, bytes: &Bytes) {
+        self.timer.record_chunk(Instant::now());
+        self.metrics.response_size += bytes.len();
+
+        let (complete, rest) = match bytes.iter().rposition(|&b| b == b'\n') {
+            Some(end) => bytes.split_at(end + 1),
+            None => (&bytes[..0], &bytes[..]),
+        };
+        if !complete.is_empty() {
+            if self.pending.is_empty() {
+                self.scan(complete);
+            } else {
+                let mut lines = std::mem::take(&mut self.pending);
+                lines.extend_from_slice(complete);
+                self.scan(&lines);
+            }
+        }
+        if self.pending.len() + rest.len() > MAX_PENDING_LINE {
+            self.pending.clear();
+        } else {
+            self.pending.extend_from_slice(rest);
+        }
+    }
+
+    /// Only events that can carry usage or an error are parsed, found by a byte
+    /// search first; the running estimate is taken from the raw bytes
+    fn scan(&mut self, lines: &[u8]) {
+        for line in lines.split(|&b| b == b'\n') {
+            let Some(data) = line.trim_ascii().strip_prefix(b"data:").map(<[u8]>::trim_ascii) else {
+                continue;
+            };
+            if data == b"[DONE]" {
+                self.done_seen = true;
+                continue;
+            }
+            self.cost_meter.record_raw(data, self.extractor.as_ref());
+            if contains(data, b"\"usage\"") {
+                let data = String::from_utf8_lossy(data);
+                if let Some(usage) = self.extractor.extract_streaming_metrics(&data).filter(reports_usage) {
+                    self.usage = usage;
+                }
+            }
+            if contains(data, b"\"error\"") {
+                if let Some(class) = serde_json::from_slice::<Value>(data)
+                    .ok()
+                    .and_then(|event| error_class::classify(self.metrics.status_code, Some(&event)))
+                {
+                    self.error_seen = true;
+                    self.metrics.error_count = 1;
+                    self.metrics.error_type = Some(class.as_str().to_string());
+                }
+            }
+        }
+    }
+
+    /// Record a stream the upstream broke off
+    fn interrupt(&mut self) {
+        self.metrics.stream_interrupted = true;
+        self.metrics.error_count = 1;
+        if self.metrics.error_type.is_none() {
+            self.metrics.error_type = Some(ErrorClass::Network.as_str().to_string());
+        }
+    }
+
+    fn finish(self) {
+        let State { mut metrics, mut usage, cost_meter, registry, start, stream_start, timer, .. } = self;
+        // Like the regular path, a stream that failed before reporting usage is priced from the
+        // estimate, as is a clean OpenAI or Groq stream sent without `include_usage`
+        let broken = metrics.stream_interrupted || metrics.error_count > 0;
+        let estimated = matches!(metrics.provider.as_str(), "openai" | "groq");
+        if (broken || estimated) && !reports_usage(&usage) {
+            usage = cost_meter.metrics();
+        }
+        let stream_duration = stream_start.elapsed();
+        metrics.total_latency = start.elapsed();
+        metrics.stream_duration = stream_duration;
+        metrics.mean_inter_chunk_latency = timer.mean_gap();
+        metrics.max_inter_chunk_latency = timer.max_gap();
+        metrics.tokens_per_second = tokens_per_second(usage.output_tokens, stream_duration);
+        if !usage.model.is_empty() {
+            metrics.model = usage.model;
+        }
+        metrics.provider_latency = usage.provider_latency;
+        metrics.input_tokens = usage.input_tokens;
+        metrics.output_tokens = usage.output_tokens;
+        metrics.total_tokens = usage.total_tokens;
+        metrics.cached_tokens = usage.cached_tokens;
+        metrics.audio_input_tokens = usage.audio_input_tokens;
+        metrics.audio_output_tokens = usage.audio_output_tokens;
+        metrics.accepted_prediction_tokens = usage.accepted_prediction_tokens;
+        metrics.rejected_prediction_tokens = usage.rejected_prediction_tokens;
+        metrics.rejected_prediction_cost = usage.rejected_prediction_cost;
+        metrics.service_tier = usage.service_tier;
+        metrics.reasoning_tokens = usage.reasoning_tokens;
+        metrics.cost = usage.cost;
+        debug!("Fast-path stream completed: {} bytes in {:?}", metrics.response_size, stream_duration);
+        tokio::spawn(async move { registry.record_metrics(metrics).await });
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+impl<S, E> Stream for Observed<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    type Item = Result<Bytes, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.closed {
+            return Poll::Ready(None);
+        }
+        match this.upstream.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(bytes))) => {
+                if let Some(state) = this.state.as_mut() {
+                    state.observe(&bytes);
+                }
+                Poll::Ready(Some(Ok(bytes)))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                if let Some(mut state) = this.state.take() {
+                    state.interrupt();
+                    state.finish();
+                }
+                let Some(format) = this.close_broken else {
+                    return Poll::Ready(Some(Err(e)));
+                };
+                this.closed = true;
+                Poll::Ready(Some(Ok(format.error_event(&e.to_string()))))
+            }
+            Poll::Ready(None) => {
+                let Some(mut state) = this.state.take() else {
+                    return Poll::Ready(None);
+                };
+                // Providers often close the stream right after an error event, without `[DONE]`
+                let unterminated = state.error_seen && !state.done_seen;
+                if unterminated {
+                    state.interrupt();
+                }
+                state.finish();
+                if let Some(done) = this.close_broken.filter(|_| unterminated).and_then(StreamFormat::done_event) {
+                    this.closed = true;
+                    return Poll::Ready(Some(Ok(done)));
+                }
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<S> Drop for Observed<S> {
+    /// Clients that disconnect early still get their request recorded
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            state.finish();
+        }
+    }
+}
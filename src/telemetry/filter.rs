@@ -0,0 +1,78 @@
+use super::RequestMetrics;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::Deserialize;
+use tracing::{debug, error, info};
+
+/// One telemetry filter rule; criteria left empty match anything.
+///
+/// A request matches when every given criterion matches. The first matching
+/// rule decides whether the request reaches the exporters: it's kept with
+/// probability `sample_rate`, so the default of `0.0` drops it outright.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FilterRule {
+    /// Exact paths, or prefixes ending in `*` such as `/v1/models*`
+    #[serde(default)]
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub providers: Vec<String>,
+    #[serde(default)]
+    pub status_codes: Vec<u16>,
+    #[serde(default)]
+    pub sample_rate: f64,
+}
+
+impl FilterRule {
+    fn matches(&self, metrics: &RequestMetrics) -> bool {
+        let path_matches = self.paths.is_empty()
+            || self.paths.iter().any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => metrics.path.starts_with(prefix),
+                None => metrics.path == *pattern,
+            });
+        let provider_matches = self.providers.is_empty()
+            || self.providers.iter().any(|p| p.eq_ignore_ascii_case(&metrics.provider));
+        let status_matches = self.status_codes.is_empty() || self.status_codes.contains(&metrics.status_code);
+
+        path_matches && provider_matches && status_matches
+    }
+}
+
+/// Rules from `TELEMETRY_FILTERS`, e.g.
+/// `[{"paths": ["/health"]}, {"providers": ["groq"], "status_codes": [200], "sample_rate": 0.1}]`.
+/// Without the variable, health checks are the only requests left out.
+static RULES: Lazy<Vec<FilterRule>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("TELEMETRY_FILTERS") else {
+        return vec![FilterRule {
+            paths: vec!["/health".to_string()],
+            providers: Vec::new(),
+            status_codes: Vec::new(),
+            sample_rate: 0.0,
+        }];
+    };
+    match serde_json::from_str::<Vec<FilterRule>>(&raw) {
+        Ok(rules) => {
+            info!("Loaded {} telemetry filter rules", rules.len());
+            rules
+        }
+        Err(e) => {
+            error!("Failed to parse TELEMETRY_FILTERS, exporting all requests: {}", e);
+            Vec::new()
+        }
+    }
+});
+
+/// Whether a completed request should be sent to the exporters
+pub fn should_export(metrics: &RequestMetrics) -> bool {
+    let Some(rule) = RULES.iter().find(|rule| rule.matches(metrics)) else {
+        return true;
+    };
+    let keep = match rule.sample_rate {
+        rate if rate <= 0.0 => false,
+        rate if rate >= 1.0 => true,
+        rate => rand::thread_rng().gen_bool(rate),
+    };
+    if !keep {
+        debug!("Telemetry filter dropped {} {} ({})", metrics.method, metrics.path, metrics.provider);
+    }
+    keep
+}
@@ -0,0 +1,38 @@
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// Headers that carry client-supplied provider credentials
+const KEY_HEADERS: &[&str] = &["authorization", "x-api-key", "x-aws-access-key-id"];
+
+/// Salt mixed into every fingerprint; random per process unless configured
+static SALT: Lazy<String> = Lazy::new(|| {
+    std::env::var("KEY_FINGERPRINT_SALT")
+        .ok()
+        .filter(|salt| !salt.is_empty())
+        .unwrap_or_else(|| {
+            warn!("KEY_FINGERPRINT_SALT is not set; key fingerprints will change on restart");
+            uuid::Uuid::new_v4().to_string()
+        })
+});
+
+/// Salted, truncated SHA-256 of a provider key, safe to store alongside telemetry
+pub fn fingerprint_key(key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(SALT.as_bytes());
+    hasher.update(key.as_bytes());
+    let digest = hasher.finalize();
+
+    let hex: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    format!("kf_{}", hex)
+}
+
+/// Fingerprint of the provider key sent by the client, if any
+pub fn fingerprint_from_headers(headers: &HeaderMap) -> Option<String> {
+    KEY_HEADERS.iter().find_map(|&name| {
+        let value = headers.get(name)?.to_str().ok()?;
+        let key = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+        (!key.is_empty()).then(|| fingerprint_key(key))
+    })
+}
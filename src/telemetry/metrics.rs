@@ -1,17 +1,61 @@
 use super::RequestMetrics;
+use super::body_cap;
+use super::capture;
+use super::pricing;
+use super::scoring;
+use super::redact;
+use super::secrets;
+use super::filter;
+use super::stats::StatsStore;
+use super::tail::RequestTail;
+use crate::tenancy;
 use async_trait::async_trait;
+use once_cell::sync::Lazy;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, info_span, Instrument};
 
 #[async_trait]
 pub trait MetricsExporter: Send + Sync {
     async fn export_metrics(&self, metrics: RequestMetrics) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Initial record of a stream that is still running; its final record goes
+    /// through `export_metrics`. Exporters that only count completed requests ignore it.
+    async fn export_started(&self, _metrics: RequestMetrics) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    /// A completed request again, now carrying its quality score. Sent only after
+    /// the completed record's own export has finished.
+    async fn export_scored(&self, _metrics: RequestMetrics) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
     fn name(&self) -> &str;
 }
 
+/// Streams get an in-flight record when their headers arrive unless `STREAM_START_TELEMETRY=false`
+static STREAM_START_ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("STREAM_START_TELEMETRY")
+        .map(|v| v.parse().unwrap_or(true))
+        .unwrap_or(true)
+});
+
+/// Which export a record goes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Started,
+    Completed,
+    Scored,
+}
+
+type Exporters = Arc<RwLock<Vec<Box<dyn MetricsExporter>>>>;
+
 pub struct MetricsRegistry {
-    exporters: Arc<RwLock<Vec<Box<dyn MetricsExporter>>>>,
+    exporters: Exporters,
+    stats: Arc<StatsStore>,
+    tail: Arc<RequestTail>,
     debug_mode: bool,
 }
 
@@ -19,6 +63,8 @@ impl MetricsRegistry {
     pub fn new(debug_mode: bool) -> Self {
         Self {
             exporters: Arc::new(RwLock::new(Vec::new())),
+            stats: Arc::new(StatsStore::default()),
+            tail: Arc::new(RequestTail::default()),
             debug_mode,
         }
     }
@@ -29,34 +75,118 @@ impl MetricsRegistry {
         exporters.push(exporter);
     }
 
-    pub async fn record_metrics(&self, metrics: RequestMetrics) {
+    /// In-memory rolling aggregates of recently recorded requests
+    pub fn stats(&self) -> Arc<StatsStore> {
+        self.stats.clone()
+    }
+
+    /// Live feed of completed request summaries
+    pub fn tail(&self) -> Arc<RequestTail> {
+        self.tail.clone()
+    }
+
+    /// Export the initial record of a stream whose response headers just arrived.
+    /// It isn't counted in the stats, tail or tenant costs; the final record is.
+    pub async fn record_started(&self, mut metrics: RequestMetrics) {
+        if !*STREAM_START_ENABLED {
+            return;
+        }
+        Self::sanitize(&mut metrics);
+        if !filter::should_export(&metrics) {
+            return;
+        }
+        Self::export(&self.exporters, metrics, Phase::Started).await;
+    }
+
+    pub async fn record_metrics(&self, mut metrics: RequestMetrics) {
+        Self::sanitize(&mut metrics);
+        // Tenants with negotiated prices are charged and reported at those, not list prices
+        pricing::apply(&mut metrics);
+
         if self.debug_mode {
-            debug!("Request Metrics: {:#?}", metrics);
+            debug!("Request Metrics: {:#?}", redact::metrics(&metrics));
         }
 
+        self.stats.record(&metrics);
+        self.tail.publish(&metrics);
+
+        if let Some(tenant_id) = &metrics.tenant_id {
+            tenancy::settle(tenant_id, metrics.id.as_deref(), metrics.cost).await;
+        }
+
+        // Configured filters (health checks by default) keep noise out of every exporter
+        if !filter::should_export(&metrics) {
+            return;
+        }
+
+        // Sampled requests are scored in the background and their record updated with the score
+        let score = scoring::should_score(&metrics).then(|| metrics.clone());
+        let exports = Self::export(&self.exporters, metrics, Phase::Completed).await;
+        if let Some(mut metrics) = score {
+            let exporters = self.exporters.clone();
+            tokio::spawn(async move {
+                let Some(score) = scoring::evaluate(&metrics).await else {
+                    return;
+                };
+                // The score updates the completed record, so that record must land first
+                for export in exports {
+                    let _ = export.await;
+                }
+                metrics.score = Some(score.value);
+                metrics.score_reason = score.reason;
+                metrics.score_evaluator = Some(score.evaluator);
+                Self::export(&exporters, metrics, Phase::Scored).await;
+            });
+        }
+    }
+
+    /// Capture profile, secret scrubbing and size caps, applied before any consumer sees a record
+    fn sanitize(metrics: &mut RequestMetrics) {
+        // The project's capture profile decides what every consumer below gets to see
+        capture::apply(metrics);
+        // Base64 audio is replaced by its size before anything scans or stores it
+        body_cap::elide_audio(metrics);
+        // Prompts often carry pasted credentials; keep them out of every sink
+        secrets::apply(metrics);
+        // Multi-megabyte completions would otherwise become giant log documents
+        body_cap::apply(metrics);
+    }
+
+    async fn export(exporters: &Exporters, metrics: RequestMetrics, phase: Phase) -> Vec<JoinHandle<()>> {
         // First, get all exporter names to process
         let exporter_names = {
-            let exporters = self.exporters.read().await;
+            let exporters = exporters.read().await;
             exporters.iter().map(|e| e.name().to_string()).collect::<Vec<_>>()
         };
 
+        let export_span = info_span!("telemetry_export", exporters = exporter_names.len(), started = (phase == Phase::Started), scored = (phase == Phase::Scored));
+
         // Process each exporter by name, getting a fresh lock for each one
+        let mut handles = Vec::with_capacity(exporter_names.len());
         for name in exporter_names {
+            let span = info_span!(parent: &export_span, "export", exporter = %name);
             let metrics_clone = metrics.clone();
-            let self_clone = self.exporters.clone();
+            let self_clone = exporters.clone();
             
             // Process each exporter in its own task to avoid holding locks
-            tokio::spawn(async move {
+            let export = async move {
                 // Get a fresh lock on the exporters
                 let exporters = self_clone.read().await;
                 
                 // Find the exporter with this name, if it still exists
                 if let Some(exporter) = exporters.iter().find(|e| e.name() == name) {
-                    if let Err(e) = exporter.export_metrics(metrics_clone).await {
+                    let result = match phase {
+                        Phase::Started => exporter.export_started(metrics_clone).await,
+                        Phase::Completed => exporter.export_metrics(metrics_clone).await,
+                        Phase::Scored => exporter.export_scored(metrics_clone).await,
+                    };
+                    if let Err(e) = result {
                         error!("Failed to export metrics to {}: {}", name, e);
                     }
                 }
-            });
+            };
+            handles.push(tokio::spawn(export.instrument(span)));
         }
+        handles
     }
 } 
\ No newline at end of file
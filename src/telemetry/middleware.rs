@@ -1,35 +1,54 @@
 use super::metrics::MetricsRegistry;
 use super::provider_metrics::{get_metrics_extractor, ProviderMetrics, MetricsExtractor};
 use super::RequestMetrics;
+use crate::context::GatewayRequestContext;
+use crate::error::AppError;
+use crate::providers::aliases::ModelRewrite;
+use crate::providers::event_stream::EventStreamMessages;
+use crate::providers::reasoning::ReasoningUsage;
+use crate::providers::stream_events::{StreamEvent, StreamTap};
+use crate::proxy::{
+    canary::CanaryAssignment, raw_passthrough, scheduler::Priority, stream_buffer, stream_failover::StreamSplice, stream_retry::RetryChain,
+    ContextRemediation, Deduplicated,
+};
+use super::fingerprint::fingerprint_from_headers;
+use super::usage_headers;
+use super::request_metadata;
+use super::extraction;
+use super::error_class::{self, ErrorClass};
+use super::cost_meter::{self, CostMeter, MAX_COST_REASON};
+use super::stream_deadline::{self, MAX_STREAM_DURATION_REASON, TIMEOUT_ERROR_TYPE};
+use super::stream_interrupt::StreamFormat;
+use super::stream_moderation::{self, StreamModerator, MODERATION_REASON};
+use super::timing::{tokens_per_second, ChunkTimer, RequestStart, UpstreamTimings};
+use super::otel;
+use super::capture::{self, CaptureProfile};
+use super::fast_path;
+use super::batch_results;
 use axum::{
     body::{Body, Bytes},
     extract::State,
     http::{Request, Response},
     middleware::Next,
+    response::IntoResponse,
 };
 use futures_util::StreamExt;
 use std::{sync::Arc, time::{Instant, Duration}};
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
-use tracing::{debug, error};
+use tracing::{debug, error, Instrument};
 use axum::body::to_bytes;
 use hyper::Error;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use http;
 use uuid;
 
 // Constants for safeguards
-const CHANNEL_SIZE: usize = 1000; // Increased buffer for streaming response
 const MAX_ACCUMULATED_TEXT: usize = 5 * 1024 * 1024; // 5MB limit
 
 pub async fn metrics_middleware(
     State(registry): State<Arc<MetricsRegistry>>,
-    mut req: Request<Body>,
+    req: Request<Body>,
     next: Next,
 ) -> Response<Body> {
-    let start = Instant::now();
-
-    // Extract provider and other request info
     let provider = req
         .headers()
         .get("x-provider")
@@ -37,73 +56,83 @@ pub async fn metrics_middleware(
         .unwrap_or("openai")
         .to_string();
 
+    // Every phase below, including work spawned for streams and exports, nests under this span
+    let span = otel::request_span(&req, &provider);
+    let response = track_request(registry, provider, req, next).instrument(span.clone()).await;
+    otel::record_status(&span, response.status().as_u16());
+    response
+}
+
+async fn track_request(
+    registry: Arc<MetricsRegistry>,
+    provider: String,
+    req: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let start = Instant::now();
+
     let path = req.uri().path().to_string();
     let method = req.method().to_string();
 
-    // Extract project_id, org_id, and user_id from headers
-    let project_id = req
-        .headers()
-        .get("x-project-id")
-        .and_then(|h| h.to_str().ok())
-        .map(|s| s.to_string());
-        
-    let org_id = req
-        .headers()
-        .get("x-organization-id")
-        .or_else(|| req.headers().get("x-organisation-id"))  // Try both British and American spellings
-        .and_then(|h| h.to_str().ok())
-        .map(|s| s.to_string());
-        
-    let user_id = req
-        .headers()
-        .get("x-user-id")
-        .and_then(|h| h.to_str().ok())
-        .map(|s| s.to_string());
-        
-    let experiment_id = req
-        .headers()
-        .get("x-experiment-id")
-        .and_then(|h| h.to_str().ok())
-        .map(|s| s.to_string());
-        
-    // Use our new utility method to extract tracking headers
-    let tracking_headers = ProviderMetrics::extract_tracking_headers(req.headers());
-    
-    // Log the tracking headers at debug level for debugging
-    debug!(
-        "Tracking headers: project_id={:?}, organization_id={:?}, user_id={:?}, experiment_id={:?}",
-        tracking_headers.project_id, 
-        tracking_headers.organization_id, 
-        tracking_headers.user_id, 
-        tracking_headers.experiment_id
-    );
+    // Clients can ask for cost/usage annotations on the response
+    let annotate_usage = usage_headers::requested(req.headers());
+
+    // Streams can be capped at an estimated cost
+    let max_cost = cost_meter::requested_cap(req.headers());
+
+    // ...and at a total duration, per provider or per request
+    let max_stream_duration = stream_deadline::limit(&provider, req.headers());
+
+    // Events the gateway adds to a stream match its format. Streams that fail
+    // partway get a closing error event, except native ones from raw passthrough.
+    let raw = raw_passthrough::requested(req.headers());
+    let stream_format = StreamFormat::for_request(&provider.to_lowercase(), req.uri().path(), raw);
+    let close_broken_streams = !raw;
+
+    // Attribute usage to the client's own provider key without keeping the secret
+    let key_fingerprint = fingerprint_from_headers(req.headers());
 
     debug!("Received request: provider={}, path={}, method={}", provider, path, method);
 
     // Get metrics extractor for this provider
     let metrics_extractor = get_metrics_extractor(&provider);
 
-    // Store original values before consuming request
-    let original_method = req.method().clone();
-    let original_uri = req.uri().clone();
-    let original_headers = req.headers().clone();
-    
+    // Split the request so the original parts (including extensions such as
+    // ConnectInfo) survive the body being buffered
+    let (mut parts, body) = req.into_parts();
+
     // Extract and store the original request body
     let (req_size, req_body, body) = {
-        let bytes = to_bytes(req.into_body(), usize::MAX).await.unwrap_or_default();
+        // A body over the decompression limit is rejected rather than forwarded empty
+        let bytes = match to_bytes(body, usize::MAX).await {
+            Ok(bytes) => bytes,
+            Err(e) => return AppError::AxumError(e).into_response(),
+        };
         let size = bytes.len();
         let req_body = serde_json::from_slice(&bytes).ok();
         debug!("Request body size: {} bytes", size);
         (size, req_body, Body::from(bytes))
     };
 
-    // Reconstruct request with original values
-    let mut new_req = Request::builder()
-        .method(original_method)
-        .uri(original_uri)
-        .body(body)
-        .unwrap();
-    *new_req.headers_mut() = original_headers;
+    // A cap the gateway can't price would never be enforced, so it is refused up front
+    if max_cost.is_some() && !cost_meter::can_price(metrics_extractor.as_ref(), req_body.as_ref()) {
+        return AppError::RequestError(format!(
+            "x-max-cost is not supported for this {} model: its cost can't be estimated",
+            provider
+        ))
+        .into_response();
+    }
+
+    // Caller-defined tags for the request log
+    let metadata = request_metadata::extract(&parts.headers, req_body.as_ref());
+    let custom_attributes = extraction::extract(req_body.as_ref());
+
+    // Kept to identify responses that never reach the handler
+    let request_headers = parts.headers.clone();
+
+    // Let the proxy measure how long the request waited before being dispatched
+    parts.extensions.insert(RequestStart(start));
+    let new_req = Request::from_parts(parts, body);
 
     // Process the response with a timeout
     let response = tokio::time::timeout(
@@ -121,15 +150,23 @@ pub async fn metrics_middleware(
         .headers()
         .get("content-type")
         .and_then(|v| v.to_str().ok())
-        .map(|v| v.contains("text/event-stream"))
+        .map(|v| v.contains("text/event-stream") || v.contains("application/vnd.amazon.eventstream"))
         .unwrap_or(false);
 
     debug!("Response is streaming: {}", is_streaming);
 
+    // Identity resolved by the handler; responses that never reached it (e.g. injected faults) get a fresh one
+    let context = response
+        .extensions()
+        .get::<GatewayRequestContext>()
+        .cloned()
+        .unwrap_or_else(|| GatewayRequestContext::new(&provider, &request_headers));
+
     if is_streaming {
         handle_streaming_response(
             response,
             registry,
+            context,
             provider,
             path,
             method,
@@ -137,16 +174,21 @@ pub async fn metrics_middleware(
             req_body,
             start,
             metrics_extractor,
-            project_id,
-            org_id,
-            user_id,
-            experiment_id,
+            key_fingerprint,
+            metadata,
+            custom_attributes,
+            annotate_usage,
+            max_cost,
+            max_stream_duration,
+            stream_format,
+            close_broken_streams,
         )
         .await
     } else {
         handle_regular_response(
             response,
             registry,
+            context,
             provider,
             path,
             method,
@@ -154,10 +196,10 @@ pub async fn metrics_middleware(
             req_body,
             start,
             metrics_extractor,
-            project_id,
-            org_id,
-            user_id,
-            experiment_id,
+            key_fingerprint,
+            metadata,
+            custom_attributes,
+            annotate_usage,
         )
         .await
     }
@@ -166,6 +208,7 @@ pub async fn metrics_middleware(
 async fn handle_regular_response(
     response: Response<Body>,
     registry: Arc<MetricsRegistry>,
+    context: GatewayRequestContext,
     provider: String,
     path: String,
     method: String,
@@ -173,17 +216,46 @@ async fn handle_regular_response(
     req_body: Option<Value>,
     start: Instant,
     metrics_extractor: Box<dyn MetricsExtractor>,
-    project_id: Option<String>,
-    org_id: Option<String>,
-    user_id: Option<String>,
-    experiment_id: Option<String>,
+    key_fingerprint: Option<String>,
+    request_metadata: Option<Map<String, Value>>,
+    custom_attributes: Option<Map<String, Value>>,
+    annotate_usage: bool,
 ) -> Response<Body> {
     // Time to first byte is essentially the time taken to get the response headers
     let ttfb = start.elapsed();
     debug!("Time to first byte (TTFB): {:?}", ttfb);
+    let GatewayRequestContext { request_id, thread_id, message_id, project_id, org_id, user_id, experiment_id, experiment_variant, tenant_id, .. } = context;
+
+    let (mut parts, body) = response.into_parts();
+    // Only responses relayed from the provider carry upstream timings
+    let from_provider = parts.extensions.get::<UpstreamTimings>().is_some();
+    let upstream_timings = parts.extensions.get::<UpstreamTimings>().copied().unwrap_or_default();
+    let model_rewrite = parts.extensions.get::<ModelRewrite>().cloned();
+    let remediation = parts.extensions.get::<ContextRemediation>().map(ToString::to_string);
+    let canary = parts.extensions.get::<CanaryAssignment>().map(CanaryAssignment::label);
+    let priority = parts.extensions.get::<Priority>().copied();
+    // Coalesced duplicates didn't reach the provider, so they carry no cost
+    let deduplicated = parts.extensions.get::<Deduplicated>().is_some();
+    let reasoning = parts.extensions.get::<ReasoningUsage>().cloned();
+    let content_type = parts
+        .headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
 
-    let (parts, body) = response.into_parts();
-    let bytes = to_bytes(body, usize::MAX).await.unwrap_or_default();
+    // Message Batch results are buffered whatever their content type, to record each item
+    let batch_id = batch_results::results_batch_id(&provider, &path)
+        .filter(|_| parts.status.is_success())
+        .map(String::from);
+
+    // Audio, images and other binary payloads go straight through instead of being buffered
+    let binary = content_type.as_deref().is_some_and(is_binary_content_type);
+    let (bytes, passthrough) = if binary && batch_id.is_none() {
+        debug!("Passing through binary response body: {:?}", content_type);
+        (Bytes::new(), Some(body))
+    } else {
+        (to_bytes(body, usize::MAX).await.unwrap_or_default(), None)
+    };
     let resp_size = bytes.len();
 
     debug!("Regular response body size: {} bytes", resp_size);
@@ -199,47 +271,177 @@ async fn handle_regular_response(
     }
 
     // Extract metrics from response body
-    let (provider_metrics, resp_body) = if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&bytes) {
-        (metrics_extractor.extract_metrics(&json), Some(json))
+    let parsed = if passthrough.is_none() {
+        serde_json::from_slice::<serde_json::Value>(&bytes).ok()
     } else {
-        (ProviderMetrics::default(), None)
+        None
+    };
+    let (mut provider_metrics, resp_body) = match parsed {
+        Some(json) => (metrics_extractor.extract_metrics(&json), Some(json)),
+        None => (ProviderMetrics::default(), None),
     };
 
+    // Audio and image endpoints are billed per character or image rather than by usage
+    if provider_metrics.cost.is_none() && parts.status.is_success() {
+        if let Some(metrics) = metrics_extractor.extract_request_metrics(&path, req_body.as_ref(), &parts.headers) {
+            provider_metrics = metrics;
+        }
+    }
+
     debug!("Extracted provider metrics: {:?}", provider_metrics);
 
+    let status_code = parts.status.as_u16();
+    let error_type = error_class::classify(status_code, resp_body.as_ref()).map(|class| class.as_str().to_string());
+    if let Some(error_type) = &error_type {
+        debug!("Classified {} response as {} error (from provider: {})", status_code, error_type, from_provider);
+    }
+
+    let total_latency = start.elapsed();
     let metrics = RequestMetrics {
         provider,
         path,
         method,
         model: provider_metrics.model,
-        total_latency: start.elapsed(),
+        original_model: model_rewrite.as_ref().map(|r| r.original.clone()),
+        rewritten_model: model_rewrite.map(|r| r.rewritten),
+        total_latency,
         provider_latency: provider_metrics.provider_latency,
         ttfb,  // Add the TTFB measurement
+        queue_time: upstream_timings.queue_time,
+        scheduler_wait: upstream_timings.scheduler_wait,
+        priority: priority.map(|p| p.as_str().to_string()),
+        provider_ttfb: upstream_timings.provider_ttfb,
+        tokens_per_second: tokens_per_second(
+            provider_metrics.output_tokens,
+            total_latency.saturating_sub(upstream_timings.queue_time),
+        ),
         request_size: req_size,
         response_size: resp_size,
         input_tokens: provider_metrics.input_tokens,
         output_tokens: provider_metrics.output_tokens,
         total_tokens: provider_metrics.total_tokens,
-        status_code: parts.status.as_u16(),
-        cost: provider_metrics.cost,
+        cached_tokens: provider_metrics.cached_tokens,
+        audio_input_tokens: provider_metrics.audio_input_tokens,
+        audio_output_tokens: provider_metrics.audio_output_tokens,
+        accepted_prediction_tokens: provider_metrics.accepted_prediction_tokens,
+        rejected_prediction_tokens: provider_metrics.rejected_prediction_tokens,
+        rejected_prediction_cost: provider_metrics.rejected_prediction_cost,
+        service_tier: provider_metrics.service_tier,
+        reasoning_tokens: provider_metrics
+            .reasoning_tokens
+            .or_else(|| reasoning.as_ref().and_then(ReasoningUsage::tokens)),
+        audio_seconds: provider_metrics.audio_seconds,
+        characters: provider_metrics.characters,
+        guardrail_action: provider_metrics.guardrail_action,
+        job_id: provider_metrics.job_id,
+        job_status: provider_metrics.job_status,
+        content_type: passthrough.as_ref().and(content_type),
+        remediation,
+        canary,
+        status_code,
+        provider_status_code: if from_provider { status_code } else { 0 },
+        error_count: u32::from(error_type.is_some()),
+        provider_error_count: u32::from(from_provider && error_type.is_some()),
+        provider_error_type: error_type.clone().filter(|_| from_provider),
+        error_type,
+        cost: if deduplicated { Some(0.0) } else { provider_metrics.cost },
         project_id: project_id.or(provider_metrics.project_id),
         org_id: org_id.or(provider_metrics.organization_id),
         user_id: user_id.or(provider_metrics.user_id),
         experiment_id: experiment_id.or(provider_metrics.experiment_id),
+        experiment_variant,
+        tenant_id,
+        key_fingerprint,
+        request_metadata,
+        custom_attributes,
         provider_request_id,
+        id: Some(request_id),
+        thread_id,
+        message_id,
         request_body: req_body,
         response_body: resp_body,
         ..Default::default()
     };
 
+    if annotate_usage {
+        usage_headers::apply(&mut parts.headers, &metrics);
+    }
+
+    if let Some(body) = passthrough {
+        return Response::from_parts(parts, forward_binary_body(body, metrics, registry, start));
+    }
+
+    let items = match batch_id.as_deref() {
+        Some(batch_id) => batch_results::item_metrics(batch_id, &metrics, &bytes, metrics_extractor.as_ref()).await,
+        None => Vec::new(),
+    };
     registry.record_metrics(metrics).await;
+    for item in items {
+        registry.record_metrics(item).await;
+    }
 
     Response::from_parts(parts, Body::from(bytes))
 }
 
+/// Stream a binary body to the client untouched, recording its metrics once the size is known
+fn forward_binary_body(
+    body: Body,
+    mut metrics: RequestMetrics,
+    registry: Arc<MetricsRegistry>,
+    start: Instant,
+) -> Body {
+    let (tx, rx) = stream_buffer::channel::<axum::Error>();
+
+    let relay = async move {
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let failed = chunk.is_err();
+            if let Ok(bytes) = &chunk {
+                metrics.response_size += bytes.len();
+            }
+            if let Err(e) = &chunk {
+                error!("Error in binary response body: {}", e);
+            }
+            if tx.send(chunk).await.is_err() {
+                debug!("Client disconnected during binary response");
+                break;
+            }
+            if failed {
+                break;
+            }
+        }
+
+        metrics.total_latency = start.elapsed();
+        debug!("Binary response completed: {} bytes", metrics.response_size);
+        registry.record_metrics(metrics).await;
+    };
+    // Runs until the client has the whole body, so the span covers the stream
+    tokio::spawn(relay.instrument(tracing::info_span!("stream")));
+
+    Body::from_stream(rx)
+}
+
+/// Content types that are forwarded as opaque bytes rather than parsed as JSON or text
+fn is_binary_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("audio/")
+        || mime.starts_with("image/")
+        || mime.starts_with("video/")
+        || matches!(
+            mime.as_str(),
+            "application/octet-stream" | "application/pdf" | "application/zip" | "application/gzip"
+        )
+}
+
 async fn handle_streaming_response(
     response: Response<Body>,
     registry: Arc<MetricsRegistry>,
+    context: GatewayRequestContext,
     provider: String,
     path: String,
     method: String,
@@ -247,17 +449,34 @@ async fn handle_streaming_response(
     req_body: Option<Value>,
     start: Instant,
     metrics_extractor: Box<dyn MetricsExtractor>,
-    project_id: Option<String>,
-    org_id: Option<String>,
-    user_id: Option<String>,
-    experiment_id: Option<String>,
+    key_fingerprint: Option<String>,
+    request_metadata: Option<Map<String, Value>>,
+    custom_attributes: Option<Map<String, Value>>,
+    annotate_usage: bool,
+    max_cost: Option<f64>,
+    max_stream_duration: Option<Duration>,
+    stream_format: StreamFormat,
+    close_broken_streams: bool,
 ) -> Response<Body> {
     // Time to first byte is essentially the time taken to get the response headers
     let ttfb = start.elapsed();
     debug!("Time to first byte for streaming response (TTFB): {:?}", ttfb);
+    let GatewayRequestContext { request_id, thread_id, message_id, project_id, org_id, user_id, experiment_id, experiment_variant, tenant_id, .. } = context;
 
+    let stream_start = Instant::now();
     let (parts, body) = response.into_parts();
-    let (tx, rx) = mpsc::channel::<Result<Bytes, Error>>(CHANNEL_SIZE);
+    let from_provider = parts.extensions.get::<UpstreamTimings>().is_some();
+    let upstream_timings = parts.extensions.get::<UpstreamTimings>().copied().unwrap_or_default();
+    let model_rewrite = parts.extensions.get::<ModelRewrite>().cloned();
+    let remediation = parts.extensions.get::<ContextRemediation>().map(ToString::to_string);
+    let canary = parts.extensions.get::<CanaryAssignment>().map(CanaryAssignment::label);
+    let splice = parts.extensions.get::<StreamSplice>().cloned();
+    let retry_chain = parts.extensions.get::<RetryChain>().map(|RetryChain(chain)| chain.clone());
+    let priority = parts.extensions.get::<Priority>().copied();
+    let reasoning = parts.extensions.get::<ReasoningUsage>().cloned();
+    // Events from providers that already parsed the stream, so it isn't parsed twice
+    let mut stream_events = parts.extensions.get::<StreamTap>().and_then(StreamTap::take);
+    let (tx, rx) = stream_buffer::channel::<Error>();
 
     // Extract provider request ID from response headers
     let provider_request_id = parts.headers.get("x-request-id")
@@ -269,24 +488,180 @@ async fn handle_streaming_response(
         debug!("Provider request ID for streaming response: {}", id);
     }
 
+    let status_code = parts.status.as_u16();
+
+    // First phase: the stream is visible as in flight until its final record replaces it
+    let started = RequestMetrics {
+        provider: provider.clone(),
+        path: path.clone(),
+        method: method.clone(),
+        model: model_rewrite
+            .as_ref()
+            .map(|r| r.rewritten.clone())
+            .or_else(|| req_body.as_ref().and_then(|b| b.get("model")).and_then(Value::as_str).map(String::from))
+            .unwrap_or_default(),
+        original_model: model_rewrite.as_ref().map(|r| r.original.clone()),
+        rewritten_model: model_rewrite.as_ref().map(|r| r.rewritten.clone()),
+        total_latency: ttfb,
+        ttfb,
+        queue_time: upstream_timings.queue_time,
+        scheduler_wait: upstream_timings.scheduler_wait,
+        priority: priority.map(|p| p.as_str().to_string()),
+        provider_ttfb: upstream_timings.provider_ttfb,
+        request_size: req_size,
+        status_code,
+        provider_status_code: if from_provider { status_code } else { 0 },
+        canary: canary.clone(),
+        retry_chain: retry_chain.clone(),
+        project_id: project_id.clone(),
+        org_id: org_id.clone(),
+        user_id: user_id.clone(),
+        experiment_id: experiment_id.clone(),
+        experiment_variant: experiment_variant.clone(),
+        tenant_id: tenant_id.clone(),
+        key_fingerprint: key_fingerprint.clone(),
+        request_metadata: request_metadata.clone(),
+        custom_attributes: custom_attributes.clone(),
+        provider_request_id: provider_request_id.clone(),
+        id: Some(request_id.clone()),
+        thread_id: thread_id.clone(),
+        message_id: message_id.clone(),
+        request_body: req_body.clone(),
+        is_streaming: true,
+        in_flight: true,
+        ..Default::default()
+    };
+    registry.record_started(started.clone()).await;
+
+    let close_broken_streams = close_broken_streams.then_some(stream_format);
+    let mut moderator = StreamModerator::for_request(project_id.as_deref(), req_body.as_ref(), stream_format);
+
+    // Nothing needs the body: no translated events, caps, annotations, moderation,
+    // failover or captured bodies, so the upstream bytes go to the client as they are.
+    // Native Bedrock event streams carry their usage in binary frames only the relay decodes.
+    let body_unused = stream_events.is_none()
+        && stream_format != StreamFormat::Bedrock
+        && splice.is_none()
+        && max_cost.is_none()
+        && max_stream_duration.is_none()
+        && !annotate_usage
+        && moderator.is_none()
+        && capture::profile_for(project_id.as_deref()) == CaptureProfile::MetadataOnly;
+    if fast_path::enabled() && body_unused {
+        debug!("Forwarding stream on the zero-copy path");
+        let metrics = RequestMetrics { in_flight: false, ..started };
+        let body = fast_path::forward(
+            body.into_data_stream(),
+            metrics,
+            metrics_extractor,
+            registry,
+            start,
+            stream_start,
+            close_broken_streams,
+        );
+        return Response::from_parts(parts, Body::from_stream(body));
+    }
+
     let metrics_registry = registry.clone();
     let mut accumulated_text = String::with_capacity(MAX_ACCUMULATED_TEXT);
 
     // Process the stream
-    tokio::spawn(async move {
+    let relay = async move {
         let mut response_size = 0;
         let mut accumulated_metrics = ProviderMetrics::default();
         let mut final_metrics_found = false;
         let mut resp_body = None;
         let mut streamed_chunks = Vec::new();
+        let mut chunk_timer = ChunkTimer::default();
+        let mut cost_meter = CostMeter::new(max_cost, req_body.as_ref());
+        let mut enforcement_reason = None;
+        let mut timed_out = false;
+        let mut done_seen = false;
+        let mut interrupted = false;
+        let mut violation = None;
+        // Trailing bytes of a UTF-8 character split across chunks
+        let mut pending_utf8 = Vec::new();
+        // Native Bedrock streams are binary event stream messages rather than text
+        let mut event_stream = (stream_format == StreamFormat::Bedrock).then(EventStreamMessages::default);
 
         let mut stream = body.into_data_stream();
-        while let Some(chunk) = stream.next().await {
-            if let Ok(bytes) = chunk {
+        loop {
+            let next = match max_stream_duration {
+                Some(limit) => match tokio::time::timeout_at((stream_start + limit).into(), stream.next()).await {
+                    Ok(next) => next,
+                    // Ran past the duration cap: end the stream with an error event and drop the upstream
+                    Err(_) => {
+                        if let Err(e) = tx.send(Ok(stream_deadline::timeout_event(limit))).await {
+                            debug!("Client disconnected before the stream timeout event was sent: {}", e);
+                        }
+                        enforcement_reason = Some(MAX_STREAM_DURATION_REASON.to_string());
+                        timed_out = true;
+                        if !final_metrics_found {
+                            accumulated_metrics = cost_meter.metrics();
+                            final_metrics_found = true;
+                        }
+                        break;
+                    }
+                },
+                None => stream.next().await,
+            };
+            let Some(chunk) = next else {
+                break;
+            };
+
+            if let Ok(mut bytes) = chunk {
+                let received_at = Instant::now();
+                chunk_timer.record_chunk(received_at);
                 response_size += bytes.len();
                 debug!("Streaming response chunk size: {} bytes", bytes.len());
 
-                if let Ok(chunk_str) = String::from_utf8(bytes.to_vec()) {
+                if let Some(events) = stream_events.as_mut() {
+                    // Each event is queued before its bytes are yielded, so these match this chunk
+                    while let Ok(event) = events.try_recv() {
+                        let StreamEvent::Chunk(chunk) = event else {
+                            debug!("Received [DONE] signal in streaming");
+                            done_seen = true;
+                            continue;
+                        };
+                        if has_generated_content(&chunk) {
+                            chunk_timer.record_token(received_at);
+                        }
+                        cost_meter.record(&chunk, metrics_extractor.as_ref());
+                        if let Some(found) = moderator.as_mut().and_then(|m| m.record(&chunk)) {
+                            violation = Some(found);
+                            break;
+                        }
+                        if let Some(chunk_metrics) = metrics_extractor.extract_streaming_event_metrics(&chunk) {
+                            debug!("Found metrics in streaming chunk: {:?}", chunk_metrics);
+                            if reports_usage(&chunk_metrics) || !reports_usage(&accumulated_metrics) {
+                                accumulated_metrics = chunk_metrics;
+                            }
+                            final_metrics_found = true;
+                        }
+                        streamed_chunks.push(Arc::try_unwrap(chunk).unwrap_or_else(|chunk| (*chunk).clone()));
+                    }
+                } else if let Some(messages) = event_stream.as_mut() {
+                    // Only whole messages go on to the client, so a stop event never follows half of one
+                    let (complete, payloads) = messages.push(&bytes);
+                    bytes = complete;
+                    for payload in payloads {
+                        if has_generated_content(&payload) {
+                            chunk_timer.record_token(received_at);
+                        }
+                        cost_meter.record(&payload, metrics_extractor.as_ref());
+                        if let Some(found) = moderator.as_mut().and_then(|m| m.record(&payload)) {
+                            violation = Some(found);
+                            break;
+                        }
+                        if let Some(chunk_metrics) = metrics_extractor.extract_streaming_event_metrics(&payload) {
+                            if reports_usage(&chunk_metrics) || !reports_usage(&accumulated_metrics) {
+                                accumulated_metrics = chunk_metrics;
+                            }
+                            final_metrics_found = true;
+                        }
+                        streamed_chunks.push(payload);
+                    }
+                } else if let Some(chunk_str) = decode_utf8_chunk(&mut pending_utf8, &bytes) {
                     if accumulated_text.len() + chunk_str.len() > MAX_ACCUMULATED_TEXT {
                         error!("Accumulated text exceeded maximum size of {} bytes", MAX_ACCUMULATED_TEXT);
                         break;
@@ -297,7 +672,14 @@ async fn handle_streaming_response(
                     if let Ok(json_chunk) = serde_json::from_str::<Value>(&chunk_str) {
                         // Only store non-empty chunks
                         if !json_chunk.is_null() && !json_chunk.as_object().map_or(true, |o| o.is_empty()) {
-                            streamed_chunks.push(json_chunk);
+                            if has_generated_content(&json_chunk) {
+                                chunk_timer.record_token(received_at);
+                            }
+                            cost_meter.record(&json_chunk, metrics_extractor.as_ref());
+                            violation = moderator.as_mut().and_then(|m| m.record(&json_chunk));
+                            if violation.is_none() {
+                                streamed_chunks.push(json_chunk);
+                            }
                         }
                     } else {
                         // For streaming that sends chunks broken up, try to parse 
@@ -307,16 +689,28 @@ async fn handle_streaming_response(
                                 let data = line.trim_start_matches("data: ");
                                 if data == "[DONE]" {
                                     debug!("Received [DONE] signal in streaming");
+                                    done_seen = true;
                                     continue;
                                 }
                                 
                                 if let Ok(json_data) = serde_json::from_str::<Value>(data) {
+                                    if has_generated_content(&json_data) {
+                                        chunk_timer.record_token(received_at);
+                                    }
+                                    cost_meter.record(&json_data, metrics_extractor.as_ref());
+                                    if let Some(found) = moderator.as_mut().and_then(|m| m.record(&json_data)) {
+                                        violation = Some(found);
+                                        break;
+                                    }
                                     streamed_chunks.push(json_data.clone());
                                     
                                     // Try to extract metrics from this chunk
                                     if let Some(chunk_metrics) = metrics_extractor.extract_streaming_metrics(data) {
                                         debug!("Found metrics in streaming chunk: {:?}", chunk_metrics);
-                                        accumulated_metrics = chunk_metrics;
+                                        // Chunks after the usage chunk mustn't replace its token counts
+                                        if reports_usage(&chunk_metrics) || !reports_usage(&accumulated_metrics) {
+                                            accumulated_metrics = chunk_metrics;
+                                        }
                                         final_metrics_found = true;
                                     }
                                 }
@@ -325,19 +719,79 @@ async fn handle_streaming_response(
                     }
                 }
                 
-                // Always forward the bytes to the client
+                // A moderation rule matched: withhold this chunk, end the stream and drop the upstream
+                if let (Some(found), Some(moderator)) = (&violation, &moderator) {
+                    if let Err(e) = tx.send(Ok(moderator.stop_events(found))).await {
+                        debug!("Client disconnected before the moderation stop events were sent: {}", e);
+                    }
+                    enforcement_reason = Some(MODERATION_REASON.to_string());
+                    done_seen = true;
+                    if !final_metrics_found {
+                        accumulated_metrics = cost_meter.metrics();
+                        final_metrics_found = true;
+                    }
+                    break;
+                }
+
+                // Otherwise forward the bytes to the client
                 if let Err(e) = tx.send(Ok(bytes)).await {
                     error!("Failed to forward streaming chunk: {}", e);
                     break;
                 }
+
+                // Over the x-max-cost cap: finish the stream ourselves and drop the upstream
+                if cost_meter.exceeded() {
+                    if let Err(e) = tx.send(Ok(cost_meter.stop_chunk())).await {
+                        debug!("Client disconnected before the cost cap stop chunk was sent: {}", e);
+                    }
+                    enforcement_reason = Some(MAX_COST_REASON.to_string());
+                    done_seen = true;
+                    if !final_metrics_found {
+                        accumulated_metrics = cost_meter.metrics();
+                        final_metrics_found = true;
+                    }
+                    break;
+                }
             } else if let Err(e) = chunk {
                 error!("Error in streaming response: {}", e);
-                // For type compatibility, we'll just break the stream instead of trying to send the error
-                // This avoids issues with error type conversions
+                interrupted = true;
+                // End the stream with an error event rather than dropping the connection
+                if let Some(format) = close_broken_streams {
+                    if let Err(e) = tx.send(Ok(format.error_event(&e.to_string()))).await {
+                        debug!("Client disconnected before the interruption event was sent: {}", e);
+                    }
+                }
                 break;
             }
         }
 
+        // Providers report failures mid-stream as error events
+        let stream_error = streamed_chunks
+            .iter()
+            .find_map(|chunk| error_class::classify(status_code, Some(chunk)));
+        // ...and often close the stream right after without `[DONE]`
+        if stream_error.is_some() && !done_seen && !interrupted && !timed_out {
+            interrupted = true;
+            if let Some(done) = close_broken_streams.and_then(StreamFormat::done_event) {
+                if let Err(e) = tx.send(Ok(done)).await {
+                    debug!("Client disconnected before [DONE] was sent: {}", e);
+                }
+            }
+        }
+        let error_type = if timed_out {
+            Some(TIMEOUT_ERROR_TYPE.to_string())
+        } else if violation.is_some() {
+            Some(ErrorClass::ContentFilter.as_str().to_string())
+        } else if let Some(class) = stream_error {
+            Some(class.as_str().to_string())
+        } else {
+            interrupted.then(|| ErrorClass::Network.as_str().to_string())
+        };
+        if (stream_error.is_some() || interrupted) && !final_metrics_found {
+            accumulated_metrics = cost_meter.metrics();
+            final_metrics_found = true;
+        }
+
         // Try to parse the accumulated response
         if !accumulated_text.is_empty() {
             resp_body = serde_json::from_str(&accumulated_text).ok();
@@ -382,6 +836,23 @@ async fn handle_streaming_response(
             final_metrics_found = true;
         }
 
+        let stream_end = Instant::now();
+        let stream_duration = stream_end.saturating_duration_since(stream_start);
+        debug!("Streaming response completed in {:?}", stream_duration);
+
+        // Throughput is measured over the generation window, i.e. after the first token
+        let first_token_latency = chunk_timer.first_token().map(|at| at.saturating_duration_since(start));
+        let generation_time = chunk_timer
+            .first_token()
+            .map(|at| stream_end.saturating_duration_since(at))
+            .unwrap_or(stream_duration);
+        debug!(
+            "Streaming timings: first_token={:?}, mean_gap={:?}, max_gap={:?}",
+            first_token_latency,
+            chunk_timer.mean_gap(),
+            chunk_timer.max_gap()
+        );
+
         // Record final metrics if we found them
         if final_metrics_found {
             let metrics = RequestMetrics {
@@ -389,32 +860,146 @@ async fn handle_streaming_response(
                 path,
                 method,
                 model: accumulated_metrics.model,
+                original_model: model_rewrite.as_ref().map(|r| r.original.clone()),
+                rewritten_model: model_rewrite.map(|r| r.rewritten),
                 total_latency: start.elapsed(),
                 provider_latency: accumulated_metrics.provider_latency,
                 ttfb,  // Add the TTFB measurement
+                queue_time: upstream_timings.queue_time,
+                scheduler_wait: upstream_timings.scheduler_wait,
+                priority: priority.map(|p| p.as_str().to_string()),
+                provider_ttfb: upstream_timings.provider_ttfb,
+                stream_duration,
+                tokens_per_second: tokens_per_second(accumulated_metrics.output_tokens, generation_time),
+                first_token_latency,
+                mean_inter_chunk_latency: chunk_timer.mean_gap(),
+                max_inter_chunk_latency: chunk_timer.max_gap(),
                 request_size: req_size,
                 response_size,
                 input_tokens: accumulated_metrics.input_tokens,
                 output_tokens: accumulated_metrics.output_tokens,
                 total_tokens: accumulated_metrics.total_tokens,
-                status_code: parts.status.as_u16(),
+                cached_tokens: accumulated_metrics.cached_tokens,
+                audio_input_tokens: accumulated_metrics.audio_input_tokens,
+                audio_output_tokens: accumulated_metrics.audio_output_tokens,
+                accepted_prediction_tokens: accumulated_metrics.accepted_prediction_tokens,
+                rejected_prediction_tokens: accumulated_metrics.rejected_prediction_tokens,
+                rejected_prediction_cost: accumulated_metrics.rejected_prediction_cost,
+                service_tier: accumulated_metrics.service_tier,
+                reasoning_tokens: accumulated_metrics
+                    .reasoning_tokens
+                    .or_else(|| reasoning.as_ref().and_then(ReasoningUsage::tokens)),
+                audio_seconds: accumulated_metrics.audio_seconds,
+                characters: accumulated_metrics.characters,
+                guardrail_action: violation
+                    .as_ref()
+                    .map(|_| stream_moderation::INTERVENED.to_string())
+                    .or(accumulated_metrics.guardrail_action),
+                enforcement_reason,
+                remediation,
+                canary,
+                stream_failover: splice.as_ref().and_then(StreamSplice::get),
+                retry_chain,
+                stream_interrupted: interrupted,
+                moderation_rule: violation.as_ref().map(|v| v.rule.clone()),
+                moderation_span: violation.map(|v| v.span),
+                error_count: u32::from(error_type.is_some()),
+                provider_status_code: if from_provider { status_code } else { 0 },
+                provider_error_count: u32::from(from_provider && stream_error.is_some()),
+                provider_error_type: stream_error.filter(|_| from_provider).map(|class| class.as_str().to_string()),
+                error_type,
+                status_code,
                 cost: accumulated_metrics.cost,
                 project_id: project_id.or(accumulated_metrics.project_id),
                 org_id: org_id.or(accumulated_metrics.organization_id),
                 user_id: user_id.or(accumulated_metrics.user_id),
                 experiment_id: experiment_id.or(accumulated_metrics.experiment_id),
+                experiment_variant,
+                tenant_id,
+                key_fingerprint,
+                request_metadata,
+                custom_attributes,
                 provider_request_id,
+                id: Some(request_id),
+                thread_id,
+                message_id,
                 request_body: req_body,
                 response_body: resp_body,
                 streamed_data: if !streamed_chunks.is_empty() { Some(streamed_chunks) } else { None },
                 is_streaming: true,
                 ..Default::default()
             };
+
+            // Headers are already sent, so usage goes out as a trailing SSE comment
+            if annotate_usage {
+                if let Err(e) = tx.send(Ok(usage_headers::sse_comment(&metrics))).await {
+                    debug!("Client disconnected before usage annotations were sent: {}", e);
+                }
+            }
+
             metrics_registry.record_metrics(metrics).await;
         } else {
             debug!("No final metrics found in streaming response. Total text accumulated: {} bytes", accumulated_text.len());
         }
-    });
+    };
+    // Runs until the client has the whole body, so the span covers the stream
+    tokio::spawn(relay.instrument(tracing::info_span!("stream")));
+
+    Response::from_parts(parts, Body::from_stream(rx))
+}
+
+/// Decode a streamed chunk as text, carrying an incomplete trailing UTF-8 sequence into the next chunk.
+///
+/// Invalid bytes are replaced rather than dropping the whole chunk from telemetry.
+fn decode_utf8_chunk(pending: &mut Vec<u8>, bytes: &[u8]) -> Option<String> {
+    pending.extend_from_slice(bytes);
+    let valid_up_to = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        // Only the tail is incomplete; hold it back for the next chunk
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => {
+            debug!("Streaming chunk contains invalid UTF-8; decoding lossily");
+            let text = String::from_utf8_lossy(pending).into_owned();
+            pending.clear();
+            return Some(text);
+        }
+    };
+    if valid_up_to == 0 {
+        return None;
+    }
+    let rest = pending.split_off(valid_up_to);
+    String::from_utf8(std::mem::replace(pending, rest)).ok()
+}
+
+/// Whether streaming metrics carry the provider's token counts rather than
+/// just the model seen on a chunk
+pub(super) fn reports_usage(metrics: &ProviderMetrics) -> bool {
+    metrics.input_tokens.is_some() || metrics.output_tokens.is_some()
+}
+
+/// Whether a streamed chunk carries generated output (text or tool call deltas)
+fn has_generated_content(chunk: &Value) -> bool {
+    let choice_has_content = chunk
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .map(|choices| {
+            choices.iter().any(|choice| {
+                let delta = choice.get("delta");
+                delta
+                    .and_then(|d| d.get("content"))
+                    .and_then(|c| c.as_str())
+                    .map_or(false, |c| !c.is_empty())
+                    || delta.and_then(|d| d.get("tool_calls")).is_some()
+            })
+        })
+        .unwrap_or(false);
+
+    // Anthropic native stream events carry text in delta.text
+    let anthropic_has_content = chunk
+        .get("delta")
+        .and_then(|d| d.get("text"))
+        .and_then(|t| t.as_str())
+        .map_or(false, |t| !t.is_empty());
 
-    Response::from_parts(parts, Body::from_stream(ReceiverStream::new(rx)))
+    choice_has_content || anthropic_has_content
 }
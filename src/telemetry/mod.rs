@@ -1,8 +1,32 @@
+pub mod anomaly;
+pub mod batch_results;
+pub mod body_cap;
+pub mod capture;
+pub mod cost_meter;
+pub mod error_class;
 pub mod exporters;
+pub mod extraction;
+pub mod fast_path;
+pub mod filter;
 pub mod metrics;
 pub mod plugins;
+pub mod pricing;
 pub mod middleware;
+pub mod otel;
 pub mod provider_metrics;
+pub mod redact;
+pub mod request_metadata;
+pub mod schema;
+pub mod scoring;
+pub mod secrets;
+pub mod fingerprint;
+pub mod stats;
+pub mod stream_deadline;
+pub mod stream_interrupt;
+pub mod stream_moderation;
+pub mod tail;
+pub mod timing;
+pub mod usage_headers;
 
 pub use self::{
     metrics::MetricsRegistry,
@@ -10,9 +34,11 @@ pub use self::{
     middleware::metrics_middleware,
 };
 
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::Duration;
-use serde_json::{Value, json};
+use serde_json::{Map, Value, json};
 use uuid::Uuid;
 use tracing::debug;
 
@@ -24,19 +50,45 @@ pub struct ResourceInfo {
     pub service_version: String,
     #[serde(rename = "deployment.environment")]
     pub deployment_environment: String,
+    #[serde(rename = "cloud.region", default, skip_serializing_if = "Option::is_none")]
+    pub cloud_region: Option<String>,
+    #[serde(rename = "vcs.revision", default, skip_serializing_if = "Option::is_none")]
+    pub git_sha: Option<String>,
+    /// Extra attributes from `RESOURCE_ATTRIBUTES`, e.g. `team=platform,cluster=prod-eu-1`
+    #[serde(flatten)]
+    pub attributes: BTreeMap<String, String>,
 }
 
 impl Default for ResourceInfo {
     fn default() -> Self {
+        let attributes = std::env::var("RESOURCE_ATTRIBUTES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
         Self {
-            service_name: "noveum_ai_gateway".to_string(),
+            service_name: std::env::var("SERVICE_NAME").unwrap_or_else(|_| "noveum_ai_gateway".to_string()),
             service_version: env!("CARGO_PKG_VERSION").to_string(),
             deployment_environment: std::env::var("DEPLOYMENT_ENVIRONMENT")
                 .unwrap_or_else(|_| "development".to_string()),
+            cloud_region: std::env::var("DEPLOYMENT_REGION").or_else(|_| std::env::var("AWS_REGION")).ok(),
+            git_sha: option_env!("GIT_SHA").map(String::from),
+            attributes,
         }
     }
 }
 
+/// Resource attributes read once from the environment and attached to every request log
+static RESOURCE: Lazy<ResourceInfo> = Lazy::new(ResourceInfo::default);
+
+impl ResourceInfo {
+    pub fn current() -> &'static ResourceInfo {
+        &RESOURCE
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogAttributes {
     // Basic identifying fields
@@ -46,10 +98,28 @@ pub struct LogAttributes {
     pub user_id: Option<String>,
     pub project_id: Option<String>,
     pub experiment_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment_variant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_fingerprint: Option<String>,
 
     // Provider/model details
     pub provider: String,
     pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rewritten_model: Option<String>,
+
+    // Caller-defined tags from x-gateway-metadata or the body's `metadata`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_metadata: Option<Map<String, Value>>,
+
+    // Attributes from the TELEMETRY_EXTRACT_RULES applied to the request body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_attributes: Option<Map<String, Value>>,
 
     // Request/Response objects (can be stored as JSON Value)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -67,8 +137,25 @@ pub struct LogMetadata {
     pub project_name: Option<String>,
     pub latency: u128,
     pub ttfb: u128,  // Time to First Byte in milliseconds
+    pub queue_time: u128,  // Time spent in the gateway before dispatching upstream, in milliseconds
+    pub scheduler_wait: u128,  // Part of queue_time spent waiting for a provider slot, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    pub provider_ttfb: u128,  // Time from upstream dispatch to provider response headers, in milliseconds
+    pub stream_duration: u128,  // Time from first byte to end of stream, in milliseconds
+    pub tokens_per_second: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_token_latency: Option<u128>,  // Milliseconds until the first generated token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inter_chunk_mean: Option<f64>,  // Mean inter-chunk gap in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inter_chunk_max: Option<u128>,  // Largest inter-chunk gap in milliseconds
     pub tokens: TokenInfo,
     pub cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billed_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billed_currency: Option<String>,
     pub status: String,
     pub path: String,
     pub method: String,
@@ -82,6 +169,44 @@ pub struct LogMetadata {
     pub provider_error_count: u32,
     pub provider_error_type: Option<String>,
     pub provider_request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guardrail_action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub characters: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enforcement_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_failover: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_chain: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stream_interrupted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderation_rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderation_span: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected_prediction_cost: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_evaluator: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +214,18 @@ pub struct TokenInfo {
     pub input: Option<u32>,
     pub output: Option<u32>,
     pub total: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_input: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_output: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accepted_prediction: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected_prediction: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,6 +233,8 @@ pub struct RequestMetrics {
     // Request metadata
     pub provider: String,
     pub model: String,
+    pub original_model: Option<String>,  // Model requested by the client, when rewritten by an alias
+    pub rewritten_model: Option<String>,  // Model the alias map sent upstream instead
     pub path: String,
     pub method: String,
     
@@ -103,6 +242,15 @@ pub struct RequestMetrics {
     pub total_latency: Duration,
     pub provider_latency: Duration,
     pub ttfb: Duration,  // Time to First Byte - time taken to receive the first byte of the response
+    pub queue_time: Duration,  // Time between the gateway accepting the request and dispatching it upstream
+    pub scheduler_wait: Duration,  // Time spent waiting for a provider concurrency slot
+    pub priority: Option<String>,  // Scheduling class the request ran under
+    pub provider_ttfb: Duration,  // Time between upstream dispatch and the provider's response headers
+    pub stream_duration: Duration,  // Time between the first byte and the end of the response stream
+    pub tokens_per_second: Option<f64>,  // Output tokens divided by generation time
+    pub first_token_latency: Option<Duration>,  // Time from request start to the first generated token (streaming only)
+    pub mean_inter_chunk_latency: Option<Duration>,  // Mean gap between consecutive stream chunks
+    pub max_inter_chunk_latency: Option<Duration>,  // Largest gap between consecutive stream chunks
     
     // Size metrics
     pub request_size: usize,
@@ -110,6 +258,16 @@ pub struct RequestMetrics {
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
     pub total_tokens: Option<u32>,
+    pub cached_tokens: Option<u32>,
+    pub reasoning_tokens: Option<u32>,
+    pub audio_input_tokens: Option<u32>,  // Audio prompt tokens of audio-capable chat models
+    pub audio_output_tokens: Option<u32>,
+    pub accepted_prediction_tokens: Option<u32>,  // Predicted outputs (`prediction`) that were used
+    pub rejected_prediction_tokens: Option<u32>,
+    pub rejected_prediction_cost: Option<f64>,  // Spent on predicted tokens that weren't used
+    pub service_tier: Option<String>,  // Tier the provider served the request on
+    pub audio_seconds: Option<f64>,  // Audio duration billed by speech-to-text providers
+    pub characters: Option<u32>,  // Characters billed by text-to-speech providers
     
     // Status metrics
     pub status_code: u16,
@@ -123,16 +281,61 @@ pub struct RequestMetrics {
     
     // Cost metrics
     pub cost: Option<f64>,
+    pub billed_cost: Option<f64>,  // Cost in the tenant's billing currency
+    pub billed_currency: Option<String>,
+
+    // Provider guardrail outcome, e.g. "INTERVENED"
+    pub guardrail_action: Option<String>,
+
+    // Gateway policy that ended the response early, e.g. "max_cost"
+    pub enforcement_reason: Option<String>,
+
+    // Response content type, recorded for binary bodies (audio, images) that aren't parsed
+    pub content_type: Option<String>,
+
+    // How the request was changed after a context-length error, e.g. "truncated:4"
+    pub remediation: Option<String>,
+
+    // Canary cohort the request was served by, e.g. "gpt-41-rollout:baseline"
+    pub canary: Option<String>,
+
+    // Fallback a broken stream was resumed on, e.g. "groq:llama-3.3-70b-versatile"
+    pub stream_failover: Option<String>,
+
+    // Upstream attempts a stream was re-dispatched past before its first byte, e.g. "openai: 503 -> openai: ok"
+    pub retry_chain: Option<String>,
+
+    // The upstream stream failed partway and the gateway ended it with an error event
+    pub stream_interrupted: bool,
+
+    // Stream moderation rule that stopped the stream, and the text it matched with the match masked
+    pub moderation_rule: Option<String>,
+    pub moderation_span: Option<String>,
+
+    // Quality score from the configured evaluator, added after completion
+    pub score: Option<f64>,
+    pub score_reason: Option<String>,
+    pub score_evaluator: Option<String>,  // "webhook" or "judge:<model>"
+
+    // Fine-tuning job lifecycle, for auditing job activity alongside inference
+    pub job_id: Option<String>,
+    pub job_status: Option<String>,
     
     // OpenTelemetry additional fields
     pub id: Option<String>,
     pub thread_id: Option<String>,
+    pub message_id: Option<String>,  // Client's own ID from x-message-id; the log's id, but never the document ID
     pub org_id: Option<String>,
     pub user_id: Option<String>,
     pub project_id: Option<String>,
     pub project_name: Option<String>,
     pub provider_request_id: Option<String>,
     pub experiment_id: Option<String>,
+    pub experiment_variant: Option<String>,  // Gateway-assigned variant of experiment_id
+    pub tenant_id: Option<String>,
+    pub key_fingerprint: Option<String>,  // Salted hash of the client-supplied provider key
+    pub request_metadata: Option<Map<String, Value>>,  // Sanitized caller-defined tags
+    pub custom_attributes: Option<Map<String, Value>>,  // Values selected by the configured extraction rules
     
     // Original request and response
     pub request_body: Option<Value>,
@@ -141,6 +344,8 @@ pub struct RequestMetrics {
     // Streaming response data
     pub streamed_data: Option<Vec<Value>>,
     pub is_streaming: bool,
+    // Initial record of a stream whose headers arrived; its final record follows
+    pub in_flight: bool,
 }
 
 impl Default for RequestMetrics {
@@ -148,16 +353,37 @@ impl Default for RequestMetrics {
         Self {
             provider: String::new(),
             model: String::new(),
+            original_model: None,
+            rewritten_model: None,
             path: String::new(),
             method: String::new(),
             total_latency: Duration::default(),
             provider_latency: Duration::default(),
             ttfb: Duration::default(),
+            queue_time: Duration::default(),
+            scheduler_wait: Duration::default(),
+            priority: None,
+            provider_ttfb: Duration::default(),
+            stream_duration: Duration::default(),
+            tokens_per_second: None,
+            first_token_latency: None,
+            mean_inter_chunk_latency: None,
+            max_inter_chunk_latency: None,
             request_size: 0,
             response_size: 0,
             input_tokens: None,
             output_tokens: None,
             total_tokens: None,
+            cached_tokens: None,
+            audio_input_tokens: None,
+            audio_output_tokens: None,
+            accepted_prediction_tokens: None,
+            rejected_prediction_tokens: None,
+            rejected_prediction_cost: None,
+            service_tier: None,
+            reasoning_tokens: None,
+            audio_seconds: None,
+            characters: None,
             status_code: 0,
             provider_status_code: 0,
             error_count: 0,
@@ -165,18 +391,42 @@ impl Default for RequestMetrics {
             provider_error_count: 0,
             provider_error_type: None,
             cost: None,
+            billed_cost: None,
+            billed_currency: None,
+            guardrail_action: None,
+            enforcement_reason: None,
+            content_type: None,
+            remediation: None,
+            canary: None,
+            stream_failover: None,
+            retry_chain: None,
+            stream_interrupted: false,
+            moderation_rule: None,
+            moderation_span: None,
+            score: None,
+            score_reason: None,
+            score_evaluator: None,
+            job_id: None,
+            job_status: None,
             id: None,
             thread_id: None,
+            message_id: None,
             org_id: None,
             user_id: None,
             project_id: None,
             project_name: None,
             provider_request_id: None,
             experiment_id: None,
+            experiment_variant: None,
+            tenant_id: None,
+            key_fingerprint: None,
+            request_metadata: None,
+            custom_attributes: None,
             request_body: None,
             response_body: None,
             streamed_data: None,
             is_streaming: false,
+            in_flight: false,
         }
     }
 }
@@ -184,7 +434,11 @@ impl Default for RequestMetrics {
 impl RequestMetrics {
     /// Convert to OpenTelemetry compatible log format
     pub fn to_otel_log(&self) -> serde_json::Value {
-        let status = if self.error_count > 0 || self.provider_error_count > 0 {
+        let status = if self.in_flight {
+            "in_flight"
+        } else if self.error_type.as_deref() == Some(stream_deadline::TIMEOUT_ERROR_TYPE) {
+            "timeout"
+        } else if self.error_count > 0 || self.provider_error_count > 0 {
             "error"
         } else {
             "success"
@@ -194,6 +448,12 @@ impl RequestMetrics {
             input: self.input_tokens,
             output: self.output_tokens,
             total: self.total_tokens,
+            cached: self.cached_tokens,
+            reasoning: self.reasoning_tokens,
+            audio_input: self.audio_input_tokens,
+            audio_output: self.audio_output_tokens,
+            accepted_prediction: self.accepted_prediction_tokens,
+            rejected_prediction: self.rejected_prediction_tokens,
         };
         
         let metadata = LogMetadata {
@@ -201,8 +461,19 @@ impl RequestMetrics {
             project_name: self.project_name.clone(),
             latency: self.total_latency.as_millis(),
             ttfb: self.ttfb.as_millis(),
+            queue_time: self.queue_time.as_millis(),
+            scheduler_wait: self.scheduler_wait.as_millis(),
+            priority: self.priority.clone(),
+            provider_ttfb: self.provider_ttfb.as_millis(),
+            stream_duration: self.stream_duration.as_millis(),
+            tokens_per_second: self.tokens_per_second,
+            first_token_latency: self.first_token_latency.map(|d| d.as_millis()),
+            inter_chunk_mean: self.mean_inter_chunk_latency.map(|d| d.as_secs_f64() * 1000.0),
+            inter_chunk_max: self.max_inter_chunk_latency.map(|d| d.as_millis()),
             tokens: token_info,
             cost: self.cost,
+            billed_cost: self.billed_cost,
+            billed_currency: self.billed_currency.clone(),
             status: status.to_string(),
             path: self.path.clone(),
             method: self.method.clone(),
@@ -216,6 +487,25 @@ impl RequestMetrics {
             provider_error_count: self.provider_error_count,
             provider_error_type: self.provider_error_type.clone(),
             provider_request_id: self.provider_request_id.clone(),
+            guardrail_action: self.guardrail_action.clone(),
+            audio_seconds: self.audio_seconds,
+            characters: self.characters,
+            job_id: self.job_id.clone(),
+            job_status: self.job_status.clone(),
+            enforcement_reason: self.enforcement_reason.clone(),
+            content_type: self.content_type.clone(),
+            remediation: self.remediation.clone(),
+            canary: self.canary.clone(),
+            stream_failover: self.stream_failover.clone(),
+            retry_chain: self.retry_chain.clone(),
+            stream_interrupted: self.stream_interrupted,
+            moderation_rule: self.moderation_rule.clone(),
+            moderation_span: self.moderation_span.clone(),
+            rejected_prediction_cost: self.rejected_prediction_cost,
+            service_tier: self.service_tier.clone(),
+            score: self.score,
+            score_reason: self.score_reason.clone(),
+            score_evaluator: self.score_evaluator.clone(),
         };
         
         // Prepare the response data based on whether it's streaming or not
@@ -238,23 +528,36 @@ impl RequestMetrics {
         let request_data = self.request_body.clone().map(|body| self.sanitize_for_elasticsearch(body));
         
         let attributes = LogAttributes {
-            id: self.id.clone().unwrap_or_else(|| format!("msg_{}", Uuid::new_v4().to_string().split('-').next().unwrap_or("unknown"))),
+            // The client's own message ID when it sent one, so logs join with its traces
+            id: self
+                .message_id
+                .clone()
+                .or_else(|| self.id.clone())
+                .unwrap_or_else(|| format!("msg_{}", Uuid::new_v4().to_string().split('-').next().unwrap_or("unknown"))),
             thread_id: self.thread_id.clone().unwrap_or_else(|| format!("thread_{}", Uuid::new_v4().to_string().split('-').next().unwrap_or("unknown"))),
             org_id: self.org_id.clone(),
             user_id: self.user_id.clone(),
             project_id: self.project_id.clone(),
             provider: self.provider.clone(),
             model: self.model.clone(),
+            original_model: self.original_model.clone(),
+            rewritten_model: self.rewritten_model.clone(),
             request: request_data,
             response: response_data,
             metadata,
             experiment_id: self.experiment_id.clone(),
+            experiment_variant: self.experiment_variant.clone(),
+            tenant_id: self.tenant_id.clone(),
+            key_fingerprint: self.key_fingerprint.clone(),
+            request_metadata: self.request_metadata.clone(),
+            custom_attributes: self.custom_attributes.clone(),
         };
         
-        let resource = ResourceInfo::default();
+        let resource = ResourceInfo::current();
         
         json!({
             "timestamp": chrono::Utc::now().to_rfc3339(),
+            "schema_version": schema::CURRENT_SCHEMA_VERSION,
             "resource": resource,
             "name": "ai_gateway_request_log",
             "attributes": attributes
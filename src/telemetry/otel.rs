@@ -0,0 +1,120 @@
+use super::ResourceInfo;
+use crate::config::TelemetryConfig;
+use axum::http::{HeaderMap, Request};
+use once_cell::sync::OnceCell;
+use opentelemetry::{
+    global,
+    propagation::Extractor,
+    trace::TracerProvider as _,
+    KeyValue,
+};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{Tracer, TracerProvider},
+    Resource,
+};
+use serde_json::Value;
+use tracing::{error, field::Empty, Span, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Set once tracing export is running, so it can be flushed on shutdown
+static PROVIDER: OnceCell<TracerProvider> = OnceCell::new();
+
+/// Reads W3C trace context from request headers
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// The gateway's resource attributes, as attached to request logs
+fn resource() -> Resource {
+    let attributes = match serde_json::to_value(ResourceInfo::current()) {
+        Ok(Value::Object(map)) => map
+            .into_iter()
+            .filter_map(|(key, value)| Some(KeyValue::new(key, value.as_str()?.to_string())))
+            .collect(),
+        _ => Vec::new(),
+    };
+    Resource::new(attributes)
+}
+
+/// Layer exporting spans over OTLP when `ENABLE_OTEL_TRACING=true`. The
+/// collector is set with the standard `OTEL_EXPORTER_OTLP_ENDPOINT` (or
+/// `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`), defaulting to `http://localhost:4318`.
+pub fn layer<S>() -> Option<OpenTelemetryLayer<S, Tracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if !TelemetryConfig::default().otel_tracing_enabled {
+        return None;
+    }
+
+    let exporter = match opentelemetry_otlp::SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            // The subscriber this layer belongs to isn't installed yet
+            eprintln!("Failed to create OTLP span exporter; tracing export disabled: {}", e);
+            return None;
+        }
+    };
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(resource())
+        .build();
+    let tracer = provider.tracer("noveum-ai-gateway");
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    let _ = PROVIDER.set(provider);
+
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Flush spans still buffered in the batch exporter
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            error!("Failed to flush trace spans: {}", e);
+        }
+    }
+}
+
+/// Root span for one request through the gateway. Phases (auth, transform,
+/// sign, upstream, stream, telemetry export) are recorded as its children, and
+/// a client's `traceparent` makes it part of the caller's trace.
+pub fn request_span<B>(request: &Request<B>, provider: &str) -> Span {
+    let span = tracing::info_span!(
+        "gateway_request",
+        otel.name = %format!("{} {}", request.method(), request.uri().path()),
+        otel.kind = "server",
+        provider = %provider,
+        http.request.method = %request.method(),
+        url.path = %request.uri().path(),
+        http.response.status_code = Empty,
+        otel.status_code = Empty,
+    );
+    if PROVIDER.get().is_some() {
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent);
+    }
+    span
+}
+
+/// Record the response status on a request or upstream span
+pub fn record_status(span: &Span, status: u16) {
+    span.record("http.response.status_code", status);
+    if status >= 500 {
+        span.record("otel.status_code", "ERROR");
+    }
+}
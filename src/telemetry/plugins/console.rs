@@ -1,4 +1,4 @@
-use crate::telemetry::{RequestMetrics, metrics::MetricsExporter};
+use crate::telemetry::{RequestMetrics, metrics::MetricsExporter, redact};
 use async_trait::async_trait;
 use std::error::Error;
 use serde_json::to_string_pretty;
@@ -14,6 +14,9 @@ impl ConsolePlugin {
 #[async_trait]
 impl MetricsExporter for ConsolePlugin {
     async fn export_metrics(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
+        // Console output is a log like any other; credentials never reach it
+        let metrics = redact::metrics(&metrics);
+
         // For console output, we have two options:
         // 1. Print the raw RequestMetrics struct
         // 2. Print the OpenTelemetry formatted log
@@ -1,13 +1,19 @@
 use super::TelemetryPlugin;
 use crate::telemetry::RequestMetrics;
 use crate::telemetry::metrics::MetricsExporter;
+use crate::telemetry::schema;
+use crate::tenancy;
 use async_trait::async_trait;
 use elasticsearch::{
     auth::Credentials,
     http::transport::{Transport, TransportBuilder, SingleNodeConnectionPool},
-    Elasticsearch, IndexParts,
+    indices::{IndicesCreateParts, IndicesPutMappingParts},
+    CreateParts, Elasticsearch, IndexParts, UpdateParts,
 };
 use opentelemetry::trace::TraceError;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::HashSet;
 use std::error::Error;
 use std::time::Duration;
 use tokio::time::timeout;
@@ -18,9 +24,120 @@ use tokio_retry::{
 };
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+/// How a document is written under its request ID
+#[derive(Debug, Clone, Copy)]
+enum WriteMode {
+    /// `PUT {index}/_doc/{id}`: create, or overwrite a retried export
+    Index,
+    /// `_update` with `doc_as_upsert`: merge into an earlier record for the request, or create it
+    Upsert,
+    /// `_create`: write only if the request has no record yet, so a stream's
+    /// in-flight record never replaces a final one that landed first
+    Create,
+}
+
+/// Explicit types for the numeric and identifier fields of request logs. Dynamic
+/// mapping types a field from the first document that has it, which can leave
+/// latency, tokens or cost unusable for aggregations.
+fn mappings() -> Value {
+    let long = || json!({ "type": "long" });
+    let double = || json!({ "type": "double" });
+    let integer = || json!({ "type": "integer" });
+    let keyword = || json!({ "type": "keyword" });
+    json!({
+        "properties": {
+            "timestamp": { "type": "date" },
+            "schema_version": integer(),
+            "name": keyword(),
+            "attributes": {
+                "properties": {
+                    "id": keyword(),
+                    "thread_id": keyword(),
+                    "org_id": keyword(),
+                    "user_id": keyword(),
+                    "project_id": keyword(),
+                    "experiment_id": keyword(),
+                    "experiment_variant": keyword(),
+                    "tenant_id": keyword(),
+                    "key_fingerprint": keyword(),
+                    "provider": keyword(),
+                    "model": keyword(),
+                    "original_model": keyword(),
+                    "rewritten_model": keyword(),
+                    "metadata": {
+                        "properties": {
+                            "project_id": keyword(),
+                            "latency": long(),
+                            "ttfb": long(),
+                            "queue_time": long(),
+                            "scheduler_wait": long(),
+                            "provider_ttfb": long(),
+                            "stream_duration": long(),
+                            "provider_latency": long(),
+                            "first_token_latency": long(),
+                            "inter_chunk_mean": double(),
+                            "inter_chunk_max": long(),
+                            "tokens_per_second": double(),
+                            "tokens": {
+                                "properties": {
+                                    "input": long(),
+                                    "output": long(),
+                                    "total": long(),
+                                    "cached": long(),
+                                    "reasoning": long(),
+                                    "audio_input": long(),
+                                    "audio_output": long(),
+                                    "accepted_prediction": long(),
+                                    "rejected_prediction": long(),
+                                }
+                            },
+                            "cost": double(),
+                            "billed_cost": double(),
+                            "billed_currency": keyword(),
+                            "status": keyword(),
+                            "method": keyword(),
+                            "request_size": long(),
+                            "response_size": long(),
+                            "status_code": integer(),
+                            "provider_status_code": integer(),
+                            "error_count": integer(),
+                            "error_type": keyword(),
+                            "provider_error_count": integer(),
+                            "provider_error_type": keyword(),
+                            "provider_request_id": keyword(),
+                            "audio_seconds": double(),
+                            "characters": long(),
+                            "score": double(),
+                            "rejected_prediction_cost": double(),
+                            "service_tier": keyword(),
+                            "canary": keyword(),
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Environment name as an index name suffix: lowercase, with anything ES rejects replaced by `-`
+fn index_suffix(environment: &str) -> String {
+    environment
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '-' })
+        .collect()
+}
+
 pub struct ElasticsearchPlugin {
     client: Elasticsearch,
     index: String,
+    /// Appended to every index name, e.g. `ai-gateway-metrics-production`
+    environment_suffix: Option<String>,
+    /// Indices whose mappings have been put this process
+    mapped: Mutex<HashSet<String>>,
+    /// Layout documents are converted to, so existing index mappings keep working
+    schema_version: u32,
     requests_processed: AtomicUsize,
     docs_exported: AtomicUsize,
 }
@@ -46,13 +163,93 @@ impl ElasticsearchPlugin {
         Ok(Self {
             client: Elasticsearch::new(transport),
             index,
+            environment_suffix: None,
+            mapped: Mutex::new(HashSet::new()),
+            schema_version: schema::CURRENT_SCHEMA_VERSION,
             requests_processed: AtomicUsize::new(0),
             docs_exported: AtomicUsize::new(0),
         })
     }
 
+    /// Export documents in an older telemetry schema, e.g. for an index whose mapping predates newer fields
+    pub fn with_schema_version(mut self, version: u32) -> Self {
+        info!("Elasticsearch telemetry documents will use schema version {}", version);
+        self.schema_version = version;
+        self
+    }
+
+    /// Keep each deployment environment's documents in its own index
+    pub fn with_environment_suffix(mut self, environment: &str) -> Self {
+        let suffix = index_suffix(environment);
+        if !suffix.is_empty() {
+            info!("Elasticsearch telemetry indices will be suffixed with -{}", suffix);
+            self.environment_suffix = Some(suffix);
+        }
+        self
+    }
+
+    /// Create the index with the request log mappings the first time it's written to.
+    /// An index that already exists gets whatever of the mappings doesn't conflict with it.
+    async fn ensure_mappings(&self, index: &str) {
+        if self.mapped.lock().contains(index) {
+            return;
+        }
+        let indices = self.client.indices();
+        let created = indices
+            .create(IndicesCreateParts::Index(index))
+            .body(json!({ "mappings": mappings() }))
+            .send()
+            .await;
+        match created {
+            Ok(response) if response.status_code().is_success() => {
+                info!("Created Elasticsearch index {} with request log mappings", index);
+            }
+            // Already exists: add the mappings for fields it hasn't seen yet
+            Ok(response) if response.status_code().as_u16() == 400 => {
+                let updated = indices
+                    .put_mapping(IndicesPutMappingParts::Index(&[index]))
+                    .body(mappings())
+                    .send()
+                    .await;
+                match updated {
+                    Ok(response) if response.status_code().is_success() => {
+                        debug!("Updated request log mappings on Elasticsearch index {}", index);
+                    }
+                    Ok(response) => {
+                        let text = response.text().await.unwrap_or_default();
+                        warn!(
+                            "Elasticsearch index {} has conflicting field types; reindex it to get numeric mappings: {}",
+                            index, text
+                        );
+                    }
+                    Err(e) => {
+                        warn!("Failed to update mappings on Elasticsearch index {}: {}", index, e);
+                        return;
+                    }
+                }
+            }
+            Ok(response) => {
+                warn!("Elasticsearch returned {} creating index {}", response.status_code(), index);
+                return;
+            }
+            Err(e) => {
+                // Tried again on the next write
+                warn!("Failed to create Elasticsearch index {}: {}", index, e);
+                return;
+            }
+        }
+        self.mapped.lock().insert(index.to_string());
+    }
+
     // Enhanced send_metrics method with retries and better error handling
-    async fn send_metrics(&self, document: serde_json::Value, request_id: &str) -> Result<(), TraceError> {
+    async fn send_metrics(
+        &self,
+        document: serde_json::Value,
+        index: String,
+        doc_id: &str,
+        mode: WriteMode,
+        request_id: &str,
+    ) -> Result<(), TraceError> {
         // Configure retry strategy with exponential backoff
         let retry_strategy = ExponentialBackoff::from_millis(100)
             .map(jitter) // Add jitter to prevent thundering herd
@@ -61,18 +258,35 @@ impl ElasticsearchPlugin {
         // Log sending to Elasticsearch at debug level instead of info to reduce noise
         debug!("Sending telemetry data to Elasticsearch, request_id: {}", request_id);
         
-        let index = self.index.clone();
         let client = self.client.clone();
         let request_id = request_id.to_string(); // Clone for use in async block
         
         let result = Retry::spawn(retry_strategy, || async {
-            match timeout(Duration::from_secs(10), client.index(IndexParts::Index(&index)).body(document.clone()).send()).await {
+            let sent = match mode {
+                WriteMode::Index => {
+                    let request = client.index(IndexParts::IndexId(&index, doc_id)).body(document.clone());
+                    timeout(Duration::from_secs(10), request.send()).await
+                }
+                WriteMode::Upsert => {
+                    let body = json!({ "doc": document.clone(), "doc_as_upsert": true });
+                    let request = client.update(UpdateParts::IndexId(&index, doc_id)).body(body);
+                    timeout(Duration::from_secs(10), request.send()).await
+                }
+                WriteMode::Create => {
+                    let request = client.create(CreateParts::IndexId(&index, doc_id)).body(document.clone());
+                    timeout(Duration::from_secs(10), request.send()).await
+                }
+            };
+            match sent {
                 Ok(response_result) => {
                     match response_result {
                         Ok(response) => {
                             if response.status_code().is_success() {
                                 debug!("Successfully sent metrics to Elasticsearch");
                                 Ok(())
+                            } else if matches!(mode, WriteMode::Create) && response.status_code().as_u16() == 409 {
+                                debug!("Request {} already has a record; skipping its in-flight record", doc_id);
+                                Ok(())
                             } else {
                                 let status = response.status_code();
                                 let error_text = response.text().await.unwrap_or_else(|_| "Unable to get response text".to_string());
@@ -185,12 +399,18 @@ impl ElasticsearchPlugin {
 #[async_trait]
 impl TelemetryPlugin for ElasticsearchPlugin {
     async fn export(&self, metrics: &RequestMetrics) -> Result<(), Box<dyn Error>> {
-        // Skip exporting health check requests to reduce noise
-        if metrics.path == "/health" {
-            debug!("Skipping telemetry export for health check request");
-            return Ok(());
-        }
-        
+        // Streams upsert so their final usage updates the in-flight record
+        let mode = if metrics.is_streaming { WriteMode::Upsert } else { WriteMode::Index };
+        self.write(metrics, mode).await
+    }
+
+    fn name(&self) -> &str {
+        "elasticsearch"
+    }
+}
+
+impl ElasticsearchPlugin {
+    async fn write(&self, metrics: &RequestMetrics, mode: WriteMode) -> Result<(), Box<dyn Error>> {
         // Increment request counter and log periodically
         let req_count = self.requests_processed.fetch_add(1, Ordering::Relaxed) + 1;
         if req_count % 500 == 0 {
@@ -198,12 +418,13 @@ impl TelemetryPlugin for ElasticsearchPlugin {
         }
 
         // Convert metrics to OpenTelemetry format
-        let document = metrics.to_otel_log();
+        let document = schema::convert(metrics.to_otel_log(), self.schema_version);
         
         // Extract some key metrics for logging context
         let provider = document["attributes"]["provider"].as_str().unwrap_or("unknown");
         let model = document["attributes"]["model"].as_str().unwrap_or("unknown");
-        let request_id = document["attributes"]["id"].as_str().unwrap_or("unknown");
+        // Documents are keyed by the gateway request ID; the log's `id` may be the client's message ID
+        let request_id = metrics.id.as_deref().unwrap_or("unknown");
         
         // Extract provider request ID if available
         let provider_request_id = document["attributes"]["metadata"]["provider_request_id"]
@@ -219,7 +440,19 @@ impl TelemetryPlugin for ElasticsearchPlugin {
             "Exporting metrics to Elasticsearch for request"
         );
         
-        if let Err(e) = self.send_metrics(document.clone(), provider_request_id).await {
+        // Tenants can route their telemetry to a dedicated index
+        let index = metrics
+            .tenant_id
+            .as_deref()
+            .and_then(tenancy::telemetry_index)
+            .unwrap_or_else(|| self.index.clone());
+        let index = match &self.environment_suffix {
+            Some(suffix) => format!("{}-{}", index, suffix),
+            None => index,
+        };
+        self.ensure_mappings(&index).await;
+
+        if let Err(e) = self.send_metrics(document.clone(), index, request_id, mode, provider_request_id).await {
             let error_message = e.to_string();
             let error_category = if error_message.contains("CONNECTION") {
                 "CONNECTION"
@@ -258,24 +491,23 @@ impl TelemetryPlugin for ElasticsearchPlugin {
         
         Ok(())
     }
-
-    fn name(&self) -> &str {
-        "elasticsearch"
-    }
 }
 
 #[async_trait]
 impl MetricsExporter for ElasticsearchPlugin {
     async fn export_metrics(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
-        // Skip exporting health check requests to reduce noise
-        if metrics.path == "/health" {
-            debug!("Skipping metrics export for health check request");
-            return Ok(());
-        }
-        
         self.export(&metrics).await
     }
 
+    async fn export_started(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
+        self.write(&metrics, WriteMode::Create).await
+    }
+
+    async fn export_scored(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
+        // Merged into the completed record, which has already been written
+        self.write(&metrics, WriteMode::Upsert).await
+    }
+
     fn name(&self) -> &str {
         "elasticsearch"
     }
@@ -1,4 +1,4 @@
-use super::RequestMetrics;
+use super::{redact, RequestMetrics};
 use async_trait::async_trait;
 use std::error::Error;
 
@@ -11,13 +11,16 @@ pub trait TelemetryPlugin: Send + Sync {
 // Example plugin stubs (to be implemented later)
 pub mod elasticsearch;
 pub mod console;
+pub mod prometheus;
+pub mod noveum;
+pub mod sqlite;
 
 pub use console::ConsolePlugin;
 
 #[async_trait]
 impl TelemetryPlugin for ConsolePlugin {
     async fn export(&self, metrics: &RequestMetrics) -> Result<(), Box<dyn Error>> {
-        println!("Request Metrics:\n{:#?}", metrics);
+        println!("Request Metrics:\n{:#?}", redact::metrics(metrics));
         Ok(())
     }
 
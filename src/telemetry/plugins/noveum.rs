@@ -0,0 +1,197 @@
+use crate::telemetry::{RequestMetrics, metrics::MetricsExporter, schema};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio_retry::{
+    strategy::{jitter, ExponentialBackoff},
+    Retry,
+};
+use tracing::{debug, error, info, warn};
+
+const DEFAULT_INGEST_URL: &str = "https://api.noveum.ai/api/v1/gateway/logs";
+
+/// Settings for shipping request logs to the Noveum platform
+#[derive(Debug, Clone)]
+pub struct NoveumConfig {
+    pub api_key: String,
+    pub ingest_url: String,
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+    pub queue_size: usize,
+    /// Batches that still fail after retries are appended here as JSON lines
+    pub dlq_path: PathBuf,
+    /// Telemetry schema version the logs are converted to before shipping
+    pub schema_version: u32,
+}
+
+impl NoveumConfig {
+    /// Read the exporter settings; `None` when `NOVEUM_API_KEY` is unset
+    pub fn from_env() -> Option<Self> {
+        let api_key = std::env::var("NOVEUM_API_KEY").ok().filter(|k| !k.is_empty())?;
+        let parse = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        Some(Self {
+            api_key,
+            ingest_url: std::env::var("NOVEUM_INGEST_URL").unwrap_or_else(|_| DEFAULT_INGEST_URL.to_string()),
+            batch_size: parse("NOVEUM_BATCH_SIZE", 100).max(1) as usize,
+            flush_interval: Duration::from_millis(parse("NOVEUM_FLUSH_INTERVAL_MS", 2000).max(100)),
+            queue_size: parse("NOVEUM_QUEUE_SIZE", 10_000).max(1) as usize,
+            dlq_path: PathBuf::from(
+                std::env::var("NOVEUM_DLQ_PATH").unwrap_or_else(|_| "noveum-dlq.jsonl".to_string()),
+            ),
+            schema_version: schema::version_from_env("NOVEUM_SCHEMA_VERSION"),
+        })
+    }
+}
+
+/// Ships request logs to the Noveum ingestion API in batches.
+///
+/// Exports only enqueue; a background task flushes when a batch fills or the
+/// flush interval passes, so the request path never waits on the network.
+pub struct NoveumPlugin {
+    sender: mpsc::Sender<Value>,
+    dropped: AtomicUsize,
+    schema_version: u32,
+}
+
+impl NoveumPlugin {
+    pub fn new(config: NoveumConfig) -> Result<Self, Box<dyn Error>> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let (sender, receiver) = mpsc::channel(config.queue_size);
+        let schema_version = config.schema_version;
+
+        info!(
+            "Initialized Noveum telemetry exporter for {} (batch size {}, flush every {:?})",
+            config.ingest_url, config.batch_size, config.flush_interval
+        );
+        tokio::spawn(run_batcher(client, config, receiver));
+
+        Ok(Self {
+            sender,
+            dropped: AtomicUsize::new(0),
+            schema_version,
+        })
+    }
+}
+
+async fn run_batcher(client: reqwest::Client, config: NoveumConfig, mut receiver: mpsc::Receiver<Value>) {
+    let mut batch = Vec::with_capacity(config.batch_size);
+    let mut ticker = tokio::time::interval(config.flush_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            document = receiver.recv() => match document {
+                Some(document) => {
+                    batch.push(document);
+                    if batch.len() < config.batch_size {
+                        continue;
+                    }
+                }
+                // Registry dropped: flush what's left and stop
+                None => {
+                    if !batch.is_empty() {
+                        flush(&client, &config, std::mem::take(&mut batch)).await;
+                    }
+                    return;
+                }
+            },
+            _ = ticker.tick() => {
+                if batch.is_empty() {
+                    continue;
+                }
+            }
+        }
+        flush(&client, &config, std::mem::take(&mut batch)).await;
+    }
+}
+
+async fn flush(client: &reqwest::Client, config: &NoveumConfig, batch: Vec<Value>) {
+    let count = batch.len();
+    let body = json!({ "logs": batch });
+
+    let retry_strategy = ExponentialBackoff::from_millis(200)
+        .map(jitter)
+        .take(3);
+    let result = Retry::spawn(retry_strategy, || send_batch(client, config, &body)).await;
+
+    match result {
+        Ok(()) => debug!("Exported {} request logs to Noveum", count),
+        Err(e) => {
+            error!("Failed to export {} request logs to Noveum after retries: {}", count, e);
+            write_dlq(&config.dlq_path, &body["logs"]).await;
+        }
+    }
+}
+
+async fn send_batch(client: &reqwest::Client, config: &NoveumConfig, body: &Value) -> Result<(), String> {
+    let response = client
+        .post(&config.ingest_url)
+        .bearer_auth(&config.api_key)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {}", e))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let text = response.text().await.unwrap_or_default();
+    warn!("Noveum ingestion returned {}: {}", status, text);
+    Err(format!("ingestion returned {}", status))
+}
+
+/// Append undeliverable logs to the dead-letter file for later replay
+async fn write_dlq(path: &Path, logs: &Value) {
+    let Some(logs) = logs.as_array() else {
+        return;
+    };
+    let mut lines = String::new();
+    for log in logs {
+        lines.push_str(&log.to_string());
+        lines.push('\n');
+    }
+
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await;
+    match file {
+        Ok(mut file) => match file.write_all(lines.as_bytes()).await {
+            Ok(()) => warn!("Wrote {} undelivered request logs to {}", logs.len(), path.display()),
+            Err(e) => error!("Failed to write Noveum dead-letter file {}: {}", path.display(), e),
+        },
+        Err(e) => error!("Failed to open Noveum dead-letter file {}: {}", path.display(), e),
+    }
+}
+
+#[async_trait]
+impl MetricsExporter for NoveumPlugin {
+    async fn export_metrics(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
+        let document = schema::convert(metrics.to_otel_log(), self.schema_version);
+        if self.sender.try_send(document).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped % 100 == 1 {
+                warn!("Noveum export queue is full; {} request logs dropped so far", dropped);
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "noveum"
+    }
+}
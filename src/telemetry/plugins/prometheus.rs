@@ -0,0 +1,87 @@
+use crate::telemetry::{RequestMetrics, metrics::MetricsExporter};
+use async_trait::async_trait;
+use metrics::{counter, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::error::Error;
+use tracing::debug;
+
+// Histogram buckets in seconds, tuned for LLM request latencies
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+// Histogram buckets for output throughput in tokens per second
+const THROUGHPUT_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 200.0, 500.0, 1000.0];
+
+pub struct PrometheusPlugin {
+    handle: PrometheusHandle,
+}
+
+impl PrometheusPlugin {
+    /// Install the global Prometheus recorder used by the `metrics` macros
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let handle = PrometheusBuilder::new()
+            .set_buckets(LATENCY_BUCKETS)?
+            .set_buckets_for_metric(
+                Matcher::Full("gateway_tokens_per_second".to_string()),
+                THROUGHPUT_BUCKETS,
+            )?
+            .install_recorder()?;
+
+        Ok(Self { handle })
+    }
+
+    /// Handle used by the `/metrics` endpoint to render the scrape output
+    pub fn handle(&self) -> PrometheusHandle {
+        self.handle.clone()
+    }
+}
+
+#[async_trait]
+impl MetricsExporter for PrometheusPlugin {
+    async fn export_metrics(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
+        debug!("Recording Prometheus metrics for provider {}", metrics.provider);
+
+        let provider = metrics.provider.clone();
+        let model = metrics.model.clone();
+        let status = metrics.status_code.to_string();
+
+        counter!("gateway_requests_total", "provider" => provider.clone(), "model" => model.clone(), "status" => status)
+            .increment(1);
+
+        histogram!("gateway_request_duration_seconds", "provider" => provider.clone(), "model" => model.clone())
+            .record(metrics.total_latency.as_secs_f64());
+        histogram!("gateway_queue_time_seconds", "provider" => provider.clone())
+            .record(metrics.queue_time.as_secs_f64());
+        if let Some(priority) = &metrics.priority {
+            histogram!("gateway_scheduler_wait_seconds", "provider" => provider.clone(), "priority" => priority.clone())
+                .record(metrics.scheduler_wait.as_secs_f64());
+        }
+        histogram!("gateway_provider_ttfb_seconds", "provider" => provider.clone(), "model" => model.clone())
+            .record(metrics.provider_ttfb.as_secs_f64());
+
+        if metrics.is_streaming {
+            histogram!("gateway_stream_duration_seconds", "provider" => provider.clone(), "model" => model.clone())
+                .record(metrics.stream_duration.as_secs_f64());
+
+            if let Some(first_token) = metrics.first_token_latency {
+                histogram!("gateway_first_token_seconds", "provider" => provider.clone(), "model" => model.clone())
+                    .record(first_token.as_secs_f64());
+            }
+            if let Some(max_gap) = metrics.max_inter_chunk_latency {
+                histogram!("gateway_max_inter_chunk_seconds", "provider" => provider.clone(), "model" => model.clone())
+                    .record(max_gap.as_secs_f64());
+            }
+        }
+
+        if let Some(tps) = metrics.tokens_per_second {
+            histogram!("gateway_tokens_per_second", "provider" => provider, "model" => model).record(tps);
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "prometheus"
+    }
+}
@@ -0,0 +1,347 @@
+use crate::error::AppError;
+use crate::telemetry::{RequestMetrics, metrics::MetricsExporter};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, TimeZone, Utc};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use utoipa::IntoParams;
+
+/// Rows returned by a query when no `limit` is given, and the most it may ask for
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+/// Inserts between retention sweeps
+const PRUNE_EVERY: u64 = 500;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS requests (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp INTEGER NOT NULL,
+        request_id TEXT,
+        provider TEXT NOT NULL,
+        model TEXT NOT NULL,
+        method TEXT NOT NULL,
+        path TEXT NOT NULL,
+        status_code INTEGER NOT NULL,
+        latency_ms INTEGER NOT NULL,
+        ttfb_ms INTEGER NOT NULL,
+        input_tokens INTEGER,
+        output_tokens INTEGER,
+        cost REAL,
+        is_streaming INTEGER NOT NULL,
+        error_type TEXT,
+        tenant_id TEXT,
+        project_id TEXT,
+        org_id TEXT,
+        user_id TEXT,
+        thread_id TEXT
+    );
+    CREATE INDEX IF NOT EXISTS requests_timestamp ON requests (timestamp);
+";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Number,
+    Bool,
+}
+
+/// Filterable fields, their column and type; also the columns a query returns
+const FIELDS: &[(&str, &str, Kind)] = &[
+    ("id", "request_id", Kind::Text),
+    ("provider", "provider", Kind::Text),
+    ("model", "model", Kind::Text),
+    ("method", "method", Kind::Text),
+    ("path", "path", Kind::Text),
+    ("status", "status_code", Kind::Number),
+    ("latency_ms", "latency_ms", Kind::Number),
+    ("ttfb_ms", "ttfb_ms", Kind::Number),
+    ("input_tokens", "input_tokens", Kind::Number),
+    ("output_tokens", "output_tokens", Kind::Number),
+    ("cost", "cost", Kind::Number),
+    ("streaming", "is_streaming", Kind::Bool),
+    ("error_type", "error_type", Kind::Text),
+    ("tenant_id", "tenant_id", Kind::Text),
+    ("project_id", "project_id", Kind::Text),
+    ("org_id", "org_id", Kind::Text),
+    ("user_id", "user_id", Kind::Text),
+    ("thread_id", "thread_id", Kind::Text),
+];
+
+/// Operators in the order they are matched, so `>=` wins over `>`
+const OPERATORS: &[&str] = &[">=", "<=", "!=", "=", ">", "<", "~"];
+
+/// Settings for the embedded request log
+#[derive(Debug, Clone)]
+pub struct RequestLogConfig {
+    /// SQLite database file, or `:memory:`
+    pub path: String,
+    /// Rows older than this are deleted
+    pub retention: Duration,
+    /// Oldest rows beyond this count are deleted
+    pub max_rows: u64,
+}
+
+impl RequestLogConfig {
+    pub fn from_env() -> Self {
+        let parse = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            path: std::env::var("REQUEST_LOG_PATH").unwrap_or_else(|_| "request-log.db".to_string()),
+            retention: Duration::from_secs(parse("REQUEST_LOG_RETENTION_HOURS", 24).max(1) * 3600),
+            max_rows: parse("REQUEST_LOG_MAX_ROWS", 100_000).max(1),
+        }
+    }
+}
+
+/// Filters accepted by the request log query endpoint
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestLogQuery {
+    /// Comma-separated conditions `<field><op><value>` with ops `=`, `!=`, `>`, `>=`, `<`, `<=`
+    /// and `~` (contains), e.g. `provider=openai,status>=400,model~gpt-4o`. Fields: id, provider,
+    /// model, method, path, status, latency_ms, ttfb_ms, input_tokens, output_tokens, cost,
+    /// streaming, error_type, tenant_id, project_id, org_id, user_id, thread_id
+    pub filter: Option<String>,
+    /// Only requests completed at or after this RFC 3339 time
+    pub since: Option<DateTime<Utc>>,
+    /// Only requests completed before this RFC 3339 time
+    pub until: Option<DateTime<Utc>>,
+    /// Most rows to return, newest first (default 100, at most 1000)
+    pub limit: Option<usize>,
+}
+
+/// Recent request summaries kept in an embedded SQLite database, so small
+/// deployments can search their history without running Elasticsearch.
+///
+/// Like the live tail it stores no bodies or headers. Rows past the retention
+/// period or row cap are swept every few hundred inserts.
+pub struct RequestLog {
+    conn: Mutex<Connection>,
+    config: RequestLogConfig,
+    inserts: AtomicU64,
+}
+
+static REQUEST_LOG: OnceCell<Arc<RequestLog>> = OnceCell::new();
+
+/// The request log, when the exporter is enabled
+pub fn get() -> Option<Arc<RequestLog>> {
+    REQUEST_LOG.get().cloned()
+}
+
+impl RequestLog {
+    /// Open (or create) the database and make it the log `/admin/requests` queries
+    pub fn open(config: RequestLogConfig) -> Result<Arc<Self>, Box<dyn Error>> {
+        let conn = Connection::open(&config.path)?;
+        // WAL lets queries read while the exporter writes; in-memory databases report "memory"
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        conn.execute_batch(SCHEMA)?;
+        info!(
+            "Opened request log at {} (retention {:?}, at most {} rows)",
+            config.path, config.retention, config.max_rows
+        );
+        let log = Arc::new(Self {
+            conn: Mutex::new(conn),
+            config,
+            inserts: AtomicU64::new(0),
+        });
+        log.prune()?;
+        REQUEST_LOG
+            .set(log.clone())
+            .map_err(|_| "request log already opened")?;
+        Ok(log)
+    }
+
+    fn insert(&self, metrics: &RequestMetrics) -> rusqlite::Result<()> {
+        self.conn.lock().execute(
+            "INSERT INTO requests (timestamp, request_id, provider, model, method, path, status_code,
+                latency_ms, ttfb_ms, input_tokens, output_tokens, cost, is_streaming, error_type,
+                tenant_id, project_id, org_id, user_id, thread_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+            params![
+                Utc::now().timestamp_millis(),
+                metrics.id,
+                metrics.provider,
+                metrics.model,
+                metrics.method,
+                metrics.path,
+                metrics.status_code,
+                metrics.total_latency.as_millis() as i64,
+                metrics.ttfb.as_millis() as i64,
+                metrics.input_tokens,
+                metrics.output_tokens,
+                metrics.cost,
+                metrics.is_streaming,
+                metrics.error_type,
+                metrics.tenant_id,
+                metrics.project_id,
+                metrics.org_id,
+                metrics.user_id,
+                metrics.thread_id,
+            ],
+        )?;
+        if self.inserts.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == PRUNE_EVERY - 1 {
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    /// Delete rows past the retention period, then the oldest beyond the row cap
+    fn prune(&self) -> rusqlite::Result<()> {
+        let cutoff = Utc::now() - ChronoDuration::from_std(self.config.retention).unwrap_or(ChronoDuration::days(1));
+        let conn = self.conn.lock();
+        let expired = conn.execute("DELETE FROM requests WHERE timestamp < ?1", params![cutoff.timestamp_millis()])?;
+        let excess = conn.execute(
+            "DELETE FROM requests WHERE seq <= (SELECT seq FROM requests ORDER BY seq DESC LIMIT 1 OFFSET ?1)",
+            params![self.config.max_rows as i64],
+        )?;
+        if expired + excess > 0 {
+            debug!("Pruned {} expired and {} excess request log rows", expired, excess);
+        }
+        Ok(())
+    }
+
+    /// Rows matching the query, newest first
+    pub fn query(&self, query: &RequestLogQuery) -> Result<Vec<Value>, AppError> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        for condition in query.filter.iter().flat_map(|f| f.split(',')).map(str::trim) {
+            if condition.is_empty() {
+                continue;
+            }
+            let (sql, value) = parse_condition(condition)?;
+            conditions.push(sql);
+            values.push(value);
+        }
+        if let Some(since) = query.since {
+            conditions.push("timestamp >= ?".to_string());
+            values.push(SqlValue::Integer(since.timestamp_millis()));
+        }
+        if let Some(until) = query.until {
+            conditions.push("timestamp < ?".to_string());
+            values.push(SqlValue::Integer(until.timestamp_millis()));
+        }
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+        let columns: Vec<&str> = FIELDS.iter().map(|(_, column, _)| *column).collect();
+        let sql = format!(
+            "SELECT timestamp, {} FROM requests{} ORDER BY seq DESC LIMIT {}",
+            columns.join(", "),
+            if conditions.is_empty() {
+                String::new()
+            } else {
+                format!(" WHERE {}", conditions.join(" AND "))
+            },
+            limit
+        );
+
+        let conn = self.conn.lock();
+        let read = |e: rusqlite::Error| AppError::RequestError(format!("Request log query failed: {}", e));
+        let mut statement = conn.prepare(&sql).map_err(read)?;
+        let rows = statement
+            .query_map(params_from_iter(values), |row| {
+                let mut record = Map::new();
+                let timestamp: i64 = row.get(0)?;
+                let timestamp = Utc.timestamp_millis_opt(timestamp).single().map(|t| t.to_rfc3339());
+                record.insert("timestamp".to_string(), timestamp.into());
+                for (index, (field, _, kind)) in FIELDS.iter().enumerate() {
+                    let value = match (kind, row.get::<_, SqlValue>(index + 1)?) {
+                        (_, SqlValue::Null) => Value::Null,
+                        (Kind::Bool, SqlValue::Integer(v)) => Value::Bool(v != 0),
+                        (_, SqlValue::Integer(v)) => v.into(),
+                        (_, SqlValue::Real(v)) => v.into(),
+                        (_, SqlValue::Text(v)) => v.into(),
+                        (_, SqlValue::Blob(_)) => Value::Null,
+                    };
+                    record.insert(field.to_string(), value);
+                }
+                Ok(Value::Object(record))
+            })
+            .map_err(read)?;
+        rows.collect::<Result<_, _>>().map_err(read)
+    }
+}
+
+/// SQL for one `<field><op><value>` condition and the value to bind to it
+fn parse_condition(condition: &str) -> Result<(String, SqlValue), AppError> {
+    let invalid = |reason: String| AppError::RequestError(format!("Invalid filter `{}`: {}", condition, reason));
+    let start = condition
+        .find(|c| matches!(c, '=' | '!' | '<' | '>' | '~'))
+        .ok_or_else(|| invalid("expected <field><op><value>".to_string()))?;
+    let (field, rest) = condition.split_at(start);
+    let operator = OPERATORS
+        .iter()
+        .find(|op| rest.starts_with(**op))
+        .ok_or_else(|| invalid("unknown operator".to_string()))?;
+    let raw = rest[operator.len()..].trim();
+    let field = field.trim();
+    let (_, column, kind) = FIELDS
+        .iter()
+        .find(|(name, _, _)| *name == field)
+        .ok_or_else(|| invalid(format!("unknown field `{}`", field)))?;
+
+    let value = match kind {
+        Kind::Text => SqlValue::Text(raw.to_string()),
+        Kind::Number => SqlValue::Real(raw.parse().map_err(|_| invalid(format!("`{}` is not a number", raw)))?),
+        Kind::Bool => SqlValue::Integer(match raw {
+            "true" | "1" => 1,
+            "false" | "0" => 0,
+            _ => return Err(invalid(format!("`{}` is not true or false", raw))),
+        }),
+    };
+    let sql = match (*operator, kind) {
+        ("~", Kind::Text) => format!("instr({}, ?) > 0", column),
+        ("~", _) => return Err(invalid("`~` only applies to text fields".to_string())),
+        (">" | ">=" | "<" | "<=", Kind::Bool) => return Err(invalid("streaming only supports = and !=".to_string())),
+        // `!=` also matches rows where the field is missing
+        ("!=", _) => format!("{} IS NOT ?", column),
+        (op, _) => format!("{} {} ?", column, op),
+    };
+    Ok((sql, value))
+}
+
+/// Writes completed requests to the embedded request log
+pub struct SqlitePlugin {
+    log: Arc<RequestLog>,
+}
+
+impl SqlitePlugin {
+    pub fn new(config: RequestLogConfig) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            log: RequestLog::open(config)?,
+        })
+    }
+}
+
+#[async_trait]
+impl MetricsExporter for SqlitePlugin {
+    async fn export_metrics(&self, metrics: RequestMetrics) -> Result<(), Box<dyn Error>> {
+        let log = self.log.clone();
+        // SQLite calls block, so they stay off the async workers
+        let result = tokio::task::spawn_blocking(move || log.insert(&metrics)).await;
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => {
+                warn!("Failed to write request log row: {}", e);
+                Err(e.into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+}
@@ -0,0 +1,124 @@
+use super::RequestMetrics;
+use crate::tenancy::TENANTS;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::{debug, error, info, warn};
+
+const PER_MILLION: f64 = 1_000_000.0;
+
+/// A tenant's negotiated price for a model, in USD
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriceOverride {
+    /// USD per million input tokens; replaces the list price when set, along with `output_per_million`
+    pub input_per_million: Option<f64>,
+    /// USD per million cached input tokens; the input price when unset
+    pub cached_input_per_million: Option<f64>,
+    /// USD per million output tokens; required along with `input_per_million`
+    pub output_per_million: Option<f64>,
+    /// Multiplier on the list price, e.g. 0.8 for a 20% discount, used when no per-token prices are set
+    pub multiplier: Option<f64>,
+}
+
+impl PriceOverride {
+    /// Why the override can't be applied, if it can't. Input and output prices go
+    /// together: only the call's total list price is known, so a side without a
+    /// price of its own has nothing to fall back to.
+    pub fn problem(&self) -> Option<&'static str> {
+        match (self.input_per_million, self.output_per_million) {
+            (Some(_), None) => Some("input_per_million is set without output_per_million"),
+            (None, Some(_)) => Some("output_per_million is set without input_per_million"),
+            _ => None,
+        }
+    }
+
+    /// Negotiated price of a call, given its list price and token counts. None, so
+    /// the list price stands, when the usage lacks a token count the override prices
+    /// or the override prices only one side.
+    pub fn price(
+        &self,
+        list_cost: Option<f64>,
+        input_tokens: Option<u32>,
+        cached_tokens: Option<u32>,
+        output_tokens: Option<u32>,
+    ) -> Option<f64> {
+        if self.input_per_million.is_none() && self.output_per_million.is_none() {
+            return Some(list_cost? * self.multiplier?);
+        }
+        let (Some(input_price), Some(output_price)) = (self.input_per_million, self.output_per_million) else {
+            return None;
+        };
+        let input = f64::from(input_tokens?);
+        let output = f64::from(output_tokens?);
+        let cached = f64::from(cached_tokens.unwrap_or(0)).min(input);
+        let cached_price = self.cached_input_per_million.unwrap_or(input_price);
+        Some(((input - cached) * input_price + cached * cached_price + output * output_price) / PER_MILLION)
+    }
+}
+
+/// Units of each currency per USD from `FX_RATES`, e.g. `{"EUR": 0.92, "GBP": 0.79}`
+static FX_RATES: Lazy<HashMap<String, f64>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("FX_RATES") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, f64>>(&raw) {
+        Ok(rates) => {
+            info!("Loaded FX rates for {} currencies", rates.len());
+            rates.into_iter().map(|(currency, rate)| (currency.to_uppercase(), rate)).collect()
+        }
+        Err(e) => {
+            error!("Failed to parse FX_RATES: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+/// The override for a request, most specific first: `provider:model`, `model`, `provider:*`, `*`
+pub fn find_override<'a>(
+    overrides: &'a HashMap<String, PriceOverride>,
+    provider: &str,
+    model: &str,
+) -> Option<&'a PriceOverride> {
+    let provider = provider.to_lowercase();
+    [format!("{}:{}", provider, model), model.to_string(), format!("{}:*", provider), "*".to_string()]
+        .iter()
+        .find_map(|key| overrides.get(key))
+}
+
+/// Reprice a completed request at its tenant's negotiated rates and convert it to
+/// the tenant's billing currency. `cost` stays in USD so budgets and aggregates
+/// remain comparable; the converted amount goes in `billed_cost`.
+pub fn apply(metrics: &mut RequestMetrics) {
+    let Some(tenant) = metrics.tenant_id.as_deref().and_then(|id| TENANTS.get(id)) else {
+        return;
+    };
+    let tenant_id = tenant.id();
+
+    if let Some(price) = find_override(&tenant.config.pricing, &metrics.provider, &metrics.model) {
+        let cost = price.price(metrics.cost, metrics.input_tokens, metrics.cached_tokens, metrics.output_tokens);
+        if let Some(cost) = cost {
+            debug!(
+                "Repriced {} request for tenant {}: {:?} -> {:.6} USD",
+                metrics.model, tenant_id, metrics.cost, cost
+            );
+            metrics.cost = Some(cost);
+        }
+    }
+
+    let Some(currency) = tenant.config.currency.as_deref().map(str::to_uppercase) else {
+        return;
+    };
+    if currency == "USD" {
+        metrics.billed_cost = metrics.cost;
+        metrics.billed_currency = Some(currency);
+        return;
+    }
+    match FX_RATES.get(&currency) {
+        Some(rate) => {
+            metrics.billed_cost = metrics.cost.map(|cost| cost * rate);
+            metrics.billed_currency = Some(currency);
+        }
+        None => warn!("No FX rate for {} (tenant {}); reporting cost in USD only", currency, tenant_id),
+    }
+}
@@ -9,6 +9,19 @@ pub struct ProviderMetrics {
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
     pub total_tokens: Option<u32>,
+    pub cached_tokens: Option<u32>,     // Prompt tokens served from the provider's prompt cache
+    pub reasoning_tokens: Option<u32>,  // Hidden reasoning tokens, included in output_tokens
+    pub audio_input_tokens: Option<u32>,   // Audio prompt tokens, included in input_tokens
+    pub audio_output_tokens: Option<u32>,  // Generated audio tokens, included in output_tokens
+    pub accepted_prediction_tokens: Option<u32>,  // Predicted output tokens that appeared in the completion
+    pub rejected_prediction_tokens: Option<u32>,  // Predicted tokens that didn't, billed as output anyway
+    pub rejected_prediction_cost: Option<f64>,    // What the rejected predicted tokens cost
+    pub service_tier: Option<String>,   // Tier the provider served the request on, e.g. "flex" or "priority"
+    pub guardrail_action: Option<String>,  // Provider guardrail outcome, e.g. "INTERVENED"
+    pub audio_seconds: Option<f64>,     // Audio transcribed, for duration-billed speech-to-text
+    pub characters: Option<u32>,        // Characters synthesized, for character-billed text-to-speech
+    pub job_id: Option<String>,         // Fine-tuning job or message batch the response describes
+    pub job_status: Option<String>,     // Lifecycle state of that job, e.g. "running" or "in_progress"
     pub cost: Option<f64>,
     pub model: String,
     pub provider_latency: Duration,
@@ -70,39 +83,6 @@ impl ProviderMetrics {
             ..Default::default()
         }
     }
-
-    /// Extract tracking headers from the original request headers
-    pub fn extract_tracking_headers(headers: &HeaderMap) -> Self {
-        let mut metrics = Self::default();
-        
-        // Extract project ID
-        if let Some(project_id) = headers.get("x-project-id").and_then(|v| v.to_str().ok()) {
-            debug!("Found x-project-id header: {}", project_id);
-            metrics.project_id = Some(project_id.to_string());
-        }
-        
-        // Extract organization ID - handle both British and American spellings
-        if let Some(org_id) = headers.get("x-organization-id")
-            .or_else(|| headers.get("x-organisation-id"))  // Try both spellings
-            .and_then(|v| v.to_str().ok()) {
-            debug!("Found organization ID header: {}", org_id);
-            metrics.organization_id = Some(org_id.to_string());
-        }
-        
-        // Extract user ID
-        if let Some(user_id) = headers.get("x-user-id").and_then(|v| v.to_str().ok()) {
-            debug!("Found x-user-id header: {}", user_id);
-            metrics.user_id = Some(user_id.to_string());
-        }
-        
-        // Extract experiment ID
-        if let Some(experiment_id) = headers.get("x-experiment-id").and_then(|v| v.to_str().ok()) {
-            debug!("Found x-experiment-id header: {}", experiment_id);
-            metrics.experiment_id = Some(experiment_id.to_string());
-        }
-        
-        metrics
-    }
 }
 
 /// The MetricsExtractor trait defines a layered approach to extracting metrics from provider responses
@@ -133,6 +113,45 @@ pub trait MetricsExtractor: Send + Sync {
     /// # Returns
     /// A ProviderMetrics instance with extracted data
     fn extract_metrics(&self, response_body: &Value) -> ProviderMetrics;
+
+    /// Estimate the cost of a call from token counts
+    ///
+    /// Used for running estimates while a stream is in flight, before the
+    /// provider reports usage. The default returns None (price unknown).
+    ///
+    /// # Arguments
+    /// * `model` - The model name
+    /// * `input_tokens` - Prompt tokens
+    /// * `output_tokens` - Generated tokens so far
+    ///
+    /// # Returns
+    /// Option<f64> with the estimated cost in USD
+    fn estimate_cost(&self, _model: &str, _input_tokens: u32, _output_tokens: u32) -> Option<f64> {
+        None
+    }
+
+    /// Extract metrics priced from the request rather than a usage block
+    ///
+    /// Used when a successful response yields no cost, e.g. synthesized audio or
+    /// generated images. Providers that bill per character, image or call derive
+    /// metrics from the endpoint, request and response headers instead. The
+    /// default returns None.
+    ///
+    /// # Arguments
+    /// * `path` - The request path, e.g. `/v1/text-to-speech/{voice_id}`
+    /// * `request_body` - The JSON request body, if the request was JSON
+    /// * `headers` - The response headers from the provider
+    ///
+    /// # Returns
+    /// Option<ProviderMetrics> with metrics if they could be derived
+    fn extract_request_metrics(
+        &self,
+        _path: &str,
+        _request_body: Option<&Value>,
+        _headers: &HeaderMap,
+    ) -> Option<ProviderMetrics> {
+        None
+    }
     
     /// Extract metrics from streaming chunks
     ///
@@ -157,6 +176,21 @@ pub trait MetricsExtractor: Send + Sync {
         // Then fall back to common extraction patterns as a safety net
         self.try_extract_common_streaming_metrics(chunk)
     }
+
+    /// Extract metrics from a streaming chunk the provider has already parsed
+    ///
+    /// Providers that emit structured stream events (see `providers::stream_events`)
+    /// override this to skip the string round-trip. The default serializes the
+    /// chunk and uses `extract_streaming_metrics`.
+    ///
+    /// # Arguments
+    /// * `chunk` - A parsed streaming chunk
+    ///
+    /// # Returns
+    /// Option<ProviderMetrics> with metrics if they could be extracted
+    fn extract_streaming_event_metrics(&self, chunk: &Value) -> Option<ProviderMetrics> {
+        self.extract_streaming_metrics(&chunk.to_string())
+    }
     
     /// Provider-specific implementation for streaming metrics
     ///
@@ -187,35 +221,42 @@ pub trait MetricsExtractor: Send + Sync {
         debug!("Attempting common streaming metrics extraction for chunk");
         
         // Try to parse the chunk as JSON
-        if let Ok(json) = serde_json::from_str::<Value>(chunk) {
-            // If we have usage data, extract full metrics
-            if json.get("usage").is_some() {
-                debug!("Found usage in streaming chunk, extracting metrics");
-                return Some(self.extract_metrics(&json));
-            }
-            
-            // For any provider's streaming, extract what we can even if usage is missing
-            let model = json.get("model").and_then(|m| m.as_str()).unwrap_or("unknown").to_string();
-            
-            // Check for general indicators that this is a model output
-            let is_llm_response = 
-                json.get("choices").is_some() || 
-                json.get("completion").is_some() ||
-                json.get("delta").is_some() ||
-                json.get("finish_reason").is_some();
-                
-            if is_llm_response {
-                debug!("LLM streaming response detected in common handler, creating partial metrics");
-                return Some(ProviderMetrics {
-                    model,
-                    provider_latency: Duration::from_millis(0),
-                    // Leave token counts and cost as None
-                    ..Default::default()
-                });
-            }
+        let metrics = serde_json::from_str::<Value>(chunk)
+            .ok()
+            .and_then(|json| self.common_streaming_metrics(&json));
+        if metrics.is_none() {
+            debug!("No metrics data found in common streaming handler");
+        }
+        metrics
+    }
+
+    /// Common extraction patterns applied to an already parsed streaming chunk
+    fn common_streaming_metrics(&self, json: &Value) -> Option<ProviderMetrics> {
+        // If we have usage data, extract full metrics
+        if json.get("usage").is_some() {
+            debug!("Found usage in streaming chunk, extracting metrics");
+            return Some(self.extract_metrics(json));
+        }
+
+        // For any provider's streaming, extract what we can even if usage is missing
+        let model = json.get("model").and_then(|m| m.as_str()).unwrap_or("unknown").to_string();
+
+        // Check for general indicators that this is a model output
+        let is_llm_response =
+            json.get("choices").is_some() ||
+            json.get("completion").is_some() ||
+            json.get("delta").is_some() ||
+            json.get("finish_reason").is_some();
+
+        if is_llm_response {
+            debug!("LLM streaming response detected in common handler, creating partial metrics");
+            return Some(ProviderMetrics {
+                model,
+                provider_latency: Duration::from_millis(0),
+                // Leave token counts and cost as None
+                ..Default::default()
+            });
         }
-        
-        debug!("No metrics data found in common streaming handler");
         None
     }
 }
@@ -224,16 +265,29 @@ pub trait MetricsExtractor: Send + Sync {
 pub fn get_metrics_extractor(provider: &str) -> Box<dyn MetricsExtractor> {
     use crate::providers::anthropic::AnthropicMetricsExtractor;
     use crate::providers::bedrock::BedrockMetricsExtractor;
+    use crate::providers::deepgram::DeepgramMetricsExtractor;
+    use crate::providers::elevenlabs::ElevenLabsMetricsExtractor;
     use crate::providers::fireworks::FireworksMetricsExtractor;
+    use crate::providers::google_ai::GoogleAiMetricsExtractor;
     use crate::providers::groq::GroqMetricsExtractor;
+    use crate::providers::jina::JinaMetricsExtractor;
     use crate::providers::openai::OpenAIMetricsExtractor;
+    use crate::providers::stability::StabilityMetricsExtractor;
+    use crate::providers::together::TogetherMetricsExtractor;
+    use crate::providers::voyage::VoyageMetricsExtractor;
     
     match provider {
         "anthropic" => Box::new(AnthropicMetricsExtractor),
         "bedrock" => Box::new(BedrockMetricsExtractor),
         "groq" => Box::new(GroqMetricsExtractor),
         "fireworks" => Box::new(FireworksMetricsExtractor), // Now using Fireworks-specific extractor
-        "together" => Box::new(OpenAIMetricsExtractor),  // Together uses OpenAI-compatible format
+        "together" => Box::new(TogetherMetricsExtractor),
+        "google-ai" => Box::new(GoogleAiMetricsExtractor),
+        "voyage" => Box::new(VoyageMetricsExtractor),
+        "jina" => Box::new(JinaMetricsExtractor),
+        "elevenlabs" => Box::new(ElevenLabsMetricsExtractor),
+        "deepgram" => Box::new(DeepgramMetricsExtractor),
+        "stability" => Box::new(StabilityMetricsExtractor),
         _ => Box::new(OpenAIMetricsExtractor),           // Default to OpenAI format
     }
 } 
\ No newline at end of file
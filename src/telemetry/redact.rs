@@ -0,0 +1,127 @@
+use super::fingerprint::fingerprint_key;
+use super::secrets;
+use super::RequestMetrics;
+use axum::http::HeaderMap;
+use serde_json::Value;
+use std::fmt;
+
+/// Name segments that mark a header or JSON key as holding a credential, e.g.
+/// `authorization`, `x-api-key`, `xi-api-key`, `x-aws-secret-access-key`, `x-amz-security-token`,
+/// `client_secret`; whole segments only, so `max_tokens` is left alone
+const SENSITIVE_SEGMENTS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "key",
+    "apikey",
+    "secret",
+    "token",
+    "password",
+    "signature",
+    "credential",
+    "credentials",
+];
+
+fn is_sensitive(name: &str) -> bool {
+    name.to_ascii_lowercase()
+        .split(['-', '_'])
+        .any(|segment| SENSITIVE_SEGMENTS.contains(&segment))
+}
+
+/// What a credential is logged as: its salted fingerprint, so the same key can be
+/// recognized across log lines without being recoverable
+fn mask(value: &str) -> String {
+    let key = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    if key.is_empty() {
+        return String::new();
+    }
+    format!("[{}]", fingerprint_key(key))
+}
+
+/// A header map as it may appear in logs; credential values are replaced by fingerprints
+pub struct RedactedHeaders<'a>(&'a HeaderMap);
+
+impl fmt::Debug for RedactedHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (name, value) in self.0 {
+            let value = value.to_str().unwrap_or("<binary>");
+            if is_sensitive(name.as_str()) {
+                map.entry(&name.as_str(), &mask(value));
+            } else {
+                map.entry(&name.as_str(), &value);
+            }
+        }
+        map.finish()
+    }
+}
+
+/// Headers for a log line
+pub fn headers(headers: &HeaderMap) -> RedactedHeaders<'_> {
+    RedactedHeaders(headers)
+}
+
+/// A JSON body for a log line, with credential-named fields fingerprinted and
+/// credential-shaped strings replaced. Applies regardless of `SECRET_SCANNING`.
+pub fn value(value: &Value) -> Value {
+    let mut value = value.clone();
+    redact_value(&mut value);
+    value
+}
+
+/// Raw body text or an SSE chunk for a log line
+pub fn text(text: &str) -> String {
+    match serde_json::from_str::<Value>(text) {
+        Ok(json) => self::value(&json).to_string(),
+        Err(_) => {
+            let mut text = text.to_string();
+            secrets::scrub_text(&mut text);
+            text
+        }
+    }
+}
+
+/// A URL for a log line, with credential query parameters such as Google's `key` fingerprinted
+pub fn url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if is_sensitive(name) => format!("{}={}", name, mask(value)),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}?{}", base, query)
+}
+
+/// A request record for a debug dump; its bodies are redacted even when
+/// `SECRET_SCANNING` has left them untouched for the exporters
+pub fn metrics(metrics: &RequestMetrics) -> RequestMetrics {
+    let mut metrics = metrics.clone();
+    for body in [&mut metrics.request_body, &mut metrics.response_body].into_iter().flatten() {
+        redact_value(body);
+    }
+    for chunk in metrics.streamed_data.iter_mut().flatten() {
+        redact_value(chunk);
+    }
+    metrics
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                match field {
+                    Value::String(secret) if is_sensitive(key) => *secret = mask(secret),
+                    _ => redact_value(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        other => {
+            secrets::scrub(other);
+        }
+    }
+}
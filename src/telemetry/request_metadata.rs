@@ -0,0 +1,77 @@
+use axum::http::HeaderMap;
+use serde_json::{Map, Value};
+use tracing::debug;
+
+/// Request header carrying a JSON object of caller-defined tags
+const METADATA_HEADER: &str = "x-gateway-metadata";
+
+const MAX_KEYS: usize = 32;
+const MAX_KEY_LEN: usize = 64;
+const MAX_VALUE_LEN: usize = 512;
+const MAX_TOTAL_BYTES: usize = 4096;
+
+/// Caller-supplied key/values to attach to the request log.
+///
+/// Read from the `x-gateway-metadata` header and the body's `metadata` object;
+/// header keys win on conflict. Keys are reduced to `[A-Za-z0-9_-]`, nested
+/// values are flattened to strings so they can't clash with index mappings,
+/// and anything past the size limits is dropped.
+pub fn extract(headers: &HeaderMap, body: Option<&Value>) -> Option<Map<String, Value>> {
+    let from_header = headers
+        .get(METADATA_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|raw| match serde_json::from_str::<Value>(raw) {
+            Ok(Value::Object(map)) => Some(map),
+            _ => {
+                debug!("Ignoring {} header that isn't a JSON object", METADATA_HEADER);
+                None
+            }
+        });
+    let from_body = body
+        .and_then(|b| b.get("metadata"))
+        .and_then(Value::as_object);
+
+    let mut metadata = Map::new();
+    let mut total_bytes = 0;
+    let entries = from_header
+        .iter()
+        .flat_map(|map| map.iter())
+        .chain(from_body.into_iter().flat_map(|map| map.iter()));
+
+    for (key, value) in entries {
+        if metadata.len() >= MAX_KEYS {
+            debug!("Request metadata truncated at {} keys", MAX_KEYS);
+            break;
+        }
+        let key = sanitize_key(key);
+        if key.is_empty() || metadata.contains_key(&key) {
+            continue;
+        }
+        let value = sanitize_value(value);
+        let size = key.len() + value.to_string().len();
+        if total_bytes + size > MAX_TOTAL_BYTES {
+            debug!("Request metadata truncated at {} bytes", MAX_TOTAL_BYTES);
+            break;
+        }
+        total_bytes += size;
+        metadata.insert(key, value);
+    }
+
+    (!metadata.is_empty()).then_some(metadata)
+}
+
+fn sanitize_key(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') { c } else { '_' })
+        .take(MAX_KEY_LEN)
+        .collect()
+}
+
+fn sanitize_value(value: &Value) -> Value {
+    let text = match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => return value.clone(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    Value::String(text.chars().take(MAX_VALUE_LEN).collect())
+}
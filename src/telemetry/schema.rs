@@ -0,0 +1,117 @@
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+/// Version stamped on every request log produced by `RequestMetrics::to_otel_log`
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// One entry in the telemetry schema history.
+///
+/// Each version lists the `attributes` and `attributes.metadata` fields it
+/// introduced, so a document can be downgraded by dropping everything newer
+/// than the version an exporter's index mapping was built for.
+#[derive(Debug, Serialize)]
+pub struct SchemaVersion {
+    pub version: u32,
+    pub description: &'static str,
+    pub attributes: &'static [&'static str],
+    pub metadata: &'static [&'static str],
+}
+
+/// All known schema versions, oldest first
+pub const REGISTRY: &[SchemaVersion] = &[
+    SchemaVersion {
+        version: 1,
+        description: "Original request log layout; documents carry no schema_version field",
+        attributes: &[
+            "id", "thread_id", "org_id", "user_id", "project_id", "experiment_id",
+            "provider", "model", "request", "response", "metadata",
+        ],
+        metadata: &[
+            "project_id", "project_name", "latency", "ttfb", "tokens", "cost", "status",
+            "path", "method", "request_size", "response_size", "provider_latency",
+            "status_code", "provider_status_code", "error_count", "error_type",
+            "provider_error_count", "provider_error_type", "provider_request_id",
+        ],
+    },
+    SchemaVersion {
+        version: 2,
+        description: "Adds tenancy, model rewrites, caller metadata, timing breakdown, media usage and enforcement fields",
+        attributes: &[
+            "tenant_id", "key_fingerprint", "original_model", "rewritten_model",
+            "request_metadata", "custom_attributes", "experiment_variant",
+        ],
+        metadata: &[
+            "queue_time", "scheduler_wait", "priority", "provider_ttfb", "stream_duration",
+            "tokens_per_second", "first_token_latency", "inter_chunk_mean", "inter_chunk_max",
+            "guardrail_action", "audio_seconds", "characters", "job_id", "job_status",
+            "enforcement_reason", "content_type", "remediation", "canary",
+            "stream_failover", "score", "score_reason", "score_evaluator", "billed_cost",
+            "billed_currency", "retry_chain", "rejected_prediction_cost",
+            "service_tier", "stream_interrupted", "moderation_rule", "moderation_span",
+        ],
+    },
+];
+
+/// Whether the registry knows how to produce this version
+pub fn is_supported(version: u32) -> bool {
+    REGISTRY.iter().any(|v| v.version == version)
+}
+
+/// Schema version of a request log document; documents without one are version 1
+pub fn version_of(document: &Value) -> u32 {
+    document
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(1, |v| v as u32)
+}
+
+/// Rewrite a request log document into the layout of `target`.
+///
+/// Downgrading drops the fields added after `target`; upgrading only restamps
+/// the version, since newer fields are all optional. Unknown targets leave the
+/// document unchanged.
+pub fn convert(mut document: Value, target: u32) -> Value {
+    if !is_supported(target) {
+        warn!("Unknown telemetry schema version {}; exporting document unchanged", target);
+        return document;
+    }
+    let source = version_of(&document);
+    if source == target {
+        return document;
+    }
+
+    for newer in REGISTRY.iter().filter(|v| v.version > target && v.version <= source) {
+        if let Some(attributes) = document.get_mut("attributes").and_then(Value::as_object_mut) {
+            if let Some(metadata) = attributes.get_mut("metadata").and_then(Value::as_object_mut) {
+                for field in newer.metadata {
+                    metadata.remove(*field);
+                }
+            }
+            for field in newer.attributes {
+                attributes.remove(*field);
+            }
+        }
+    }
+
+    if let Some(root) = document.as_object_mut() {
+        if target == 1 {
+            root.remove("schema_version");
+        } else {
+            root.insert("schema_version".to_string(), Value::from(target));
+        }
+    }
+    document
+}
+
+/// Read an exporter's target version from the environment, falling back to the current one
+pub fn version_from_env(name: &str) -> u32 {
+    match std::env::var(name).ok().and_then(|v| v.trim().parse::<u32>().ok()) {
+        Some(version) if is_supported(version) => version,
+        Some(version) => {
+            warn!("{}={} is not a known telemetry schema version; using {}", name, version, CURRENT_SCHEMA_VERSION);
+            CURRENT_SCHEMA_VERSION
+        }
+        None => CURRENT_SCHEMA_VERSION,
+    }
+}
@@ -0,0 +1,175 @@
+use super::RequestMetrics;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const DEFAULT_JUDGE_URL: &str = "https://api.openai.com/v1/chat/completions";
+const DEFAULT_JUDGE_MODEL: &str = "gpt-4o-mini";
+
+const DEFAULT_RUBRIC: &str = "You grade AI assistant responses. Given the request and the response, \
+rate how helpful, correct and on-topic the response is from 0 (useless) to 1 (excellent). \
+Reply with a JSON object: {\"score\": <number between 0 and 1>, \"reason\": \"<one sentence>\"}.";
+
+/// Request and response text sent to the judge is cut to this many characters each
+const MAX_JUDGE_INPUT_CHARS: usize = 16 * 1024;
+
+/// Where completed requests are sent for a quality score
+#[derive(Debug, Clone)]
+enum Evaluator {
+    /// POSTs the request/response pair and reads `{"score": .., "reason": ..}` back
+    Webhook { url: String },
+    /// Asks a chat model to grade the pair against a rubric
+    Judge { url: String, model: String, api_key: String, rubric: String },
+}
+
+impl Evaluator {
+    fn name(&self) -> String {
+        match self {
+            Self::Webhook { .. } => "webhook".to_string(),
+            Self::Judge { model, .. } => format!("judge:{}", model),
+        }
+    }
+}
+
+/// `SCORING_WEBHOOK_URL`, or an LLM judge when `SCORING_JUDGE_API_KEY` is set
+static EVALUATOR: Lazy<Option<Evaluator>> = Lazy::new(|| {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let evaluator = if let Some(url) = env("SCORING_WEBHOOK_URL") {
+        Evaluator::Webhook { url }
+    } else if let Some(api_key) = env("SCORING_JUDGE_API_KEY") {
+        Evaluator::Judge {
+            url: env("SCORING_JUDGE_URL").unwrap_or_else(|| DEFAULT_JUDGE_URL.to_string()),
+            model: env("SCORING_JUDGE_MODEL").unwrap_or_else(|| DEFAULT_JUDGE_MODEL.to_string()),
+            api_key,
+            rubric: env("SCORING_JUDGE_RUBRIC").unwrap_or_else(|| DEFAULT_RUBRIC.to_string()),
+        }
+    } else {
+        return None;
+    };
+    info!("Scoring completed requests with the {} evaluator", evaluator.name());
+    Some(evaluator)
+});
+
+/// Share of eligible requests scored, from `SCORING_SAMPLE_RATE`
+static SAMPLE_RATE: Lazy<f64> = Lazy::new(|| {
+    std::env::var("SCORING_SAMPLE_RATE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1.0)
+});
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .expect("Failed to create scoring HTTP client")
+});
+
+/// A quality score for one request
+#[derive(Debug, Clone)]
+pub struct Score {
+    pub value: f64,
+    pub reason: Option<String>,
+    pub evaluator: String,
+}
+
+/// Whether a completed request should be scored: an evaluator is configured, the
+/// request succeeded, its capture profile kept both bodies, and it was sampled
+pub fn should_score(metrics: &RequestMetrics) -> bool {
+    if EVALUATOR.is_none() || metrics.in_flight || metrics.error_count > 0 || metrics.status_code >= 400 {
+        return false;
+    }
+    if metrics.request_body.is_none() || (metrics.response_body.is_none() && metrics.streamed_data.is_none()) {
+        return false;
+    }
+    match *SAMPLE_RATE {
+        rate if rate <= 0.0 => false,
+        rate if rate >= 1.0 => true,
+        rate => rand::thread_rng().gen_bool(rate),
+    }
+}
+
+/// Score a completed request with the configured evaluator
+pub async fn evaluate(metrics: &RequestMetrics) -> Option<Score> {
+    let evaluator = EVALUATOR.as_ref()?;
+    let request = metrics.request_body.clone().unwrap_or(Value::Null);
+    let response = match (&metrics.response_body, &metrics.streamed_data) {
+        (Some(body), _) => body.clone(),
+        (None, Some(chunks)) => json!(chunks),
+        (None, None) => Value::Null,
+    };
+
+    let verdict = match evaluator {
+        Evaluator::Webhook { url } => {
+            let payload = json!({
+                "request_id": metrics.id,
+                "provider": metrics.provider,
+                "model": metrics.model,
+                "experiment_id": metrics.experiment_id,
+                "experiment_variant": metrics.experiment_variant,
+                "request": request,
+                "response": response,
+            });
+            post(CLIENT.post(url).json(&payload)).await?
+        }
+        Evaluator::Judge { url, model, api_key, rubric } => {
+            let transcript = format!(
+                "Request:\n{}\n\nResponse:\n{}",
+                truncate(&request.to_string()),
+                truncate(&response_text(&response))
+            );
+            let body = json!({
+                "model": model,
+                "max_tokens": 256,
+                "response_format": { "type": "json_object" },
+                "messages": [
+                    { "role": "system", "content": rubric },
+                    { "role": "user", "content": transcript },
+                ],
+            });
+            let reply = post(CLIENT.post(url).bearer_auth(api_key).json(&body)).await?;
+            let content = reply.pointer("/choices/0/message/content").and_then(Value::as_str)?;
+            serde_json::from_str::<Value>(content).ok()?
+        }
+    };
+
+    let Some(value) = verdict.get("score").and_then(Value::as_f64) else {
+        warn!("Scoring {} returned no numeric score", evaluator.name());
+        return None;
+    };
+    debug!("Request {:?} scored {} by {}", metrics.id, value, evaluator.name());
+    Some(Score {
+        value,
+        reason: verdict.get("reason").and_then(Value::as_str).map(String::from),
+        evaluator: evaluator.name(),
+    })
+}
+
+async fn post(request: reqwest::RequestBuilder) -> Option<Value> {
+    match request.send().await.and_then(|r| r.error_for_status()) {
+        Ok(response) => response.json::<Value>().await.ok(),
+        Err(e) => {
+            warn!("Scoring request failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Assistant text of a chat or messages response, falling back to the raw JSON
+fn response_text(response: &Value) -> String {
+    response
+        .pointer("/choices/0/message/content")
+        .or_else(|| response.pointer("/content/0/text"))
+        .and_then(Value::as_str)
+        .map(String::from)
+        .unwrap_or_else(|| response.to_string())
+}
+
+fn truncate(text: &str) -> &str {
+    match text.char_indices().nth(MAX_JUDGE_INPUT_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
@@ -0,0 +1,75 @@
+use super::RequestMetrics;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use tracing::{debug, info};
+
+/// Scanning is on unless `SECRET_SCANNING=false`
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("SECRET_SCANNING")
+        .map(|v| v.parse().unwrap_or(true))
+        .unwrap_or(true)
+});
+
+/// Credential shapes that show up in prompts, paired with the label they're replaced by
+static PATTERNS: Lazy<Vec<(&'static str, Regex)>> = Lazy::new(|| {
+    [
+        ("private_key", r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----"),
+        ("api_key", r"\bsk-[A-Za-z0-9_-]{20,}"),
+        ("aws_access_key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+        ("aws_secret_key", r#"(?i)aws_secret_access_key["']?\s*[:=]\s*["']?[A-Za-z0-9/+=]{40}"#),
+        ("google_api_key", r"\bAIza[0-9A-Za-z_-]{35}"),
+        ("github_token", r"\bgh[pousr]_[A-Za-z0-9]{36,}"),
+        ("slack_token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}"),
+        ("jwt", r"\beyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}"),
+        ("bearer_token", r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]{16,}"),
+    ]
+    .into_iter()
+    .map(|(label, pattern)| (label, Regex::new(pattern).expect("secret pattern is valid")))
+    .collect()
+});
+
+/// Replace credential-like strings in the captured bodies before anything is exported
+pub fn apply(metrics: &mut RequestMetrics) {
+    if !*ENABLED {
+        return;
+    }
+    let mut redacted = 0;
+    for body in [&mut metrics.request_body, &mut metrics.response_body].into_iter().flatten() {
+        redacted += scrub(body);
+    }
+    for chunk in metrics.streamed_data.iter_mut().flatten() {
+        redacted += scrub(chunk);
+    }
+    if redacted > 0 {
+        info!(
+            "Redacted {} secrets from the {} request log for {}",
+            redacted, metrics.provider, metrics.path
+        );
+    }
+}
+
+pub(super) fn scrub(value: &mut Value) -> usize {
+    match value {
+        Value::String(text) => scrub_text(text),
+        Value::Array(items) => items.iter_mut().map(scrub).sum(),
+        Value::Object(map) => map.values_mut().map(scrub).sum(),
+        _ => 0,
+    }
+}
+
+pub(super) fn scrub_text(text: &mut String) -> usize {
+    let mut found = 0;
+    for (label, pattern) in PATTERNS.iter() {
+        let matches = pattern.find_iter(text).count();
+        if matches == 0 {
+            continue;
+        }
+        debug!("Found {} {} matches", matches, label);
+        *text = pattern
+            .replace_all(text, format!("[REDACTED:{}]", label).as_str())
+            .into_owned();
+        found += matches;
+    }
+    found
+}
@@ -0,0 +1,212 @@
+use super::RequestMetrics;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use tracing::debug;
+
+/// A single completed request as seen by the in-memory aggregates
+#[derive(Debug, Clone)]
+pub struct StatsSample {
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    pub org_id: Option<String>,
+    pub project_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub key_fingerprint: Option<String>,
+    pub canary: Option<String>,
+    pub latency_ms: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+    pub is_error: bool,
+}
+
+impl StatsSample {
+    fn from_metrics(metrics: &RequestMetrics) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            provider: metrics.provider.clone(),
+            model: metrics.model.clone(),
+            org_id: metrics.org_id.clone(),
+            project_id: metrics.project_id.clone(),
+            tenant_id: metrics.tenant_id.clone(),
+            key_fingerprint: metrics.key_fingerprint.clone(),
+            canary: metrics.canary.clone(),
+            latency_ms: metrics.total_latency.as_secs_f64() * 1000.0,
+            input_tokens: metrics.input_tokens.unwrap_or(0) as u64,
+            output_tokens: metrics.output_tokens.unwrap_or(0) as u64,
+            cost: metrics.cost.unwrap_or(0.0),
+            is_error: metrics.status_code >= 400 || metrics.error_count > 0,
+        }
+    }
+}
+
+/// Filters accepted by the stats endpoint
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub org_id: Option<String>,
+    pub project_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub key_fingerprint: Option<String>,
+    /// Canary cohort label, e.g. `gpt-41-rollout:canary`
+    pub canary: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl StatsQuery {
+    fn matches(&self, sample: &StatsSample) -> bool {
+        self.provider.as_ref().map_or(true, |p| p == &sample.provider)
+            && self.model.as_ref().map_or(true, |m| m == &sample.model)
+            && self.org_id.as_ref().map_or(true, |o| sample.org_id.as_ref() == Some(o))
+            && self.project_id.as_ref().map_or(true, |p| sample.project_id.as_ref() == Some(p))
+            && self.tenant_id.as_ref().map_or(true, |t| sample.tenant_id.as_ref() == Some(t))
+            && self.key_fingerprint.as_ref().map_or(true, |k| sample.key_fingerprint.as_ref() == Some(k))
+            && self.canary.as_ref().map_or(true, |c| sample.canary.as_ref() == Some(c))
+            && self.since.map_or(true, |since| sample.timestamp >= since)
+            && self.until.map_or(true, |until| sample.timestamp <= until)
+    }
+}
+
+/// Aggregated view of the samples for one provider/model/org combination
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateStats {
+    pub provider: String,
+    pub model: String,
+    pub org_id: Option<String>,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+    pub cost: f64,
+}
+
+/// Rolling window of recent requests used for instant, in-process visibility
+pub struct StatsStore {
+    samples: Mutex<VecDeque<StatsSample>>,
+    window: Duration,
+    max_samples: usize,
+}
+
+impl Default for StatsStore {
+    fn default() -> Self {
+        let window_secs = std::env::var("STATS_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600);
+        let max_samples = std::env::var("STATS_MAX_SAMPLES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100_000);
+        Self::new(Duration::from_secs(window_secs), max_samples)
+    }
+}
+
+impl StatsStore {
+    pub fn new(window: Duration, max_samples: usize) -> Self {
+        Self {
+            samples: Mutex::new(VecDeque::new()),
+            window,
+            max_samples,
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Add a completed request to the rolling window
+    pub fn record(&self, metrics: &RequestMetrics) {
+        let sample = StatsSample::from_metrics(metrics);
+        let mut samples = self.samples.lock();
+        samples.push_back(sample);
+        self.prune(&mut samples);
+    }
+
+    /// Snapshot of the raw samples matching a query
+    pub fn samples(&self, query: &StatsQuery) -> Vec<StatsSample> {
+        let mut samples = self.samples.lock();
+        self.prune(&mut samples);
+        samples.iter().filter(|s| query.matches(s)).cloned().collect()
+    }
+
+    /// Aggregate the samples matching a query, grouped by provider/model/org
+    pub fn query(&self, query: &StatsQuery) -> Vec<AggregateStats> {
+        let mut groups: BTreeMap<(String, String, Option<String>), Vec<StatsSample>> = BTreeMap::new();
+        for sample in self.samples(query) {
+            groups
+                .entry((sample.provider.clone(), sample.model.clone(), sample.org_id.clone()))
+                .or_default()
+                .push(sample);
+        }
+
+        groups
+            .into_iter()
+            .map(|((provider, model, org_id), samples)| {
+                let mut aggregate = aggregate(&samples);
+                aggregate.provider = provider;
+                aggregate.model = model;
+                aggregate.org_id = org_id;
+                aggregate
+            })
+            .collect()
+    }
+
+    fn prune(&self, samples: &mut VecDeque<StatsSample>) {
+        let cutoff = Utc::now() - chrono::Duration::from_std(self.window).unwrap_or_else(|_| chrono::Duration::hours(1));
+        while samples.front().map_or(false, |s| s.timestamp < cutoff) {
+            samples.pop_front();
+        }
+        while samples.len() > self.max_samples {
+            samples.pop_front();
+        }
+    }
+}
+
+/// Aggregate a set of samples; identity fields are left empty for the caller to fill in
+pub fn aggregate(samples: &[StatsSample]) -> AggregateStats {
+    let requests = samples.len() as u64;
+    let errors = samples.iter().filter(|s| s.is_error).count() as u64;
+    let mut latencies: Vec<f64> = samples.iter().map(|s| s.latency_ms).collect();
+    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let input_tokens = samples.iter().map(|s| s.input_tokens).sum::<u64>();
+    let output_tokens = samples.iter().map(|s| s.output_tokens).sum::<u64>();
+
+    debug!("Aggregated {} samples ({} errors)", requests, errors);
+
+    AggregateStats {
+        provider: String::new(),
+        model: String::new(),
+        org_id: None,
+        requests,
+        errors,
+        error_rate: if requests > 0 { errors as f64 / requests as f64 } else { 0.0 },
+        latency_p50_ms: percentile(&latencies, 0.50),
+        latency_p95_ms: percentile(&latencies, 0.95),
+        input_tokens,
+        output_tokens,
+        total_tokens: input_tokens + output_tokens,
+        cost: samples.iter().map(|s| s.cost).sum(),
+    }
+}
+
+/// Nearest-rank percentile over an already sorted slice
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
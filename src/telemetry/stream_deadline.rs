@@ -0,0 +1,87 @@
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Request header capping how long a streamed response may run, in seconds
+const MAX_DURATION_HEADER: &str = "x-max-stream-duration";
+
+/// Enforcement reason recorded in telemetry when the cap ends a stream
+pub const MAX_STREAM_DURATION_REASON: &str = "max_stream_duration";
+
+/// Error type recorded for streams cut off by the cap
+pub const TIMEOUT_ERROR_TYPE: &str = "timeout";
+
+/// Gateway-wide cap from `MAX_STREAM_DURATION_SECS`
+static DEFAULT_LIMIT: Lazy<Option<Duration>> = Lazy::new(|| {
+    std::env::var("MAX_STREAM_DURATION_SECS")
+        .ok()
+        .and_then(|v| parse_secs(&v))
+});
+
+/// Per-provider caps from `MAX_STREAM_DURATION_BY_PROVIDER`, e.g. `{"bedrock": 300, "groq": 60}`
+static PROVIDER_LIMITS: Lazy<HashMap<String, Duration>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("MAX_STREAM_DURATION_BY_PROVIDER") else {
+        return HashMap::new();
+    };
+    match serde_json::from_str::<HashMap<String, f64>>(&raw) {
+        Ok(limits) => {
+            let limits: HashMap<_, _> = limits
+                .into_iter()
+                .filter(|(_, secs)| secs.is_finite() && *secs > 0.0)
+                .map(|(provider, secs)| (provider.to_lowercase(), Duration::from_secs_f64(secs)))
+                .collect();
+            info!("Loaded stream duration limits for {} providers", limits.len());
+            limits
+        }
+        Err(e) => {
+            error!("Failed to parse MAX_STREAM_DURATION_BY_PROVIDER: {}", e);
+            HashMap::new()
+        }
+    }
+});
+
+fn parse_secs(value: &str) -> Option<Duration> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// The longest a stream for this request may run.
+///
+/// The provider limit takes precedence over the gateway default, and a client's
+/// `x-max-stream-duration` can only shorten whichever applies.
+pub fn limit(provider: &str, headers: &HeaderMap) -> Option<Duration> {
+    let configured = PROVIDER_LIMITS
+        .get(&provider.to_lowercase())
+        .copied()
+        .or(*DEFAULT_LIMIT);
+    let requested = headers
+        .get(MAX_DURATION_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(parse_secs);
+
+    match (configured, requested) {
+        (Some(configured), Some(requested)) => Some(configured.min(requested)),
+        (configured, requested) => configured.or(requested),
+    }
+}
+
+/// SSE error event sent in place of the rest of a stream that ran too long
+pub fn timeout_event(limit: Duration) -> Bytes {
+    info!("Ending stream after exceeding maximum duration of {:?}", limit);
+    let event = json!({
+        "error": {
+            "message": format!("Stream exceeded the maximum duration of {}s", limit.as_secs_f64()),
+            "type": TIMEOUT_ERROR_TYPE,
+            "code": MAX_STREAM_DURATION_REASON,
+        }
+    });
+    Bytes::from(format!("event: error\ndata: {}\n\ndata: [DONE]\n\n", event))
+}
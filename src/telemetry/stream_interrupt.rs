@@ -0,0 +1,81 @@
+use axum::body::Bytes;
+use serde_json::json;
+use tracing::warn;
+
+use crate::providers::event_stream;
+
+/// Wire format of a stream the gateway has to end itself, so the events it
+/// adds match the ones the client is already parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// OpenAI chunks ending in `data: [DONE]`, including streams translated to them
+    OpenAi,
+    /// Anthropic Messages events, which Anthropic streams are relayed as
+    Anthropic,
+    /// AWS event stream messages, from native Bedrock streams
+    Bedrock,
+}
+
+impl StreamFormat {
+    pub fn for_path(path: &str) -> Self {
+        if path.trim_end_matches('/').ends_with("/v1/messages") {
+            Self::Anthropic
+        } else {
+            Self::OpenAi
+        }
+    }
+
+    /// Format of a request's stream. Anthropic streams are relayed untranslated,
+    /// Bedrock's only under raw passthrough; others follow the request path.
+    pub fn for_request(provider: &str, path: &str, raw_passthrough: bool) -> Self {
+        match provider {
+            "anthropic" => Self::Anthropic,
+            "bedrock" if raw_passthrough => Self::Bedrock,
+            _ => Self::for_path(path),
+        }
+    }
+
+    /// End-of-stream marker appended after a provider's own error event. An
+    /// Anthropic or Bedrock error event already ends its stream, so nothing follows it.
+    pub fn done_event(self) -> Option<Bytes> {
+        match self {
+            Self::OpenAi => Some(Bytes::from_static(b"data: [DONE]\n\n")),
+            Self::Anthropic | Self::Bedrock => None,
+        }
+    }
+
+    /// Error event (and, for OpenAI, `[DONE]`) sent in place of the rest of a
+    /// stream whose upstream connection failed, so clients see a well-formed end
+    /// instead of a dropped connection
+    pub fn error_event(self, reason: &str) -> Bytes {
+        warn!("Upstream stream failed partway: {}", reason);
+        let message = format!("Upstream stream was interrupted: {}", reason);
+        match self {
+            Self::OpenAi => {
+                let event = json!({
+                    "error": {
+                        "message": message,
+                        "type": "server_error",
+                        "code": "stream_interrupted",
+                    }
+                });
+                Bytes::from(format!("data: {}\n\ndata: [DONE]\n\n", event))
+            }
+            Self::Anthropic => {
+                let event = json!({
+                    "type": "error",
+                    "error": {
+                        "type": "api_error",
+                        "message": message,
+                    }
+                });
+                Bytes::from(format!("event: error\ndata: {}\n\n", event))
+            }
+            Self::Bedrock => event_stream::encode_message(
+                "exception",
+                "internalServerException",
+                &json!({ "message": message }),
+            ),
+        }
+    }
+}
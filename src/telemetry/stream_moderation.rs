@@ -0,0 +1,294 @@
+use axum::body::Bytes;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{error, info, warn};
+
+use super::stream_interrupt::StreamFormat;
+use crate::providers::event_stream;
+
+/// Enforcement reason recorded in telemetry when moderation ends a stream
+pub const MODERATION_REASON: &str = "stream_moderation";
+
+/// Guardrail action recorded when moderation stops a stream, as Bedrock reports it
+pub const INTERVENED: &str = "INTERVENED";
+
+/// Characters of context kept on each side of a match in the recorded span
+const SPAN_CONTEXT_CHARS: usize = 40;
+
+/// A moderation rule as configured; `pattern` and `terms` may be combined
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleConfig {
+    name: String,
+    /// Regular expression matched against the generated text
+    #[serde(default)]
+    pattern: Option<String>,
+    /// Words or phrases matched case-insensitively
+    #[serde(default)]
+    terms: Vec<String>,
+    /// Only streams for these projects; every project when empty
+    #[serde(default)]
+    projects: Vec<String>,
+}
+
+struct Rule {
+    name: String,
+    regex: Regex,
+    projects: Vec<String>,
+}
+
+impl Rule {
+    fn compile(config: RuleConfig) -> Result<Self, String> {
+        let mut alternatives: Vec<String> = config.pattern.into_iter().collect();
+        if !config.terms.is_empty() {
+            let terms: Vec<String> = config.terms.iter().map(|term| regex::escape(term)).collect();
+            alternatives.push(format!("(?i:{})", terms.join("|")));
+        }
+        if alternatives.is_empty() {
+            return Err(format!("rule {} has neither a pattern nor terms", config.name));
+        }
+        let regex = Regex::new(&alternatives.join("|")).map_err(|e| format!("rule {}: {}", config.name, e))?;
+        Ok(Self {
+            name: config.name,
+            regex,
+            projects: config.projects,
+        })
+    }
+
+    fn applies_to(&self, project_id: Option<&str>) -> bool {
+        self.projects.is_empty() || project_id.is_some_and(|id| self.projects.iter().any(|p| p == id))
+    }
+}
+
+/// Rules from `STREAM_MODERATION_RULES`, e.g.
+/// `[{"name": "ssn", "pattern": "\\b\\d{3}-\\d{2}-\\d{4}\\b"}, {"name": "blocklist", "terms": ["project falcon"]}]`
+static RULES: Lazy<Vec<Rule>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("STREAM_MODERATION_RULES") else {
+        return Vec::new();
+    };
+    match serde_json::from_str::<Vec<RuleConfig>>(&raw) {
+        Ok(configs) => {
+            let rules: Vec<Rule> = configs
+                .into_iter()
+                .filter_map(|config| {
+                    Rule::compile(config)
+                        .map_err(|e| error!("Ignoring stream moderation {}", e))
+                        .ok()
+                })
+                .collect();
+            info!("Loaded {} stream moderation rules", rules.len());
+            rules
+        }
+        Err(e) => {
+            error!("Failed to parse STREAM_MODERATION_RULES: {}", e);
+            Vec::new()
+        }
+    }
+});
+
+/// Generated text kept for matching, from `STREAM_MODERATION_WINDOW_CHARS`, so
+/// a phrase split across chunks is still caught
+static WINDOW_CHARS: Lazy<usize> = Lazy::new(|| {
+    std::env::var("STREAM_MODERATION_WINDOW_CHARS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(256)
+        .max(16)
+});
+
+/// A rule matched in the generated text
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub rule: String,
+    /// The match in its surrounding text, with the match itself masked
+    pub span: String,
+}
+
+/// Scans the text of a streamed response through a sliding window as it is
+/// generated, against the moderation rules that apply to the request's project.
+pub struct StreamModerator {
+    rules: Vec<&'static Rule>,
+    window: String,
+    model: String,
+    format: StreamFormat,
+    /// Index of the native content block left open, closed before the stop events
+    open_block: Option<u64>,
+    /// Output tokens the native stream last reported, repeated in the stop event
+    output_tokens: u64,
+}
+
+impl StreamModerator {
+    /// A moderator for this request's stream, or None when no rule applies to it
+    pub fn for_request(project_id: Option<&str>, request_body: Option<&Value>, format: StreamFormat) -> Option<Self> {
+        let rules: Vec<&'static Rule> = RULES.iter().filter(|rule| rule.applies_to(project_id)).collect();
+        if rules.is_empty() {
+            return None;
+        }
+        let model = request_body
+            .and_then(|r| r.get("model"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        Some(Self {
+            rules,
+            window: String::new(),
+            model,
+            format,
+            open_block: None,
+            output_tokens: 0,
+        })
+    }
+
+    /// Add a streamed chunk's generated text to the window, returning the first rule it now matches
+    pub fn record(&mut self, chunk: &Value) -> Option<Violation> {
+        if let Some(model) = chunk.get("model").and_then(Value::as_str) {
+            if model != self.model {
+                self.model = model.to_string();
+            }
+        }
+        self.track_native_state(chunk);
+        let text = generated_text(chunk);
+        if text.is_empty() {
+            return None;
+        }
+        self.window.push_str(&text);
+
+        let violation = self.rules.iter().find_map(|rule| {
+            let found = rule.regex.find(&self.window)?;
+            Some(Violation {
+                rule: rule.name.clone(),
+                span: masked_span(&self.window, found.start(), found.end(), &rule.name),
+            })
+        });
+        if let Some(violation) = &violation {
+            warn!("Stream moderation rule {} matched; ending the stream", violation.rule);
+        }
+
+        // Keep only the tail of the window, on a character boundary
+        let excess = self.window.chars().count().saturating_sub(*WINDOW_CHARS);
+        if excess > 0 {
+            let cut = self.window.char_indices().nth(excess).map_or(self.window.len(), |(i, _)| i);
+            self.window.drain(..cut);
+        }
+        violation
+    }
+
+    /// Follow which native content block is open and the output tokens reported so far
+    fn track_native_state(&mut self, chunk: &Value) {
+        if let Some(tokens) = chunk
+            .pointer("/usage/output_tokens")
+            .or_else(|| chunk.pointer("/message/usage/output_tokens"))
+            .and_then(Value::as_u64)
+        {
+            self.output_tokens = tokens;
+        }
+        match self.format {
+            StreamFormat::Anthropic => match chunk.get("type").and_then(Value::as_str) {
+                Some("content_block_start") => self.open_block = chunk.get("index").and_then(Value::as_u64),
+                Some("content_block_stop") => self.open_block = None,
+                _ => {}
+            },
+            // Bedrock payloads don't name their event: a block index with a delta
+            // or start opens the block, on its own it closes it
+            StreamFormat::Bedrock => {
+                if let Some(index) = chunk.get("contentBlockIndex").and_then(Value::as_u64) {
+                    let opens = chunk.get("delta").is_some() || chunk.get("start").is_some();
+                    self.open_block = opens.then_some(index);
+                }
+            }
+            StreamFormat::OpenAi => {}
+        }
+    }
+
+    /// Events sent in place of the rest of the stream, ending it in its own format
+    pub fn stop_events(&self, violation: &Violation) -> Bytes {
+        let policy_violation = json!({
+            "type": MODERATION_REASON,
+            "rule": violation.rule,
+            "message": "The response was stopped by the gateway's content policy",
+        });
+        match self.format {
+            StreamFormat::OpenAi => {
+                let chunk = json!({
+                    "id": format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+                    "object": "chat.completion.chunk",
+                    "created": chrono::Utc::now().timestamp(),
+                    "model": self.model,
+                    "choices": [{ "index": 0, "delta": {}, "finish_reason": "content_filter" }],
+                    "policy_violation": policy_violation,
+                });
+                Bytes::from(format!("data: {}\n\ndata: [DONE]\n\n", chunk))
+            }
+            StreamFormat::Anthropic => {
+                let mut events = String::new();
+                if let Some(index) = self.open_block {
+                    let stop = json!({ "type": "content_block_stop", "index": index });
+                    events.push_str(&format!("event: content_block_stop\ndata: {}\n\n", stop));
+                }
+                let delta = json!({
+                    "type": "message_delta",
+                    "delta": { "stop_reason": "refusal", "stop_sequence": null },
+                    "usage": { "output_tokens": self.output_tokens },
+                    "policy_violation": policy_violation,
+                });
+                events.push_str(&format!("event: message_delta\ndata: {}\n\n", delta));
+                events.push_str(&format!("event: message_stop\ndata: {}\n\n", json!({ "type": "message_stop" })));
+                Bytes::from(events)
+            }
+            StreamFormat::Bedrock => {
+                let mut events = Vec::new();
+                if let Some(index) = self.open_block {
+                    let stop = json!({ "contentBlockIndex": index });
+                    events.extend_from_slice(&event_stream::encode_message("event", "contentBlockStop", &stop));
+                }
+                let stop = json!({
+                    "stopReason": "guardrail_intervened",
+                    "additionalModelResponseFields": { "policy_violation": policy_violation },
+                });
+                events.extend_from_slice(&event_stream::encode_message("event", "messageStop", &stop));
+                Bytes::from(events)
+            }
+        }
+    }
+}
+
+/// Up to `SPAN_CONTEXT_CHARS` either side of a match, with the match replaced
+fn masked_span(text: &str, start: usize, end: usize, rule: &str) -> String {
+    let before: String = {
+        let chars: Vec<char> = text[..start].chars().rev().take(SPAN_CONTEXT_CHARS).collect();
+        chars.into_iter().rev().collect()
+    };
+    let after: String = text[end..].chars().take(SPAN_CONTEXT_CHARS).collect();
+    format!("{}[REDACTED:{}]{}", before, rule, after)
+}
+
+/// Generated text (content, reasoning or tool arguments) in an OpenAI-style
+/// chunk, an Anthropic native event or a Bedrock event stream payload
+fn generated_text(chunk: &Value) -> String {
+    let mut text = String::new();
+    for delta in chunk
+        .get("choices")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|choice| choice.get("delta"))
+    {
+        for key in ["content", "reasoning_content"] {
+            if let Some(part) = delta.get(key).and_then(Value::as_str) {
+                text.push_str(part);
+            }
+        }
+        for call in delta.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(arguments) = call.pointer("/function/arguments").and_then(Value::as_str) {
+                text.push_str(arguments);
+            }
+        }
+    }
+    for pointer in ["/delta/text", "/delta/partial_json", "/delta/toolUse/input"] {
+        if let Some(part) = chunk.pointer(pointer).and_then(Value::as_str) {
+            text.push_str(part);
+        }
+    }
+    text
+}
@@ -0,0 +1,103 @@
+use super::RequestMetrics;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::debug;
+use utoipa::IntoParams;
+
+/// Completed requests buffered per subscriber before slow readers start skipping
+const TAIL_CAPACITY: usize = 1024;
+
+/// Redacted summary of a completed request for the live tail.
+///
+/// Carries no request or response bodies, headers, or user identifiers.
+#[derive(Debug, Clone, Serialize)]
+pub struct TailEvent {
+    pub timestamp: DateTime<Utc>,
+    pub id: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub method: String,
+    pub path: String,
+    pub status_code: u16,
+    pub latency_ms: u128,
+    pub ttfb_ms: u128,
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub reasoning_tokens: Option<u32>,
+    pub cost: Option<f64>,
+    pub is_streaming: bool,
+    pub error_type: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+impl TailEvent {
+    fn from_metrics(metrics: &RequestMetrics) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            id: metrics.id.clone(),
+            provider: metrics.provider.clone(),
+            model: metrics.model.clone(),
+            method: metrics.method.clone(),
+            path: metrics.path.clone(),
+            status_code: metrics.status_code,
+            latency_ms: metrics.total_latency.as_millis(),
+            ttfb_ms: metrics.ttfb.as_millis(),
+            input_tokens: metrics.input_tokens,
+            output_tokens: metrics.output_tokens,
+            reasoning_tokens: metrics.reasoning_tokens,
+            cost: metrics.cost,
+            is_streaming: metrics.is_streaming,
+            error_type: metrics.error_type.clone(),
+            tenant_id: metrics.tenant_id.clone(),
+        }
+    }
+}
+
+/// Filters accepted by the tail endpoint
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TailQuery {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub tenant_id: Option<String>,
+    /// Only show responses with status >= 400
+    #[serde(default)]
+    pub errors_only: bool,
+}
+
+impl TailQuery {
+    pub fn matches(&self, event: &TailEvent) -> bool {
+        self.provider.as_ref().map_or(true, |p| p == &event.provider)
+            && self.model.as_ref().map_or(true, |m| m == &event.model)
+            && self.tenant_id.as_ref().map_or(true, |t| event.tenant_id.as_ref() == Some(t))
+            && (!self.errors_only || event.status_code >= 400)
+    }
+}
+
+/// Fan-out of completed request summaries to live subscribers
+pub struct RequestTail {
+    sender: broadcast::Sender<TailEvent>,
+}
+
+impl Default for RequestTail {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(TAIL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl RequestTail {
+    /// Publish a completed request; a no-op when nobody is watching
+    pub fn publish(&self, metrics: &RequestMetrics) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        let _ = self.sender.send(TailEvent::from_metrics(metrics));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TailEvent> {
+        debug!("New tail subscriber ({} already watching)", self.sender.receiver_count());
+        self.sender.subscribe()
+    }
+}
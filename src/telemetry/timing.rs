@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+/// Instant at which the gateway accepted the request.
+///
+/// Inserted into the request extensions by the metrics middleware so the proxy
+/// can work out how long the request spent inside the gateway before it was
+/// dispatched upstream.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestStart(pub Instant);
+
+/// Upstream timing breakdown measured by the proxy.
+///
+/// The proxy attaches this to the response extensions; the metrics middleware
+/// reads it back when building `RequestMetrics`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpstreamTimings {
+    /// Time between the gateway accepting the request and dispatching it upstream
+    pub queue_time: Duration,
+    /// Time between dispatching the request and receiving the provider's response headers
+    pub provider_ttfb: Duration,
+    /// Part of the queue time spent waiting for a provider concurrency slot
+    pub scheduler_wait: Duration,
+}
+
+/// Output throughput in tokens per second over the given generation window
+pub fn tokens_per_second(output_tokens: Option<u32>, duration: Duration) -> Option<f64> {
+    let tokens = output_tokens?;
+    let secs = duration.as_secs_f64();
+    if tokens == 0 || secs <= 0.0 {
+        return None;
+    }
+    Some(tokens as f64 / secs)
+}
+
+/// Tracks chunk arrival times for a streaming response
+#[derive(Debug, Default)]
+pub struct ChunkTimer {
+    last_chunk: Option<Instant>,
+    first_token: Option<Instant>,
+    total_gap: Duration,
+    gap_count: u32,
+    max_gap: Duration,
+}
+
+impl ChunkTimer {
+    /// Record the arrival of a chunk and update the inter-chunk gap statistics
+    pub fn record_chunk(&mut self, at: Instant) {
+        if let Some(previous) = self.last_chunk {
+            let gap = at.saturating_duration_since(previous);
+            self.total_gap += gap;
+            self.gap_count += 1;
+            self.max_gap = self.max_gap.max(gap);
+        }
+        self.last_chunk = Some(at);
+    }
+
+    /// Record that generated content was seen; only the first call is kept
+    pub fn record_token(&mut self, at: Instant) {
+        if self.first_token.is_none() {
+            self.first_token = Some(at);
+        }
+    }
+
+    pub fn first_token(&self) -> Option<Instant> {
+        self.first_token
+    }
+
+    pub fn mean_gap(&self) -> Option<Duration> {
+        if self.gap_count == 0 {
+            return None;
+        }
+        Some(self.total_gap / self.gap_count)
+    }
+
+    pub fn max_gap(&self) -> Option<Duration> {
+        if self.gap_count == 0 {
+            return None;
+        }
+        Some(self.max_gap)
+    }
+}
@@ -0,0 +1,62 @@
+use super::RequestMetrics;
+use axum::body::Bytes;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use once_cell::sync::Lazy;
+use tracing::debug;
+
+/// Request header that opts a single call into usage annotations
+const OPT_IN_HEADER: &str = "x-gateway-usage";
+
+/// Annotate every response, read once from `ENABLE_USAGE_HEADERS`
+static ENABLED: Lazy<bool> = Lazy::new(|| {
+    std::env::var("ENABLE_USAGE_HEADERS")
+        .map(|v| v.parse().unwrap_or(false))
+        .unwrap_or(false)
+});
+
+/// Whether the response to this request should carry usage annotations
+pub fn requested(headers: &HeaderMap) -> bool {
+    *ENABLED
+        || headers
+            .get(OPT_IN_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map_or(false, |v| v.eq_ignore_ascii_case("true") || v == "1")
+}
+
+/// Name/value pairs for the annotations available in the metrics
+fn annotations(metrics: &RequestMetrics) -> Vec<(&'static str, String)> {
+    let mut values = vec![("x-gateway-latency-ms", metrics.total_latency.as_millis().to_string())];
+    if let Some(cost) = metrics.cost {
+        values.push(("x-gateway-cost", format!("{:.8}", cost)));
+    }
+    if let Some(input) = metrics.input_tokens {
+        values.push(("x-gateway-input-tokens", input.to_string()));
+    }
+    if let Some(output) = metrics.output_tokens {
+        values.push(("x-gateway-output-tokens", output.to_string()));
+    }
+    values
+}
+
+/// Add the usage annotations as response headers
+pub fn apply(headers: &mut HeaderMap, metrics: &RequestMetrics) {
+    for (name, value) in annotations(metrics) {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    }
+}
+
+/// Usage annotations as SSE comment lines, sent after the final chunk of a stream.
+///
+/// Comments are ignored by SSE clients that don't look for them, so this is
+/// safe to append to any OpenAI-compatible stream.
+pub fn sse_comment(metrics: &RequestMetrics) -> Bytes {
+    let mut comment = String::new();
+    for (name, value) in annotations(metrics) {
+        comment.push_str(&format!(": {}: {}\n", name, value));
+    }
+    comment.push('\n');
+    debug!("Appending usage annotations to stream: {}", comment.trim_end());
+    Bytes::from(comment)
+}
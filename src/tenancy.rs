@@ -0,0 +1,523 @@
+use crate::cluster;
+use crate::error::AppError;
+use crate::shared_state;
+use crate::telemetry::pricing::PriceOverride;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Header carrying the gateway key that identifies a tenant
+pub const TENANT_KEY_HEADER: &str = "x-gateway-key";
+
+/// Tenant definition as read from `GATEWAY_TENANTS` / `GATEWAY_TENANTS_FILE`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantConfig {
+    pub id: String,
+    /// Gateway keys that authenticate as this tenant
+    pub api_keys: Vec<String>,
+    /// Headers injected per provider, e.g. `{"openai": {"authorization": "Bearer sk-..."}}`
+    #[serde(default)]
+    pub provider_headers: HashMap<String, HashMap<String, String>>,
+    /// Providers the tenant may use; all when unset
+    pub allowed_providers: Option<Vec<String>>,
+    /// Models the tenant may use; all when unset
+    pub allowed_models: Option<Vec<String>>,
+    /// Spend limit in USD for the lifetime of the process, or of the Redis state when `REDIS_URL` is set
+    pub budget_usd: Option<f64>,
+    pub requests_per_minute: Option<u32>,
+    /// Elasticsearch index for this tenant's telemetry
+    pub telemetry_index: Option<String>,
+    /// Default scheduling class (`interactive`, `normal` or `batch`) when `x-priority` is absent
+    pub priority: Option<String>,
+    /// Negotiated prices keyed by `provider:model`, `model`, `provider:*` or `*`
+    #[serde(default)]
+    pub pricing: HashMap<String, PriceOverride>,
+    /// Currency costs are reported in, converted with `FX_RATES`; USD when unset
+    pub currency: Option<String>,
+}
+
+/// Reservations left unsettled this long are dropped, from `BUDGET_RESERVATION_TTL_SECS`
+static RESERVATION_TTL: Lazy<Duration> = Lazy::new(|| {
+    Duration::from_secs(
+        std::env::var("BUDGET_RESERVATION_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(600),
+    )
+});
+
+/// Window of the per-minute request limit
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Fractions of a budget or rate limit at which clients are warned and an alert is raised
+const SOFT_LIMIT_THRESHOLDS: [f64; 2] = [0.8, 0.95];
+
+/// Replicas send the webhook for a budget threshold at most this often
+const BUDGET_ALERT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Receives soft-limit alerts as JSON, from `SOFT_LIMIT_WEBHOOK_URL`
+static SOFT_LIMIT_WEBHOOK_URL: Lazy<Option<String>> =
+    Lazy::new(|| std::env::var("SOFT_LIMIT_WEBHOOK_URL").ok().filter(|u| !u.is_empty()));
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+/// A limit that is close to being reached
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoftLimit {
+    Budget,
+    RateLimit,
+}
+
+impl SoftLimit {
+    /// How long a crossing stays claimed across the cluster; a rate limit can
+    /// be crossed again every window, a budget rarely falls back
+    fn alert_ttl(self) -> Duration {
+        match self {
+            SoftLimit::Budget => BUDGET_ALERT_TTL,
+            SoftLimit::RateLimit => RATE_WINDOW,
+        }
+    }
+}
+
+/// A tenant nearing one of its limits; sent to clients as headers and raised as an alert
+#[derive(Debug, Clone, Serialize)]
+pub struct SoftLimitWarning {
+    pub tenant_id: String,
+    pub limit: SoftLimit,
+    /// Highest threshold crossed, e.g. 0.95
+    pub threshold: f64,
+    /// USD spent and reserved, or requests in the last minute
+    pub used: f64,
+    pub max: f64,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl SoftLimitWarning {
+    /// The response header announcing this warning
+    fn header(&self) -> (&'static str, String) {
+        match self.limit {
+            SoftLimit::Budget => ("x-gateway-budget-remaining", format!("{:.4}", (self.max - self.used).max(0.0))),
+            SoftLimit::RateLimit => (
+                "x-gateway-ratelimit-warning",
+                format!("{:.0}% of {} requests per minute used", self.threshold * 100.0, self.max),
+            ),
+        }
+    }
+
+    fn alert(self) {
+        warn!(
+            tenant_id = %self.tenant_id,
+            limit = ?self.limit,
+            threshold = self.threshold,
+            "Tenant {} has used {:.0}% of its {:?} limit ({:.4} of {:.4})",
+            self.tenant_id, self.threshold * 100.0, self.limit, self.used, self.max
+        );
+        let Some(url) = SOFT_LIMIT_WEBHOOK_URL.clone() else {
+            return;
+        };
+        tokio::spawn(async move {
+            // Usage is shared between replicas, so each of them crosses the same threshold;
+            // whichever does first sends the webhook
+            let crossing = format!("soft-limit:{}:{:?}:{}", self.tenant_id, self.limit, self.threshold);
+            if !cluster::claim(&crossing, self.limit.alert_ttl()).await {
+                debug!("Soft-limit webhook for {} already sent by another replica", self.tenant_id);
+                return;
+            }
+            match CLIENT.post(&url).json(&self).send().await {
+                Ok(response) if !response.status().is_success() => {
+                    error!("Soft-limit webhook returned {}", response.status());
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to deliver soft-limit webhook: {}", e),
+            }
+        });
+    }
+}
+
+/// Add the headers for a request's soft-limit warnings to its response
+pub fn add_soft_limit_headers(headers: &mut HeaderMap, warnings: &[SoftLimitWarning]) {
+    for warning in warnings {
+        let (name, value) = warning.header();
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+/// An estimated cost held against a tenant's budget while its request runs
+struct Reservation {
+    amount: f64,
+    reserved_at: Instant,
+}
+
+/// A tenant's usage as counted when its latest request was admitted; across
+/// every replica when shared state is enabled
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    pub spent_usd: f64,
+    pub reserved_usd: f64,
+    /// Requests in the last minute, including the one just admitted
+    pub recent_requests: u64,
+}
+
+/// A configured tenant plus its runtime usage counters. With `REDIS_URL` set the
+/// counters live in Redis and these local ones only serve while it is unreachable.
+pub struct Tenant {
+    pub config: TenantConfig,
+    spent_usd: Mutex<f64>,
+    /// Outstanding reservations by gateway request ID, which is generated per request
+    /// and never taken from the client, so one request can't replace another's
+    reservations: Mutex<HashMap<String, Reservation>>,
+    recent_requests: Mutex<VecDeque<Instant>>,
+    /// Soft-limit thresholds crossed when last checked, so each crossing alerts once
+    budget_alert_level: AtomicUsize,
+    rate_alert_level: AtomicUsize,
+}
+
+/// Identifies the tenant a request was resolved to; stored in request extensions
+#[derive(Clone)]
+pub struct TenantContext(pub Arc<Tenant>);
+
+impl Tenant {
+    fn new(config: TenantConfig) -> Self {
+        Self {
+            config,
+            spent_usd: Mutex::new(0.0),
+            reservations: Mutex::new(HashMap::new()),
+            recent_requests: Mutex::new(VecDeque::new()),
+            budget_alert_level: AtomicUsize::new(0),
+            rate_alert_level: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.config.id
+    }
+
+    pub fn spent_usd(&self) -> f64 {
+        *self.spent_usd.lock()
+    }
+
+    /// Estimated cost of requests still in flight
+    pub fn reserved_usd(&self) -> f64 {
+        self.reservations.lock().values().map(|r| r.amount).sum()
+    }
+
+    /// Check that the tenant may call this provider
+    pub fn authorize_provider(&self, provider: &str) -> Result<(), AppError> {
+        match &self.config.allowed_providers {
+            Some(allowed) if !allowed.iter().any(|p| p.eq_ignore_ascii_case(provider)) => {
+                Err(AppError::Forbidden(format!(
+                    "Provider {} is not enabled for tenant {}",
+                    provider, self.config.id
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the tenant may call this model
+    pub fn authorize_model(&self, model: &str) -> Result<(), AppError> {
+        match &self.config.allowed_models {
+            Some(allowed) if !allowed.iter().any(|m| m == model) => Err(AppError::Forbidden(format!(
+                "Model {} is not enabled for tenant {}",
+                model, self.config.id
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Key of this tenant's counters in the shared state
+    fn shared_key(&self) -> String {
+        format!("tenant:{}", self.config.id)
+    }
+
+    /// Enforce the tenant's budget and per-minute request limit, counting this request
+    pub async fn admit(&self) -> Result<Usage, AppError> {
+        let mut usage = Usage::default();
+        if let Some(budget) = self.config.budget_usd {
+            let (spent, reserved) = self.budget_usage().await;
+            usage.spent_usd = spent;
+            usage.reserved_usd = reserved;
+            if spent + reserved >= budget {
+                return Err(AppError::BudgetExceeded(format!(
+                    "Tenant {} has spent ${:.4} (${:.4} more reserved) of its ${:.2} budget",
+                    self.config.id, spent, reserved, budget
+                )));
+            }
+        }
+
+        if let Some(limit) = self.config.requests_per_minute {
+            let (admitted, count) = self.count_request(limit).await;
+            usage.recent_requests = count;
+            if !admitted {
+                return Err(AppError::RateLimited(format!(
+                    "Tenant {} exceeded {} requests per minute",
+                    self.config.id, limit
+                )));
+            }
+        }
+
+        Ok(usage)
+    }
+
+    /// USD spent and reserved, from the shared state when it is available
+    async fn budget_usage(&self) -> (f64, f64) {
+        if let Some(shared) = shared_state::get().await {
+            match shared.budget_usage(&self.shared_key(), *RESERVATION_TTL).await {
+                Ok(usage) => return usage,
+                Err(e) => warn!("Shared budget of tenant {} unavailable, using this replica's: {}", self.config.id, e),
+            }
+        }
+        (self.spent_usd(), self.reserved_usd())
+    }
+
+    /// Count a request against the per-minute limit unless it is reached;
+    /// returns whether it was admitted and the requests in the window
+    async fn count_request(&self, limit: u32) -> (bool, u64) {
+        if let Some(shared) = shared_state::get().await {
+            let key = format!("{}:requests", self.shared_key());
+            match shared.count_request(&key, limit, RATE_WINDOW).await {
+                Ok(counted) => return counted,
+                Err(e) => warn!("Shared rate limit of tenant {} unavailable, using this replica's: {}", self.config.id, e),
+            }
+        }
+        let now = Instant::now();
+        let mut recent = self.recent_requests.lock();
+        while recent
+            .front()
+            .map_or(false, |t| now.saturating_duration_since(*t) >= RATE_WINDOW)
+        {
+            recent.pop_front();
+        }
+        if recent.len() >= limit as usize {
+            return (false, recent.len() as u64);
+        }
+        recent.push_back(now);
+        (true, recent.len() as u64)
+    }
+
+    /// Limits this tenant is close to reaching, alerting the first time each threshold is crossed
+    pub fn soft_limit_warnings(&self, usage: &Usage) -> Vec<SoftLimitWarning> {
+        let mut warnings = Vec::new();
+        if let Some(budget) = self.config.budget_usd {
+            let used = usage.spent_usd + usage.reserved_usd;
+            warnings.extend(self.soft_limit(SoftLimit::Budget, &self.budget_alert_level, used, budget));
+        }
+        if let Some(limit) = self.config.requests_per_minute {
+            let used = usage.recent_requests as f64;
+            warnings.extend(self.soft_limit(SoftLimit::RateLimit, &self.rate_alert_level, used, f64::from(limit)));
+        }
+        warnings
+    }
+
+    fn soft_limit(&self, limit: SoftLimit, alerted: &AtomicUsize, used: f64, max: f64) -> Option<SoftLimitWarning> {
+        let level = SOFT_LIMIT_THRESHOLDS
+            .iter()
+            .filter(|threshold| max > 0.0 && used >= *threshold * max)
+            .count();
+        // Falling back below a threshold re-arms its alert
+        let previous = alerted.swap(level, Ordering::Relaxed);
+        if level == 0 {
+            return None;
+        }
+        let warning = SoftLimitWarning {
+            tenant_id: self.config.id.clone(),
+            limit,
+            threshold: SOFT_LIMIT_THRESHOLDS[level - 1],
+            used,
+            max,
+            detected_at: Utc::now(),
+        };
+        if level > previous {
+            warning.clone().alert();
+        }
+        Some(warning)
+    }
+
+    /// Whether the tenant has its own credentials configured for the provider
+    pub fn has_provider_headers(&self, provider: &str) -> bool {
+        self.config
+            .provider_headers
+            .get(&provider.to_lowercase())
+            .is_some_and(|headers| !headers.is_empty())
+    }
+
+    /// Inject the tenant's own credentials for the provider, replacing any sent by the client
+    pub fn apply_provider_headers(&self, provider: &str, headers: &mut HeaderMap) {
+        let Some(provider_headers) = self.config.provider_headers.get(&provider.to_lowercase()) else {
+            return;
+        };
+        for (name, value) in provider_headers {
+            match (HeaderName::try_from(name.as_str()), HeaderValue::from_str(value)) {
+                (Ok(name), Ok(value)) => {
+                    headers.insert(name, value);
+                }
+                _ => warn!("Skipping invalid {} header {} for tenant {}", provider, name, self.config.id),
+            }
+        }
+    }
+
+    /// Hold a request's estimated cost against the budget until it settles, so
+    /// concurrent requests can't together spend past a nearly exhausted budget
+    pub async fn reserve(&self, request_id: &str, estimate: f64) -> Result<(), AppError> {
+        let Some(budget) = self.config.budget_usd else {
+            return Ok(());
+        };
+        if let Some(shared) = shared_state::get().await {
+            match shared.reserve(&self.shared_key(), request_id, estimate, budget, *RESERVATION_TTL).await {
+                Ok((true, _, _)) => {
+                    debug!("Reserved ${:.4} for request {} of tenant {}", estimate, request_id, self.config.id);
+                    return Ok(());
+                }
+                Ok((false, spent, reserved)) => return Err(self.over_budget(spent, reserved, budget, estimate)),
+                Err(e) => warn!("Shared budget of tenant {} unavailable, using this replica's: {}", self.config.id, e),
+            }
+        }
+        let spent = self.spent_usd();
+        let now = Instant::now();
+        let mut reservations = self.reservations.lock();
+        reservations.retain(|id, r| {
+            let live = now.saturating_duration_since(r.reserved_at) < *RESERVATION_TTL;
+            if !live {
+                warn!("Dropping unsettled ${:.4} reservation for request {} of tenant {}", r.amount, id, self.config.id);
+            }
+            live
+        });
+        let reserved: f64 = reservations.values().map(|r| r.amount).sum();
+        if spent + reserved + estimate > budget {
+            return Err(self.over_budget(spent, reserved, budget, estimate));
+        }
+        debug!("Reserved ${:.4} for request {} of tenant {}", estimate, request_id, self.config.id);
+        reservations.insert(
+            request_id.to_string(),
+            Reservation {
+                amount: estimate,
+                reserved_at: now,
+            },
+        );
+        Ok(())
+    }
+
+    fn over_budget(&self, spent: f64, reserved: f64, budget: f64, estimate: f64) -> AppError {
+        AppError::BudgetExceeded(format!(
+            "Tenant {} has spent ${:.4} (${:.4} more reserved) of its ${:.2} budget; this request is estimated at ${:.4}",
+            self.config.id, spent, reserved, budget, estimate
+        ))
+    }
+
+    /// Release a request's reservation and charge what it actually cost
+    pub async fn settle(&self, request_id: Option<&str>, cost: Option<f64>) {
+        if let Some(request_id) = request_id {
+            self.reservations.lock().remove(request_id);
+        }
+        if let Some(cost) = cost {
+            *self.spent_usd.lock() += cost;
+        }
+        if let Some(shared) = shared_state::get().await {
+            if let Err(e) = shared.settle(&self.shared_key(), request_id, cost).await {
+                error!("Failed to settle request of tenant {} in shared state: {}", self.config.id, e);
+            }
+        }
+    }
+}
+
+/// All configured tenants, indexed by gateway key and by id
+pub struct Tenants {
+    by_key: HashMap<String, Arc<Tenant>>,
+    by_id: HashMap<String, Arc<Tenant>>,
+    required: bool,
+}
+
+impl Tenants {
+    fn from_configs(configs: Vec<TenantConfig>, required: bool) -> Self {
+        let mut by_key = HashMap::new();
+        let mut by_id = HashMap::new();
+        for mut config in configs {
+            config.pricing.retain(|key, price| match price.problem() {
+                Some(problem) => {
+                    error!("Ignoring price override {} of tenant {}: {}", key, config.id, problem);
+                    false
+                }
+                None => true,
+            });
+            let tenant = Arc::new(Tenant::new(config));
+            for key in &tenant.config.api_keys {
+                by_key.insert(key.clone(), tenant.clone());
+            }
+            by_id.insert(tenant.config.id.clone(), tenant);
+        }
+        Self { by_key, by_id, required }
+    }
+
+    fn load() -> Self {
+        let raw = std::env::var("GATEWAY_TENANTS").ok().or_else(|| {
+            let path = std::env::var("GATEWAY_TENANTS_FILE").ok()?;
+            std::fs::read_to_string(&path)
+                .map_err(|e| error!("Failed to read tenants file {}: {}", path, e))
+                .ok()
+        });
+        let required = std::env::var("REQUIRE_TENANT")
+            .map(|v| v.parse().unwrap_or(false))
+            .unwrap_or(false);
+
+        let configs: Vec<TenantConfig> = match raw {
+            Some(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+                error!("Failed to parse tenant configuration: {}", e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        info!("Loaded {} tenants (tenant key required: {})", configs.len(), required);
+        Self::from_configs(configs, required)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Tenant>> {
+        self.by_id.get(id).cloned()
+    }
+
+    /// Resolve the tenant for a request from its gateway key
+    pub fn resolve(&self, headers: &HeaderMap) -> Result<Option<Arc<Tenant>>, AppError> {
+        let key = headers.get(TENANT_KEY_HEADER).and_then(|h| h.to_str().ok());
+        match key {
+            Some(key) => match self.by_key.get(key) {
+                Some(tenant) => {
+                    debug!("Resolved tenant {}", tenant.id());
+                    Ok(Some(tenant.clone()))
+                }
+                None => {
+                    warn!("Rejected request with unknown gateway key");
+                    Err(AppError::MissingApiKey)
+                }
+            },
+            None if self.required => Err(AppError::MissingApiKey),
+            None => Ok(None),
+        }
+    }
+}
+
+pub static TENANTS: Lazy<Tenants> = Lazy::new(Tenants::load);
+
+/// Settle a completed request against its tenant: release its reservation and add its cost
+pub async fn settle(tenant_id: &str, request_id: Option<&str>, cost: Option<f64>) {
+    if let Some(tenant) = TENANTS.get(tenant_id) {
+        tenant.settle(request_id, cost).await;
+    }
+}
+
+/// Telemetry index override for a tenant, if any
+pub fn telemetry_index(tenant_id: &str) -> Option<String> {
+    TENANTS.get(tenant_id)?.config.telemetry_index.clone()
+}
@@ -0,0 +1,164 @@
+use super::common::*;
+use serde_json::Value;
+
+const MODEL: &str = "claude-3-5-haiku-latest";
+
+#[tokio::test]
+async fn test_anthropic_non_streaming_is_translated() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("anthropic", chat_body(MODEL, false)).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_chat_completion(&body);
+    assert_eq!(
+        body["usage"]["total_tokens"].as_u64(),
+        Some(body["usage"]["prompt_tokens"].as_u64().unwrap() + body["usage"]["completion_tokens"].as_u64().unwrap())
+    );
+}
+
+#[tokio::test]
+async fn test_anthropic_raw_passthrough_is_native() {
+    let gateway = MockGateway::start().await;
+    let body = serde_json::json!({
+        "model": MODEL,
+        "max_tokens": 64,
+        "messages": [{ "role": "user", "content": "Hello" }]
+    });
+    let response = gateway.chat_with_headers("anthropic", body, &[("x-raw-passthrough", "true")]).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["type"], "message");
+    assert_eq!(body["content"][0]["text"], MOCK_REPLY);
+    assert!(body.get("choices").is_none());
+}
+
+#[tokio::test]
+async fn test_anthropic_streaming() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("anthropic", chat_body(MODEL, true)).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, _) = read_events(response).await;
+    assert!(events.iter().any(|e| e["type"] == "message_start"));
+    assert!(events.iter().any(|e| e["type"] == "message_stop"));
+}
+
+#[tokio::test]
+async fn test_anthropic_stream_moderation_is_native() {
+    let gateway = MockGateway::start_with_env(&[(
+        "STREAM_MODERATION_RULES",
+        r#"[{"name": "mock-mention", "terms": ["Mock Provider"]}]"#,
+    )])
+    .await;
+    let response = gateway.chat("anthropic", chat_body(MODEL, true)).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(!done, "an Anthropic stream has no [DONE]");
+    let types: Vec<&str> = events.iter().filter_map(|e| e["type"].as_str()).collect();
+    assert_eq!(types[types.len() - 3..], ["content_block_stop", "message_delta", "message_stop"]);
+    let delta = &events[events.len() - 2];
+    assert_eq!(delta["delta"]["stop_reason"], "refusal");
+    assert_eq!(delta["policy_violation"]["rule"], "mock-mention");
+    let content: String = events.iter().filter_map(|e| e["delta"]["text"].as_str()).collect();
+    assert!(!content.contains("provider"), "unexpected content: {}", content);
+}
+
+#[tokio::test]
+async fn test_anthropic_server_error() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("anthropic", chat_body("mock-error-500", false)).await;
+
+    assert_eq!(response.status(), 500);
+}
+
+#[tokio::test]
+async fn test_anthropic_tool_use_is_translated() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("anthropic", chat_body("mock-tool-use", false)).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    let message = &body["choices"][0]["message"];
+    assert_eq!(body["choices"][0]["finish_reason"], "tool_calls");
+    assert!(message["content"].is_null());
+    assert_eq!(message["tool_calls"][0]["id"], "toolu_mock_weather");
+    assert_eq!(message["tool_calls"][0]["type"], "function");
+    assert_eq!(message["tool_calls"][0]["function"]["name"], "get_weather");
+    let arguments: Value =
+        serde_json::from_str(message["tool_calls"][0]["function"]["arguments"].as_str().unwrap()).unwrap();
+    assert_eq!(arguments["city"], "London");
+}
+
+#[tokio::test]
+async fn test_anthropic_tool_conversation_round_trips() {
+    let gateway = MockGateway::start().await;
+    let body = serde_json::json!({
+        "model": MODEL,
+        "max_tokens": 50,
+        "messages": [
+            { "role": "system", "content": "You are a weather assistant." },
+            { "role": "user", "content": "Weather in London and Paris?" },
+            {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [
+                    { "id": "call_1", "type": "function", "function": { "name": "get_weather", "arguments": "{\"city\":\"London\"}" } },
+                    { "id": "call_2", "type": "function", "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" } }
+                ]
+            },
+            { "role": "tool", "tool_call_id": "call_1", "content": "12C and raining" },
+            { "role": "tool", "tool_call_id": "call_2", "content": "18C and sunny" },
+            { "role": "user", "content": "Which is warmer?" }
+        ],
+        "tools": [{
+            "type": "function",
+            "function": {
+                "name": "get_weather",
+                "parameters": { "type": "object", "properties": { "city": { "type": "string" } } }
+            }
+        }],
+        "tool_choice": "auto"
+    });
+    let response = gateway.chat("anthropic", body).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_chat_completion(&body);
+}
+
+#[tokio::test]
+async fn test_anthropic_string_stop_becomes_a_list() {
+    let gateway = MockGateway::start().await;
+    let mut body = chat_body(MODEL, false);
+    body["stop"] = serde_json::json!("\n");
+    let response = gateway.chat("anthropic", body).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_chat_completion(&body);
+}
+
+#[tokio::test]
+async fn test_anthropic_experiment_template_replaces_openai_system_message() {
+    let gateway = MockGateway::start_with_env(&[(
+        "EXPERIMENTS",
+        r#"[{"name": "brief", "model": "mock-echo-system", "variants": [{"name": "brief", "prompt_template": "Be brief. {{system}}"}]}]"#,
+    )])
+    .await;
+    let body = serde_json::json!({
+        "model": "mock-echo-system",
+        "max_tokens": 50,
+        "messages": [
+            { "role": "developer", "content": "You are a pirate." },
+            { "role": "user", "content": "Say hello" }
+        ]
+    });
+    let response = gateway.chat_with_headers("anthropic", body, &[("x-user-id", "user-1")]).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "Be brief. You are a pirate.");
+}
@@ -0,0 +1,101 @@
+use super::common::*;
+use aws_event_stream_parser::{parse_message, HeaderValue};
+use serde_json::Value;
+
+const MODEL: &str = "anthropic.claude-3-haiku-20240307-v1:0";
+
+#[tokio::test]
+async fn test_bedrock_non_streaming_is_translated() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("bedrock", chat_body(MODEL, false)).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_chat_completion(&body);
+}
+
+#[tokio::test]
+async fn test_bedrock_raw_passthrough_is_native() {
+    let gateway = MockGateway::start().await;
+    let body = serde_json::json!({
+        "model": MODEL,
+        "messages": [{ "role": "user", "content": [{ "text": "Hello" }] }]
+    });
+    let response = gateway.chat_with_headers("bedrock", body, &[("x-raw-passthrough", "true")]).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["output"]["message"]["content"][0]["text"], MOCK_REPLY);
+    assert_eq!(body["stopReason"], "end_turn");
+}
+
+#[tokio::test]
+async fn test_bedrock_event_stream_is_translated() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("bedrock", chat_body(MODEL, true)).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done, "translated stream should end with [DONE]");
+    assert_eq!(streamed_text(&events), MOCK_REPLY);
+    assert!(events.iter().all(|e| e["object"] == "chat.completion.chunk"));
+
+    let last = events.last().expect("no chunks received");
+    assert_eq!(last["choices"][0]["finish_reason"], "stop");
+    assert!(last["usage"]["completion_tokens"].as_u64().unwrap_or(0) > 0);
+}
+
+#[tokio::test]
+async fn test_bedrock_native_stream_moderation() {
+    let gateway = MockGateway::start_with_env(&[(
+        "STREAM_MODERATION_RULES",
+        r#"[{"name": "mock-mention", "terms": ["Mock Provider"]}]"#,
+    )])
+    .await;
+    let body = serde_json::json!({
+        "model": MODEL,
+        "stream": true,
+        "messages": [{ "role": "user", "content": [{ "text": "Hello" }] }]
+    });
+    let response = gateway.chat_with_headers("bedrock", body, &[("x-raw-passthrough", "true")]).await;
+
+    assert_eq!(response.status(), 200);
+    let bytes = response.bytes().await.unwrap();
+    let events = event_stream_events(&bytes);
+    let types: Vec<&str> = events.iter().map(|(event_type, _)| event_type.as_str()).collect();
+    assert_eq!(types[types.len() - 2..], ["contentBlockStop", "messageStop"]);
+    let (_, stop) = events.last().unwrap();
+    assert_eq!(stop["stopReason"], "guardrail_intervened");
+    assert_eq!(stop["additionalModelResponseFields"]["policy_violation"]["rule"], "mock-mention");
+    let content: String = events.iter().filter_map(|(_, e)| e["delta"]["text"].as_str()).collect();
+    assert!(!content.contains("provider"), "unexpected content: {}", content);
+}
+
+/// Event types and JSON payloads of a native Bedrock event stream
+fn event_stream_events(mut bytes: &[u8]) -> Vec<(String, Value)> {
+    let mut events = Vec::new();
+    while !bytes.is_empty() {
+        let (rest, message) = parse_message(bytes).expect("malformed event stream message");
+        let event_type = message
+            .headers
+            .headers
+            .iter()
+            .find(|h| h.key == ":event-type")
+            .and_then(|h| match &h.value {
+                HeaderValue::String(s) => Some(s.to_string()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        events.push((event_type, serde_json::from_slice(message.body).unwrap()));
+        bytes = rest;
+    }
+    events
+}
+
+#[tokio::test]
+async fn test_bedrock_throttling_error() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("bedrock", chat_body("mock-error-429", false)).await;
+
+    assert_eq!(response.status(), 429);
+}
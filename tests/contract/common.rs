@@ -0,0 +1,195 @@
+use futures_util::StreamExt;
+use reqwest::{Client, Response};
+use serde_json::Value;
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Text the mock providers reply with
+pub const MOCK_REPLY: &str = "Hello from the mock provider.";
+
+/// Reasoning the mock providers return for `reasoning` models
+pub const MOCK_REASONING: &str = "The user wants a short greeting, so reply briefly.";
+
+/// A gateway process routed to the mock providers, killed when dropped
+pub struct MockGateway {
+    child: Child,
+    pub url: String,
+    client: Client,
+}
+
+impl MockGateway {
+    pub async fn start() -> Self {
+        Self::start_with_env(&[]).await
+    }
+
+    /// Start a gateway with extra environment variables, e.g. to enable an optional feature
+    pub async fn start_with_env(env: &[(&str, &str)]) -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port")
+            .port();
+
+        let child = Command::new(env!("CARGO_BIN_EXE_noveum-ai-gateway"))
+            .env("PORT", port.to_string())
+            .env("HOST", "127.0.0.1")
+            .env("MOCK_PROVIDERS", "true")
+            .env("ENABLE_ELASTICSEARCH", "false")
+            .env("RUST_LOG", "warn")
+            .envs(env.iter().copied())
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("Failed to start the gateway");
+
+        let gateway = Self {
+            child,
+            url: format!("http://127.0.0.1:{}", port),
+            client: Client::new(),
+        };
+        gateway.wait_until_healthy().await;
+        gateway
+    }
+
+    async fn wait_until_healthy(&self) {
+        for _ in 0..120 {
+            if let Ok(response) = self.client.get(format!("{}/health", self.url)).send().await {
+                if response.status().is_success() {
+                    return;
+                }
+            }
+            sleep(Duration::from_millis(250)).await;
+        }
+        panic!("Gateway did not become healthy at {}", self.url);
+    }
+
+    /// Send SIGTERM, starting a graceful shutdown
+    pub fn terminate(&self) {
+        Command::new("kill")
+            .args(["-TERM", &self.child.id().to_string()])
+            .status()
+            .expect("Failed to signal the gateway");
+    }
+
+    /// POST a chat completion through the gateway
+    pub async fn chat(&self, provider: &str, body: Value) -> Response {
+        self.chat_with_headers(provider, body, &[]).await
+    }
+
+    /// POST a chat completion with extra request headers
+    pub async fn chat_with_headers(&self, provider: &str, body: Value, headers: &[(&str, &str)]) -> Response {
+        let mut request = self
+            .client
+            .post(format!("{}/v1/chat/completions", self.url))
+            .header("x-provider", provider)
+            .json(&body);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        request = match provider {
+            "bedrock" => request
+                .header("x-aws-access-key-id", "AKIAMOCK")
+                .header("x-aws-secret-access-key", "mock-secret")
+                .header("x-aws-region", "us-east-1"),
+            _ => request.header("authorization", "Bearer mock-key"),
+        };
+
+        request.send().await.expect("Gateway request failed")
+    }
+
+    /// Subscribe to `/admin/tail` for one provider; needs `ADMIN_API_KEY=admin-test-key`
+    pub async fn tail(&self, provider: &str) -> Response {
+        let response = self
+            .client
+            .get(format!("{}/admin/tail", self.url))
+            .query(&[("provider", provider)])
+            .header("x-admin-key", "admin-test-key")
+            .send()
+            .await
+            .expect("Tail request failed");
+        assert_eq!(response.status(), 200);
+        response
+    }
+}
+
+impl Drop for MockGateway {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub fn chat_body(model: &str, stream: bool) -> Value {
+    serde_json::json!({
+        "model": model,
+        "messages": [{ "role": "user", "content": "Say hello" }],
+        "max_tokens": 50,
+        "stream": stream
+    })
+}
+
+/// Read a server-sent event stream, returning the JSON payloads and whether `[DONE]` was seen
+pub async fn read_events(response: Response) -> (Vec<Value>, bool) {
+    let mut stream = response.bytes_stream();
+    let mut buffer = String::new();
+    while let Some(chunk) = stream.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk.expect("Stream failed")));
+    }
+
+    let mut events = Vec::new();
+    let mut done = false;
+    for line in buffer.lines() {
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            continue;
+        };
+        if data == "[DONE]" {
+            done = true;
+        } else if let Ok(value) = serde_json::from_str(data) {
+            events.push(value);
+        }
+    }
+    (events, done)
+}
+
+/// First request recorded on a tail subscription, waiting until its metrics are in
+pub async fn next_tail_event(tail: Response) -> Value {
+    let mut stream = tail.bytes_stream();
+    let mut buffer = String::new();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let chunk = stream.next().await.expect("Tail ended").expect("Tail failed");
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+            let event = buffer
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .find_map(|data| serde_json::from_str::<Value>(data.trim()).ok());
+            if let Some(event) = event {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("No tail event")
+}
+
+/// Concatenated `choices[0].delta.content` of OpenAI-style chunks
+pub fn streamed_text(events: &[Value]) -> String {
+    events
+        .iter()
+        .filter_map(|e| e["choices"][0]["delta"]["content"].as_str())
+        .collect()
+}
+
+/// Check the fields every OpenAI chat completion carries
+pub fn assert_chat_completion(body: &Value) {
+    assert_eq!(body["object"], "chat.completion", "unexpected body: {}", body);
+    assert!(body["id"].is_string());
+    assert!(body["created"].is_u64());
+    assert_eq!(body["choices"][0]["index"], 0);
+    assert_eq!(body["choices"][0]["message"]["role"], "assistant");
+    assert_eq!(body["choices"][0]["message"]["content"], MOCK_REPLY);
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+    assert!(body["usage"]["prompt_tokens"].as_u64().unwrap_or(0) > 0);
+    assert!(body["usage"]["completion_tokens"].as_u64().unwrap_or(0) > 0);
+}
@@ -0,0 +1,64 @@
+use super::common::*;
+use serde_json::Value;
+
+const MODEL: &str = "mock-reasoning";
+
+fn streamed_reasoning(events: &[Value]) -> String {
+    events
+        .iter()
+        .filter_map(|e| e["choices"][0]["delta"]["reasoning_content"].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_groq_reasoning_is_passed_through() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("groq", chat_body(MODEL, true)).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done, "stream should end with [DONE]");
+    assert_eq!(streamed_reasoning(&events), MOCK_REASONING);
+    assert_eq!(streamed_text(&events), MOCK_REPLY);
+}
+
+#[tokio::test]
+async fn test_groq_reasoning_is_stripped_from_stream() {
+    let gateway = MockGateway::start().await;
+    let response = gateway
+        .chat_with_headers("groq", chat_body(MODEL, true), &[("x-strip-reasoning", "true")])
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done, "stream should end with [DONE]");
+    assert!(streamed_reasoning(&events).is_empty());
+    assert_eq!(streamed_text(&events), MOCK_REPLY);
+}
+
+#[tokio::test]
+async fn test_groq_reasoning_is_stripped_from_completion() {
+    let gateway = MockGateway::start().await;
+    let response = gateway
+        .chat_with_headers("groq", chat_body(MODEL, false), &[("x-strip-reasoning", "true")])
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_chat_completion(&body);
+    assert!(body["choices"][0]["message"].get("reasoning_content").is_none());
+}
+
+#[tokio::test]
+async fn test_groq_streamed_reasoning_tokens_are_recorded() {
+    let gateway = MockGateway::start_with_env(&[("ADMIN_API_KEY", "admin-test-key")]).await;
+    let tail = gateway.tail("groq").await;
+
+    let response = gateway.chat("groq", chat_body(MODEL, true)).await;
+    assert_eq!(response.status(), 200);
+    read_events(response).await;
+
+    let event = next_tail_event(tail).await;
+    assert_eq!(event["is_streaming"], true);
+    assert!(event["reasoning_tokens"].as_u64().unwrap_or(0) > 0, "unexpected event: {}", event);
+}
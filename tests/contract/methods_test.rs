@@ -0,0 +1,66 @@
+use super::common::*;
+use reqwest::{Client, Method};
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_preflight_lists_route_methods() {
+    let gateway = MockGateway::start().await;
+    let response = Client::new()
+        .request(Method::OPTIONS, format!("{}/v1/files", gateway.url))
+        .header("origin", "https://app.example.com")
+        .header("access-control-request-method", "DELETE")
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    let headers = response.headers();
+    assert_eq!(headers["access-control-allow-methods"], "GET, POST, DELETE");
+    assert_eq!(headers["allow"], "GET, POST, DELETE, HEAD, OPTIONS");
+}
+
+#[tokio::test]
+async fn test_head_is_answered_by_the_gateway() {
+    let gateway = MockGateway::start().await;
+    let response = Client::new()
+        .head(format!("{}/v1/chat/completions", gateway.url))
+        .header("x-provider", "openai")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["allow"], "POST, HEAD, OPTIONS");
+}
+
+#[tokio::test]
+async fn test_wrong_method_is_rejected() {
+    let gateway = MockGateway::start().await;
+    let response = Client::new()
+        .get(format!("{}/v1/chat/completions", gateway.url))
+        .header("x-provider", "openai")
+        .header("authorization", "Bearer mock-key")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 405);
+    assert_eq!(response.headers()["allow"], "POST, HEAD, OPTIONS");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "method_not_allowed");
+}
+
+#[tokio::test]
+async fn test_preflight_allows_get_for_stability_results() {
+    let gateway = MockGateway::start().await;
+    let response = Client::new()
+        .request(Method::OPTIONS, format!("{}/v2beta/results/gen-123", gateway.url))
+        .header("origin", "https://app.example.com")
+        .header("access-control-request-method", "GET")
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_success());
+    assert_eq!(response.headers()["allow"], "GET, HEAD, OPTIONS");
+}
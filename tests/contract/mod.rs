@@ -0,0 +1,12 @@
+//! Contract tests for the provider translators.
+//!
+//! The mock upstream answers in each provider's native wire format; these
+//! tests check what the gateway hands back to OpenAI-style clients.
+//! Scenarios are picked through the model name (see `src/mock.rs`).
+
+pub mod common;
+pub mod openai_test;
+pub mod anthropic_test;
+pub mod bedrock_test;
+pub mod methods_test;
+pub mod groq_test;
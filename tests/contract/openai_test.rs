@@ -0,0 +1,235 @@
+use super::common::*;
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_openai_non_streaming() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("openai", chat_body("gpt-4o-mini", false)).await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_chat_completion(&body);
+}
+
+#[tokio::test]
+async fn test_openai_routed_headers() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("openai", chat_body("gpt-4o-mini", false)).await;
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["x-gateway-routed-provider"], "openai");
+    assert_eq!(response.headers()["x-gateway-routed-model"], "gpt-4o-mini");
+}
+
+#[tokio::test]
+async fn test_openai_streaming() {
+    let gateway = MockGateway::start().await;
+    let mut body = chat_body("gpt-4o-mini", true);
+    body["stream_options"] = serde_json::json!({ "include_usage": true });
+    let response = gateway.chat("openai", body).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done, "stream should end with [DONE]");
+    assert_eq!(streamed_text(&events), MOCK_REPLY);
+    assert!(events.iter().all(|e| e["object"] == "chat.completion.chunk"));
+    assert!(events.iter().any(|e| e["choices"][0]["finish_reason"] == "stop"));
+    assert!(events.iter().any(|e| e["usage"]["total_tokens"].as_u64().unwrap_or(0) > 0));
+}
+
+#[tokio::test]
+async fn test_openai_fast_path_stream_without_usage_is_estimated() {
+    // Nothing is captured, so the stream takes the zero-copy path
+    let gateway = MockGateway::start_with_env(&[
+        ("ADMIN_API_KEY", "admin-test-key"),
+        ("CAPTURE_PROFILE", "metadata-only"),
+    ])
+    .await;
+    let tail = gateway.tail("openai").await;
+
+    let response = gateway.chat("openai", chat_body("gpt-4o-mini", true)).await;
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done);
+    assert!(events.iter().all(|e| e["usage"].is_null()));
+
+    let event = next_tail_event(tail).await;
+    assert!(event["output_tokens"].as_u64().unwrap_or(0) > 0, "unexpected event: {}", event);
+    assert!(event["cost"].as_f64().unwrap_or(0.0) > 0.0, "unexpected event: {}", event);
+}
+
+#[tokio::test]
+async fn test_openai_rate_limit_error() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("openai", chat_body("mock-error-429", false)).await;
+
+    assert_eq!(response.status(), 429);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "rate_limit_exceeded");
+}
+
+#[tokio::test]
+async fn test_openai_stream_error() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("openai", chat_body("mock-stream-error", true)).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done, "an interrupted stream should still end with [DONE]");
+    assert!(events.last().is_some_and(|e| e.get("error").is_some()));
+}
+
+#[tokio::test]
+async fn test_openai_dropped_stream_is_closed() {
+    let gateway = MockGateway::start().await;
+    let response = gateway.chat("openai", chat_body("mock-stream-drop", true)).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done, "a dropped upstream should end with [DONE]");
+    let error = events.last().expect("stream should carry events");
+    assert_eq!(error["error"]["code"], "stream_interrupted");
+    assert!(events.iter().any(|e| e["object"] == "chat.completion.chunk"));
+}
+
+#[tokio::test]
+async fn test_openai_stream_moderation() {
+    let gateway = MockGateway::start_with_env(&[(
+        "STREAM_MODERATION_RULES",
+        r#"[{"name": "mock-mention", "terms": ["Mock Provider"]}]"#,
+    )])
+    .await;
+    let response = gateway.chat("openai", chat_body("gpt-4o-mini", true)).await;
+
+    assert_eq!(response.status(), 200);
+    let (events, done) = read_events(response).await;
+    assert!(done, "a moderated stream should end with [DONE]");
+    let stop = events.last().expect("stream should carry events");
+    assert_eq!(stop["choices"][0]["finish_reason"], "content_filter");
+    assert_eq!(stop["policy_violation"]["rule"], "mock-mention");
+    // The match spans two chunks; the one completing it is withheld
+    let content = streamed_text(&events);
+    assert!(!content.contains("provider"), "unexpected content: {}", content);
+}
+
+#[tokio::test]
+async fn test_max_cost_on_unpriced_model_is_rejected() {
+    let gateway = MockGateway::start().await;
+    let response = gateway
+        .chat_with_headers("openai", chat_body("mock-unpriced-model", true), &[("x-max-cost", "0.01")])
+        .await;
+
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_request_log_query() {
+    let gateway = MockGateway::start_with_env(&[
+        ("ENABLE_REQUEST_LOG", "true"),
+        ("REQUEST_LOG_PATH", ":memory:"),
+        ("ADMIN_API_KEY", "admin-test-key"),
+    ])
+    .await;
+    let response = gateway.chat("openai", chat_body("gpt-4o-mini", false)).await;
+    assert_eq!(response.status(), 200);
+
+    let query = |filter: &str| {
+        reqwest::Client::new()
+            .get(format!("{}/admin/requests", gateway.url))
+            .query(&[("filter", filter)])
+            .header("x-admin-key", "admin-test-key")
+            .send()
+    };
+    // Exporters run after the response is sent
+    let mut rows = Vec::new();
+    for _ in 0..40 {
+        let body: Value = query("provider=openai,status<400").await.unwrap().json().await.unwrap();
+        rows = body["requests"].as_array().cloned().unwrap_or_default();
+        if !rows.is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["model"], "gpt-4o-mini");
+    assert_eq!(rows[0]["status"], 200);
+
+    let body: Value = query("provider=groq").await.unwrap().json().await.unwrap();
+    assert_eq!(body["count"], 0);
+    assert_eq!(query("colour=red").await.unwrap().status(), 400);
+}
+
+#[tokio::test]
+async fn test_open_circuit_breaker_returns_retryable_503() {
+    let gateway = MockGateway::start_with_env(&[
+        ("CIRCUIT_BREAKER_THRESHOLD", "2"),
+        ("CIRCUIT_BREAKER_COOLDOWN_SECS", "60"),
+    ])
+    .await;
+    for _ in 0..2 {
+        let response = gateway.chat("openai", chat_body("mock-error-500", false)).await;
+        assert_eq!(response.status(), 500);
+    }
+
+    let response = gateway.chat("openai", chat_body("gpt-4o-mini", false)).await;
+    assert_eq!(response.status(), 503);
+    let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after));
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "server_error");
+    assert_eq!(body["error"]["code"], "circuit_open");
+
+    // Other providers are unaffected
+    let response = gateway.chat("groq", chat_body("llama-3.1-8b-instant", false)).await;
+    assert_eq!(response.status(), 200);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_draining_gateway_returns_retryable_503() {
+    let gateway = MockGateway::start_with_env(&[("SHUTDOWN_DRAIN_SECS", "5")]).await;
+    gateway.terminate();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let response = gateway.chat("openai", chat_body("gpt-4o-mini", false)).await;
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.headers()["retry-after"], "1");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "gateway_draining");
+}
+
+#[tokio::test]
+async fn test_one_sided_price_override_keeps_list_price() {
+    let gateway = MockGateway::start_with_env(&[
+        (
+            "GATEWAY_TENANTS",
+            r#"[{"id": "acme", "api_keys": ["gw-acme"], "budget_usd": 0.000001, "pricing": {"*": {"output_per_million": 0.0}}}]"#,
+        ),
+        ("BUDGET_RESERVATION_MIN_USD", "0"),
+    ])
+    .await;
+    let response = gateway
+        .chat_with_headers("openai", chat_body("gpt-4o-mini", false), &[("x-gateway-key", "gw-acme")])
+        .await;
+
+    // Output isn't free: the request is estimated at list price, over the tiny budget
+    assert_eq!(response.status(), 402);
+}
+
+#[tokio::test]
+async fn test_aliased_model_is_checked_against_tenant_allowlist() {
+    let gateway = MockGateway::start_with_env(&[
+        (
+            "GATEWAY_TENANTS",
+            r#"[{"id": "acme", "api_keys": ["gw-acme"], "allowed_models": ["gpt-4o-mini"]}]"#,
+        ),
+        ("MODEL_ALIASES", r#"{"gpt-4o-mini": "gpt-4o"}"#),
+    ])
+    .await;
+    let response = gateway
+        .chat_with_headers("openai", chat_body("gpt-4o-mini", false), &[("x-gateway-key", "gw-acme")])
+        .await;
+
+    // The alias target isn't on the tenant's list, whatever name the client used
+    assert_eq!(response.status(), 403);
+}
@@ -0,0 +1,99 @@
+//! Runs the `noveum-gateway-client` examples against a local gateway, so the
+//! documented usage keeps working.
+
+use noveum_gateway_client::{ChatCompletionRequest, ChatMessage, ClientError, GatewayClient, GatewayHeaders};
+use std::env;
+use super::common::{generate_request_id, get_api_key};
+
+#[allow(dead_code)]
+#[path = "../../client/examples/chat.rs"]
+mod chat_example;
+
+#[allow(dead_code)]
+#[path = "../../client/examples/stream.rs"]
+mod stream_example;
+
+#[allow(dead_code)]
+#[path = "../../client/examples/embeddings.rs"]
+mod embeddings_example;
+
+fn client(provider: &str, api_key_env_var: &str) -> GatewayClient {
+    let gateway_url = env::var("GATEWAY_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    GatewayClient::builder(gateway_url)
+        .provider(provider)
+        .api_key(get_api_key(api_key_env_var))
+        .organization_id("TEST_ORG")
+        .project_id("TEST_PROJECT")
+        .experiment_id("TEST_EXPERIMENT")
+        .build()
+        .expect("Failed to build client")
+}
+
+#[tokio::test]
+async fn test_client_chat_example() {
+    let client = client("openai", "OPENAI_API_KEY");
+    let completion = chat_example::run(&client, "gpt-4o-mini").await.expect("Chat request failed");
+
+    let text = completion.text().expect("Missing completion content");
+    assert!(!text.is_empty(), "Empty completion");
+    let usage = completion.usage.expect("Missing usage");
+    assert!(usage.total_tokens > 0, "Usage not reported: {:?}", usage);
+}
+
+#[tokio::test]
+async fn test_client_stream_example() {
+    let client = client("groq", "GROQ_API_KEY");
+    let mut deltas = 0;
+    let text = stream_example::run(&client, "llama-3.1-8b-instant", |_| deltas += 1)
+        .await
+        .expect("Streaming request failed");
+
+    assert!(deltas > 1, "Expected several deltas, got {}", deltas);
+    assert!(text.to_lowercase().contains("ten"), "Unexpected streamed text: {}", text);
+}
+
+#[tokio::test]
+async fn test_client_embeddings_example() {
+    let client = client("openai", "OPENAI_API_KEY");
+    let response = embeddings_example::run(&client, "text-embedding-3-small")
+        .await
+        .expect("Embeddings request failed");
+
+    assert_eq!(response.data.len(), 2);
+    assert!(response.data.iter().all(|e| !e.embedding.is_empty()));
+}
+
+#[tokio::test]
+async fn test_client_collects_stream_with_request_headers() {
+    let client = client("groq", "GROQ_API_KEY")
+        .with_headers(GatewayHeaders::new().user_id("TEST_USER").thread_id(generate_request_id()))
+        .expect("Invalid headers");
+    let request = ChatCompletionRequest::new("llama-3.1-8b-instant", vec![ChatMessage::user("Say hello")])
+        .max_tokens(20);
+
+    let text = client
+        .chat_stream(&request)
+        .await
+        .expect("Streaming request failed")
+        .collect_text()
+        .await
+        .expect("Stream failed");
+    assert!(!text.is_empty(), "Empty streamed text");
+}
+
+#[tokio::test]
+async fn test_client_surfaces_gateway_errors() {
+    let client = client("openai", "OPENAI_API_KEY");
+    let request = ChatCompletionRequest::new("gpt-4o-mini", vec![ChatMessage::user("Hello")]);
+
+    let error = client
+        .with_headers(GatewayHeaders::new().provider("not-a-provider"))
+        .expect("Invalid headers")
+        .chat(&request)
+        .await
+        .expect_err("Unknown provider should fail");
+    match error {
+        ClientError::Api { status, .. } => assert!((400..500).contains(&status), "Unexpected status {}", status),
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+}
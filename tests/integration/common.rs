@@ -155,7 +155,7 @@ pub fn create_test_request_body(config: &ProviderTestConfig, stream: bool) -> Va
 }
 
 /// Get API key for the provider
-fn get_api_key(env_var_name: &str) -> String {
+pub fn get_api_key(env_var_name: &str) -> String {
     // Ensure environment variables are loaded
     init_test_env();
     
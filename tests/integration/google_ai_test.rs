@@ -0,0 +1,15 @@
+use super::common::{ProviderTestConfig, run_non_streaming_test, run_streaming_test};
+
+#[tokio::test]
+async fn test_google_ai_non_streaming() {
+    let config = ProviderTestConfig::new("google-ai", "GOOGLE_AI_API_KEY", "gemini-2.0-flash")
+        .with_max_tokens(300);
+    run_non_streaming_test(&config).await;
+}
+
+#[tokio::test]
+async fn test_google_ai_streaming() {
+    let config = ProviderTestConfig::new("google-ai", "GOOGLE_AI_API_KEY", "gemini-2.0-flash")
+        .with_max_tokens(300);
+    run_streaming_test(&config).await;
+}
@@ -5,7 +5,7 @@ use std::time::Duration;
 use tokio::time::sleep;
 use dotenv::dotenv;
 use uuid::Uuid;
-use super::common::{ProviderTestConfig, run_non_streaming_test, run_streaming_test};
+use super::common::{ProviderTestConfig, run_non_streaming_test, run_streaming_test, get_api_key, setup_test_headers};
 
 // Helper function to generate a unique request ID for tracking
 fn generate_request_id() -> String {
@@ -51,4 +51,35 @@ async fn test_groq_streaming() {
     let config = ProviderTestConfig::new("groq", "GROQ_API_KEY", "llama-3.1-8b-instant")
         .with_max_tokens(300);
     run_streaming_test(&config).await;
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_groq_drops_unsupported_params() {
+    let api_key = get_api_key("GROQ_API_KEY");
+    let gateway_url = env::var("GATEWAY_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let headers = setup_test_headers("groq", &api_key, &generate_request_id());
+
+    // Groq rejects logit_bias, so the gateway should strip it and say so
+    let response = Client::new()
+        .post(format!("{}/v1/chat/completions", gateway_url))
+        .headers(headers)
+        .json(&json!({
+            "model": "llama-3.1-8b-instant",
+            "messages": [{"role": "user", "content": "Say hello"}],
+            "max_tokens": 20,
+            "stop": ["\n\n"],
+            "logit_bias": {"50256": -100}
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert!(response.status().is_success(), "Request failed with status {}", response.status());
+    let warning = response
+        .headers()
+        .get("x-gateway-warning")
+        .and_then(|v| v.to_str().ok())
+        .expect("Missing x-gateway-warning header");
+    assert!(warning.contains("logit_bias"), "Unexpected warning: {}", warning);
+    assert!(!warning.contains("stop"), "stop should be forwarded: {}", warning);
+}
@@ -20,5 +20,7 @@ pub mod groq_test;
 pub mod fireworks_test;
 pub mod together_test;
 pub mod bedrock_test;
+pub mod google_ai_test;
+pub mod client_test;
 
 // Add more provider test modules here as they are implemented 
\ No newline at end of file
@@ -0,0 +1,15 @@
+//! Contract tests for provider translation, run against the built-in mock providers.
+//!
+//! Each test starts the gateway binary with `MOCK_PROVIDERS=true`, so no API
+//! keys, network access or Elasticsearch are needed.
+//!
+//! # Running the tests
+//!
+//! ```bash
+//! cargo test --features mock-providers --test run_contract_tests
+//!
+//! # Run tests for specific providers
+//! cargo test --features mock-providers --test run_contract_tests bedrock
+//! ```
+
+mod contract;
@@ -44,6 +44,7 @@
 //! cargo test --test run_integration_tests fireworks -- --nocapture
 //! cargo test --test run_integration_tests together -- --nocapture
 //! cargo test --test run_integration_tests bedrock -- --nocapture
+//! cargo test --test run_integration_tests client -- --nocapture
 //! ```
 
 mod integration; 
\ No newline at end of file